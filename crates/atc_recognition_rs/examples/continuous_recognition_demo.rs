@@ -9,7 +9,7 @@ use std::{
     time::{Duration, Instant},
 };
 
-use atc_recognition_rs::{Error, SpeechToText, SpeechToTextConfig};
+use atc_recognition_rs::{Error, SpeechToText, SpeechToTextConfig, to_f32_samples};
 use aviation_helper_rs::{
     clearance::{airlines::Airlines, aviation_command::AviationCommandPart},
     types::altitude::Altitude,
@@ -102,6 +102,31 @@ fn run_basic_continuous_recognition(
                 format_altitude(&altitude)
             );
         }
+        AviationCommandPart::MaintainBlock { lower, upper } => {
+            println!(
+                "📏 Altitude Command: block {} to {}",
+                format_altitude(&lower),
+                format_altitude(&upper)
+            );
+        }
+        AviationCommandPart::CrossFix {
+            fix,
+            constraint,
+            altitude,
+        } => {
+            println!(
+                "📏 Crossing Command: cross {} {:?} {}",
+                fix,
+                constraint,
+                format_altitude(&altitude)
+            );
+        }
+        AviationCommandPart::ChangeSpeed { knots } => {
+            println!("💨 Speed Command: speed {} knots", knots);
+        }
+        AviationCommandPart::ResumeNormalSpeed => {
+            println!("💨 Speed Command: resume normal speed");
+        }
         AviationCommandPart::ContactFrequency { frequency, station } => {
             let station_str = station.as_deref().unwrap_or("ATC");
             println!(
@@ -112,9 +137,54 @@ fn run_basic_continuous_recognition(
         AviationCommandPart::ProceedDirect(waypoint) => {
             println!("🎯 Navigation Command: proceed direct {}", waypoint);
         }
+        AviationCommandPart::DirectAfterFix { fix, waypoint } => {
+            println!(
+                "🎯 Navigation Command: until {} then direct {}",
+                fix, waypoint
+            );
+        }
         AviationCommandPart::RadarContact => {
             println!("📡 Radar Command: radar contact");
         }
+        AviationCommandPart::ClearedToLand { runway } => {
+            println!("🛬 Landing Command: cleared to land runway {}", runway);
+        }
+        AviationCommandPart::ClearedForTheOption { runway } => {
+            println!("🛬 Landing Command: cleared for the option runway {}", runway);
+        }
+        AviationCommandPart::VectorsForIls { runway } => {
+            println!("🧭 Approach Command: vectors for ILS runway {}", runway);
+        }
+        AviationCommandPart::ClearedVisualApproach { runway } => {
+            println!(
+                "🧭 Approach Command: cleared visual approach runway {}",
+                runway
+            );
+        }
+        AviationCommandPart::Query { field } => {
+            println!("❓ Query Command: report {:?}", field);
+        }
+        AviationCommandPart::ResumeOwnNavigation => {
+            println!("🧭 Navigation Command: resume own navigation");
+        }
+        AviationCommandPart::Altimeter { hpa, inhg } => {
+            println!("🌡️  Altimeter Command: {:?} hPa, {:?} inHg/100", hpa, inhg);
+        }
+        AviationCommandPart::Squawk { code } => {
+            println!("📟 Squawk Command: squawk {:04}", code);
+        }
+        AviationCommandPart::DeclareEmergency => {
+            println!("🚨 Emergency: declaring emergency");
+        }
+        AviationCommandPart::SequenceBehind { callsign } => {
+            println!("🔗 Sequence Command: sequence behind {}", callsign);
+        }
+        AviationCommandPart::Disregard => {
+            println!("↩️  Disregard Command: cancel last instruction");
+        }
+        AviationCommandPart::Standby => {
+            println!("⏸️  Standby Command: standby");
+        }
     };
 
     // Start continuous recognition (this blocks)
@@ -181,6 +251,31 @@ fn run_advanced_continuous_recognition(
                     format_altitude(&altitude)
                 );
             }
+            AviationCommandPart::MaintainBlock { lower, upper } => {
+                println!(
+                    "   📏 BLOCK: {} to {}",
+                    format_altitude(&lower),
+                    format_altitude(&upper)
+                );
+            }
+            AviationCommandPart::CrossFix {
+                fix,
+                constraint,
+                altitude,
+            } => {
+                println!(
+                    "   📏 CROSS: {} {:?} {}",
+                    fix,
+                    constraint,
+                    format_altitude(&altitude)
+                );
+            }
+            AviationCommandPart::ChangeSpeed { knots } => {
+                println!("   💨 SPEED: {} knots", knots);
+            }
+            AviationCommandPart::ResumeNormalSpeed => {
+                println!("   💨 SPEED: resume normal speed");
+            }
             AviationCommandPart::ContactFrequency { frequency, station } => {
                 let station_str = station.as_deref().unwrap_or("ATC");
                 println!("   📡 CONTACT: {} on {:.2}", station_str, frequency.num);
@@ -188,9 +283,48 @@ fn run_advanced_continuous_recognition(
             AviationCommandPart::ProceedDirect(waypoint) => {
                 println!("   🎯 NAVIGATE: proceed direct {}", waypoint);
             }
+            AviationCommandPart::DirectAfterFix { fix, waypoint } => {
+                println!("   🎯 NAVIGATE: until {} then direct {}", fix, waypoint);
+            }
             AviationCommandPart::RadarContact => {
                 println!("   📡 RADAR: radar contact");
             }
+            AviationCommandPart::ClearedToLand { runway } => {
+                println!("   🛬 LANDING: cleared to land runway {}", runway);
+            }
+            AviationCommandPart::ClearedForTheOption { runway } => {
+                println!("   🛬 LANDING: cleared for the option runway {}", runway);
+            }
+            AviationCommandPart::VectorsForIls { runway } => {
+                println!("   🧭 APPROACH: vectors for ILS runway {}", runway);
+            }
+            AviationCommandPart::ClearedVisualApproach { runway } => {
+                println!("   🧭 APPROACH: cleared visual approach runway {}", runway);
+            }
+            AviationCommandPart::Query { field } => {
+                println!("   ❓ QUERY: report {:?}", field);
+            }
+            AviationCommandPart::ResumeOwnNavigation => {
+                println!("   🧭 NAVIGATE: resume own navigation");
+            }
+            AviationCommandPart::Altimeter { hpa, inhg } => {
+                println!("   🌡️  ALTIMETER: {:?} hPa, {:?} inHg/100", hpa, inhg);
+            }
+            AviationCommandPart::Squawk { code } => {
+                println!("   📟 SQUAWK: {:04}", code);
+            }
+            AviationCommandPart::DeclareEmergency => {
+                println!("   🚨 EMERGENCY: declaring emergency");
+            }
+            AviationCommandPart::SequenceBehind { callsign } => {
+                println!("   🔗 SEQUENCE: behind {}", callsign);
+            }
+            AviationCommandPart::Disregard => {
+                println!("   ↩️  DISREGARD: cancel last instruction");
+            }
+            AviationCommandPart::Standby => {
+                println!("   ⏸️  STANDBY");
+            }
         }
 
         // Print log summary every 5 commands
@@ -331,7 +465,10 @@ fn capture_audio_samples(
         .default_input_device()
         .ok_or(Error::FailedToFindDefaultInputDevice)?;
 
-    let config: cpal::StreamConfig = input_device.default_input_config()?.into();
+    // Devices deliver their own sample format, which is converted to f32 in the callback
+    let supported_config = input_device.default_input_config()?;
+    let sample_format = supported_config.sample_format();
+    let config: cpal::StreamConfig = supported_config.into();
     let sample_rate_in = config.sample_rate.0;
     let channel_count_in = config.channels;
 
@@ -342,7 +479,9 @@ fn capture_audio_samples(
 
     let resample_buffer: Arc<Mutex<[Vec<f32>; 1]>> = Arc::new(Mutex::new([vec![]]));
 
-    let input_data_fn = move |data: &[f32], _: &cpal::InputCallbackInfo| {
+    let input_data_fn = move |data: &cpal::Data, _: &cpal::InputCallbackInfo| {
+        let samples = to_f32_samples(data.bytes(), sample_format);
+        let data = &samples[..];
         let Ok(mut rb) = resample_buffer.lock() else {
             eprintln!("Could not lock mutex");
             return;
@@ -382,7 +521,8 @@ fn capture_audio_samples(
         }
     };
 
-    let input_stream = input_device.build_input_stream(&config, input_data_fn, err_fn, None)?;
+    let input_stream =
+        input_device.build_input_stream_raw(&config, sample_format, input_data_fn, err_fn, None)?;
     input_stream.play()?;
 
     // Record for the specified duration
@@ -0,0 +1,354 @@
+//! Bookkeeping for the ring buffer between the audio callback and the recognition thread
+
+use std::{ops::Range, time::Duration};
+
+use crate::{SAMPLE_RATE_HZ, TranscriptionResult};
+
+/// Number of windows, that fit into the ring buffer
+const RING_CAPACITY_WINDOWS: usize = 8;
+/// Shorter audio is not worth transcribing yet
+const MIN_SNIPPET_LEN_SECONDS: usize = 1;
+
+/// Timing of the streaming recognition. The ring buffer is sized from the window length.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecognitionConfig {
+    /// Length of the audio, that the recognition thread reads from the ring buffer at once
+    pub window_len_seconds: u32,
+    /// Pause of the recognition thread between two reads
+    pub check_interval_ms: u64,
+    /// Maximum length of a snippet, that is passed to Whisper. Whisper handles at most 30 seconds
+    /// and gets less reliable towards that limit.
+    pub max_snippet_len_seconds: u32,
+    /// Let the speech-to-text engine forget everything after each final text. Whisper never
+    /// takes the previous snippet as context, so this only frees its state, which is allocated
+    /// again for the next utterance. Backends with context forget it.
+    pub reset_state_each_utterance: bool,
+}
+
+impl Default for RecognitionConfig {
+    fn default() -> Self {
+        Self {
+            window_len_seconds: 20,
+            check_interval_ms: 3000,
+            max_snippet_len_seconds: 17,
+            reset_state_each_utterance: true,
+        }
+    }
+}
+
+impl RecognitionConfig {
+    pub fn window_len_samples(&self) -> usize {
+        SAMPLE_RATE_HZ as usize * self.window_len_seconds as usize
+    }
+
+    /// Capacity of the ring buffer between the audio callback and the recognition thread
+    pub fn ring_capacity(&self) -> usize {
+        self.window_len_samples() * RING_CAPACITY_WINDOWS
+    }
+
+    pub fn check_interval(&self) -> Duration {
+        Duration::from_millis(self.check_interval_ms)
+    }
+
+    /// Number of the buffered samples, that are transcribed and then flushed from the ring
+    /// buffer. None, if there is not enough audio yet.
+    pub fn snippet_len(&self, buffered_len: usize) -> Option<usize> {
+        if buffered_len < SAMPLE_RATE_HZ as usize * MIN_SNIPPET_LEN_SECONDS {
+            return None;
+        }
+        Some(buffered_len.min(self.max_snippet_len()))
+    }
+
+    /// Length of the longest snippet, that is transcribed at once
+    pub fn max_snippet_len(&self) -> usize {
+        let max_snippet_len_samples =
+            SAMPLE_RATE_HZ as usize * self.max_snippet_len_seconds as usize;
+        self.window_len_samples().min(max_snippet_len_samples)
+    }
+}
+
+/// Where the transcription of a snippet is cut. The audio up to the cut is consumed and its
+/// text is final. The rest stays in the ring buffer and is transcribed again together with the
+/// following audio, so that a transmission at the end of the snippet isn't cut in half.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SentenceBoundary {
+    /// After the last segment, that ends with ".", "!" or "?"
+    #[default]
+    LastPunctuation,
+    /// After the whole snippet
+    FullBuffer,
+    /// In the last pause after a segment, that is at least this long
+    SilenceGap { min_gap_ms: u32 },
+}
+
+/// Consumed start of a snippet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SentenceCut {
+    /// Number of samples from the start of the snippet, that are dropped from the ring buffer
+    pub consumed_len: usize,
+    /// Text of the consumed samples
+    pub text: String,
+}
+
+impl SentenceBoundary {
+    /// Where to cut the transcription of a snippet with `snippet_len` samples. None, if there
+    /// is no boundary in it yet. Only `FullBuffer` works for backends, that don't report the
+    /// timing of segments.
+    pub fn cut(self, result: &TranscriptionResult, snippet_len: usize) -> Option<SentenceCut> {
+        let segments = &result.segments;
+        let full_buffer = || SentenceCut {
+            consumed_len: snippet_len,
+            text: result.text.clone(),
+        };
+        // Cut after the first `count` segments
+        let cut_after = |count: usize| SentenceCut {
+            consumed_len: ms_to_samples(segments[count - 1].end_ms).min(snippet_len),
+            text: segments[..count]
+                .iter()
+                .map(|segment| segment.text.trim())
+                .collect::<Vec<_>>()
+                .join(" "),
+        };
+        match self {
+            SentenceBoundary::FullBuffer => Some(full_buffer()),
+            SentenceBoundary::LastPunctuation => {
+                let index = segments
+                    .iter()
+                    .rposition(|segment| segment.text.trim_end().ends_with(['.', '!', '?']))?;
+                Some(cut_after(index + 1))
+            }
+            SentenceBoundary::SilenceGap { min_gap_ms } => {
+                let snippet_end_ms = (snippet_len * 1000 / SAMPLE_RATE_HZ as usize) as u32;
+                let index = (0..segments.len()).rev().find(|&index| {
+                    // Each segment is followed by the next one or the end of the snippet
+                    let next_start_ms = segments
+                        .get(index + 1)
+                        .map_or(snippet_end_ms, |next| next.start_ms);
+                    next_start_ms.saturating_sub(segments[index].end_ms) >= min_gap_ms
+                })?;
+                if index + 1 == segments.len() {
+                    // Silence until the end, nothing is cut in half
+                    Some(full_buffer())
+                } else {
+                    Some(cut_after(index + 1))
+                }
+            }
+        }
+    }
+}
+
+fn ms_to_samples(ms: u32) -> usize {
+    ms as usize * SAMPLE_RATE_HZ as usize / 1000
+}
+
+/// What to do, if the recognition thread can't keep up and the ring buffer is full
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Skip the oldest buffered samples, so the latest audio is retained
+    DropOldest,
+    /// Keep the buffered samples and drop the incoming ones
+    #[default]
+    DropNewest,
+}
+
+/// Samples to drop, so that incoming samples fit into the ring buffer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OverflowPlan {
+    /// Number of samples to skip on the consumer side before pushing
+    pub skip_buffered: usize,
+    /// Range of the incoming samples, that are pushed
+    pub push_range: Range<usize>,
+}
+
+impl OverflowPlan {
+    /// Total number of samples, that are lost
+    pub fn dropped_len(&self, incoming_len: usize) -> usize {
+        self.skip_buffered + incoming_len - self.push_range.len()
+    }
+}
+
+impl OverflowPolicy {
+    /// Plan how to push `incoming_len` samples into a ring buffer with the given capacity and
+    /// `vacant_len` free slots
+    pub fn plan(self, capacity: usize, vacant_len: usize, incoming_len: usize) -> OverflowPlan {
+        match self {
+            OverflowPolicy::DropNewest => OverflowPlan {
+                skip_buffered: 0,
+                push_range: 0..incoming_len.min(vacant_len),
+            },
+            OverflowPolicy::DropOldest => {
+                let occupied_len = capacity - vacant_len;
+                if incoming_len > capacity {
+                    // Even an empty buffer can't take everything, keep the end of the input
+                    OverflowPlan {
+                        skip_buffered: occupied_len,
+                        push_range: incoming_len - capacity..incoming_len,
+                    }
+                } else {
+                    OverflowPlan {
+                        skip_buffered: incoming_len.saturating_sub(vacant_len),
+                        push_range: 0..incoming_len,
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ringbuf::{
+        HeapRb,
+        traits::{Consumer, Observer, Producer, Split},
+    };
+
+    use super::{OverflowPlan, OverflowPolicy, RecognitionConfig, SentenceBoundary, SentenceCut};
+    use crate::{SAMPLE_RATE_HZ, TranscribedSegment, TranscriptionResult};
+
+    fn push_with_policy(policy: OverflowPolicy, buffered: &[f32], incoming: &[f32]) -> Vec<f32> {
+        let (mut producer, mut consumer) = HeapRb::<f32>::new(4).split();
+        producer.push_slice(buffered);
+
+        let plan = policy.plan(
+            producer.capacity().get(),
+            producer.vacant_len(),
+            incoming.len(),
+        );
+        consumer.skip(plan.skip_buffered);
+        producer.push_slice(&incoming[plan.push_range]);
+        consumer.pop_iter().collect()
+    }
+
+    #[test]
+    fn test_overflow_bookkeeping() {
+        assert_eq!(
+            OverflowPolicy::DropOldest.plan(4, 1, 3),
+            OverflowPlan {
+                skip_buffered: 2,
+                push_range: 0..3,
+            }
+        );
+        assert_eq!(OverflowPolicy::DropOldest.plan(4, 1, 3).dropped_len(3), 2);
+        assert_eq!(OverflowPolicy::DropNewest.plan(4, 1, 3).dropped_len(3), 2);
+        // No overflow
+        assert_eq!(OverflowPolicy::DropOldest.plan(4, 3, 3).dropped_len(3), 0);
+
+        let buffered = [1., 2., 3.];
+        let incoming = [4., 5., 6.];
+        assert_eq!(
+            push_with_policy(OverflowPolicy::DropOldest, &buffered, &incoming),
+            vec![3., 4., 5., 6.]
+        );
+        assert_eq!(
+            push_with_policy(OverflowPolicy::DropNewest, &buffered, &incoming),
+            vec![1., 2., 3., 4.]
+        );
+        // More samples than the capacity
+        assert_eq!(
+            push_with_policy(OverflowPolicy::DropOldest, &buffered, &[4., 5., 6., 7., 8.]),
+            vec![5., 6., 7., 8.]
+        );
+    }
+
+    #[test]
+    fn test_recognition_config_sizes_and_flushes_ring_buffer() {
+        let seconds = |seconds: f32| (SAMPLE_RATE_HZ as f32 * seconds) as usize;
+        let config = RecognitionConfig {
+            window_len_seconds: 5,
+            ..Default::default()
+        };
+        assert_eq!(config.window_len_samples(), 80_000);
+        assert_eq!(config.ring_capacity(), 640_000);
+
+        let (mut producer, mut consumer) = HeapRb::<f32>::new(config.ring_capacity()).split();
+        assert_eq!(producer.capacity().get(), 640_000);
+        let mut window = vec![0.0f32; config.window_len_samples()];
+        let mut flush = |incoming_len: usize| {
+            producer.push_slice(&vec![0.5; incoming_len]);
+            let read_len = consumer.peek_slice(&mut window);
+            let snippet_len = config.snippet_len(read_len);
+            if let Some(snippet_len) = snippet_len {
+                consumer.skip(snippet_len);
+            }
+            (snippet_len, consumer.occupied_len())
+        };
+
+        // Not enough audio yet
+        assert_eq!(flush(seconds(0.5)), (None, seconds(0.5)));
+        // Everything buffered fits into one window
+        assert_eq!(flush(seconds(2.5)), (Some(seconds(3.)), 0));
+        // At most one window is flushed at once
+        assert_eq!(flush(seconds(7.)), (Some(seconds(5.)), seconds(2.)));
+        assert_eq!(flush(0), (Some(seconds(2.)), 0));
+
+        let short_snippets = RecognitionConfig {
+            window_len_seconds: 5,
+            max_snippet_len_seconds: 2,
+            ..Default::default()
+        };
+        assert_eq!(short_snippets.snippet_len(seconds(5.)), Some(seconds(2.)));
+    }
+
+    fn transcription(segments: &[(&str, u32, u32)]) -> TranscriptionResult {
+        let segments: Vec<TranscribedSegment> = segments
+            .iter()
+            .map(|&(text, start_ms, end_ms)| TranscribedSegment {
+                text: text.to_string(),
+                start_ms,
+                end_ms,
+            })
+            .collect();
+        TranscriptionResult {
+            text: segments
+                .iter()
+                .map(|segment| segment.text.as_str())
+                .collect::<Vec<_>>()
+                .join(" "),
+            segments,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_sentence_boundaries() {
+        let snippet_len = 5 * SAMPLE_RATE_HZ as usize;
+        let result = transcription(&[
+            ("Lufthansa 123,", 0, 1200),
+            ("turn left heading 270.", 1300, 3000),
+            ("Descend", 3800, 4500),
+        ]);
+        let cut = |boundary: SentenceBoundary| boundary.cut(&result, snippet_len);
+        let first_two = Some(SentenceCut {
+            consumed_len: 48_000,
+            text: "Lufthansa 123, turn left heading 270.".to_string(),
+        });
+        let everything = Some(SentenceCut {
+            consumed_len: snippet_len,
+            text: "Lufthansa 123, turn left heading 270. Descend".to_string(),
+        });
+
+        assert_eq!(cut(SentenceBoundary::LastPunctuation), first_two);
+        assert_eq!(cut(SentenceBoundary::FullBuffer), everything);
+        let silence_gap = |min_gap_ms| cut(SentenceBoundary::SilenceGap { min_gap_ms });
+        // The snippet ends with 500 ms of silence
+        assert_eq!(silence_gap(500), everything);
+        // Only the 800 ms between the second and the third segment are long enough
+        assert_eq!(silence_gap(600), first_two);
+        assert_eq!(silence_gap(1000), None);
+
+        // Nothing is cut before the transmission ends
+        let unfinished = transcription(&[("Lufthansa 123, turn left", 0, 2000)]);
+        assert_eq!(
+            SentenceBoundary::LastPunctuation.cut(&unfinished, snippet_len),
+            None
+        );
+        // Timings after the end of the snippet are clamped
+        let overlong = transcription(&[("Standby.", 0, 6000)]);
+        assert_eq!(
+            SentenceBoundary::LastPunctuation
+                .cut(&overlong, snippet_len)
+                .map(|cut| cut.consumed_len),
+            Some(snippet_len)
+        );
+    }
+}
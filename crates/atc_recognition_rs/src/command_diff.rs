@@ -0,0 +1,138 @@
+//! Field by field comparison of commands, for finding systematic parser errors
+//!
+//! An evaluation, that only knows, that a command was wrong, can't tell a wrong heading from a
+//! wrong turn direction. The fields are compared in their serialized form, so that every
+//! variant of `AviationCommandPart` is covered without listing its fields.
+
+use serde_json::Value;
+
+use crate::AviationCommandPart;
+
+/// Field name, that is reported, if the commands are of different variants
+pub const VARIANT_FIELD: &str = "variant";
+
+/// A field, in which the parsed command differs from the expected one
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FieldDiff {
+    /// Name of the field, e.g. "heading", or the position in a tuple variant, e.g. "0"
+    pub field: String,
+    pub expected: Value,
+    pub actual: Value,
+}
+
+/// All fields, in which `actual` differs from `expected`. Commands of different variants
+/// differ only in the variant. Empty, if the commands are equal.
+pub fn diff_command(
+    expected: &AviationCommandPart,
+    actual: &AviationCommandPart,
+) -> Vec<FieldDiff> {
+    if expected.name() != actual.name() {
+        return vec![FieldDiff {
+            field: VARIANT_FIELD.to_string(),
+            expected: Value::from(expected.name()),
+            actual: Value::from(actual.name()),
+        }];
+    }
+    let (Ok(expected), Ok(actual)) = (serde_json::to_value(expected), serde_json::to_value(actual))
+    else {
+        return Vec::new();
+    };
+    diff_fields(variant_content(expected), variant_content(actual))
+}
+
+/// The fields of a variant. Variants are serialized as `{"Name": content}`, unit variants
+/// as `"Name"`.
+fn variant_content(value: Value) -> Value {
+    match value {
+        Value::Object(object) => object.into_iter().next().map(|(_, content)| content),
+        _ => None,
+    }
+    .unwrap_or(Value::Null)
+}
+
+fn diff_fields(expected: Value, actual: Value) -> Vec<FieldDiff> {
+    let field_diff = |field: String, expected: &Value, actual: &Value| {
+        (expected != actual).then(|| FieldDiff {
+            field,
+            expected: expected.clone(),
+            actual: actual.clone(),
+        })
+    };
+    match (&expected, &actual) {
+        (Value::Object(expected), Value::Object(actual)) => expected
+            .iter()
+            .filter_map(|(field, value)| {
+                field_diff(
+                    field.clone(),
+                    value,
+                    actual.get(field).unwrap_or(&Value::Null),
+                )
+            })
+            .collect(),
+        (Value::Array(expected), Value::Array(actual)) => expected
+            .iter()
+            .zip(actual)
+            .enumerate()
+            .filter_map(|(index, (expected, actual))| {
+                field_diff(index.to_string(), expected, actual)
+            })
+            .collect(),
+        // A variant with a single unnamed field
+        _ => field_diff("0".to_string(), &expected, &actual)
+            .into_iter()
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use aviation_helper_rs::clearance::aviation_command::HeadingDirection;
+    use aviation_helper_rs::types::heading::{Heading, TurnDirection};
+    use serde_json::Value;
+
+    use super::{FieldDiff, VARIANT_FIELD, diff_command};
+    use crate::AviationCommandPart;
+
+    fn fly_heading(degrees: f64) -> AviationCommandPart {
+        AviationCommandPart::FlyHeading {
+            heading: HeadingDirection::Heading(Heading::from(degrees)),
+            turn_direction: Some(TurnDirection::Left),
+        }
+    }
+
+    #[test]
+    fn test_diff_command_names_the_differing_field() {
+        let heading_value =
+            |degrees| serde_json::to_value(HeadingDirection::Heading(Heading::from(degrees)));
+        assert_eq!(
+            diff_command(&fly_heading(90.), &fly_heading(100.)),
+            vec![FieldDiff {
+                field: "heading".to_string(),
+                expected: heading_value(90.).unwrap(),
+                actual: heading_value(100.).unwrap(),
+            }]
+        );
+        assert!(diff_command(&fly_heading(90.), &fly_heading(90.)).is_empty());
+
+        assert_eq!(
+            diff_command(
+                &AviationCommandPart::ProceedDirect("BAGEL".to_string()),
+                &AviationCommandPart::ProceedDirect("BAGGY".to_string())
+            ),
+            vec![FieldDiff {
+                field: "0".to_string(),
+                expected: Value::from("BAGEL"),
+                actual: Value::from("BAGGY"),
+            }]
+        );
+        assert_eq!(
+            diff_command(&fly_heading(90.), &AviationCommandPart::RadarContact),
+            vec![FieldDiff {
+                field: VARIANT_FIELD.to_string(),
+                expected: Value::from("FlyHeading"),
+                actual: Value::from("RadarContact"),
+            }]
+        );
+    }
+}
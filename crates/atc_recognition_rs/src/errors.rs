@@ -18,4 +18,6 @@ pub enum Error {
     AviationHelper(#[from] aviation_helper_rs::errors::Error),
     #[error("Whisper error: {0}")]
     WhisperError(String),
+    #[error("Transcription error: {0}")]
+    Transcription(String),
 }
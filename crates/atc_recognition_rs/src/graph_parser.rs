@@ -6,14 +6,19 @@
 
 use aviation_helper_rs::{
     clearance::airlines::Airlines,
-    clearance::aviation_command::{AviationCommandPart, HeadingDirection},
+    clearance::aviation_command::{AviationCommandPart, Frequency, HeadingDirection},
     types::{
-        altitude::VerticalDirection,
+        altitude::{Altitude, VerticalDirection},
         heading::{Heading, TurnDirection},
     },
 };
+use regex::Regex;
 use serde::Deserialize;
 use std::collections::HashMap;
+use std::fmt;
+
+use crate::parse_trace::{MatcherMiss, MissReason, ParseTrace, TokenTrace};
+use crate::parser::DEFAULT_TRANSITION_ALTITUDE_FEET;
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
 pub enum ParseState {
@@ -37,6 +42,14 @@ pub enum ParseState {
     ParseComplete,
 }
 
+impl ParseState {
+    /// Airlines are only matched before the first command. Afterwards a word like "delta" is
+    /// part of the commands, not a new callsign.
+    pub fn allows_airline(&self) -> bool {
+        matches!(self, ParseState::Start | ParseState::ExpectingCallsign)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct ParseEdge {
     pub from: ParseState,
@@ -57,6 +70,31 @@ pub enum TokenMatcher {
     Optional(Box<TokenMatcher>), // optional token
 }
 
+impl TokenMatcher {
+    /// Whether the matcher matches airlines, also as an optional token
+    pub fn is_airline(&self) -> bool {
+        match self {
+            TokenMatcher::Airline => true,
+            TokenMatcher::Optional(inner) => inner.is_airline(),
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for TokenMatcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TokenMatcher::Exact(expected) => write!(f, "\"{expected}\""),
+            TokenMatcher::OneOf(options) => write!(f, "one of {}", options.join("/")),
+            TokenMatcher::Number(number_type) => write!(f, "{number_type:?} number"),
+            TokenMatcher::Airline => write!(f, "airline"),
+            TokenMatcher::Pattern(pattern) => write!(f, "pattern {pattern}"),
+            TokenMatcher::Fuzzy(expected, _) => write!(f, "similar to \"{expected}\""),
+            TokenMatcher::Optional(inner) => write!(f, "optional {inner}"),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum NumberType {
     Heading,      // 0-360
@@ -66,6 +104,7 @@ pub enum NumberType {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ParsedCommand {
     pub callsign: String,
     pub callsign_confidence: f32,
@@ -73,6 +112,7 @@ pub struct ParsedCommand {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CommandWithConfidence {
     pub command: AviationCommandPart,
     pub confidence: f32,
@@ -80,6 +120,7 @@ pub struct CommandWithConfidence {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ParseResult {
     Success(ParsedCommand),
     PartialSuccess {
@@ -106,8 +147,11 @@ struct ParsePath {
 struct ParseStep {
     state: ParseState,
     token: String,
+    /// Confidence of the edge multiplied with the confidence of the token match
     confidence: f32,
     matcher_used: TokenMatcher,
+    /// Whether the step advanced past the token
+    consumed: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -117,7 +161,7 @@ enum ParsedValue {
     Heading(f32),
     Altitude(u32),
     Station(String),
-    Frequency(u32, u32), // (num, dec)
+    Frequency(u32, u32), // (num, dec in kHz)
 }
 
 pub struct GraphParser {
@@ -131,8 +175,14 @@ pub struct GraphParser {
     recognition_corrections: HashMap<String, String>,
     fuzzy_threshold: f32,
     confidence_threshold: f32,
+    /// Shape of the most common transmission, a single heading command, e.g.
+    /// "delta 123 turn left heading 270". Its path is followed without exploring the graph.
+    fast_path_pattern: Regex,
 }
 
+/// Paths with a confidence at or below this are not explored further
+const MIN_PATH_CONFIDENCE: f32 = 0.1;
+
 #[derive(Clone, Debug, Deserialize)]
 pub struct ParserConfig {
     pub recognition_corrections: HashMap<String, String>,
@@ -140,6 +190,10 @@ pub struct ParserConfig {
     pub direction_words: HashMap<String, TurnDirection>,
     pub altitude_words: HashMap<String, VerticalDirection>,
     pub phonetic_alphabet: HashMap<String, String>,
+    /// Words, that Whisper writes instead of a digit. `AviationCommandParser` reads them as the
+    /// digit next to another digit.
+    #[serde(default)]
+    pub number_homophones: HashMap<String, u32>,
     pub fuzzy_threshold: f32,
     pub confidence_threshold: f32,
 }
@@ -154,9 +208,12 @@ impl ParserConfig {
         Ok(config)
     }
 
-    /// Load parser configuration from the default location
+    /// Load parser configuration from the default location in this crate
     pub fn load_default() -> Result<Self, Box<dyn std::error::Error>> {
-        Self::load_from_file("resources/parser/parser_config.ron")
+        Self::load_from_file(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/resources/parser/parser_config.ron"
+        ))
     }
 }
 
@@ -170,6 +227,7 @@ impl GraphParser {
             phonetic_alphabet,
             fuzzy_threshold,
             confidence_threshold,
+            ..
         } = config;
 
         let (airline_name_to_icao, callsign_to_icao) = load_airlines(airlines);
@@ -185,6 +243,10 @@ impl GraphParser {
             recognition_corrections,
             fuzzy_threshold,
             confidence_threshold,
+            fast_path_pattern: Regex::new(
+                r"^\S+ \S+ (?:, )?(fly|turn left|turn right) heading \S+$",
+            )
+            .unwrap(),
         };
 
         parser.build_graph();
@@ -201,7 +263,16 @@ impl GraphParser {
             true,
         );
 
-        // After callsign, expect command
+        // Flight number following the airline
+        self.add_edge(
+            ParseState::ExpectingCallsign,
+            ParseState::ExpectingCallsign,
+            TokenMatcher::Number(NumberType::FlightNumber),
+            0.9,
+            true,
+        );
+
+        // After callsign, expect command (the comma is consumed only if present)
         self.add_edge(
             ParseState::ExpectingCallsign,
             ParseState::ExpectingCommand,
@@ -290,7 +361,12 @@ impl GraphParser {
         self.add_edge(
             ParseState::ClimbCommand,
             ParseState::ExpectingAltitude,
-            TokenMatcher::OneOf(vec!["to".into(), "and".into(), "maintain".into()]),
+            TokenMatcher::OneOf(vec![
+                "to".into(),
+                "for".into(),
+                "and".into(),
+                "maintain".into(),
+            ]),
             0.7,
             true,
         );
@@ -298,7 +374,12 @@ impl GraphParser {
         self.add_edge(
             ParseState::DescendCommand,
             ParseState::ExpectingAltitude,
-            TokenMatcher::OneOf(vec!["to".into(), "and".into(), "maintain".into()]),
+            TokenMatcher::OneOf(vec![
+                "to".into(),
+                "for".into(),
+                "and".into(),
+                "maintain".into(),
+            ]),
             0.7,
             true,
         );
@@ -319,6 +400,25 @@ impl GraphParser {
             true,
         );
 
+        // A bare altitude, e.g. "descend 3000", is left for `ExpectingAltitude` to consume. Some
+        // regions leave out "flight" ("descend level 100").
+        for altitude_command in [ParseState::ClimbCommand, ParseState::DescendCommand] {
+            self.add_edge(
+                altitude_command.clone(),
+                ParseState::ExpectingAltitude,
+                TokenMatcher::Exact("level".into()),
+                0.8,
+                true,
+            );
+            self.add_edge(
+                altitude_command,
+                ParseState::ExpectingAltitude,
+                TokenMatcher::Number(NumberType::Altitude),
+                0.9,
+                false,
+            );
+        }
+
         self.add_edge(
             ParseState::ExpectingAltitude,
             ParseState::CommandComplete,
@@ -395,6 +495,15 @@ impl GraphParser {
         });
     }
 
+    /// Edges, that can be taken from the state. Airline edges are skipped in states, that don't
+    /// allow airlines, even if the graph has them.
+    fn edges_from(&self, state: &ParseState) -> impl Iterator<Item = &ParseEdge> {
+        let state = state.clone();
+        self.edges.iter().filter(move |edge| {
+            edge.from == state && (state.allows_airline() || !edge.matcher.is_airline())
+        })
+    }
+
     /// Parse a transmission using the graph-based parser
     pub fn parse(&self, text: &str) -> ParseResult {
         self.parse_transmission_enhanced(text)
@@ -405,55 +514,218 @@ impl GraphParser {
         let preprocessed = self.preprocess_whisper_text(text);
         let tokens = self.tokenize(&preprocessed);
 
+        self.parse_fast_path(&tokens)
+            .unwrap_or_else(|| self.parse_tokens(text, &tokens))
+    }
+
+    /// Like `parse_transmission_enhanced`, but also explains per token, which edges were tried
+    /// and why they failed. The whole graph is explored and the paths, that got stuck, are
+    /// kept, so it is meant for tuning phraseology, not for every transmission.
+    pub fn parse_transmission_traced(&self, text: &str) -> (ParseResult, ParseTrace) {
+        let preprocessed = self.preprocess_whisper_text(text);
+        let tokens = self.tokenize(&preprocessed);
+        let mut dead_ends = Vec::new();
+        let best_path = self.best_complete_path(self.explored_paths(&tokens, Some(&mut dead_ends)));
+
+        // The best complete path explains the parse, otherwise the path, that got the furthest,
+        // shows where it got stuck
+        let stuck_path = best_path.clone().or_else(|| {
+            dead_ends.into_iter().max_by(|a, b| {
+                a.tokens_consumed
+                    .cmp(&b.tokens_consumed)
+                    .then(a.total_confidence.total_cmp(&b.total_confidence))
+            })
+        });
+        let trace = match stuck_path {
+            Some(path) => self.trace_path(&path, &tokens),
+            None => ParseTrace::default(),
+        };
+        (self.result_of_best_path(best_path, text, &tokens), trace)
+    }
+
+    /// Trace of the tokens, that the path consumed, followed by the misses at the next token.
+    /// Tokens after it weren't reached, so they have no misses.
+    fn trace_path(&self, path: &ParsePath, tokens: &[String]) -> ParseTrace {
+        let mut trace = ParseTrace::default();
+        for step in path.steps.iter().filter(|step| step.consumed) {
+            let matcher = step.matcher_used.to_string();
+            trace.tokens.push(TokenTrace::matched(&step.token, matcher));
+        }
+        let mut misses = Some(self.misses_at(path, tokens));
+        for token in tokens.iter().skip(path.tokens_consumed) {
+            trace
+                .tokens
+                .push(TokenTrace::failed(token, misses.take().unwrap_or_default()));
+        }
+        trace
+    }
+
+    /// Why none of the edges from the end of the path took the next token
+    fn misses_at(&self, path: &ParsePath, tokens: &[String]) -> Vec<MatcherMiss> {
+        let index = path.tokens_consumed;
+        let Some(token) = tokens.get(index) else {
+            return Vec::new();
+        };
+        self.edges_from(&path.final_state)
+            .map(|edge| {
+                let reason = match self.test_matcher(&edge.matcher, token, index, tokens) {
+                    Some((match_confidence, _)) => MissReason::BelowThreshold {
+                        confidence: path.total_confidence * edge.confidence * match_confidence,
+                        threshold: MIN_PATH_CONFIDENCE,
+                    },
+                    None if matches!(edge.matcher, TokenMatcher::Airline) => {
+                        MissReason::NoAirlineMatch
+                    }
+                    None => MissReason::NoMatch,
+                };
+                MatcherMiss {
+                    matcher: edge.matcher.to_string(),
+                    reason,
+                }
+            })
+            .collect()
+    }
+
+    /// Follows the path of a single heading command directly. Returns `None`, if the tokens
+    /// don't have that shape, so that the whole graph is explored.
+    fn parse_fast_path(&self, tokens: &[String]) -> Option<ParseResult> {
+        let captures = self.fast_path_pattern.captures(&tokens.join(" "))?;
+        let mut states = vec![
+            ParseState::ExpectingCallsign,
+            ParseState::ExpectingCallsign,
+            ParseState::ExpectingCommand,
+            ParseState::TurnCommand,
+        ];
+        if captures[1].starts_with("turn") {
+            states.push(ParseState::ExpectingDirection);
+        }
+        states.extend([ParseState::ExpectingHeading, ParseState::CommandComplete]);
+
+        let mut path = ParsePath {
+            final_state: ParseState::Start,
+            total_confidence: 1.0,
+            steps: Vec::new(),
+            tokens_consumed: 0,
+            extracted_data: HashMap::new(),
+        };
+        for state in states {
+            self.follow_edge(&mut path, state, tokens)?;
+        }
+        (path.tokens_consumed == tokens.len()).then(|| self.path_to_result(path, tokens))
+    }
+
+    /// Takes the best matching edge from the end of the path to the state, the same way as the
+    /// exploration does. Fails, if no edge matches or the exploration would prune the path.
+    fn follow_edge(&self, path: &mut ParsePath, to: ParseState, tokens: &[String]) -> Option<()> {
+        let index = path.tokens_consumed;
+        let token = tokens.get(index)?;
+        let (edge, (match_confidence, extracted_value)) = self
+            .edges_from(&path.final_state)
+            .filter(|edge| edge.to == to)
+            .filter_map(|edge| {
+                Some((
+                    edge,
+                    self.test_matcher(&edge.matcher, token, index, tokens)?,
+                ))
+            })
+            .max_by(|(a, (a_match, _)), (b, (b_match, _))| {
+                (a.confidence * a_match).total_cmp(&(b.confidence * b_match))
+            })?;
+
+        let step_confidence = edge.confidence * match_confidence;
+        let confidence = path.total_confidence * step_confidence;
+        if confidence <= MIN_PATH_CONFIDENCE {
+            return None;
+        }
+        let consumed = self.edge_consumes_token(edge, token, index, tokens);
+        path.steps.push(ParseStep {
+            state: to.clone(),
+            token: token.clone(),
+            confidence: step_confidence,
+            matcher_used: edge.matcher.clone(),
+            consumed,
+        });
+        if let Some(value) = extracted_value {
+            let key = format!("{:?}_{}", to, path.steps.len());
+            path.extracted_data.insert(key, value);
+        }
+        if consumed {
+            path.tokens_consumed += 1;
+        }
+        path.total_confidence = confidence;
+        path.final_state = to;
+        Some(())
+    }
+
+    /// Explores all paths through the graph and picks the most confident complete one
+    fn parse_tokens(&self, text: &str, tokens: &[String]) -> ParseResult {
+        let best_path = self.best_complete_path(self.explored_paths(tokens, None));
+        self.result_of_best_path(best_path, text, tokens)
+    }
+
+    fn result_of_best_path(
+        &self,
+        best_path: Option<ParsePath>,
+        text: &str,
+        tokens: &[String],
+    ) -> ParseResult {
+        if let Some(best_path) = best_path {
+            self.path_to_result(best_path, tokens)
+        } else {
+            ParseResult::Failed {
+                reason: "No complete parse path found".into(),
+                raw_text: text.to_string(),
+            }
+        }
+    }
+
+    /// All paths, that reached the end of the tokens or `ParseComplete`. Paths, that got stuck
+    /// before, are collected in `dead_ends`, if it is given.
+    fn explored_paths(
+        &self,
+        tokens: &[String],
+        dead_ends: Option<&mut Vec<ParsePath>>,
+    ) -> Vec<ParsePath> {
         let mut best_paths = Vec::new();
 
         // Start exploration from initial state
         self.explore_paths(
             ParseState::Start,
             0,
-            &tokens,
+            tokens,
             1.0,
             Vec::new(),
             HashMap::new(),
             &mut best_paths,
+            dead_ends,
         );
+        best_paths
+    }
 
-        // Find best complete path
-        if let Some(best_path) = best_paths
+    fn best_complete_path(&self, paths: Vec<ParsePath>) -> Option<ParsePath> {
+        paths
             .into_iter()
             .filter(|path| {
                 path.final_state == ParseState::ParseComplete
                     || path.final_state == ParseState::CommandComplete
             })
             .max_by(|a, b| a.total_confidence.partial_cmp(&b.total_confidence).unwrap())
-        {
-            self.path_to_result(best_path, &tokens)
-        } else {
-            ParseResult::Failed {
-                reason: "No complete parse path found".into(),
-                raw_text: text.to_string(),
-            }
-        }
     }
 
     fn preprocess_whisper_text(&self, text: &str) -> String {
-        let result = text.to_lowercase();
-
-        // Apply recognition corrections from config
-        self.recognition_corrections
-            .iter()
-            .fold(result, |acc, (incorrect, correct)| {
-                acc.replace(incorrect, correct)
-            })
+        preprocess_whisper_text(text, &self.recognition_corrections)
     }
 
     fn tokenize(&self, text: &str) -> Vec<String> {
-        text.split_whitespace()
+        // Commas are separate tokens, so that edges can match them
+        text.replace(',', " , ")
+            .split_whitespace()
             .map(|s| s.to_string())
             .filter(|s| !s.is_empty())
             .collect()
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn explore_paths(
         &self,
         current_state: ParseState,
@@ -463,6 +735,7 @@ impl GraphParser {
         current_path: Vec<ParseStep>,
         current_data: HashMap<String, ParsedValue>,
         best_paths: &mut Vec<ParsePath>,
+        mut dead_ends: Option<&mut Vec<ParsePath>>,
     ) {
         // Terminal condition
         if token_index >= tokens.len() || current_state == ParseState::ParseComplete {
@@ -482,27 +755,29 @@ impl GraphParser {
         }
 
         let current_token = &tokens[token_index];
+        let mut advanced = false;
 
         // Find all possible transitions from current state
-        for edge in &self.edges {
-            if edge.from != current_state {
-                continue;
-            }
-
+        for edge in self.edges_from(&current_state) {
             // Test if this edge matches the current token
             if let Some((match_confidence, extracted_value)) =
                 self.test_matcher(&edge.matcher, current_token, token_index, tokens)
             {
-                let new_confidence = current_confidence * edge.confidence * match_confidence;
+                let step_confidence = edge.confidence * match_confidence;
+                let new_confidence = current_confidence * step_confidence;
 
                 // Only pursue promising paths (confidence threshold)
-                if new_confidence > 0.1 {
+                if new_confidence > MIN_PATH_CONFIDENCE {
+                    advanced = true;
+                    let consumed =
+                        self.edge_consumes_token(edge, current_token, token_index, tokens);
                     let mut new_path = current_path.clone();
                     new_path.push(ParseStep {
                         state: edge.to.clone(),
                         token: current_token.clone(),
-                        confidence: match_confidence,
+                        confidence: step_confidence,
                         matcher_used: edge.matcher.clone(),
+                        consumed,
                     });
 
                     let mut new_data = current_data.clone();
@@ -511,7 +786,7 @@ impl GraphParser {
                         new_data.insert(key, value);
                     }
 
-                    let next_token_index = if edge.consumes_token {
+                    let next_token_index = if consumed {
                         token_index + 1
                     } else {
                         token_index
@@ -526,18 +801,51 @@ impl GraphParser {
                         new_path,
                         new_data,
                         best_paths,
+                        dead_ends.as_deref_mut(),
                     );
                 }
             }
         }
+
+        if !advanced {
+            if let Some(dead_ends) = dead_ends {
+                dead_ends.push(ParsePath {
+                    final_state: current_state,
+                    total_confidence: current_confidence,
+                    steps: current_path,
+                    tokens_consumed: token_index,
+                    extracted_data: current_data,
+                });
+            }
+        }
+    }
+
+    /// Whether taking the edge advances past the current token.
+    /// Optional matchers only advance if their inner matcher matched the token.
+    fn edge_consumes_token(
+        &self,
+        edge: &ParseEdge,
+        token: &str,
+        index: usize,
+        tokens: &[String],
+    ) -> bool {
+        if !edge.consumes_token {
+            return false;
+        }
+        match &edge.matcher {
+            TokenMatcher::Optional(inner) => {
+                self.test_matcher(inner, token, index, tokens).is_some()
+            }
+            _ => true,
+        }
     }
 
     fn test_matcher(
         &self,
         matcher: &TokenMatcher,
         token: &str,
-        _index: usize,
-        _tokens: &[String],
+        index: usize,
+        tokens: &[String],
     ) -> Option<(f32, Option<ParsedValue>)> {
         match matcher {
             TokenMatcher::Exact(expected) => {
@@ -592,9 +900,12 @@ impl GraphParser {
                     None
                 }
             }
-            TokenMatcher::Optional(_) => {
-                // Optional tokens always match but don't advance
-                Some((0.9, None))
+            TokenMatcher::Optional(inner) => {
+                // Optional tokens always match, using the inner match if there is one
+                Some(
+                    self.test_matcher(inner, token, index, tokens)
+                        .unwrap_or((0.9, None)),
+                )
             }
         }
     }
@@ -635,20 +946,9 @@ impl GraphParser {
                     } else {
                         None
                     }
-                } else if token.contains('.') {
-                    let parts: Vec<&str> = token.split('.').collect();
-                    if parts.len() == 2 {
-                        if let (Ok(num), Ok(dec)) =
-                            (parts[0].parse::<u32>(), parts[1].parse::<u32>())
-                        {
-                            if (118..=137).contains(&num) && dec <= 999 {
-                                Some((0.9, Some(ParsedValue::Frequency(num, dec))))
-                            } else {
-                                None
-                            }
-                        } else {
-                            None
-                        }
+                } else if let Ok(Frequency { num, dec }) = token.parse::<Frequency>() {
+                    if (118..=137).contains(&num) {
+                        Some((0.9, Some(ParsedValue::Frequency(num, dec))))
                     } else {
                         None
                     }
@@ -657,9 +957,10 @@ impl GraphParser {
                 }
             }
             NumberType::FlightNumber => {
-                if token
-                    .chars()
-                    .all(|c| c.is_ascii_digit() || c.is_ascii_alphabetic())
+                if token.chars().any(|c| c.is_ascii_digit())
+                    && token
+                        .chars()
+                        .all(|c| c.is_ascii_digit() || c.is_ascii_alphabetic())
                 {
                     Some((0.8, None))
                 } else {
@@ -727,41 +1028,54 @@ impl GraphParser {
             })
             .unwrap_or_else(|| "UNKNOWN".to_string());
 
-        // Extract commands from path
-        let mut commands = Vec::new();
-
-        // Simple command extraction based on final state and collected data
-        // This is a simplified version - in a full implementation, you'd track
-        // command building through the state transitions
-
-        if path
+        // The callsign is built by the airline and flight number steps
+        let callsign_confidence: f32 = path
             .steps
             .iter()
-            .any(|step| step.state == ParseState::CommandComplete)
-        {
-            // Build commands from extracted data
-            if let Some(ParsedValue::Heading(heading)) =
-                path.extracted_data.values().find_map(|v| match v {
-                    ParsedValue::Heading(h) => Some(ParsedValue::Heading(*h)),
-                    _ => None,
-                })
-            {
-                commands.push(CommandWithConfidence {
-                    command: AviationCommandPart::FlyHeading {
-                        heading: HeadingDirection::Heading(Heading::from(heading as f64)),
-                        turn_direction: None,
-                    },
-                    confidence: 0.8,
-                    source_text: tokens[..path.tokens_consumed].join(" "),
-                });
-            }
+            .filter(|step| step.state == ParseState::ExpectingCallsign)
+            .map(|step| step.confidence)
+            .product();
 
-            // Add more command extraction logic here...
+        // Each command is built by the steps from entering `ExpectingCommand` up to
+        // `CommandComplete`
+        let mut commands = Vec::new();
+        let mut command_confidence = 1.0;
+        let mut command_start = 0;
+        for (index, step) in path.steps.iter().enumerate() {
+            if step.state == ParseState::ExpectingCommand {
+                command_confidence = 1.0;
+                command_start = index + 1;
+                continue;
+            }
+            command_confidence *= step.confidence;
+            if step.state != ParseState::CommandComplete {
+                continue;
+            }
+            // Keys of extracted data are the target state and the path length at that step
+            let key = format!("{:?}_{}", step.state, index + 1);
+            let command = match path.extracted_data.get(&key) {
+                Some(ParsedValue::Heading(heading)) => AviationCommandPart::FlyHeading {
+                    heading: HeadingDirection::Heading(Heading::from(*heading as f64)),
+                    turn_direction: None,
+                },
+                Some(ParsedValue::Altitude(value)) => {
+                    match altitude_command(*value, &path.steps[command_start..=index]) {
+                        Some(command) => command,
+                        None => continue,
+                    }
+                }
+                _ => continue,
+            };
+            commands.push(CommandWithConfidence {
+                command,
+                confidence: command_confidence,
+                source_text: tokens[..path.tokens_consumed].join(" "),
+            });
         }
 
         let parsed = ParsedCommand {
             callsign,
-            callsign_confidence: 0.8, // Calculate based on path confidence
+            callsign_confidence,
             commands,
         };
 
@@ -776,6 +1090,52 @@ impl GraphParser {
     }
 }
 
+/// Climb or descend command from the steps of one command. After "flight level" or just
+/// "level" it is a flight level, without unit it is resolved like "maintain 100" by the regex
+/// parser.
+fn altitude_command(value: u32, steps: &[ParseStep]) -> Option<AviationCommandPart> {
+    let turn_direction = steps.iter().find_map(|step| match step.state {
+        ParseState::ClimbCommand => Some(VerticalDirection::Climb),
+        ParseState::DescendCommand => Some(VerticalDirection::Descend),
+        _ => None,
+    });
+    let mentions_level = steps.iter().any(|step| {
+        step.state == ParseState::ExpectingAltitude
+            && matches!(
+                &step.matcher_used,
+                TokenMatcher::Exact(word) | TokenMatcher::Fuzzy(word, _)
+                    if word == "level" || word == "flight"
+            )
+    });
+    let altitude = if mentions_level {
+        Altitude::FlightLevel(value)
+    } else {
+        Altitude::from_unitless(value, DEFAULT_TRANSITION_ALTITUDE_FEET, None)?
+    };
+    let maintain = steps
+        .iter()
+        .any(|step| step.consumed && step.token == "maintain");
+    Some(AviationCommandPart::ChangeAltitude {
+        altitude,
+        maintain,
+        discretion: false,
+        turn_direction,
+    })
+}
+
+/// Lowercases the text and applies the recognition corrections of the parser config, e.g.
+/// "hedding" to "heading". The voice recognizer applies them to the transcribed text, too.
+pub fn preprocess_whisper_text(
+    text: &str,
+    recognition_corrections: &HashMap<String, String>,
+) -> String {
+    recognition_corrections
+        .iter()
+        .fold(text.to_lowercase(), |acc, (incorrect, correct)| {
+            acc.replace(incorrect, correct)
+        })
+}
+
 // Standalone function to load airlines data
 fn load_airlines(airlines: &Airlines) -> (HashMap<String, String>, HashMap<String, String>) {
     let mut callsign_to_icao = HashMap::new();
@@ -831,12 +1191,12 @@ mod tests {
         number_words.insert("three".to_string(), 3);
 
         let mut direction_words = HashMap::new();
-        direction_words.insert("left".to_string(), "left".to_string());
-        direction_words.insert("right".to_string(), "right".to_string());
+        direction_words.insert("left".to_string(), TurnDirection::Left);
+        direction_words.insert("right".to_string(), TurnDirection::Right);
 
         let mut altitude_words = HashMap::new();
-        altitude_words.insert("climb".to_string(), "climb".to_string());
-        altitude_words.insert("descend".to_string(), "descend".to_string());
+        altitude_words.insert("climb".to_string(), VerticalDirection::Climb);
+        altitude_words.insert("descend".to_string(), VerticalDirection::Descend);
 
         let mut phonetic_alphabet = HashMap::new();
         phonetic_alphabet.insert("delta".to_string(), "D".to_string());
@@ -847,6 +1207,7 @@ mod tests {
             direction_words,
             altitude_words,
             phonetic_alphabet,
+            number_homophones: HashMap::new(),
             fuzzy_threshold: 0.8,
             confidence_threshold: 0.1,
         }
@@ -896,6 +1257,36 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parser_config_from_ron() {
+        let config: ParserConfig = ron::from_str(
+            r#"(
+                recognition_corrections: { "flyheading": "fly heading" },
+                number_words: { "one": 1, "two": 2, "three": 3 },
+                direction_words: { "left": Left, "right": Right },
+                altitude_words: { "climb": Climb, "descend": Descend, "descent": Descend },
+                phonetic_alphabet: { "delta": "D" },
+                fuzzy_threshold: 0.8,
+                confidence_threshold: 0.1,
+            )"#,
+        )
+        .expect("Config should deserialize");
+        assert_eq!(config.direction_words["left"], TurnDirection::Left);
+        assert_eq!(config.altitude_words["descent"], VerticalDirection::Descend);
+
+        let parser = GraphParser::new(config, &create_test_airlines());
+        let result = parser.parse_transmission_enhanced("delta 123 turn left heading 270");
+        let ParseResult::Success(parsed) = result else {
+            panic!("Expected success, got {result:?}");
+        };
+        assert_eq!(parsed.callsign, "DAL123");
+
+        // The shipped config uses the same format
+        let config = ParserConfig::load_default().expect("Default config should load");
+        assert_eq!(config.direction_words["right"], TurnDirection::Right);
+        GraphParser::new(config, &create_test_airlines());
+    }
+
     #[test]
     fn test_whisper_preprocessing() {
         let config = create_test_config();
@@ -922,4 +1313,233 @@ mod tests {
             _ => panic!("Fuzzy matching should work for 'hedding' -> 'heading'"),
         }
     }
+
+    #[test]
+    fn test_optional_comma_after_callsign() {
+        let config = create_test_config();
+        let airlines = create_test_airlines();
+        let parser = GraphParser::new(config, &airlines);
+
+        for text in [
+            "delta 123, turn left heading 270",
+            "delta 123 turn left heading 270",
+        ] {
+            match parser.parse_transmission_enhanced(text) {
+                ParseResult::Success(parsed) => {
+                    assert_eq!(parsed.commands.len(), 1, "Wrong commands for '{text}'");
+                    assert_eq!(
+                        parsed.commands[0].command,
+                        AviationCommandPart::FlyHeading {
+                            heading: HeadingDirection::Heading(Heading::new(270.0)),
+                            turn_direction: None,
+                        }
+                    );
+                }
+                other => panic!("Expected success for '{text}', got: {other:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_altitude_connectors() {
+        let config = create_test_config();
+        let airlines = create_test_airlines();
+        let parser = GraphParser::new(config, &airlines);
+
+        for text in ["delta 123 climb to 5000", "delta 123 climb for 5000"] {
+            let result = parser.parse_transmission_enhanced(text);
+            assert!(
+                matches!(result, ParseResult::Success(_)),
+                "Expected success for '{text}', got: {result:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_descend_without_connector() {
+        let config = create_test_config();
+        let airlines = create_test_airlines();
+        let parser = GraphParser::new(config, &airlines);
+
+        for (text, turn_direction, feet) in [
+            ("delta 123 descend 3000", VerticalDirection::Descend, 3000.),
+            ("delta 123 climb 5000", VerticalDirection::Climb, 5000.),
+        ] {
+            let result = parser.parse_transmission_enhanced(text);
+            let ParseResult::Success(parsed) = &result else {
+                panic!("Expected success for '{text}', got: {result:?}");
+            };
+            let commands: Vec<_> = parsed.commands.iter().map(|c| &c.command).collect();
+            assert_eq!(
+                commands,
+                vec![&AviationCommandPart::ChangeAltitude {
+                    altitude: Altitude::Feet(feet),
+                    maintain: false,
+                    discretion: false,
+                    turn_direction: Some(turn_direction),
+                }],
+                "Commands of '{text}'"
+            );
+        }
+    }
+
+    #[test]
+    fn test_level_without_flight() {
+        let config = create_test_config();
+        let airlines = create_test_airlines();
+        let parser = GraphParser::new(config, &airlines);
+
+        for (text, turn_direction, flight_level) in [
+            (
+                "delta 123 descend level 100",
+                VerticalDirection::Descend,
+                100,
+            ),
+            ("delta 123 climb level 240", VerticalDirection::Climb, 240),
+        ] {
+            let result = parser.parse_transmission_enhanced(text);
+            let ParseResult::Success(parsed) = &result else {
+                panic!("Expected success for '{text}', got: {result:?}");
+            };
+            let commands: Vec<_> = parsed.commands.iter().map(|c| &c.command).collect();
+            assert_eq!(
+                commands,
+                vec![&AviationCommandPart::ChangeAltitude {
+                    altitude: Altitude::FlightLevel(flight_level),
+                    maintain: false,
+                    discretion: false,
+                    turn_direction: Some(turn_direction),
+                }],
+                "Commands of '{text}'"
+            );
+        }
+    }
+
+    #[test]
+    fn test_confidence_of_clean_and_fuzzy_input() {
+        let config = create_test_config();
+        let airlines = create_test_airlines();
+        let parser = GraphParser::new(config, &airlines);
+
+        let parse = |text| match parser.parse_transmission_enhanced(text) {
+            ParseResult::Success(parsed) => parsed,
+            other => panic!("Expected success for '{text}', got: {other:?}"),
+        };
+        let clean = parse("delta 123 fly heading 090");
+        let fuzzy = parse("delta 123 fly hedding 090");
+
+        assert_eq!(clean.callsign_confidence, fuzzy.callsign_confidence);
+        assert!(clean.callsign_confidence > 0.0 && clean.callsign_confidence < 1.0);
+        assert_eq!(clean.commands.len(), 1);
+        assert_eq!(fuzzy.commands.len(), 1);
+        assert!(clean.commands[0].confidence > fuzzy.commands[0].confidence);
+    }
+
+    #[test]
+    fn test_parse_trace_finds_failing_token() {
+        let config = create_test_config();
+        let airlines = create_test_airlines();
+        let parser = GraphParser::new(config, &airlines);
+
+        // There is no heading 400
+        let (result, trace) = parser.parse_transmission_traced("delta 123 fly heading 400");
+        assert!(
+            matches!(result, ParseResult::Failed { .. }),
+            "Expected failure, got: {result:?}"
+        );
+        let matched: Vec<_> = trace
+            .tokens
+            .iter()
+            .take_while(|token| token.matched.is_some())
+            .map(|token| token.token.as_str())
+            .collect();
+        assert_eq!(matched, ["delta", "123", "fly", "heading"]);
+
+        let failing = trace
+            .first_failing_token()
+            .unwrap_or_else(|| panic!("Expected a failing token, got: {trace:?}"));
+        assert_eq!(failing.token, "400");
+        assert_eq!(
+            failing.misses,
+            vec![MatcherMiss {
+                matcher: "Heading number".to_string(),
+                reason: MissReason::NoMatch,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_airline_is_not_matched_after_a_command() {
+        let config = create_test_config();
+        let airlines = create_test_airlines();
+        let mut parser = GraphParser::new(config, &airlines);
+        // An edge, that would read "delta" after a command as a new callsign
+        parser.add_edge(
+            ParseState::CommandComplete,
+            ParseState::ExpectingCallsign,
+            TokenMatcher::Airline,
+            1.0,
+            true,
+        );
+
+        let text = "lufthansa 123 turn left heading 270 delta";
+        let tokens = parser.tokenize(&parser.preprocess_whisper_text(text));
+        for path in parser.explored_paths(&tokens, None) {
+            let first_command = path
+                .steps
+                .iter()
+                .position(|step| step.state == ParseState::ExpectingCommand)
+                .unwrap_or(path.steps.len());
+            assert!(
+                path.steps[first_command..]
+                    .iter()
+                    .all(|step| !step.matcher_used.is_airline()),
+                "Airline matched after a command: {path:?}"
+            );
+        }
+
+        match parser.parse_transmission_enhanced(text) {
+            ParseResult::Success(parsed) | ParseResult::PartialSuccess { parsed, .. } => {
+                assert_eq!(parsed.callsign, "DLH");
+            }
+            other => panic!("Expected a parse for '{text}', got: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_fast_path_matches_exploration() {
+        let config = create_test_config();
+        let airlines = create_test_airlines();
+        let parser = GraphParser::new(config, &airlines);
+
+        for text in [
+            "delta 123 turn left heading 270",
+            "delta 123, turn right heading 090",
+            "lufthansa 456 fly heading 180",
+            "Delta 42, fly heading 360",
+        ] {
+            let tokens = parser.tokenize(&parser.preprocess_whisper_text(text));
+            let fast = parser
+                .parse_fast_path(&tokens)
+                .unwrap_or_else(|| panic!("Expected the fast path for '{text}'"));
+            let full = parser.parse_tokens(text, &tokens);
+            assert_eq!(
+                format!("{fast:?}"),
+                format!("{full:?}"),
+                "Mismatch for '{text}'"
+            );
+        }
+
+        for text in [
+            "delta 123 climb to 5000",
+            "delta 123 turn left heading 270 and turn right heading 300",
+            "delta 123 fly heading 400",
+        ] {
+            let tokens = parser.tokenize(&parser.preprocess_whisper_text(text));
+            assert!(
+                parser.parse_fast_path(&tokens).is_none(),
+                "Unexpected fast path for '{text}'"
+            );
+        }
+    }
 }
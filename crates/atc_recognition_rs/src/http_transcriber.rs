@@ -0,0 +1,178 @@
+//! Transcription on a remote server, e.g. for clients, that are too slow to run Whisper
+//!
+//! The samples are posted as raw 16-bit little-endian mono PCM at 16kHz. The server answers with
+//! JSON like `{"text": "...", "avg_prob": 0.9, "words": [{"word": "...", "prob": 0.9}]}`.
+
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use crate::{Error, Transcribe, TranscribedWord, TranscriptionResult};
+
+/// Time to wait for the server, before the transcription fails
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+pub struct HttpTranscriber {
+    endpoint: String,
+    agent: ureq::Agent,
+}
+
+#[derive(Debug, Deserialize)]
+struct TranscriptionResponse {
+    text: String,
+    avg_prob: Option<f32>,
+    #[serde(default)]
+    words: Vec<WordResponse>,
+}
+
+#[derive(Debug, Deserialize)]
+struct WordResponse {
+    word: String,
+    prob: f32,
+}
+
+impl HttpTranscriber {
+    /// Transcriber, that posts the samples to the URL
+    pub fn new(endpoint: impl Into<String>) -> Self {
+        Self {
+            endpoint: endpoint.into(),
+            agent: Self::agent(DEFAULT_TIMEOUT),
+        }
+    }
+
+    /// Set the time to wait for the server
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.agent = Self::agent(timeout);
+        self
+    }
+
+    fn agent(timeout: Duration) -> ureq::Agent {
+        ureq::AgentBuilder::new().timeout(timeout).build()
+    }
+}
+
+/// Converts samples in [-1, 1] to 16-bit little-endian PCM
+fn to_pcm_bytes(samples: &[f32]) -> Vec<u8> {
+    samples
+        .iter()
+        .flat_map(|sample| ((sample.clamp(-1., 1.) * i16::MAX as f32) as i16).to_le_bytes())
+        .collect()
+}
+
+impl Transcribe for HttpTranscriber {
+    fn transcribe(&mut self, samples: &[f32]) -> Result<TranscriptionResult, Error> {
+        let response: TranscriptionResponse = self
+            .agent
+            .post(&self.endpoint)
+            .set("Content-Type", "application/octet-stream")
+            .send_bytes(&to_pcm_bytes(samples))
+            .map_err(|e| Error::Transcription(format!("Request failed: {}", e)))?
+            .into_json()
+            .map_err(|e| Error::Transcription(format!("Invalid response: {}", e)))?;
+        Ok(TranscriptionResult {
+            text: response.text.trim().to_string(),
+            avg_probability: response.avg_prob,
+            words: response
+                .words
+                .into_iter()
+                .map(|WordResponse { word, prob }| TranscribedWord {
+                    text: word,
+                    probability: prob,
+                })
+                .collect(),
+            ..Default::default()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    use super::{HttpTranscriber, to_pcm_bytes};
+    use crate::{Error, Transcribe, TranscribedWord};
+
+    /// Answers a single request with the JSON and returns the received body
+    fn serve_once(listener: TcpListener, json: &'static str) -> thread::JoinHandle<Vec<u8>> {
+        thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut content_length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                let line = line.trim_end();
+                if line.is_empty() {
+                    break;
+                }
+                if let Some((_, value)) = line
+                    .split_once(':')
+                    .filter(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+                {
+                    content_length = value.trim().parse().unwrap();
+                }
+            }
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body).unwrap();
+
+            let mut stream = reader.into_inner();
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                json.len(),
+                json
+            )
+            .unwrap();
+            body
+        })
+    }
+
+    #[test]
+    fn test_transcribe_with_mock_server() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = serve_once(
+            listener,
+            r#"{"text": " Lufthansa 123, standby", "avg_prob": 0.92, "words": [{"word": "Lufthansa", "prob": 0.9}]}"#,
+        );
+
+        let samples = [0.0, 0.5, -0.5, 1.0];
+        let mut transcriber = HttpTranscriber::new(format!("http://{}/transcribe", address));
+        let result = transcriber.transcribe(&samples).unwrap();
+
+        assert_eq!(server.join().unwrap(), to_pcm_bytes(&samples));
+        assert_eq!(result.text, "Lufthansa 123, standby");
+        assert_eq!(result.avg_probability, Some(0.92));
+        assert_eq!(
+            result.words,
+            vec![TranscribedWord {
+                text: "Lufthansa".to_string(),
+                probability: 0.9,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_unreachable_server() {
+        // Nothing listens on the port anymore
+        let address = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let mut transcriber = HttpTranscriber::new(format!("http://{}/transcribe", address));
+        assert!(matches!(
+            transcriber.transcribe(&[0.0; 4]),
+            Err(Error::Transcription(_))
+        ));
+    }
+
+    #[test]
+    fn test_pcm_bytes() {
+        assert_eq!(
+            to_pcm_bytes(&[0.0, 1.0, -1.0, 2.0]),
+            [0, 0, 0xff, 0x7f, 0x01, 0x80, 0xff, 0x7f]
+        );
+    }
+}
@@ -7,22 +7,36 @@ use std::path::Path;
 
 use rubato::{SincFixedIn, SincInterpolationParameters, SincInterpolationType, WindowFunction};
 
+pub mod audio_buffer;
+pub mod command_diff;
 pub mod errors;
 pub mod parser;
 pub mod graph_parser;
-// pub mod recognition;
+pub mod parse_trace;
+#[cfg(feature = "http-transcriber")]
+pub mod http_transcriber;
+pub mod recognition;
 pub mod speech_to_text;
 #[cfg(any(test, feature = "test-utils"))]
 pub mod test_utils;
 
+pub use audio_buffer::{OverflowPolicy, RecognitionConfig, SentenceBoundary, SentenceCut};
+pub use command_diff::{FieldDiff, diff_command};
 pub use errors::Error;
 pub use parser::{
-    AviationCommandParser, CallsignMatch, CommandWithConfidence, ParseResult, ParsedCommand,
+    AviationCommandParser, CallsignMatch, CommandSpec, CommandWithConfidence, ParseResult,
+    ParsedCommand,
 };
 pub use graph_parser::{
     GraphParser, GraphParseResult, GraphParsedCommand, GraphCommandWithConfidence,
 };
-pub use speech_to_text::SpeechToText;
+pub use parse_trace::{MatcherMiss, MissReason, ParseTrace, TokenTrace};
+#[cfg(feature = "http-transcriber")]
+pub use http_transcriber::HttpTranscriber;
+pub use recognition::{RecognitionEvent, RecognitionHandle, TextPreprocessor, VoiceRecognizer};
+pub use speech_to_text::{
+    SpeechToText, Transcribe, TranscribedSegment, TranscribedWord, TranscriptionResult,
+};
 
 // Re-export specific aviation command types for convenience
 pub use aviation_helper_rs::clearance::aviation_command::AviationCommandPart;
@@ -42,6 +56,33 @@ pub fn create_resampler(sample_rate_in: u32) -> SincFixedIn<f32> {
     SincFixedIn::<f32>::new(resample_ratio, 2.0, params, 1024, 1).unwrap()
 }
 
+/// Converts raw input samples in the native byte order to `f32` samples in [-1, 1], which the
+/// resampler and Whisper expect. Formats, that microphones don't use, yield no samples.
+pub fn to_f32_samples(data: &[u8], format: cpal::SampleFormat) -> Vec<f32> {
+    fn convert<const N: usize>(data: &[u8], to_f32: impl Fn([u8; N]) -> f32) -> Vec<f32> {
+        data.chunks_exact(N)
+            .map(|bytes| to_f32(bytes.try_into().unwrap()))
+            .collect()
+    }
+
+    use cpal::SampleFormat;
+    match format {
+        SampleFormat::I8 => convert(data, |b| i8::from_ne_bytes(b) as f32 / 128.),
+        SampleFormat::I16 => convert(data, |b| i16::from_ne_bytes(b) as f32 / 32768.),
+        SampleFormat::I32 => convert(data, |b| {
+            (i32::from_ne_bytes(b) as f64 / 2147483648.) as f32
+        }),
+        SampleFormat::U8 => convert(data, |b| (u8::from_ne_bytes(b) as f32 - 128.) / 128.),
+        SampleFormat::U16 => convert(data, |b| (u16::from_ne_bytes(b) as f32 - 32768.) / 32768.),
+        SampleFormat::U32 => convert(data, |b| {
+            ((u32::from_ne_bytes(b) as f64 - 2147483648.) / 2147483648.) as f32
+        }),
+        SampleFormat::F32 => convert(data, f32::from_ne_bytes),
+        SampleFormat::F64 => convert(data, |b| f64::from_ne_bytes(b) as f32),
+        _ => Vec::new(),
+    }
+}
+
 /// Configuration for voice recognition
 #[derive(Debug, Clone)]
 pub struct SpeechToTextConfig {
@@ -58,3 +99,40 @@ impl Default for SpeechToTextConfig {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use cpal::SampleFormat;
+
+    use super::to_f32_samples;
+
+    #[test]
+    fn test_i16_to_f32_samples() {
+        let data: Vec<u8> = [i16::MIN, -16384, 0, 16384, i16::MAX]
+            .iter()
+            .flat_map(|sample| sample.to_ne_bytes())
+            .collect();
+        assert_eq!(
+            to_f32_samples(&data, SampleFormat::I16),
+            [-1.0, -0.5, 0.0, 0.5, 32767. / 32768.]
+        );
+    }
+
+    #[test]
+    fn test_u8_to_f32_samples() {
+        let data = [0u8, 64, 128, 192, 255];
+        assert_eq!(
+            to_f32_samples(&data, SampleFormat::U8),
+            [-1.0, -0.5, 0.0, 0.5, 127. / 128.]
+        );
+    }
+
+    #[test]
+    fn test_f32_samples_are_kept() {
+        let data: Vec<u8> = [-0.25f32, 0.75]
+            .iter()
+            .flat_map(|sample| sample.to_ne_bytes())
+            .collect();
+        assert_eq!(to_f32_samples(&data, SampleFormat::F32), [-0.25, 0.75]);
+    }
+}
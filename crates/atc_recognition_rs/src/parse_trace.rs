@@ -0,0 +1,66 @@
+//! Explanation of a parse, for tuning phraseology
+//!
+//! Both parsers can record, per token, which matchers were tried and why they didn't accept
+//! it. Recording is opt-in (`parse_transmission_traced`), so that normal parsing doesn't pay
+//! for it.
+
+/// Why a matcher didn't accept a token
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MissReason {
+    /// The pattern doesn't fit the token
+    NoMatch,
+    /// The pattern fits, but not confidently enough
+    BelowThreshold { confidence: f32, threshold: f32 },
+    /// The word isn't an airline, that the parser knows
+    NoAirlineMatch,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MatcherMiss {
+    pub matcher: String,
+    pub reason: MissReason,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TokenTrace {
+    pub token: String,
+    /// Matcher, that accepted the token. None, if none did.
+    pub matched: Option<String>,
+    /// Matchers, that were tried before or instead of the one, that matched
+    pub misses: Vec<MatcherMiss>,
+}
+
+impl TokenTrace {
+    pub fn matched(token: &str, matcher: impl Into<String>) -> Self {
+        Self {
+            token: token.to_string(),
+            matched: Some(matcher.into()),
+            misses: Vec::new(),
+        }
+    }
+
+    pub fn failed(token: &str, misses: Vec<MatcherMiss>) -> Self {
+        Self {
+            token: token.to_string(),
+            matched: None,
+            misses,
+        }
+    }
+}
+
+/// What the parser did with each token of a transmission
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ParseTrace {
+    pub tokens: Vec<TokenTrace>,
+}
+
+impl ParseTrace {
+    /// First token, that no matcher accepted
+    pub fn first_failing_token(&self) -> Option<&TokenTrace> {
+        self.tokens.iter().find(|token| token.matched.is_none())
+    }
+}
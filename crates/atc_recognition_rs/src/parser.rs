@@ -6,14 +6,22 @@
 
 use aviation_helper_rs::{
     clearance::airlines::Airlines,
-    clearance::aviation_command::{AviationCommandPart, Frequency, HeadingDirection},
+    clearance::aviation_command::{
+        AviationCommandGroup, AviationCommandPart, CommunicationEntity, Frequency,
+        HeadingDirection, QueryField,
+    },
+    clearance::frequencies::FrequencyTable,
     types::{
-        altitude::{Altitude, VerticalDirection},
+        altitude::{Altitude, AltitudeConstraint, VerticalDirection},
         heading::{Degrees, Heading, TurnDirection},
     },
 };
-use regex::Regex;
+use regex::{Captures, Regex};
 use std::collections::{HashMap, HashSet};
+use std::sync::LazyLock;
+
+use crate::graph_parser::ParserConfig;
+use crate::parse_trace::{MatcherMiss, MissReason, ParseTrace, TokenTrace};
 
 #[derive(Debug, Clone)]
 pub struct CallsignMatch {
@@ -22,20 +30,202 @@ pub struct CallsignMatch {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ParsedCommand {
+    /// Machine form of the callsign, e.g. "DLH123"
     pub callsign: String,
+    /// Display form of the callsign, with the telephony name of the airline if it is known,
+    /// e.g. "Lufthansa 123"
+    pub display_callsign: String,
     pub callsign_confidence: f32,
     pub commands: Vec<CommandWithConfidence>,
+    /// Wake turbulence suffix following the callsign ("heavy" or "super")
+    pub weight_suffix: Option<String>,
+    /// False for a bare airline name like "Lufthansa", so that the full callsign can be asked for
+    pub has_flight_number: bool,
+}
+
+impl ParsedCommand {
+    /// The parse result, that is expected for a command group, e.g. from a test index. All
+    /// confidences are 1.0.
+    pub fn from_command_group(group: &AviationCommandGroup) -> Self {
+        let callsign = match &group.target {
+            Some(CommunicationEntity::Aircraft { full_name }) => full_name.clone(),
+            _ => "UNKNOWN".to_string(),
+        };
+        Self {
+            display_callsign: callsign.clone(),
+            callsign,
+            callsign_confidence: 1.0,
+            commands: group
+                .parts
+                .iter()
+                .map(|command| CommandWithConfidence {
+                    command: command.clone(),
+                    confidence: 1.0,
+                    source_text: String::new(),
+                    when_able: false,
+                })
+                .collect(),
+            weight_suffix: None,
+            has_flight_number: true,
+        }
+    }
+
+    /// The callsign and commands without confidences, to compare them with an expected command
+    /// group in one assertion
+    pub fn to_command_group(&self) -> AviationCommandGroup {
+        AviationCommandGroup {
+            target: Some(CommunicationEntity::Aircraft {
+                full_name: self.callsign.clone(),
+            }),
+            parts: self
+                .commands
+                .iter()
+                .map(|command| command.command.clone())
+                .collect(),
+        }
+    }
 }
 
+/// Words that may trail a callsign to indicate the wake turbulence category
+const WEIGHT_SUFFIXES: [&str; 2] = ["heavy", "super"];
+
+/// Command start keywords that indicate a new command is beginning
+const DEFAULT_COMMAND_KEYWORDS: [&str; 28] = [
+    "turn",
+    "fly",
+    "climb",
+    "descend",
+    "maintain",
+    "contact",
+    "cleared",
+    "proceed",
+    "direct",
+    "cross",
+    "radar",
+    "heading",
+    "vector",
+    "vectors",
+    "squawk",
+    "say",
+    "report",
+    "disregard",
+    "standby",
+    "resume",
+    "qnh",
+    "altimeter",
+    "declare",
+    "declaring",
+    "reduce",
+    "increase",
+    "sequence",
+    "until",
+];
+
+/// Number words, that are also ordinary words ("oh"). They are only read as digits next to
+/// another digit, e.g. "two oh oh" or "oh niner oh".
+const CONTEXTUAL_NUMBER_WORDS: [&str; 1] = ["oh"];
+
+/// A word, that may be a number word
+static WORD: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\b\w+\b").unwrap());
+
+/// A word after a digit, that may be a contextual number word
+static WORD_AFTER_DIGIT: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\b(\d)\s+([a-z]+)\b").unwrap());
+
+/// A word before a digit, that may be a contextual number word
+static WORD_BEFORE_DIGIT: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\b([a-z]+)\s+(\d)").unwrap());
+
+/// Words to ignore/skip between commands
+const DEFAULT_FILLER_WORDS: [&str; 5] = ["and", "then", "also", "now", "please"];
+
+/// Maximum number of words of an airline callsign, that starts a second radio call
+const MAX_AIRLINE_WORDS: usize = 3;
+
+/// Parses the text as one kind of command with a confidence
+type CommandMatcher = fn(&AviationCommandParser, &str) -> Option<(AviationCommandPart, f32)>;
+
+/// Matchers, that `try_parse_command_at_position` tries in this order, by their name in the
+/// parse trace. More specific patterns come first, e.g. "turn left heading 220" is a heading
+/// command, so simple turns without a heading are tried last.
+const COMMAND_MATCHERS: [(&str, CommandMatcher); 15] = [
+    (
+        "heading",
+        AviationCommandParser::parse_heading_command_with_confidence,
+    ),
+    (
+        "altitude",
+        AviationCommandParser::parse_altitude_command_with_confidence,
+    ),
+    (
+        "speed",
+        AviationCommandParser::parse_speed_command_with_confidence,
+    ),
+    (
+        "frequency",
+        AviationCommandParser::parse_frequency_command_with_confidence,
+    ),
+    (
+        "radar contact",
+        AviationCommandParser::parse_radar_contact_with_confidence,
+    ),
+    (
+        "landing clearance",
+        AviationCommandParser::parse_landing_clearance_with_confidence,
+    ),
+    (
+        "vectors",
+        AviationCommandParser::parse_vectors_with_confidence,
+    ),
+    (
+        "navigation",
+        AviationCommandParser::parse_navigation_with_confidence,
+    ),
+    (
+        "crossing",
+        AviationCommandParser::parse_crossing_with_confidence,
+    ),
+    (
+        "sequence",
+        AviationCommandParser::parse_sequence_with_confidence,
+    ),
+    (
+        "altimeter",
+        AviationCommandParser::parse_altimeter_with_confidence,
+    ),
+    (
+        "squawk",
+        AviationCommandParser::parse_squawk_or_emergency_with_confidence,
+    ),
+    ("query", AviationCommandParser::parse_query_with_confidence),
+    (
+        "disregard",
+        AviationCommandParser::parse_disregard_or_standby_with_confidence,
+    ),
+    (
+        "turn",
+        AviationCommandParser::parse_turn_command_with_confidence,
+    ),
+];
+
+/// Transition altitude, that "maintain 100" is resolved with, until another one is set
+pub(crate) const DEFAULT_TRANSITION_ALTITUDE_FEET: f64 = 18000.;
+
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CommandWithConfidence {
     pub command: AviationCommandPart,
     pub confidence: f32,
     pub source_text: String, // Der ursprüngliche Text für diesen Command
+    /// The pilot may choose the moment to follow the command, e.g. "when able, direct ALPHA"
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub when_able: bool,
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ParseResult {
     Success(ParsedCommand),
     PartialSuccess {
@@ -49,6 +239,80 @@ pub enum ParseResult {
     },
 }
 
+/// Confidence of a spoken callsign
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Where `parse_transmission_traced` records the tokens, while the transmission is parsed
+struct TraceRecorder<'a> {
+    trace: &'a mut ParseTrace,
+    /// Commands below it are recorded as misses, as they will be rejected
+    min_confidence: f32,
+}
+
+impl TraceRecorder<'_> {
+    fn matched(&mut self, word: &str, matcher: &str) {
+        self.trace.tokens.push(TokenTrace::matched(word, matcher));
+    }
+
+    fn failed(&mut self, word: &str, misses: Vec<MatcherMiss>) {
+        self.trace.tokens.push(TokenTrace::failed(word, misses));
+    }
+
+    fn callsign(&mut self, callsign: &str, known_airline: bool) {
+        let airline_miss = (!known_airline).then(|| MatcherMiss {
+            matcher: "airline".to_string(),
+            reason: MissReason::NoAirlineMatch,
+        });
+        for word in callsign.split_whitespace() {
+            let mut token = TokenTrace::matched(word, "callsign");
+            token.misses.extend(airline_miss.clone());
+            self.trace.tokens.push(token);
+        }
+    }
+
+    /// Records the words of a parsed command. The misses of the matchers, that were tried
+    /// before, go to the first word.
+    fn command(
+        &mut self,
+        words: &[&str],
+        command: &AviationCommandPart,
+        confidence: f32,
+        mut misses: Vec<MatcherMiss>,
+    ) {
+        for word in words {
+            let mut token = if confidence >= self.min_confidence {
+                TokenTrace::matched(word, command.name())
+            } else {
+                let miss = MatcherMiss {
+                    matcher: command.name().to_string(),
+                    reason: MissReason::BelowThreshold {
+                        confidence,
+                        threshold: self.min_confidence,
+                    },
+                };
+                TokenTrace::failed(word, vec![miss])
+            };
+            token.misses.append(&mut misses);
+            self.trace.tokens.push(token);
+        }
+    }
+}
+
+struct CallsignConfidence {
+    confidence: f32,
+    /// False for a bare airline name like "Lufthansa", so that the full callsign can be asked for
+    has_flight_number: bool,
+}
+
+/// A kind of command, that the parser understands
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommandSpec {
+    pub name: &'static str,
+    /// Phrases after the callsign, that parse to this command
+    pub example_phrases: Vec<&'static str>,
+    /// Name of the produced `AviationCommandPart` variant
+    pub produces: &'static str,
+}
+
 #[derive(Debug, Clone)]
 /// Smart aviation command parser that follows real ATC communication structure
 pub struct AviationCommandParser {
@@ -56,19 +320,49 @@ pub struct AviationCommandParser {
     callsign_patterns: Vec<Regex>,
     turn_patterns: Vec<Regex>,
     altitude_patterns: Vec<Regex>,
+    discretion_patterns: Vec<Regex>,
+    speed_patterns: Vec<Regex>,
     frequency_patterns: Vec<Regex>,
     heading_patterns: Vec<Regex>,
+    landing_patterns: Vec<Regex>,
+    vector_patterns: Vec<Regex>,
+    direct_patterns: Vec<Regex>,
+    direct_after_fix_patterns: Vec<Regex>,
+    crossing_patterns: Vec<Regex>,
+    altimeter_patterns: Vec<Regex>,
+    squawk_patterns: Vec<Regex>,
+    query_patterns: Vec<Regex>,
+    sequence_patterns: Vec<Regex>,
+    unitless_altitude_patterns: Vec<Regex>,
+    block_altitude_patterns: Vec<Regex>,
 
     // Word mappings for numbers and directions
     number_words: HashMap<String, u32>,
     direction_words: HashMap<String, TurnDirection>,
     altitude_words: HashMap<String, VerticalDirection>,
     phonetic_alphabet: HashMap<String, String>,
+    // Other words, that are only digits next to another digit
+    number_homophones: HashMap<String, u32>,
+
+    // Words that structure a transmission into commands
+    command_keywords: HashSet<String>,
+    filler_words: HashSet<String>,
 
     // Airlines database for callsign matching
     icao_to_callsign: HashSet<String>,
+    // Telephony names by lowercase ICAO code, e.g. "dlh" -> "Lufthansa"
+    telephony_names: HashMap<String, String>,
     callsign_to_icao: HashMap<String, String>,
     airline_name_to_icao: HashMap<String, String>, // Fallback only
+
+    // Station kinds for contact clearances without a station word
+    frequency_table: FrequencyTable,
+
+    // Commands per transmission, above which only the most confident ones are kept
+    max_commands: Option<usize>,
+
+    // Tells flight levels from feet, if the controller didn't say the unit
+    transition_altitude_feet: f64,
 }
 
 impl AviationCommandParser {
@@ -77,15 +371,41 @@ impl AviationCommandParser {
             callsign_patterns: Vec::new(),
             turn_patterns: Vec::new(),
             altitude_patterns: Vec::new(),
+            discretion_patterns: Vec::new(),
+            speed_patterns: Vec::new(),
             frequency_patterns: Vec::new(),
             heading_patterns: Vec::new(),
+            landing_patterns: Vec::new(),
+            vector_patterns: Vec::new(),
+            direct_patterns: Vec::new(),
+            direct_after_fix_patterns: Vec::new(),
+            crossing_patterns: Vec::new(),
+            altimeter_patterns: Vec::new(),
+            squawk_patterns: Vec::new(),
+            query_patterns: Vec::new(),
+            sequence_patterns: Vec::new(),
+            unitless_altitude_patterns: Vec::new(),
+            block_altitude_patterns: Vec::new(),
             number_words: HashMap::new(),
             direction_words: HashMap::new(),
             altitude_words: HashMap::new(),
             phonetic_alphabet: HashMap::new(),
+            number_homophones: HashMap::new(),
+            command_keywords: DEFAULT_COMMAND_KEYWORDS
+                .iter()
+                .map(|keyword| keyword.to_string())
+                .collect(),
+            filler_words: DEFAULT_FILLER_WORDS
+                .iter()
+                .map(|word| word.to_string())
+                .collect(),
             airline_name_to_icao: HashMap::new(),
             icao_to_callsign: HashSet::new(),
+            telephony_names: HashMap::new(),
             callsign_to_icao: HashMap::new(),
+            frequency_table: FrequencyTable::default(),
+            max_commands: None,
+            transition_altitude_feet: DEFAULT_TRANSITION_ALTITUDE_FEET,
         };
 
         parser.initialize_patterns();
@@ -95,6 +415,135 @@ impl AviationCommandParser {
         parser
     }
 
+    /// All kinds of commands, that the parser understands, with example phrasings, e.g. for a
+    /// cheat sheet
+    pub fn supported_commands() -> Vec<CommandSpec> {
+        let spec = |name, example_phrases: &[&'static str], produces| CommandSpec {
+            name,
+            example_phrases: example_phrases.to_vec(),
+            produces,
+        };
+        vec![
+            spec("Radar contact", &["radar contact"], "RadarContact"),
+            spec("Turn", &["turn left", "turn right"], "TurnBy"),
+            spec(
+                "Heading",
+                &[
+                    "fly heading 270",
+                    "turn left heading 180",
+                    "continue present heading",
+                ],
+                "FlyHeading",
+            ),
+            spec(
+                "Direct to",
+                &["proceed direct BAGEL", "direct to TANGO"],
+                "ProceedDirect",
+            ),
+            spec(
+                "Direct to after a fix",
+                &["until ALPHA then direct BRAVO"],
+                "DirectAfterFix",
+            ),
+            spec(
+                "Altitude",
+                &[
+                    "climb and maintain flight level 350",
+                    "descend to six thousand feet",
+                ],
+                "ChangeAltitude",
+            ),
+            spec(
+                "Block altitude",
+                &["maintain block flight level 100 to 120"],
+                "MaintainBlock",
+            ),
+            spec(
+                "Crossing restriction",
+                &[
+                    "cross BAGEL at or above 6000",
+                    "cleared to cross TANGO at flight level 100",
+                ],
+                "CrossFix",
+            ),
+            spec(
+                "Speed",
+                &["reduce speed to 210 knots", "increase speed for 250"],
+                "ChangeSpeed",
+            ),
+            spec(
+                "Resume normal speed",
+                &["resume normal speed", "no speed restriction"],
+                "ResumeNormalSpeed",
+            ),
+            spec(
+                "Contact",
+                &["contact tower 118.1", "contact 121.5"],
+                "ContactFrequency",
+            ),
+            spec(
+                "Landing clearance",
+                &["cleared to land runway 27"],
+                "ClearedToLand",
+            ),
+            spec(
+                "Cleared for the option",
+                &["cleared for the option runway 25 left"],
+                "ClearedForTheOption",
+            ),
+            spec(
+                "Vectors for ILS",
+                &["vectors for ILS runway 27"],
+                "VectorsForIls",
+            ),
+            spec(
+                "Visual approach",
+                &["cleared visual approach runway 27"],
+                "ClearedVisualApproach",
+            ),
+            spec("Query", &["say altitude", "report heading"], "Query"),
+            spec(
+                "Resume own navigation",
+                &["resume own navigation"],
+                "ResumeOwnNavigation",
+            ),
+            spec("Altimeter", &["QNH 1013", "altimeter 29.92"], "Altimeter"),
+            spec("Squawk", &["squawk 7700", "squawk 1 2 3 4"], "Squawk"),
+            spec("Emergency", &["declaring emergency"], "DeclareEmergency"),
+            spec(
+                "Sequence behind",
+                &[
+                    "sequence behind Ryanair 456",
+                    "sequence behind Speedbird 2 7",
+                ],
+                "SequenceBehind",
+            ),
+            spec("Disregard", &["disregard"], "Disregard"),
+            spec("Standby", &["standby"], "Standby"),
+        ]
+    }
+
+    /// Create a parser without airlines database. Callsigns are still recognized, but not
+    /// mapped to ICAO codes, and get a lower confidence.
+    pub fn new_empty() -> Self {
+        Self::new(Airlines::default())
+    }
+
+    /// Create a parser from an airlines database. If the database can't be read, the error is
+    /// logged and the parser continues without it.
+    pub fn load_airlines_or_empty<R>(reader: R) -> Self
+    where
+        R: std::io::Read,
+    {
+        match Airlines::load_airlines(reader) {
+            Ok(airlines) => Self::new(airlines),
+            Err(err) => {
+                eprintln!("Failed to load airlines database, continuing without it: {err}");
+                Self::new_empty()
+            }
+        }
+    }
+
     /// Create a new parser (deprecated - use new() with airlines)
     #[deprecated(note = "Use new(airlines) instead")]
     pub fn new_with_airlines(airlines: Airlines) -> Self {
@@ -105,6 +554,7 @@ impl AviationCommandParser {
     pub fn load_airlines(&mut self, airlines: Airlines) {
         // Clear all maps
         self.icao_to_callsign.clear();
+        self.telephony_names.clear();
         self.callsign_to_icao.clear();
         self.airline_name_to_icao.clear();
 
@@ -126,6 +576,8 @@ impl AviationCommandParser {
                     let callsign_key = callsign.to_lowercase().replace(" ", "");
                     self.callsign_to_icao
                         .insert(callsign_key, icao_lower.clone());
+                    self.telephony_names
+                        .insert(icao_lower.clone(), title_case(callsign));
                 }
             }
 
@@ -141,6 +593,114 @@ impl AviationCommandParser {
         }
     }
 
+    /// Replace the table, that names the station of a frequency in "contact 121.5"
+    pub fn load_frequency_table(&mut self, frequency_table: FrequencyTable) {
+        self.frequency_table = frequency_table;
+    }
+
+    /// Limit the number of commands of a transmission. Long chains of commands are most likely
+    /// misrecognized, so only the `max_commands` most confident ones are kept and the others
+    /// become unparsed parts. `None` (the default) keeps all commands.
+    pub fn set_max_commands(&mut self, max_commands: Option<usize>) {
+        self.max_commands = max_commands;
+    }
+
+    /// Set the transition altitude, that altitudes without unit are resolved with, e.g. the
+    /// transition altitude of the level
+    pub fn set_transition_altitude(&mut self, transition_altitude_feet: f64) {
+        self.transition_altitude_feet = transition_altitude_feet;
+    }
+
+    /// Add a word that starts a new command (e.g. regional phraseology)
+    pub fn add_command_keyword(&mut self, keyword: &str) {
+        self.command_keywords.insert(keyword.to_lowercase());
+    }
+
+    /// Add a word that is skipped between commands
+    pub fn add_filler_word(&mut self, word: &str) {
+        self.filler_words.insert(word.to_lowercase());
+    }
+
+    /// Add a word, that Whisper writes instead of a digit, e.g. "for" for 4. It is only read as
+    /// the digit next to another digit, so "two for oh" becomes 240.
+    pub fn add_number_homophone(&mut self, word: &str, digit: u32) {
+        self.number_homophones.insert(word.to_lowercase(), digit);
+    }
+
+    /// Add the number homophones of a parser config
+    pub fn add_number_homophones(&mut self, homophones: &HashMap<String, u32>) {
+        for (word, digit) in homophones {
+            self.add_number_homophone(word, *digit);
+        }
+    }
+
+    /// Check that the phonetic alphabet covers A-Z, the number words cover 0-9 and that no word
+    /// has more than one meaning. Returns a description of every gap or conflict.
+    pub fn verify_word_maps(&self) -> Result<(), Vec<String>> {
+        let mut problems = Vec::new();
+
+        for letter in 'A'..='Z' {
+            if !self
+                .phonetic_alphabet
+                .values()
+                .any(|value| value.as_str() == letter.to_string())
+            {
+                problems.push(format!("No phonetic word for letter {letter}"));
+            }
+        }
+        for (word, letter) in &self.phonetic_alphabet {
+            if letter.len() != 1 || !letter.chars().all(|c| c.is_ascii_uppercase()) {
+                problems.push(format!("Phonetic word '{word}' maps to '{letter}', not a letter"));
+            }
+        }
+
+        for digit in 0..=9 {
+            if !self.number_words.values().any(|&value| value == digit) {
+                problems.push(format!("No number word for digit {digit}"));
+            }
+        }
+        for (word, digit) in &self.number_words {
+            if *digit > 9 {
+                problems.push(format!("Number word '{word}' maps to {digit}, not a digit"));
+            }
+        }
+
+        // A word may only have one meaning
+        let word_sets: [(&str, Vec<&String>); 6] = [
+            ("number word", self.number_words.keys().collect()),
+            ("phonetic word", self.phonetic_alphabet.keys().collect()),
+            ("direction word", self.direction_words.keys().collect()),
+            ("altitude word", self.altitude_words.keys().collect()),
+            ("command keyword", self.command_keywords.iter().collect()),
+            ("filler word", self.filler_words.iter().collect()),
+        ];
+        // Keywords may carry the meaning of their command (e.g. "climb")
+        let may_overlap = |name_a: &str, name_b: &str| {
+            name_b == "command keyword" && matches!(name_a, "direction word" | "altitude word")
+        };
+        for (i, (name_a, words_a)) in word_sets.iter().enumerate() {
+            for (name_b, words_b) in &word_sets[i + 1..] {
+                if may_overlap(name_a, name_b) {
+                    continue;
+                }
+                let mut conflicts: Vec<_> = words_a
+                    .iter()
+                    .filter(|word| words_b.contains(word))
+                    .collect();
+                conflicts.sort();
+                for word in conflicts {
+                    problems.push(format!("'{word}' is used as {name_a} and as {name_b}"));
+                }
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(problems)
+        }
+    }
+
     fn initialize_patterns(&mut self) {
         // Callsign patterns - airline + flight number, more flexible for phonetic alphabet and spoken numbers
         // Pattern for long airline names with spoken numbers (e.g., "delta lima hotel one two three")
@@ -164,32 +724,131 @@ impl AviationCommandParser {
 
         // Heading patterns - specific headings
         self.heading_patterns
-            .push(Regex::new(r"turn\s+(?:left|right)\s+heading\s+(\d{1,3})").unwrap()); // "turn left heading 220"
+            .push(Regex::new(r"turn\s+(?:left|right)\s+heading\s+(\d(?:\s?\d){0,2})").unwrap()); // "turn left heading 220"
         self.heading_patterns
-            .push(Regex::new(r"fly\s+heading\s+(\d{1,3})").unwrap()); // "fly heading 090"
+            .push(Regex::new(r"fly\s+heading\s+(\d(?:\s?\d){0,2})").unwrap()); // "fly heading 090"
         self.heading_patterns
-            .push(Regex::new(r"heading\s+(\d{1,3})").unwrap()); // Just "heading 090"
+            .push(Regex::new(r"heading\s+(\d(?:\s?\d){0,2})").unwrap()); // "heading 090" or "heading 0 9 0"
+
+        // Landing clearance patterns - "cleared to land runway 24", "cleared for the option runway 2 4 left",
+        // "cleared visual approach runway 27"
+        self.landing_patterns.push(
+            Regex::new(
+                r"cleared\s+(to\s+land|for\s+the\s+option|(?:for\s+(?:the\s+|a\s+)?)?visual\s+approach)\s+runway\s+(\d(?:\s*\d)?)(?:\s*(left|right|center|l|r|c)\b)?",
+            )
+            .unwrap(),
+        );
+
+        // Vector patterns - "vectors for ILS runway 27", "vectors to the ILS approach runway 2 5 left"
+        self.vector_patterns.push(
+            Regex::new(
+                r"vectors?\s+(?:for|to)\s+(?:the\s+)?ils\s+(?:approach\s+)?runway\s+(\d(?:\s*\d)?)(?:\s*(left|right|center|l|r|c)\b)?",
+            )
+            .unwrap(),
+        );
+
+        // Direct-to patterns - "proceed direct BAGEL", "direct to BAGEL"
+        self.direct_patterns
+            .push(Regex::new(r"^(?:proceed\s+)?direct\s+(?:to\s+)?([a-z]+)[,.]?$").unwrap());
+        // Conditional direct-to, following a heading - "until ALPHA then direct BRAVO"
+        self.direct_after_fix_patterns.push(
+            Regex::new(
+                r"^until\s+([a-z]+),?\s+(?:then\s+)?(?:proceed\s+)?direct\s+(?:to\s+)?([a-z]+)[,.]?$",
+            )
+            .unwrap(),
+        );
+
+        // Crossing restrictions - "cross BAGEL at or above 6000", "cleared to cross TANGO at
+        // flight level 100", "cross BRAVO at or below 5 thousand 5 hundred feet"
+        self.crossing_patterns.push(
+            Regex::new(
+                r"^(?:cleared\s+to\s+)?cross\s+([a-z]+)\s+(at\s+or\s+above|at\s+or\s+below|at)\s+(?:(?:flight\s+)?level\s+(\d(?:\s?\d){1,2})|(\d{1,2})\s+thousand(?:\s+(\d)\s+hundred)?|(\d{1,2}),?(\d{3})|(\d{3}))(?:\s+feet)?[,.]?$",
+            )
+            .unwrap(),
+        );
+
+        // Altimeter patterns - "QNH 1013", "altimeter 29.92", "altimeter 2 9 decimal 9 2"
+        self.altimeter_patterns.push(
+            Regex::new(
+                r"^(?:qnh|altimeter)\s+(\d\s?\d(?:\s?\d){0,2})(?:(?:\.|\s+(?:decimal|point)\s+)(\d\s?\d))?(?:\s+(?:hectopascals?|hpa|millibars?|inches))?[,.]?$",
+            )
+            .unwrap(),
+        );
+
+        // Squawk patterns - "squawk 7700", "squawk 1 2 3 4"
+        self.squawk_patterns
+            .push(Regex::new(r"^squawk\s+([0-7](?:\s?[0-7]){3})[,.]?$").unwrap());
+
+        // Query patterns - "report heading", "say altitude"
+        self.query_patterns.push(
+            Regex::new(r"^(?:say|report)\s+(?:your\s+)?(heading|altitude|level|speed)\b").unwrap(),
+        );
+
+        // Sequence patterns - "sequence behind Ryanair 456", "sequence behind the Speedbird 2 7"
+        self.sequence_patterns.push(
+            Regex::new(
+                r"^sequence\s+behind\s+(?:the\s+)?([a-z]+(?:\s+[a-z]+)*\s+\d(?:\s?\d)*)[,.]?$",
+            )
+            .unwrap(),
+        );
 
         // Altitude patterns - must include specific altitudes
         self.altitude_patterns.push(
             Regex::new(
-                r"(climb|descend)(?:\s+and\s+maintain)?\s+(?:to\s+)?flight\s+level\s+(\d{2,3})",
+                r"(climb|descend)(?:\s+and\s+maintain)?\s+(?:(?:to|for)\s+)?(?:flight\s+)?level\s+(\d(?:\s?\d){1,2})",
+            )
+            .unwrap(),
+        );
+        self.altitude_patterns.push(
+            Regex::new(
+                r"(climb|descend)(?:\s+and\s+maintain)?\s+(?:(?:to|for)\s+)?(\d{1,2}),?(\d{3})\s+feet",
             )
             .unwrap(),
         );
+        // Spoken altitudes, after number words were converted (e.g. "descend to 6 thousand feet",
+        // "climb to 1 thousand 5 hundred")
         self.altitude_patterns.push(
             Regex::new(
-                r"(climb|descend)(?:\s+and\s+maintain)?\s+(?:to\s+)?(\d{1,2}),?(\d{3})\s+feet",
+                r"(climb|descend)(?:\s+and\s+maintain)?\s+(?:(?:to|for)\s+)?(\d{1,2})\s+thousand(?:\s+(\d)\s+hundred)?",
             )
             .unwrap(),
         );
         self.altitude_patterns
-            .push(Regex::new(r"maintain\s+flight\s+level\s+(\d{2,3})").unwrap());
+            .push(Regex::new(r"maintain\s+(?:flight\s+)?level\s+(\d(?:\s?\d){1,2})").unwrap());
         self.altitude_patterns
             .push(Regex::new(r"maintain\s+(\d{1,2}),?(\d{3})\s+feet").unwrap());
         // Pattern for simple maintain altitude (e.g., "maintain 3000 feet")
         self.altitude_patterns
             .push(Regex::new(r"maintain\s+(\d{3,5})\s+feet").unwrap());
+        self.altitude_patterns
+            .push(Regex::new(r"maintain\s+(\d{1,2})\s+thousand(?:\s+(\d)\s+hundred)?").unwrap());
+
+        // Altitudes without unit - "maintain 100", "descend and maintain 3000"
+        self.unitless_altitude_patterns.push(
+            Regex::new(r"^(?:(climb|descend)\s+and\s+)?maintain\s+(\d(?:\s?\d){1,4})[,.]?$")
+                .unwrap(),
+        );
+
+        // Block altitudes - "maintain block flight level 100 to 120", "climb and maintain block
+        // flight level 2 3 0 to flight level 2 5 0"
+        self.block_altitude_patterns.push(
+            Regex::new(
+                r"^(?:(?:climb|descend)\s+and\s+)?maintain\s+block\s+(?:flight\s+)?level\s+(\d(?:\s?\d){1,2})\s+(?:to|through|thru)\s+(?:(?:flight\s+)?level\s+)?(\d(?:\s?\d){1,2})[,.]?$",
+            )
+            .unwrap(),
+        );
+
+        // Pilot's discretion - "descend at pilot's discretion flight level 100", also trailing
+        self.discretion_patterns
+            .push(Regex::new(r"\s*(?:at\s+)?(?:the\s+)?pilot'?s\s+discretion[,.]?").unwrap());
+
+        // Speed patterns - "reduce speed to 210 knots", "increase speed for 250"
+        self.speed_patterns.push(
+            Regex::new(
+                r"^(?:reduce|increase)\s+(?:speed\s+)?(?:(?:to|for)\s+)?(\d(?:\s?\d){1,2})(?:\s+knots)?[,.]?$",
+            )
+            .unwrap(),
+        );
 
         // Frequency patterns - must include actual frequencies (including space-separated digits)
         self.frequency_patterns
@@ -220,6 +879,8 @@ impl AviationCommandParser {
             ("niner", 9),
             ("tree", 3),
             ("fife", 5),
+            ("fiver", 5),
+            ("oh", 0),
             // Also support written numbers for flexibility
             ("0", 0),
             ("1", 1),
@@ -280,6 +941,9 @@ impl AviationCommandParser {
             ("xray", "X"),
             ("yankee", "Y"),
             ("zulu", "Z"),
+            // ICAO spellings
+            ("alfa", "A"),
+            ("juliett", "J"),
         ];
 
         for (phonetic, letter) in phonetic_alphabet {
@@ -290,25 +954,44 @@ impl AviationCommandParser {
 
     /// Parse a complete ATC transmission with enhanced feedback
     pub fn parse_transmission_enhanced(&self, text: &str) -> ParseResult {
+        self.parse_transmission_recorded(text, None)
+    }
+
+    fn parse_transmission_recorded(
+        &self,
+        text: &str,
+        mut recorder: Option<&mut TraceRecorder>,
+    ) -> ParseResult {
         let text = text.trim();
 
         // First, try to extract callsign and command part
         if let Some((callsign, command_text)) = self.extract_callsign_and_commands(text) {
             // Normalize callsign using phonetic alphabet
             let normalized_callsign = self.normalize_callsign(&callsign);
-            let callsign_confidence = self.calculate_callsign_confidence(&callsign);
+            let CallsignConfidence {
+                confidence: callsign_confidence,
+                has_flight_number,
+            } = self.calculate_callsign_confidence(&callsign);
+            let (_, weight_suffix) = Self::split_weight_suffix(&callsign);
+            if let Some(recorder) = recorder.as_deref_mut() {
+                recorder.callsign(&callsign, self.get_airline_info(&callsign).is_some());
+            }
 
             // Parse individual commands
-            let (commands, unparsed_parts) = self.parse_commands_with_feedback(&command_text);
+            let (commands, unparsed_parts) =
+                self.parse_commands_with_feedback(&command_text, recorder);
 
             if commands.is_empty() && !unparsed_parts.is_empty() {
                 return ParseResult::CallsignOnly(normalized_callsign);
             }
 
             let parsed_command = ParsedCommand {
+                display_callsign: self.display_callsign(&normalized_callsign),
                 callsign: normalized_callsign,
                 callsign_confidence,
                 commands,
+                weight_suffix,
+                has_flight_number,
             };
 
             if unparsed_parts.is_empty() {
@@ -321,13 +1004,16 @@ impl AviationCommandParser {
             }
         } else {
             // Try parsing as single command without callsign
-            let (commands, unparsed_parts) = self.parse_commands_with_feedback(text);
+            let (commands, unparsed_parts) = self.parse_commands_with_feedback(text, recorder);
 
             if !commands.is_empty() {
                 let parsed_command = ParsedCommand {
                     callsign: "UNKNOWN".to_string(),
+                    display_callsign: "UNKNOWN".to_string(),
                     callsign_confidence: 0.0,
                     commands,
+                    weight_suffix: None,
+                    has_flight_number: false,
                 };
 
                 if unparsed_parts.is_empty() {
@@ -347,6 +1033,117 @@ impl AviationCommandParser {
         }
     }
 
+    /// Like `parse_transmission_enhanced`, but commands with a confidence below `min_confidence`
+    /// are rejected. Their source text is moved to the unparsed parts, and the result is
+    /// downgraded to `PartialSuccess`, or to `Failed` if no command is left.
+    pub fn parse_transmission_enhanced_with_min_confidence(
+        &self,
+        text: &str,
+        min_confidence: f32,
+    ) -> ParseResult {
+        let result = self.parse_transmission_enhanced(text);
+        Self::reject_less_confident(result, text, min_confidence)
+    }
+
+    fn reject_less_confident(result: ParseResult, text: &str, min_confidence: f32) -> ParseResult {
+        let (parsed, mut unparsed_parts) = match result {
+            ParseResult::Success(parsed) => (parsed, Vec::new()),
+            ParseResult::PartialSuccess {
+                parsed,
+                unparsed_parts,
+            } => (parsed, unparsed_parts),
+            other => return other,
+        };
+
+        let (commands, rejected): (Vec<_>, Vec<_>) = parsed
+            .commands
+            .into_iter()
+            .partition(|command| command.confidence >= min_confidence);
+        if commands.is_empty() {
+            return ParseResult::Failed {
+                reason: format!("No command with a confidence of at least {min_confidence}"),
+                raw_text: text.to_string(),
+            };
+        }
+        unparsed_parts.extend(rejected.into_iter().map(|command| command.source_text));
+
+        let parsed = ParsedCommand { commands, ..parsed };
+        if unparsed_parts.is_empty() {
+            ParseResult::Success(parsed)
+        } else {
+            ParseResult::PartialSuccess {
+                parsed,
+                unparsed_parts,
+            }
+        }
+    }
+
+    /// Like `parse_transmission_enhanced_with_min_confidence`, but also explains per token,
+    /// which matchers were tried and why they failed. Recording allocates for every token, so
+    /// it is meant for tuning phraseology, not for every transmission.
+    pub fn parse_transmission_traced(
+        &self,
+        text: &str,
+        min_confidence: f32,
+    ) -> (ParseResult, ParseTrace) {
+        let mut trace = ParseTrace::default();
+        let mut recorder = TraceRecorder {
+            trace: &mut trace,
+            min_confidence,
+        };
+        let result = self.parse_transmission_recorded(text, Some(&mut recorder));
+        let result = Self::reject_less_confident(result, text, min_confidence);
+        (result, trace)
+    }
+
+    /// Split a transcription of several radio calls back to back (e.g. "Lufthansa 123 turn left
+    /// heading 180 Ryanair 456 descend flight level 100") and parse each of them. A new call
+    /// starts at a known airline followed by a flight number after at least one command.
+    pub fn parse_multi_transmission(&self, text: &str) -> Vec<ParseResult> {
+        let normalized_text = self.convert_spoken_to_digits(text.trim());
+        let words: Vec<&str> = normalized_text.split_whitespace().collect();
+
+        let mut starts = vec![0];
+        for index in 1..words.len() {
+            let current_start = *starts.last().unwrap();
+            let has_command = words[current_start..index]
+                .iter()
+                .any(|word| self.command_keywords.contains(&word.to_lowercase()));
+            // The callsign of the leader in "sequence behind Ryanair 456" doesn't start a call
+            let names_leader = words[index - 1].eq_ignore_ascii_case("behind");
+            if has_command && !names_leader && self.is_callsign_start(&words[index..]) {
+                starts.push(index);
+            }
+        }
+
+        starts
+            .iter()
+            .zip(starts.iter().skip(1).chain([&words.len()]))
+            .map(|(start, end)| self.parse_transmission_enhanced(&words[*start..*end].join(" ")))
+            .collect()
+    }
+
+    /// Whether the words start with an airline callsign or name and a flight number
+    fn is_callsign_start(&self, words: &[&str]) -> bool {
+        let is_number = |word: &str| {
+            let word = word.trim_end_matches(',');
+            !word.is_empty() && word.chars().all(|c| c.is_ascii_digit())
+        };
+        let max_airline_words = MAX_AIRLINE_WORDS.min(words.len().saturating_sub(1));
+        (1..=max_airline_words).any(|airline_len| {
+            let airline = &words[..airline_len];
+            airline.iter().all(|word| {
+                let word = word.to_lowercase();
+                word.chars().all(|c| c.is_ascii_alphabetic())
+                    && !self.command_keywords.contains(&word)
+                    && !self.filler_words.contains(&word)
+            }) && is_number(words[airline_len])
+                && self
+                    .match_airline_name_exactly(&airline.join(" "))
+                    .is_some()
+        })
+    }
+
     /// Parse a complete ATC transmission: Callsign + Commands (legacy)
     pub fn parse_transmission(&self, text: &str) -> Option<ParsedCommand> {
         match self.parse_transmission_enhanced(text) {
@@ -373,33 +1170,130 @@ impl AviationCommandParser {
         // First, try to normalize spoken words to see if we can find a pattern
         let normalized_text = self.convert_spoken_to_digits(text);
 
+        // A known airline followed by a flight number doesn't need a comma to end the callsign
+        if let Some((callsign, commands)) = self.split_at_known_airline(&normalized_text) {
+            return Some(Self::move_weight_suffix_to_callsign(&callsign, &commands));
+        }
+
         // Try patterns on both original and normalized text
         for pattern in &self.callsign_patterns {
             // Try normalized text first
             if let Some(captures) = pattern.captures(&normalized_text) {
-                let callsign = captures.get(1)?.as_str().trim().to_string();
-                let commands = captures.get(2)?.as_str().trim().to_string();
-                return Some((callsign, commands));
+                let callsign = captures.get(1)?.as_str().trim();
+                let commands = captures.get(2)?.as_str().trim();
+                return Some(Self::move_weight_suffix_to_callsign(callsign, commands));
             }
 
             // Try original text
             if let Some(captures) = pattern.captures(text) {
-                let callsign = captures.get(1)?.as_str().trim().to_string();
-                let commands = captures.get(2)?.as_str().trim().to_string();
-                return Some((callsign, commands));
+                let callsign = captures.get(1)?.as_str().trim();
+                let commands = captures.get(2)?.as_str().trim();
+                return Some(Self::move_weight_suffix_to_callsign(callsign, commands));
             }
         }
+
+        // A bare airline name ("Lufthansa, turn left ..."), the full callsign is asked for
+        let (airline, commands) = normalized_text.split_once(',')?;
+        let airline = airline.trim();
+        if airline
+            .chars()
+            .all(|c| c.is_ascii_alphabetic() || c.is_whitespace())
+            && self.match_airline_name_exactly(airline).is_some()
+        {
+            return Some((airline.to_string(), commands.trim().to_string()));
+        }
         None
     }
 
+    /// Split a transmission into callsign and commands, if it starts with a known airline
+    /// (by name, callsign or spelled ICAO code) and a flight number. Whisper often omits the
+    /// comma after the callsign (e.g. "lufthansa 1 2 3 turn left heading 2 7 0").
+    fn split_at_known_airline(&self, normalized_text: &str) -> Option<(String, String)> {
+        let words: Vec<&str> = normalized_text.split_whitespace().collect();
+        let is_number = |word: &str| {
+            let word = word.trim_end_matches(',');
+            !word.is_empty() && word.chars().all(|c| c.is_ascii_digit())
+        };
+
+        let airline_end = words.iter().position(|word| is_number(word))?;
+        if airline_end == 0
+            || !words[..airline_end]
+                .iter()
+                .all(|word| word.chars().all(|c| c.is_ascii_alphabetic()))
+        {
+            return None;
+        }
+        let airline = words[..airline_end].join(" ");
+        if self.match_airline_name(&airline).is_none()
+            && self
+                .match_airline_name(&self.convert_phonetic_callsign(&airline))
+                .is_none()
+        {
+            return None;
+        }
+
+        // Contiguous flight number digits, a comma ends the callsign early
+        let mut callsign_end = airline_end;
+        while callsign_end < words.len() && is_number(words[callsign_end]) {
+            callsign_end += 1;
+            if words[callsign_end - 1].ends_with(',') {
+                break;
+            }
+        }
+        // Optional phonetic suffix letter of the flight number (e.g. "1 2 3 alpha")
+        if callsign_end + 1 < words.len()
+            && !words[callsign_end - 1].ends_with(',')
+            && self
+                .phonetic_alphabet
+                .contains_key(&words[callsign_end].trim_end_matches(',').to_lowercase())
+        {
+            callsign_end += 1;
+        }
+
+        let commands = words[callsign_end..].join(" ");
+        if commands.is_empty() {
+            return None;
+        }
+        let callsign = words[..callsign_end].join(" ");
+        Some((callsign.trim_end_matches(',').to_string(), commands))
+    }
+
+    /// A weight suffix trails the flight number, so the callsign patterns leave it at the start
+    /// of the commands (e.g. "heavy, descend ..."). Move it back to the callsign.
+    fn move_weight_suffix_to_callsign(callsign: &str, commands: &str) -> (String, String) {
+        let (first_word, rest) = commands
+            .split_once(char::is_whitespace)
+            .unwrap_or((commands, ""));
+        let first_word = first_word.trim_end_matches(',');
+        if WEIGHT_SUFFIXES.contains(&first_word.to_lowercase().as_str()) && !rest.trim().is_empty()
+        {
+            (format!("{} {}", callsign, first_word), rest.trim().to_string())
+        } else {
+            (callsign.to_string(), commands.to_string())
+        }
+    }
+
+    /// Split a trailing weight suffix ("heavy"/"super") from the callsign
+    fn split_weight_suffix(callsign: &str) -> (&str, Option<String>) {
+        let callsign = callsign.trim();
+        if let Some((rest, last_word)) = callsign.rsplit_once(char::is_whitespace) {
+            let last_word = last_word.to_lowercase();
+            if WEIGHT_SUFFIXES.contains(&last_word.as_str()) {
+                return (rest.trim_end(), Some(last_word));
+            }
+        }
+        (callsign, None)
+    }
+
     fn parse_commands(&self, text: &str) -> Vec<AviationCommandPart> {
-        let (commands, _) = self.parse_commands_with_feedback(text);
+        let (commands, _) = self.parse_commands_with_feedback(text, None);
         commands.into_iter().map(|c| c.command).collect()
     }
 
     fn parse_commands_with_feedback(
         &self,
         text: &str,
+        recorder: Option<&mut TraceRecorder>,
     ) -> (Vec<CommandWithConfidence>, Vec<String>) {
         let mut commands = Vec::new();
         let mut unparsed_parts = Vec::new();
@@ -409,179 +1303,227 @@ impl AviationCommandParser {
         let normalized_text = self.convert_spoken_to_digits(&text_lower);
 
         // Parse from left to right greedily
-        self.parse_commands_greedy(&normalized_text, &mut commands, &mut unparsed_parts);
+        self.parse_commands_greedy(
+            &normalized_text,
+            &mut commands,
+            &mut unparsed_parts,
+            recorder,
+        );
+
+        if let Some(max_commands) = self.max_commands {
+            let dropped = Self::keep_most_confident(&mut commands, max_commands);
+            unparsed_parts.extend(dropped.into_iter().map(|command| command.source_text));
+        }
 
         (commands, unparsed_parts)
     }
 
+    /// Keep the `max_commands` commands with the highest confidence in their original order.
+    /// Returns the removed commands.
+    fn keep_most_confident(
+        commands: &mut Vec<CommandWithConfidence>,
+        max_commands: usize,
+    ) -> Vec<CommandWithConfidence> {
+        if commands.len() <= max_commands {
+            return Vec::new();
+        }
+        let mut by_confidence: Vec<usize> = (0..commands.len()).collect();
+        // Stable, so that earlier commands win ties
+        by_confidence.sort_by(|a, b| commands[*b].confidence.total_cmp(&commands[*a].confidence));
+        let kept: HashSet<usize> = by_confidence.into_iter().take(max_commands).collect();
+        let (kept, dropped): (Vec<_>, Vec<_>) = std::mem::take(commands)
+            .into_iter()
+            .enumerate()
+            .partition(|(index, _)| kept.contains(index));
+        *commands = kept.into_iter().map(|(_, command)| command).collect();
+        dropped.into_iter().map(|(_, command)| command).collect()
+    }
+
     /// Parse commands greedily from left to right
     fn parse_commands_greedy(
         &self,
         text: &str,
         commands: &mut Vec<CommandWithConfidence>,
         unparsed_parts: &mut Vec<String>,
+        mut recorder: Option<&mut TraceRecorder>,
     ) {
-        // Command start keywords that indicate a new command is beginning
-        let command_keywords = [
-            "turn", "fly", "climb", "descend", "maintain", "contact", "cleared", "proceed",
-            "direct", "radar", "heading", "vector", "squawk",
-        ];
-
-        // Words to ignore/skip
-        let filler_words = ["and", "then", "also", "now", "please"];
-
         let words: Vec<&str> = text.split_whitespace().collect();
         let mut word_index = 0;
+        // "when able" applies to the following command
+        let mut when_able = false;
 
         while word_index < words.len() {
             // Skip filler words
-            if filler_words.contains(&words[word_index].to_lowercase().as_str()) {
+            if self.filler_words.contains(&words[word_index].to_lowercase()) {
+                if let Some(recorder) = recorder.as_deref_mut() {
+                    recorder.matched(words[word_index], "filler");
+                }
                 word_index += 1;
                 continue;
             }
 
-            // Check for multi-word commands first (most specific first)
-            let current_word = words[word_index].to_lowercase();
+            if Self::is_when_able(&words, word_index) {
+                if let Some(recorder) = recorder.as_deref_mut() {
+                    recorder.matched(words[word_index], "when able");
+                    recorder.matched(words[word_index + 1], "when able");
+                }
+                when_able = true;
+                word_index += 2;
+                continue;
+            }
 
-            // Special handling for multi-word commands
-            if current_word == "fly"
-                && word_index + 1 < words.len()
-                && words[word_index + 1].to_lowercase() == "heading"
-            {
-                // "fly heading" should be parsed as one command
-                if let Some((command, confidence, consumed_words)) =
-                    self.try_parse_command_at_position(&words, word_index)
-                {
-                    let source_text = words[word_index..word_index + consumed_words].join(" ");
-                    commands.push(CommandWithConfidence {
-                        command,
-                        confidence,
-                        source_text,
-                    });
-                    word_index += consumed_words;
-                } else {
-                    unparsed_parts.push(words[word_index].to_string());
-                    word_index += 1;
+            if !self.starts_command(&words, word_index) {
+                if let Some(recorder) = recorder.as_deref_mut() {
+                    let miss = MatcherMiss {
+                        matcher: "command keyword".to_string(),
+                        reason: MissReason::NoMatch,
+                    };
+                    recorder.failed(words[word_index], vec![miss]);
                 }
-            } else if current_word == "radar"
-                && word_index + 1 < words.len()
-                && words[word_index + 1].to_lowercase() == "contact"
+                // Not a command keyword, add to unparsed
+                unparsed_parts.push(words[word_index].to_string());
+                word_index += 1;
+                continue;
+            }
+
+            let mut misses = Vec::new();
+            let tried_misses = recorder.is_some().then_some(&mut misses);
+            if let Some((command, confidence, consumed_words)) =
+                self.try_parse_command_at_position(&words, word_index, tried_misses)
             {
-                // "radar contact" should be parsed as one command
-                if let Some((command, confidence, consumed_words)) =
-                    self.try_parse_command_at_position(&words, word_index)
-                {
-                    let source_text = words[word_index..word_index + consumed_words].join(" ");
-                    commands.push(CommandWithConfidence {
-                        command,
-                        confidence,
-                        source_text,
-                    });
-                    word_index += consumed_words;
-                } else {
-                    unparsed_parts.push(words[word_index].to_string());
-                    word_index += 1;
-                }
-            } else if command_keywords.contains(&current_word.as_str()) {
-                // Try to parse a single-word command starting from this position
-                if let Some((command, confidence, consumed_words)) =
-                    self.try_parse_command_at_position(&words, word_index)
-                {
-                    let source_text = words[word_index..word_index + consumed_words].join(" ");
-                    commands.push(CommandWithConfidence {
-                        command,
-                        confidence,
-                        source_text,
-                    });
-                    word_index += consumed_words;
-                } else {
-                    // Couldn't parse command, add to unparsed
-                    unparsed_parts.push(words[word_index].to_string());
-                    word_index += 1;
+                let command_words = &words[word_index..word_index + consumed_words];
+                if let Some(recorder) = recorder.as_deref_mut() {
+                    recorder.command(command_words, &command, confidence, misses);
                 }
+                let source_text = command_words.join(" ");
+                commands.push(CommandWithConfidence {
+                    command,
+                    confidence,
+                    source_text,
+                    when_able: std::mem::take(&mut when_able),
+                });
+                word_index += consumed_words;
             } else {
-                // Not a command keyword, add to unparsed
+                if let Some(recorder) = recorder.as_deref_mut() {
+                    recorder.failed(words[word_index], misses);
+                }
+                // Couldn't parse command, add to unparsed
                 unparsed_parts.push(words[word_index].to_string());
                 word_index += 1;
             }
         }
+
+        // Trailing, e.g. "descend flight level 100 when able"
+        if when_able {
+            if let Some(last) = commands.last_mut() {
+                last.when_able = true;
+            }
+        }
+    }
+
+    /// Whether the words at the index are "when able"
+    fn is_when_able(words: &[&str], index: usize) -> bool {
+        let word = |index: usize| {
+            words
+                .get(index)
+                .map(|word| word.trim_end_matches(',').to_lowercase())
+        };
+        word(index).as_deref() == Some("when") && word(index + 1).as_deref() == Some("able")
+    }
+
+    /// Whether a command is tried at the word, because it is a command keyword or starts a
+    /// multi-word command ("fly heading", "continue present heading", "no speed restriction")
+    fn starts_command(&self, words: &[&str], index: usize) -> bool {
+        let current_word = words[index].to_lowercase();
+        let next_word = words.get(index + 1).map(|word| word.to_lowercase());
+        (current_word == "fly" && next_word.as_deref() == Some("heading"))
+            || (current_word == "continue"
+                && matches!(next_word.as_deref(), Some("heading" | "present")))
+            || (current_word == "radar" && next_word.as_deref() == Some("contact"))
+            || (current_word == "no" && next_word.as_deref() == Some("speed"))
+            || (current_word == "speed"
+                && next_word
+                    .as_deref()
+                    .is_some_and(|word| word.starts_with("restriction")))
+            || self.command_keywords.contains(&current_word)
     }
 
     /// Try to parse a command starting at the given word position
-    /// Returns (command, confidence, number_of_words_consumed) if successful
+    /// Returns (command, confidence, number_of_words_consumed) if successful. The matchers, that
+    /// didn't accept the words, are added to `misses`.
     fn try_parse_command_at_position(
         &self,
         words: &[&str],
         start_index: usize,
+        mut misses: Option<&mut Vec<MatcherMiss>>,
     ) -> Option<(AviationCommandPart, f32, usize)> {
         if start_index >= words.len() {
             return None;
         }
 
-        let command_keywords = [
-            "turn", "fly", "climb", "descend", "maintain", "contact", "cleared", "proceed",
-            "direct", "radar", "heading", "vector", "squawk",
+        // Keywords that are part of a multi-word command (e.g. "heading" in "fly heading 090")
+        // don't start a new command. "*" stands for any word, e.g. the fix in "until ALPHA then
+        // direct BRAVO".
+        let multi_word_commands: [&[&str]; 13] = [
+            &["fly", "heading"],
+            &["continue", "heading"],
+            &["continue", "present", "heading"],
+            &["until", "*", "then", "direct"],
+            &["proceed", "direct"],
+            &["cleared", "to", "cross"],
+            &["say", "heading"],
+            &["report", "heading"],
+            &["radar", "contact"],
+            &["turn", "left", "heading"],
+            &["turn", "right", "heading"],
+            &["climb", "and", "maintain"],
+            &["descend", "and", "maintain"],
         ];
+        let multi_word_prefix_len = multi_word_commands
+            .iter()
+            .filter(|prefix| {
+                words.len() - start_index >= prefix.len()
+                    && prefix
+                        .iter()
+                        .zip(&words[start_index..])
+                        .all(|(keyword, word)| {
+                            *keyword == "*" || word.eq_ignore_ascii_case(keyword)
+                        })
+            })
+            .map(|prefix| prefix.len())
+            .max()
+            .unwrap_or(1);
 
         // Try different command lengths, starting with longer ones (greedy)
         for end_index in (start_index + 1..=words.len()).rev() {
-            // Allow certain multi-word combinations even if they contain keywords
-            let command_text = words[start_index..end_index].join(" ").to_lowercase();
-            let is_multi_word_command = command_text.starts_with("fly heading")
-                || command_text.starts_with("radar contact")
-                || command_text == "radar contact";
-
-            // Make sure we don't go past another command keyword (except for allowed multi-word commands)
-            if !is_multi_word_command {
-                let mut has_intermediate_keyword = false;
-                for word in words {
-                    if command_keywords.contains(&word.to_lowercase().as_str()) {
-                        has_intermediate_keyword = true;
-                        break;
-                    }
-                }
-
-                if has_intermediate_keyword {
-                    continue; // Skip this range if it contains another command keyword
-                }
+            // Make sure we don't go past another command keyword
+            let keyword_check_start = (start_index + multi_word_prefix_len).min(end_index);
+            let has_intermediate_keyword = words[keyword_check_start..end_index]
+                .iter()
+                .any(|word| self.command_keywords.contains(&word.to_lowercase()))
+                || (keyword_check_start..end_index).any(|index| Self::is_when_able(words, index));
+            if has_intermediate_keyword {
+                continue; // Skip this range if it contains another command keyword
             }
 
             let command_text = words[start_index..end_index].join(" ");
 
             // Try parsing as different command types in priority order
-            // IMPORTANT: More specific patterns should be checked first!
-            
-            // 1. Check heading commands first (includes "turn left heading 220" → FlyHeading)
-            if let Some((cmd, confidence)) =
-                self.parse_heading_command_with_confidence(&command_text)
-            {
-                return Some((cmd, confidence, end_index - start_index));
-            }
-            
-            // 2. Check altitude commands (climb/descend)
-            if let Some((cmd, confidence)) =
-                self.parse_altitude_command_with_confidence(&command_text)
-            {
-                return Some((cmd, confidence, end_index - start_index));
-            }
-            
-            // 3. Check frequency commands (contact)
-            if let Some((cmd, confidence)) =
-                self.parse_frequency_command_with_confidence(&command_text)
-            {
-                return Some((cmd, confidence, end_index - start_index));
-            }
-            
-            // 4. Check radar contact
-            if let Some((cmd, confidence)) = self.parse_radar_contact_with_confidence(&command_text)
-            {
-                return Some((cmd, confidence, end_index - start_index));
-            }
-            
-            // 5. Check turn commands last (only for simple turns without heading)
-            if let Some((cmd, confidence)) = self.parse_turn_command_with_confidence(&command_text)
-            {
-                return Some((cmd, confidence, end_index - start_index));
+            for (matcher, parse) in COMMAND_MATCHERS {
+                if let Some((cmd, confidence)) = parse(self, &command_text) {
+                    if let Some(misses) = misses {
+                        misses.retain(|miss| miss.matcher != matcher);
+                    }
+                    return Some((cmd, confidence, end_index - start_index));
+                }
+                if let Some(misses) = misses.as_deref_mut() {
+                    if !misses.iter().any(|miss| miss.matcher == matcher) {
+                        misses.push(MatcherMiss {
+                            matcher: matcher.to_string(),
+                            reason: MissReason::NoMatch,
+                        });
+                    }
+                }
             }
         }
 
@@ -589,6 +1531,9 @@ impl AviationCommandParser {
     }
 
     fn normalize_callsign(&self, callsign: &str) -> String {
+        // The weight suffix is not part of the callsign
+        let (callsign, _) = Self::split_weight_suffix(callsign);
+
         // First, try to match against airlines database
         if let Some(normalized) = self.normalize_callsign_with_airlines(callsign) {
             return normalized;
@@ -665,6 +1610,23 @@ impl AviationCommandParser {
             .join("") // No spaces - callsigns should be concatenated
     }
 
+    /// Display form of a normalized callsign, e.g. "DLH123" -> "Lufthansa 123". Airlines
+    /// without telephony name keep their ICAO code, separated from the flight number.
+    fn display_callsign(&self, callsign: &str) -> String {
+        let split = callsign
+            .find(|c: char| c.is_ascii_digit())
+            .unwrap_or(callsign.len());
+        let (prefix, flight_number) = callsign.split_at(split);
+        let icao = prefix.to_lowercase();
+        if flight_number.is_empty() || !self.icao_to_callsign.contains(&icao) {
+            return callsign.to_string();
+        }
+        match self.telephony_names.get(&icao) {
+            Some(telephony_name) => format!("{telephony_name} {flight_number}"),
+            None => format!("{prefix} {flight_number}"),
+        }
+    }
+
     /// Normalize callsign using airlines database
     fn normalize_callsign_with_airlines(&self, callsign: &str) -> Option<String> {
         let parts: Vec<&str> = callsign.split_whitespace().collect();
@@ -694,19 +1656,28 @@ impl AviationCommandParser {
         let airline_part = parts[..airline_end_idx].join(" ");
         let flight_number_parts = &parts[airline_end_idx..];
 
-        // Convert spoken numbers in flight number to digits
+        // Convert spoken numbers in flight number to digits, and a phonetic suffix to its letter
+        // (e.g. "one two three alpha" -> "123A")
         let flight_number = flight_number_parts
             .iter()
             .map(|part| {
-                if let Some(&digit) = self.number_words.get(&part.to_lowercase()) {
+                let part_lower = part.to_lowercase();
+                if let Some(&digit) = self.number_words.get(&part_lower) {
                     digit.to_string()
+                } else if let Some(letter) = self.phonetic_alphabet.get(&part_lower) {
+                    letter.to_uppercase()
                 } else {
-                    part.to_string()
+                    part.to_uppercase()
                 }
             })
             .collect::<Vec<_>>()
             .join("");
 
+        // The ICAO code spelled out, e.g. "delta alpha lima", needs no lookup
+        if let Some(icao) = self.spelled_icao_code(&airline_part) {
+            return Some(format!("{icao}{flight_number}"));
+        }
+
         // Try direct name match
         if let Some(icao) = self.match_airline_name(&airline_part) {
             return Some(format!("{}{}", icao.to_uppercase(), flight_number));
@@ -744,6 +1715,11 @@ impl AviationCommandParser {
             return Some(icao.clone());
         }
 
+        // The ICAO code itself (e.g. "DLH 456")
+        if self.icao_to_callsign.contains(&name_key) {
+            return Some(name_key);
+        }
+
         // Try with spaces preserved for multi-word airline names
         if let Some(icao) = self.airline_name_to_icao.get(&name_with_spaces) {
             return Some(icao.clone());
@@ -795,6 +1771,21 @@ impl AviationCommandParser {
         None
     }
 
+    /// Match the callsign or name of an airline without ICAO codes and partial matches, which
+    /// are too ambiguous in the middle of a transmission
+    fn match_airline_name_exactly(&self, name: &str) -> Option<String> {
+        let name_key = name.to_lowercase().replace(" ", "");
+        let name_with_spaces = name.to_lowercase();
+        [name_key, name_with_spaces]
+            .iter()
+            .find_map(|key| {
+                self.callsign_to_icao
+                    .get(key)
+                    .or_else(|| self.airline_name_to_icao.get(key))
+            })
+            .cloned()
+    }
+
     /// Convert phonetic alphabet in airline callsign (e.g., "delta lima hotel" -> "dlh")
     fn convert_phonetic_callsign(&self, name: &str) -> String {
         let words: Vec<&str> = name.split_whitespace().collect();
@@ -812,11 +1803,46 @@ impl AviationCommandParser {
         result
     }
 
-    fn calculate_callsign_confidence(&self, callsign: &str) -> f32 {
+    /// Three words of the phonetic alphabet, optionally joined by hyphens, as ICAO code
+    /// (e.g. "delta-alfa-lima" -> "DAL")
+    fn spelled_icao_code(&self, name: &str) -> Option<String> {
+        let letters = name
+            .split(|c: char| c.is_whitespace() || c == '-')
+            .filter(|word| !word.is_empty())
+            .map(|word| self.phonetic_alphabet.get(&word.to_lowercase()))
+            .collect::<Option<Vec<_>>>()?;
+        (letters.len() == 3).then(|| letters.iter().map(|letter| letter.to_uppercase()).collect())
+    }
+
+    fn calculate_callsign_confidence(&self, callsign: &str) -> CallsignConfidence {
+        let (callsign, _) = Self::split_weight_suffix(callsign);
+        let parts: Vec<&str> = callsign.split_whitespace().collect();
+        let has_flight_number = parts.iter().any(|part| {
+            part.chars().any(|c| c.is_ascii_digit())
+                || self.number_words.contains_key(&part.to_lowercase())
+        });
+        let confidence = if has_flight_number {
+            self.calculate_flight_callsign_confidence(callsign, &parts)
+        } else if self.match_airline_name(callsign).is_some()
+            || self
+                .match_airline_name(&self.convert_phonetic_callsign(callsign))
+                .is_some()
+        {
+            // A known airline alone, e.g. "Lufthansa"
+            0.9
+        } else {
+            self.calculate_flight_callsign_confidence(callsign, &parts)
+        };
+        CallsignConfidence {
+            confidence,
+            has_flight_number,
+        }
+    }
+
+    fn calculate_flight_callsign_confidence(&self, callsign: &str, parts: &[&str]) -> f32 {
         let normalized = self.normalize_callsign(callsign);
 
         // Airlines database is always available
-        let parts: Vec<&str> = callsign.split_whitespace().collect();
         if parts.len() >= 2 {
             // For phonetic callsigns, we need to identify the airline part differently
             // Look for the last part that contains digits or number words
@@ -870,12 +1896,46 @@ impl AviationCommandParser {
         result = result.replace(" point ", ".");
 
         // Handle number words individually
-        for (word, digit) in &self.number_words {
-            // Use word boundaries to avoid partial replacements
-            let pattern = format!(r"\b{}\b", regex::escape(word));
-            if let Ok(regex) = regex::Regex::new(&pattern) {
-                result = regex.replace_all(&result, digit.to_string()).to_string();
+        result = WORD
+            .replace_all(&result, |captures: &Captures| {
+                let word = &captures[0];
+                match self.number_words.get(word) {
+                    Some(digit) if !CONTEXTUAL_NUMBER_WORDS.contains(&word) => digit.to_string(),
+                    _ => word.to_string(),
+                }
+            })
+            .into_owned();
+
+        // Contextual number words and homophones only count next to a digit. Each replacement
+        // creates a new digit, so "two oh oh" and "two for oh" need two rounds.
+        let contextual_digit = |word: &str| {
+            if CONTEXTUAL_NUMBER_WORDS.contains(&word) {
+                self.number_words.get(word)
+            } else {
+                self.number_homophones.get(word)
+            }
+        };
+        loop {
+            let replaced = WORD_AFTER_DIGIT
+                .replace_all(&result, |captures: &Captures| {
+                    match contextual_digit(&captures[2]) {
+                        Some(digit) => format!("{} {digit}", &captures[1]),
+                        None => captures[0].to_string(),
+                    }
+                })
+                .into_owned();
+            let replaced = WORD_BEFORE_DIGIT
+                .replace_all(&replaced, |captures: &Captures| {
+                    match contextual_digit(&captures[1]) {
+                        Some(digit) => format!("{digit} {}", &captures[2]),
+                        None => captures[0].to_string(),
+                    }
+                })
+                .into_owned();
+            if replaced == result {
+                break;
             }
+            result = replaced;
         }
 
         result
@@ -951,38 +2011,56 @@ impl AviationCommandParser {
         let mut best_match = None;
         let mut best_confidence = 0.0f32;
 
+        // The discretion phrase is removed, so that the altitude patterns don't need to know it
+        let discretion = self
+            .discretion_patterns
+            .iter()
+            .any(|pattern| pattern.is_match(text));
+        let without_discretion = self
+            .discretion_patterns
+            .iter()
+            .fold(text.to_string(), |text, pattern| {
+                pattern.replace_all(&text, "").into_owned()
+            });
+        let text = without_discretion.as_str();
+
+        // Checked first, the generic "maintain ... flight level" fallback would match a block
+        if let Some(block) = self.parse_block_altitude_with_confidence(text) {
+            return Some(block);
+        }
+
         for pattern in &self.altitude_patterns {
             if let Some(captures) = pattern.captures(text) {
                 // Check if this is a "maintain" only pattern (new pattern we added)
                 if text.contains("maintain") && !text.contains("climb") && !text.contains("descend")
                 {
                     // Handle "maintain XXXX feet" pattern
-                    if let Some(altitude_str) = captures.get(1) {
-                        if let Ok(altitude_feet) = altitude_str.as_str().parse::<u32>() {
-                            let mut confidence = 0.8; // High confidence for clear maintain command
+                    if let Some(altitude) = Self::altitude_from_captures(&captures, 1) {
+                        let mut confidence = 0.8; // High confidence for clear maintain command
 
-                            if text.contains("feet") {
-                                confidence += 0.1;
-                            }
+                        if text.contains("feet") {
+                            confidence += 0.1;
+                        }
 
-                            let altitude = if altitude_feet >= 18000 {
-                                // Convert to flight level if above 18000 feet
-                                Altitude::FlightLevel(altitude_feet / 100)
-                            } else {
-                                Altitude::Feet(altitude_feet as f64)
-                            };
-
-                            if confidence > best_confidence {
-                                best_confidence = confidence.min(1.0);
-                                best_match = Some((
-                                    AviationCommandPart::ChangeAltitude {
-                                        altitude,
-                                        maintain: true,
-                                        turn_direction: None, // No direction for maintain commands
-                                    },
-                                    best_confidence,
-                                ));
+                        let altitude = match altitude {
+                            // Convert to flight level if above 18000 feet
+                            Altitude::Feet(feet) if feet >= 18000.0 => {
+                                Altitude::FlightLevel((feet / 100.0) as u32)
                             }
+                            altitude => altitude,
+                        };
+
+                        if confidence > best_confidence {
+                            best_confidence = confidence.min(1.0);
+                            best_match = Some((
+                                AviationCommandPart::ChangeAltitude {
+                                    altitude,
+                                    maintain: true,
+                                    discretion,
+                                    turn_direction: None, // No direction for maintain commands
+                                },
+                                best_confidence,
+                            ));
                         }
                     }
                 } else if let Some(direction_str) = captures.get(1) {
@@ -991,7 +2069,7 @@ impl AviationCommandParser {
                         let mut confidence = 0.7; // Base confidence
 
                         // Higher confidence for specific altitude mentions
-                        if text.contains("flight level") {
+                        if mentions_level(text) {
                             confidence += 0.2;
                         }
                         if text.contains("feet") {
@@ -1001,12 +2079,19 @@ impl AviationCommandParser {
                             confidence += 0.1;
                         }
 
+                        let Some(altitude) = Self::altitude_from_captures(&captures, 2) else {
+                            continue;
+                        };
+                        // "climb and maintain" holds the altitude, "climb to" may be an interim
+                        let maintain = captures.get(0)?.as_str().contains("maintain");
+
                         if confidence > best_confidence {
                             best_confidence = confidence.min(1.0);
                             best_match = Some((
                                 AviationCommandPart::ChangeAltitude {
-                                    altitude: Altitude::FlightLevel(100), // Default FL100
-                                    maintain: false,
+                                    altitude,
+                                    maintain,
+                                    discretion,
                                     turn_direction: Some(direction),
                                 },
                                 best_confidence,
@@ -1017,13 +2102,39 @@ impl AviationCommandParser {
             }
         }
 
-        // Check for "maintain" commands without direction
-        if text.contains("maintain") && (text.contains("flight level") || text.contains("feet")) {
+        // "maintain 100" means flight level 100, "maintain 3000" means feet. The guess is
+        // surfaced as a lower confidence.
+        if best_match.is_none() {
+            if let Some((direction, value)) = self.unitless_altitude_captures(text) {
+                let altitude = Altitude::from_unitless(value, self.transition_altitude_feet, None)?;
+                let confidence = if Altitude::is_unitless_ambiguous(value) {
+                    0.6
+                } else {
+                    0.7
+                };
+                return Some((
+                    AviationCommandPart::ChangeAltitude {
+                        altitude,
+                        maintain: true,
+                        discretion,
+                        turn_direction: direction,
+                    },
+                    confidence,
+                ));
+            }
+        }
+
+        // Check for "maintain" commands without direction or recognizable altitude
+        if best_match.is_none()
+            && text.contains("maintain")
+            && (mentions_level(text) || text.contains("feet"))
+        {
             // Generic maintain command - could be either climb or descend context
             return Some((
                 AviationCommandPart::ChangeAltitude {
                     altitude: Altitude::FlightLevel(100), // Default FL100
                     maintain: true,
+                    discretion: false,
                     turn_direction: Some(VerticalDirection::Climb),
                 },
                 0.6,
@@ -1033,6 +2144,98 @@ impl AviationCommandParser {
         best_match
     }
 
+    /// Parse a block altitude (maintain block flight level 100 to 120) with confidence scoring.
+    /// The limits may be said in either order.
+    fn parse_block_altitude_with_confidence(
+        &self,
+        text: &str,
+    ) -> Option<(AviationCommandPart, f32)> {
+        let text = text.to_lowercase();
+        let captures = self
+            .block_altitude_patterns
+            .iter()
+            .find_map(|pattern| pattern.captures(&text))?;
+        let flight_level = |group: usize| {
+            captures
+                .get(group)?
+                .as_str()
+                .chars()
+                .filter(|c| !c.is_whitespace())
+                .collect::<String>()
+                .parse::<u32>()
+                .ok()
+        };
+        let (first, second) = (flight_level(1)?, flight_level(2)?);
+        Some((
+            AviationCommandPart::MaintainBlock {
+                lower: Altitude::FlightLevel(first.min(second)),
+                upper: Altitude::FlightLevel(first.max(second)),
+            },
+            0.9,
+        ))
+    }
+
+    /// The number of an altitude clearance without "feet" or "flight level", e.g. 100 for the
+    /// source text "maintain 1 0 0". The caller can resolve it again with
+    /// `Altitude::from_unitless`, once it knows the present altitude of the aircraft.
+    pub fn unitless_altitude(&self, text: &str) -> Option<u32> {
+        self.unitless_altitude_captures(&text.to_lowercase())
+            .map(|(_, value)| value)
+    }
+
+    fn unitless_altitude_captures(&self, text: &str) -> Option<(Option<VerticalDirection>, u32)> {
+        let captures = self
+            .unitless_altitude_patterns
+            .iter()
+            .find_map(|pattern| pattern.captures(text))?;
+        let direction = match captures.get(1) {
+            Some(direction) => Some(*self.altitude_words.get(direction.as_str())?),
+            None => None,
+        };
+        let value = captures
+            .get(2)?
+            .as_str()
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect::<String>()
+            .parse()
+            .ok()?;
+        Some((direction, value))
+    }
+
+    /// Read the altitude from the capture groups of an altitude pattern, starting at
+    /// `first_group`. Flight levels are captured as one group, feet may be split at the
+    /// thousands separator ("5,000 feet") or spoken ("5 thousand 5 hundred").
+    fn altitude_from_captures(captures: &regex::Captures, first_group: usize) -> Option<Altitude> {
+        let matched = captures.get(0)?.as_str();
+        let mut groups = captures
+            .iter()
+            .skip(first_group)
+            .flatten()
+            .map(|group| group.as_str());
+        if matched.contains("thousand") {
+            let thousands = groups.next()?.parse::<u32>().ok()?;
+            let hundreds = match groups.next() {
+                Some(hundreds) => hundreds.parse::<u32>().ok()?,
+                None => 0,
+            };
+            return Some(Altitude::Feet((thousands * 1000 + hundreds * 100) as f64));
+        }
+
+        // Flight levels may be spoken digit by digit ("flight level 2 1 0"), some regions leave
+        // out "flight" ("level 100")
+        let digits: String = groups
+            .flat_map(str::chars)
+            .filter(|c| !c.is_whitespace())
+            .collect();
+        let value = digits.parse::<u32>().ok()?;
+        if mentions_level(matched) {
+            Some(Altitude::FlightLevel(value))
+        } else {
+            Some(Altitude::Feet(value as f64))
+        }
+    }
+
     /// Parse frequency command with confidence scoring
     fn parse_frequency_command_with_confidence(
         &self,
@@ -1047,23 +2250,32 @@ impl AviationCommandParser {
                     // Standard format: contact tower 121.5
                     let station = captures.get(1).map(|m| m.as_str().to_string());
                     let num = captures.get(2)?.as_str().parse::<u32>().ok()?;
-                    let dec = captures.get(3)?.as_str().parse::<u32>().ok()?;
+                    let dec = captures.get(3)?.as_str();
                     (num, dec, station, 0.8)
+                } else if captures.len() == 3 {
+                    // Frequency without station: frequency 121.5
+                    let num = captures.get(1)?.as_str().parse::<u32>().ok()?;
+                    let dec = captures.get(2)?.as_str();
+                    (num, dec, None, 0.8)
                 } else if captures.len() == 6 {
                     // Space-separated format: contact tower 1 2 1.5
                     let station = captures.get(1).map(|m| m.as_str().to_string());
                     let d1 = captures.get(2)?.as_str().parse::<u32>().ok()?;
                     let d2 = captures.get(3)?.as_str().parse::<u32>().ok()?;
                     let d3 = captures.get(4)?.as_str().parse::<u32>().ok()?;
-                    let dec = captures.get(5)?.as_str().parse::<u32>().ok()?;
+                    let dec = captures.get(5)?.as_str();
                     let num = d1 * 100 + d2 * 10 + d3;
                     (num, dec, station, 0.7) // Slightly lower confidence for spoken format
                 } else {
                     continue;
                 };
 
+                let Ok(frequency) = Frequency::from_parts(num, dec) else {
+                    continue;
+                };
+
                 // Validate frequency range (aviation frequencies are typically 118-137 MHz)
-                if (118..=137).contains(&num) && dec <= 999 {
+                if (118..=137).contains(&num) {
                     // Higher confidence for known station types
                     if let Some(ref station_name) = station {
                         match station_name.as_str() {
@@ -1079,7 +2291,11 @@ impl AviationCommandParser {
                         confidence += 0.05;
                     }
 
-                    let frequency = Frequency { num, dec };
+                    let station = station.or_else(|| {
+                        self.frequency_table
+                            .station(&frequency)
+                            .map(|station| station.to_string())
+                    });
                     let command = AviationCommandPart::ContactFrequency { frequency, station };
 
                     if confidence > best_confidence {
@@ -1098,6 +2314,18 @@ impl AviationCommandParser {
         &self,
         text: &str,
     ) -> Option<(AviationCommandPart, f32)> {
+        if let "continue present heading" | "fly present heading" =
+            text.trim().trim_end_matches([',', '.'])
+        {
+            return Some((
+                AviationCommandPart::FlyHeading {
+                    heading: HeadingDirection::PresentHeading,
+                    turn_direction: None,
+                },
+                0.95,
+            ));
+        }
+
         let mut best_match = None;
         let mut best_confidence = 0.0f32;
 
@@ -1106,14 +2334,22 @@ impl AviationCommandParser {
             if let Some(captures) = pattern.captures(text) {
                 // Get the heading number (should be in the first capture group)
                 if let Some(heading_str) = captures.get(1) {
-                    if let Ok(heading_degrees) = heading_str.as_str().parse::<f32>() {
-                        let normalized_heading = heading_degrees % 360.0;
+                    // Spoken headings are separated digits ("2 7 0")
+                    if let Ok(heading_degrees) =
+                        heading_str.as_str().replace(' ', "").parse::<f32>()
+                    {
+                        // 360 is north, larger values are no heading and most likely misheard
+                        if heading_degrees > 360.0 {
+                            return None;
+                        }
                         
                         // Determine confidence based on the specific pattern matched
                         let mut confidence = if text.contains("turn") && text.contains("heading") {
                             0.9 // High confidence for "turn left/right heading XXX"
                         } else if text.contains("fly") && text.contains("heading") {
                             0.95 // Very high confidence for "fly heading XXX"
+                        } else if text.starts_with("continue") && text.contains("heading") {
+                            0.9 // High confidence for "continue heading XXX"
                         } else {
                             0.7 // Lower confidence for just "heading XXX"
                         };
@@ -1129,7 +2365,7 @@ impl AviationCommandParser {
                             best_match = Some((
                                 AviationCommandPart::FlyHeading {
                                     heading: HeadingDirection::Heading(Heading::from(
-                                        normalized_heading as f64,
+                                        heading_degrees as f64,
                                     )),
                                     turn_direction: None, // Direction is implicit in the heading
                                 },
@@ -1141,133 +2377,1759 @@ impl AviationCommandParser {
             }
         }
 
-        best_match
+        best_match
+    }
+
+    /// Parse landing or visual approach clearance with confidence scoring (cleared to land runway 24)
+    fn parse_landing_clearance_with_confidence(
+        &self,
+        text: &str,
+    ) -> Option<(AviationCommandPart, f32)> {
+        let text = text.to_lowercase();
+        for pattern in &self.landing_patterns {
+            let Some(captures) = pattern.captures(&text) else {
+                continue;
+            };
+            let runway = Self::runway_designator(captures.get(2)?.as_str(), captures.get(3));
+
+            let kind = captures.get(1)?.as_str();
+            let command = if kind.starts_with("to") {
+                AviationCommandPart::ClearedToLand { runway }
+            } else if kind.ends_with("approach") {
+                AviationCommandPart::ClearedVisualApproach { runway }
+            } else {
+                AviationCommandPart::ClearedForTheOption { runway }
+            };
+            return Some((command, 0.9));
+        }
+        None
+    }
+
+    /// Parse vectors for an ILS approach with confidence scoring (vectors for ILS runway 27)
+    fn parse_vectors_with_confidence(&self, text: &str) -> Option<(AviationCommandPart, f32)> {
+        let text = text.to_lowercase();
+        for pattern in &self.vector_patterns {
+            let Some(captures) = pattern.captures(&text) else {
+                continue;
+            };
+            let runway = Self::runway_designator(captures.get(1)?.as_str(), captures.get(2));
+            return Some((AviationCommandPart::VectorsForIls { runway }, 0.9));
+        }
+        None
+    }
+
+    /// Parse a direct-to (proceed direct BAGEL), a direct-to after a fix (until ALPHA then direct
+    /// BRAVO) or "resume own navigation" with confidence scoring
+    fn parse_navigation_with_confidence(&self, text: &str) -> Option<(AviationCommandPart, f32)> {
+        let text = text.to_lowercase();
+        if let "resume own navigation" | "resume navigation" = text.trim_end_matches([',', '.']) {
+            return Some((AviationCommandPart::ResumeOwnNavigation, 0.95));
+        }
+        for pattern in &self.direct_after_fix_patterns {
+            let Some(captures) = pattern.captures(&text) else {
+                continue;
+            };
+            let fix = captures.get(1)?.as_str().to_uppercase();
+            let waypoint = captures.get(2)?.as_str().to_uppercase();
+            return Some((AviationCommandPart::DirectAfterFix { fix, waypoint }, 0.9));
+        }
+        for pattern in &self.direct_patterns {
+            let Some(captures) = pattern.captures(&text) else {
+                continue;
+            };
+            let waypoint = captures.get(1)?.as_str().to_uppercase();
+            return Some((AviationCommandPart::ProceedDirect(waypoint), 0.9));
+        }
+        None
+    }
+
+    /// Parse a crossing restriction (cross BAGEL at or above 6000) with confidence scoring
+    fn parse_crossing_with_confidence(&self, text: &str) -> Option<(AviationCommandPart, f32)> {
+        let text = text.to_lowercase();
+        for pattern in &self.crossing_patterns {
+            let Some(captures) = pattern.captures(&text) else {
+                continue;
+            };
+            let fix = captures.get(1)?.as_str().to_uppercase();
+            let constraint = match captures.get(2)?.as_str() {
+                constraint if constraint.ends_with("above") => AltitudeConstraint::AtOrAbove,
+                constraint if constraint.ends_with("below") => AltitudeConstraint::AtOrBelow,
+                _ => AltitudeConstraint::At,
+            };
+            let altitude = Self::altitude_from_captures(&captures, 3)?;
+            return Some((
+                AviationCommandPart::CrossFix {
+                    fix,
+                    constraint,
+                    altitude,
+                },
+                0.9,
+            ));
+        }
+        None
+    }
+
+    /// Parse "sequence behind Ryanair 456" with confidence scoring. A leader of an unknown
+    /// airline is less likely to be recognized correctly.
+    fn parse_sequence_with_confidence(&self, text: &str) -> Option<(AviationCommandPart, f32)> {
+        let text = text.to_lowercase();
+        for pattern in &self.sequence_patterns {
+            let Some(captures) = pattern.captures(&text) else {
+                continue;
+            };
+            let leader = captures.get(1)?.as_str();
+            let confidence = if self.get_airline_info(leader).is_some() {
+                0.9
+            } else {
+                0.7
+            };
+            return Some((
+                AviationCommandPart::SequenceBehind {
+                    callsign: self.normalize_callsign(leader),
+                },
+                confidence,
+            ));
+        }
+        None
+    }
+
+    /// Parse a speed command with confidence scoring
+    fn parse_speed_command_with_confidence(
+        &self,
+        text: &str,
+    ) -> Option<(AviationCommandPart, f32)> {
+        let text = text.to_lowercase();
+        if let "resume normal speed"
+        | "no speed restriction"
+        | "no speed restrictions"
+        | "speed restriction cancelled"
+        | "speed restrictions cancelled" = text.trim_end_matches([',', '.'])
+        {
+            return Some((AviationCommandPart::ResumeNormalSpeed, 0.95));
+        }
+        for pattern in &self.speed_patterns {
+            let Some(captures) = pattern.captures(&text) else {
+                continue;
+            };
+            let knots: String = captures
+                .get(1)?
+                .as_str()
+                .chars()
+                .filter(|c| c.is_ascii_digit())
+                .collect();
+            let knots = knots.parse().ok()?;
+            let confidence = if text.contains("knots") { 0.95 } else { 0.85 };
+            return Some((AviationCommandPart::ChangeSpeed { knots }, confidence));
+        }
+        None
+    }
+
+    /// Parse an altimeter setting with confidence scoring. Values with decimals and values
+    /// between 2500 and 3300 are inHg, values between 900 and 1100 are hPa.
+    fn parse_altimeter_with_confidence(&self, text: &str) -> Option<(AviationCommandPart, f32)> {
+        let text = text.to_lowercase();
+        for pattern in &self.altimeter_patterns {
+            let Some(captures) = pattern.captures(&text) else {
+                continue;
+            };
+            let digits = |m: regex::Match| -> String {
+                m.as_str().chars().filter(|c| c.is_ascii_digit()).collect()
+            };
+            let integer = digits(captures.get(1)?);
+            let command = match captures.get(2) {
+                Some(decimals) if integer.len() == 2 => AviationCommandPart::Altimeter {
+                    hpa: None,
+                    inhg: Some(format!("{integer}{}", digits(decimals)).parse().ok()?),
+                },
+                Some(_) => continue,
+                None => match integer.parse::<u32>().ok()? {
+                    value @ 900..=1100 => AviationCommandPart::Altimeter {
+                        hpa: Some(value),
+                        inhg: None,
+                    },
+                    value @ 2500..=3300 => AviationCommandPart::Altimeter {
+                        hpa: None,
+                        inhg: Some(value),
+                    },
+                    _ => continue,
+                },
+            };
+            return Some((command, 0.9));
+        }
+        None
+    }
+
+    /// Parse a squawk code or a declared emergency with confidence scoring
+    fn parse_squawk_or_emergency_with_confidence(
+        &self,
+        text: &str,
+    ) -> Option<(AviationCommandPart, f32)> {
+        let text = text.to_lowercase();
+        if let "declaring emergency" | "declaring an emergency" | "declare emergency" =
+            text.trim_end_matches([',', '.'])
+        {
+            return Some((AviationCommandPart::DeclareEmergency, 0.95));
+        }
+        for pattern in &self.squawk_patterns {
+            let Some(captures) = pattern.captures(&text) else {
+                continue;
+            };
+            let code: String = captures
+                .get(1)?
+                .as_str()
+                .chars()
+                .filter(|c| c.is_ascii_digit())
+                .collect();
+            let code = code.parse().ok()?;
+            return Some((AviationCommandPart::Squawk { code }, 0.9));
+        }
+        None
+    }
+
+    /// Formats the runway number and side like "06" or "25L"
+    fn runway_designator(number: &str, side: Option<regex::Match>) -> String {
+        let digits: String = number.chars().filter(|c| c.is_ascii_digit()).collect();
+        let side = side
+            .and_then(|side| side.as_str().chars().next())
+            .map(|side| side.to_ascii_uppercase().to_string())
+            .unwrap_or_default();
+        format!("{:0>2}{}", digits, side)
+    }
+
+    /// Parse a query of the aircraft state with confidence scoring (report heading)
+    fn parse_query_with_confidence(&self, text: &str) -> Option<(AviationCommandPart, f32)> {
+        let text = text.to_lowercase();
+        for pattern in &self.query_patterns {
+            let Some(captures) = pattern.captures(&text) else {
+                continue;
+            };
+            let field = match captures.get(1)?.as_str() {
+                "heading" => QueryField::Heading,
+                "altitude" | "level" => QueryField::Altitude,
+                "speed" => QueryField::Speed,
+                _ => continue,
+            };
+            // Extra words after the query lower the confidence
+            let confidence = if captures.get(0)?.as_str() == text.trim_end_matches(',') {
+                0.95
+            } else {
+                0.8
+            };
+            return Some((AviationCommandPart::Query { field }, confidence));
+        }
+        None
+    }
+
+    /// Parse radar contact command with confidence scoring
+    fn parse_radar_contact_with_confidence(
+        &self,
+        text: &str,
+    ) -> Option<(AviationCommandPart, f32)> {
+        // Only match exact "radar contact" or very short variants
+        if text == "radar contact" {
+            return Some((
+                AviationCommandPart::RadarContact,
+                0.98, // Perfect match
+            ));
+        }
+
+        // Allow some short variations but not too much extra text
+        if text.contains("radar")
+            && text.contains("contact")
+            && text.split_whitespace().count() <= 3
+        {
+            return Some((
+                AviationCommandPart::RadarContact,
+                0.85, // Good match but with extra words
+            ));
+        }
+
+        None
+    }
+
+    /// Parse "disregard" and "standby", which consist of the keyword only
+    fn parse_disregard_or_standby_with_confidence(
+        &self,
+        text: &str,
+    ) -> Option<(AviationCommandPart, f32)> {
+        match text.trim_end_matches([',', '.']) {
+            "disregard" => Some((AviationCommandPart::Disregard, 0.98)),
+            "standby" => Some((AviationCommandPart::Standby, 0.98)),
+            _ => None,
+        }
+    }
+
+    /// Get information about a matched airline
+    pub fn get_airline_info(&self, callsign: &str) -> Option<CallsignMatch> {
+        let parts: Vec<&str> = callsign.split_whitespace().collect();
+        if parts.len() < 2 {
+            return None;
+        }
+
+        // Try different combinations of the first parts as airline name
+        for i in 1..parts.len() {
+            let airline_part = parts[..i].join(" ");
+
+            // Try direct name match
+            if let Some(icao) = self.match_airline_name(&airline_part) {
+                return Some(CallsignMatch {
+                    icao_code: icao.to_uppercase(),
+                    confidence: 0.9,
+                });
+            }
+
+            // Try phonetic alphabet conversion
+            let phonetic_converted = self.convert_phonetic_callsign(&airline_part);
+            if let Some(icao) = self.match_airline_name(&phonetic_converted) {
+                return Some(CallsignMatch {
+                    icao_code: icao.to_uppercase(),
+                    confidence: 0.85,
+                });
+            }
+        }
+
+        None
+    }
+
+    /// Check if a callsign matches a known airline pattern
+    pub fn is_known_airline(&self, callsign: &str) -> bool {
+        self.get_airline_info(callsign).is_some()
+    }
+
+    /// Get all possible airline matches for debugging. Callsigns and airline names are
+    /// searched, longer stored names come first, equal lengths are sorted alphabetically.
+    pub fn get_all_airline_matches(&self, name: &str) -> Vec<(String, String)> {
+        let mut matches = Vec::new();
+        let name_key = name.to_lowercase().replace(" ", "");
+
+        let stored_names = self
+            .callsign_to_icao
+            .iter()
+            .chain(&self.airline_name_to_icao);
+        for (stored_name, icao) in stored_names {
+            if stored_name.contains(&name_key) || name_key.contains(stored_name) {
+                matches.push((icao.to_uppercase(), stored_name.clone()));
+            }
+        }
+
+        matches.sort_by(|(icao_a, name_a), (icao_b, name_b)| {
+            name_b
+                .len()
+                .cmp(&name_a.len())
+                .then_with(|| name_a.cmp(name_b))
+                .then_with(|| icao_a.cmp(icao_b))
+        });
+        matches.dedup();
+        matches
+    }
+}
+
+impl Default for AviationCommandParser {
+    fn default() -> Self {
+        // Load airlines from the default path for default constructor
+        let mut parser = match std::fs::File::open(
+            "crates/aviation_helper_rs/resources/known_strings/airlines.json",
+        ) {
+            Ok(file) => Self::load_airlines_or_empty(std::io::BufReader::new(file)),
+            Err(err) => {
+                eprintln!("Failed to open airlines database, continuing without it: {err}");
+                Self::new_empty()
+            }
+        };
+        match ParserConfig::load_default() {
+            Ok(config) => parser.add_number_homophones(&config.number_homophones),
+            Err(err) => {
+                eprintln!("Failed to load the parser config, continuing without homophones: {err}")
+            }
+        }
+        parser
+    }
+}
+
+/// "LUFTHANSA CARGO" -> "Lufthansa Cargo"
+fn title_case(text: &str) -> String {
+    text.split_whitespace()
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first
+                    .to_uppercase()
+                    .chain(chars.flat_map(char::to_lowercase))
+                    .collect(),
+                None => String::new(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Whether an altitude is given as a flight level, "flight level 100" or just "level 100"
+fn mentions_level(text: &str) -> bool {
+    text.split(|c: char| !c.is_alphanumeric())
+        .any(|word| word == "level")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::LazyLock;
+
+    use super::*;
+    use aviation_helper_rs::clearance::airlines::AirlineEntry;
+
+    static AIRLINES: LazyLock<Airlines> =
+        LazyLock::new(|| Airlines::load_airlines_from_file().unwrap());
+
+    static COMMAND_PARSER: LazyLock<AviationCommandParser> =
+        LazyLock::new(|| AviationCommandParser::new(AIRLINES.clone()));
+
+    #[test]
+    fn test_display_callsign() {
+        let parsed = COMMAND_PARSER
+            .parse_transmission("Lufthansa 123, turn left 30 degrees")
+            .unwrap();
+        assert_eq!(parsed.callsign, "DLH123");
+        assert_eq!(parsed.display_callsign, "Lufthansa 123");
+
+        let parsed = COMMAND_PARSER
+            .parse_transmission("Speedbird 27, climb flight level 120")
+            .unwrap();
+        assert_eq!(parsed.callsign, "BAW27");
+        assert_eq!(parsed.display_callsign, "Speedbird 27");
+    }
+
+    #[test]
+    fn test_parse_complete_transmission() {
+        let result = COMMAND_PARSER.parse_transmission("Lufthansa 123, turn left 30 degrees");
+
+        assert!(result.is_some());
+        let parsed = result.unwrap();
+        assert_eq!(parsed.callsign, "DLH123"); // Expect ICAO code without space: DLH123
+
+        assert_eq!(
+            &parsed
+                .commands
+                .into_iter()
+                .map(|c| c.command)
+                .collect::<Vec<_>>()[..],
+            &[AviationCommandPart::TurnBy {
+                turn_direction: Some(TurnDirection::Left),
+                degrees: Degrees(30.0)
+            }],
+            "Expected: \"turn left heading 270\""
+        );
+    }
+
+    #[test]
+    fn test_parse_callsign_with_weight_suffix() {
+        let result = COMMAND_PARSER
+            .parse_transmission_enhanced("Speedbird 123 heavy, turn left heading 180");
+
+        let ParseResult::Success(parsed) = result else {
+            panic!("Expected success, got: {:?}", result);
+        };
+        assert_eq!(parsed.callsign, "BAW123");
+        assert_eq!(parsed.weight_suffix.as_deref(), Some("heavy"));
+        assert_eq!(
+            parsed.commands.into_iter().map(|c| c.command).collect::<Vec<_>>(),
+            vec![AviationCommandPart::FlyHeading {
+                heading: HeadingDirection::Heading(Heading::new(180.0)),
+                turn_direction: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_add_command_keyword() {
+        let text = "Lufthansa 123, turn left heading 270 frequency 121.5";
+
+        let Some(parsed) = COMMAND_PARSER.parse_transmission(text) else {
+            panic!("Failed to parse: {}", text);
+        };
+        assert_eq!(parsed.commands.len(), 1);
+
+        let mut parser = COMMAND_PARSER.clone();
+        parser.add_command_keyword("Frequency");
+        let result = parser.parse_transmission_enhanced(text);
+        let ParseResult::Success(parsed) = result else {
+            panic!("Expected success, got: {:?}", result);
+        };
+        assert_eq!(
+            parsed.commands.into_iter().map(|c| c.command).collect::<Vec<_>>(),
+            vec![
+                AviationCommandPart::FlyHeading {
+                    heading: HeadingDirection::Heading(Heading::new(270.0)),
+                    turn_direction: None,
+                },
+                AviationCommandPart::ContactFrequency {
+                    frequency: Frequency { num: 121, dec: 500 },
+                    station: Some("emergency".to_string()),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_landing_clearances() {
+        let test_cases = [
+            (
+                "Lufthansa 123, cleared to land runway 24",
+                AviationCommandPart::ClearedToLand {
+                    runway: "24".to_string(),
+                },
+            ),
+            (
+                "Lufthansa 123, cleared to land runway two five left",
+                AviationCommandPart::ClearedToLand {
+                    runway: "25L".to_string(),
+                },
+            ),
+            (
+                "Lufthansa 123, cleared for the option runway 6",
+                AviationCommandPart::ClearedForTheOption {
+                    runway: "06".to_string(),
+                },
+            ),
+        ];
+
+        for (text, expected) in test_cases {
+            let result = COMMAND_PARSER.parse_transmission_enhanced(text);
+            let ParseResult::Success(parsed) = result else {
+                panic!("Expected success for '{}', got: {:?}", text, result);
+            };
+            assert_eq!(parsed.callsign, "DLH123");
+            assert_eq!(
+                parsed.commands.into_iter().map(|c| c.command).collect::<Vec<_>>(),
+                vec![expected]
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_visual_approach() {
+        for (text, runway) in [
+            ("Lufthansa 123, cleared visual approach runway 27", "27"),
+            (
+                "Lufthansa 123 cleared for the visual approach runway 2 5 right",
+                "25R",
+            ),
+            ("Lufthansa 123, cleared for visual approach runway 6", "06"),
+        ] {
+            let result = COMMAND_PARSER.parse_transmission_enhanced(text);
+            let ParseResult::Success(parsed) = result else {
+                panic!("Expected success for '{}', got: {:?}", text, result);
+            };
+            assert_eq!(
+                parsed
+                    .commands
+                    .into_iter()
+                    .map(|c| c.command)
+                    .collect::<Vec<_>>(),
+                vec![AviationCommandPart::ClearedVisualApproach {
+                    runway: runway.to_string(),
+                }]
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_heading_360() {
+        for text in [
+            "Lufthansa 123, fly heading 360",
+            "Lufthansa 123, fly heading three six zero",
+        ] {
+            let result = COMMAND_PARSER.parse_transmission_enhanced(text);
+            let ParseResult::Success(parsed) = result else {
+                panic!("Expected success for '{}', got: {:?}", text, result);
+            };
+            let AviationCommandPart::FlyHeading {
+                heading: HeadingDirection::Heading(heading),
+                ..
+            } = parsed.commands[0].command
+            else {
+                panic!("Expected a heading for '{}'", text);
+            };
+            assert_eq!(heading, Heading::from(360.0));
+            assert_eq!(heading.to_string(), "360");
+        }
+    }
+
+    #[test]
+    fn test_parse_heading_above_360() {
+        let text = "Lufthansa 123, fly heading 400";
+        let result = COMMAND_PARSER.parse_transmission_enhanced(text);
+        if let ParseResult::Success(parsed) | ParseResult::PartialSuccess { parsed, .. } = &result {
+            assert!(
+                !parsed.commands.iter().any(|command| matches!(
+                    command.command,
+                    AviationCommandPart::FlyHeading { .. }
+                )),
+                "Expected no heading for '{}', got: {:?}",
+                text,
+                result
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_crossing_restriction() {
+        for (text, fix, constraint, altitude) in [
+            (
+                "Lufthansa 123, cross BRAVO at or above six thousand",
+                "BRAVO",
+                AltitudeConstraint::AtOrAbove,
+                Altitude::Feet(6000.),
+            ),
+            (
+                "Lufthansa 123, cross CHARLIE at flight level 100",
+                "CHARLIE",
+                AltitudeConstraint::At,
+                Altitude::FlightLevel(100),
+            ),
+            (
+                "Lufthansa 123, cleared to cross TANGO at or below 5,500 feet",
+                "TANGO",
+                AltitudeConstraint::AtOrBelow,
+                Altitude::Feet(5500.),
+            ),
+        ] {
+            let result = COMMAND_PARSER.parse_transmission_enhanced(text);
+            let ParseResult::Success(parsed) = result else {
+                panic!("Expected success for '{}', got: {:?}", text, result);
+            };
+            assert_eq!(
+                parsed
+                    .commands
+                    .into_iter()
+                    .map(|c| c.command)
+                    .collect::<Vec<_>>(),
+                vec![AviationCommandPart::CrossFix {
+                    fix: fix.to_string(),
+                    constraint,
+                    altitude,
+                }]
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_sequence_behind() {
+        for (text, leader) in [
+            ("Lufthansa 123, sequence behind Ryanair 456", "RYR456"),
+            (
+                "Lufthansa 123, sequence behind the speedbird two seven",
+                "BAW27",
+            ),
+        ] {
+            let result = COMMAND_PARSER.parse_transmission_enhanced(text);
+            let ParseResult::Success(parsed) = result else {
+                panic!("Expected success for '{}', got: {:?}", text, result);
+            };
+            assert_eq!(parsed.callsign, "DLH123");
+            assert_eq!(
+                parsed
+                    .commands
+                    .into_iter()
+                    .map(|c| c.command)
+                    .collect::<Vec<_>>(),
+                vec![AviationCommandPart::SequenceBehind {
+                    callsign: leader.to_string(),
+                }]
+            );
+        }
+
+        // The leader doesn't start a second radio call
+        let results = COMMAND_PARSER.parse_multi_transmission(
+            "Lufthansa 123 sequence behind Ryanair 456 reduce speed to 210 knots",
+        );
+        assert_eq!(results.len(), 1, "Got: {:?}", results);
+    }
+
+    #[test]
+    fn test_parse_multi_transmission() {
+        let results = COMMAND_PARSER.parse_multi_transmission(
+            "Lufthansa 123 turn left heading 180 Ryanair 456 descend flight level 100",
+        );
+        assert_eq!(results.len(), 2, "Got: {:?}", results);
+
+        let ParseResult::Success(first) = &results[0] else {
+            panic!("Expected success, got: {:?}", results[0]);
+        };
+        assert_eq!(first.callsign, "DLH123");
+        assert_eq!(
+            first
+                .commands
+                .iter()
+                .map(|c| &c.command)
+                .collect::<Vec<_>>(),
+            vec![&AviationCommandPart::FlyHeading {
+                heading: HeadingDirection::Heading(Heading::from(180.)),
+                turn_direction: None,
+            }]
+        );
+
+        let ParseResult::Success(second) = &results[1] else {
+            panic!("Expected success, got: {:?}", results[1]);
+        };
+        assert_eq!(second.callsign, "RYR456");
+        assert_eq!(second.commands.len(), 1);
+        assert!(matches!(
+            second.commands[0].command,
+            AviationCommandPart::ChangeAltitude {
+                altitude: Altitude::FlightLevel(100),
+                ..
+            }
+        ));
+
+        // A single transmission is not split
+        let results = COMMAND_PARSER.parse_multi_transmission(
+            "Lufthansa 123, turn left heading 180, descend flight level 100",
+        );
+        assert_eq!(results.len(), 1);
+        let ParseResult::Success(parsed) = &results[0] else {
+            panic!("Expected success, got: {:?}", results[0]);
+        };
+        assert_eq!(parsed.commands.len(), 2);
+    }
+
+    #[test]
+    fn test_parse_when_able() {
+        let when_able = |text: &str| {
+            let result = COMMAND_PARSER.parse_transmission_enhanced(text);
+            let ParseResult::Success(parsed) = result else {
+                panic!("Expected success for '{text}', got: {result:?}");
+            };
+            parsed
+                .commands
+                .into_iter()
+                .map(|c| (c.command.name(), c.when_able))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            when_able("Lufthansa 123, when able, direct ALPHA"),
+            vec![("ProceedDirect", true)]
+        );
+        assert_eq!(
+            when_able("Lufthansa 123, when able descend flight level 100"),
+            vec![("ChangeAltitude", true)]
+        );
+        assert_eq!(
+            when_able("Lufthansa 123, turn left heading 180, descend flight level 100 when able"),
+            vec![("FlyHeading", false), ("ChangeAltitude", true)]
+        );
+        assert_eq!(
+            when_able("Lufthansa 123, descend flight level 100"),
+            vec![("ChangeAltitude", false)]
+        );
+    }
+
+    #[test]
+    fn test_parse_level_without_flight() {
+        let test_cases = [
+            (
+                "Lufthansa 123, descend level 100",
+                Altitude::FlightLevel(100),
+            ),
+            // Feet stay feet
+            ("Lufthansa 123, descend 3000 feet", Altitude::Feet(3000.0)),
+        ];
+
+        for (text, altitude) in test_cases {
+            let result = COMMAND_PARSER.parse_transmission_enhanced(text);
+            let ParseResult::Success(parsed) = result else {
+                panic!("Expected success for '{}', got: {:?}", text, result);
+            };
+            assert_eq!(
+                parsed
+                    .commands
+                    .into_iter()
+                    .map(|c| c.command)
+                    .collect::<Vec<_>>(),
+                vec![AviationCommandPart::ChangeAltitude {
+                    altitude,
+                    maintain: false,
+                    discretion: false,
+                    turn_direction: Some(VerticalDirection::Descend),
+                }],
+                "Wrong command for '{}'",
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_direct_then_resume_own_navigation() {
+        let result = COMMAND_PARSER
+            .parse_transmission_enhanced("Lufthansa 123, direct ALPHA then resume own navigation");
+        let ParseResult::Success(parsed) = result else {
+            panic!("Expected success, got: {:?}", result);
+        };
+        assert_eq!(
+            parsed
+                .commands
+                .into_iter()
+                .map(|c| c.command)
+                .collect::<Vec<_>>(),
+            vec![
+                AviationCommandPart::ProceedDirect("ALPHA".to_string()),
+                AviationCommandPart::ResumeOwnNavigation,
+            ]
+        );
+
+        let result =
+            COMMAND_PARSER.parse_transmission_enhanced("Lufthansa 123, proceed direct to BAGEL");
+        let ParseResult::Success(parsed) = result else {
+            panic!("Expected success, got: {:?}", result);
+        };
+        assert_eq!(
+            parsed.commands[0].command,
+            AviationCommandPart::ProceedDirect("BAGEL".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_resume_normal_speed() {
+        for text in [
+            "Lufthansa 123, resume normal speed",
+            "Lufthansa 123, no speed restriction",
+            "Lufthansa 123, speed restriction cancelled.",
+        ] {
+            let result = COMMAND_PARSER.parse_transmission_enhanced(text);
+            let ParseResult::Success(parsed) = result else {
+                panic!("Expected success for '{}', got: {:?}", text, result);
+            };
+            assert_eq!(
+                parsed
+                    .commands
+                    .into_iter()
+                    .map(|c| c.command)
+                    .collect::<Vec<_>>(),
+                vec![AviationCommandPart::ResumeNormalSpeed],
+                "Wrong parse of '{}'",
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_heading_until_fix_then_direct() {
+        let commands = |text: &str| {
+            let result = COMMAND_PARSER.parse_transmission_enhanced(text);
+            let ParseResult::Success(parsed) = result else {
+                panic!("Expected success for '{}', got: {:?}", text, result);
+            };
+            parsed
+                .commands
+                .into_iter()
+                .map(|c| c.command)
+                .collect::<Vec<_>>()
+        };
+        let direct_after_alpha = AviationCommandPart::DirectAfterFix {
+            fix: "ALPHA".to_string(),
+            waypoint: "BRAVO".to_string(),
+        };
+        assert_eq!(
+            commands("Lufthansa 123, continue heading 090 until ALPHA then direct BRAVO"),
+            vec![
+                AviationCommandPart::FlyHeading {
+                    heading: HeadingDirection::Heading(Heading::from(90.)),
+                    turn_direction: None,
+                },
+                direct_after_alpha.clone(),
+            ]
+        );
+        assert_eq!(
+            commands("Lufthansa 123, continue present heading until ALPHA, then direct BRAVO"),
+            vec![
+                AviationCommandPart::FlyHeading {
+                    heading: HeadingDirection::PresentHeading,
+                    turn_direction: None,
+                },
+                direct_after_alpha,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_callsign_confidence_without_flight_number() {
+        let airline_only = COMMAND_PARSER.calculate_callsign_confidence("Lufthansa");
+        assert!(
+            airline_only.confidence >= 0.9,
+            "Expected high confidence, got {}",
+            airline_only.confidence
+        );
+        assert!(!airline_only.has_flight_number);
+
+        let unknown = COMMAND_PARSER.calculate_callsign_confidence("Qwxzv");
+        assert!(unknown.confidence < 0.9);
+        assert!(!unknown.has_flight_number);
+
+        let full = COMMAND_PARSER.calculate_callsign_confidence("Lufthansa 123");
+        assert!(full.confidence >= 0.9);
+        assert!(full.has_flight_number);
+
+        // The game asks for the full callsign
+        let result = COMMAND_PARSER.parse_transmission_enhanced("Lufthansa, turn left heading 180");
+        let ParseResult::Success(parsed) = result else {
+            panic!("Expected success, got: {:?}", result);
+        };
+        assert!(!parsed.has_flight_number);
+        let result =
+            COMMAND_PARSER.parse_transmission_enhanced("Lufthansa 123, turn left heading 180");
+        let ParseResult::Success(parsed) = result else {
+            panic!("Expected success, got: {:?}", result);
+        };
+        assert!(parsed.has_flight_number);
+    }
+
+    #[test]
+    fn test_supported_commands_parse_to_their_type() {
+        let specs = AviationCommandParser::supported_commands();
+        // Every variant of AviationCommandPart can be emitted by the parser
+        let emitted = [
+            "RadarContact",
+            "TurnBy",
+            "FlyHeading",
+            "ProceedDirect",
+            "DirectAfterFix",
+            "ChangeAltitude",
+            "MaintainBlock",
+            "CrossFix",
+            "ChangeSpeed",
+            "ResumeNormalSpeed",
+            "ContactFrequency",
+            "ClearedToLand",
+            "ClearedForTheOption",
+            "VectorsForIls",
+            "ClearedVisualApproach",
+            "Query",
+            "ResumeOwnNavigation",
+            "Altimeter",
+            "Squawk",
+            "DeclareEmergency",
+            "SequenceBehind",
+            "Disregard",
+            "Standby",
+        ];
+        for produces in emitted {
+            assert!(
+                specs.iter().any(|spec| spec.produces == produces),
+                "No example for {}",
+                produces
+            );
+        }
+
+        for spec in specs {
+            assert!(!spec.example_phrases.is_empty());
+            for phrase in spec.example_phrases {
+                let text = format!("Lufthansa 123, {}", phrase);
+                let result = COMMAND_PARSER.parse_transmission_enhanced(&text);
+                let ParseResult::Success(parsed) = result else {
+                    panic!("Expected success for '{}', got: {:?}", text, result);
+                };
+                assert_eq!(
+                    parsed.commands.len(),
+                    1,
+                    "Wrong command count for '{}'",
+                    text
+                );
+                assert_eq!(
+                    parsed.commands[0].command.name(),
+                    spec.produces,
+                    "Wrong command for '{}'",
+                    text
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_squawk_and_emergency() {
+        let test_cases = [
+            (
+                "Lufthansa 123, squawk 7700",
+                AviationCommandPart::Squawk { code: 7700 },
+            ),
+            (
+                "Lufthansa 123, squawk seven six zero zero",
+                AviationCommandPart::Squawk { code: 7600 },
+            ),
+            (
+                "Lufthansa 123, declaring emergency",
+                AviationCommandPart::DeclareEmergency,
+            ),
+        ];
+
+        for (text, expected) in test_cases {
+            let result = COMMAND_PARSER.parse_transmission_enhanced(text);
+            let ParseResult::Success(parsed) = result else {
+                panic!("Expected success for '{}', got: {:?}", text, result);
+            };
+            assert_eq!(
+                parsed.commands[0].command, expected,
+                "Wrong command for '{}'",
+                text
+            );
+        }
+
+        // Transponder codes only have octal digits
+        let result = COMMAND_PARSER.parse_transmission_enhanced("Lufthansa 123, squawk 7800");
+        assert!(!matches!(result, ParseResult::Success(_)));
+    }
+
+    #[test]
+    fn test_for_as_connector() {
+        let test_cases = [
+            (
+                "Lufthansa 123, climb for flight level 350",
+                AviationCommandPart::ChangeAltitude {
+                    altitude: Altitude::FlightLevel(350),
+                    maintain: false,
+                    discretion: false,
+                    turn_direction: Some(VerticalDirection::Climb),
+                },
+            ),
+            (
+                "Lufthansa 123, reduce speed for 210 knots",
+                AviationCommandPart::ChangeSpeed { knots: 210 },
+            ),
+            (
+                "Lufthansa 123, reduce speed to 210 knots",
+                AviationCommandPart::ChangeSpeed { knots: 210 },
+            ),
+        ];
+
+        for (text, expected) in test_cases {
+            let result = COMMAND_PARSER.parse_transmission_enhanced(text);
+            let ParseResult::Success(parsed) = result else {
+                panic!("Expected success for '{}', got: {:?}", text, result);
+            };
+            assert_eq!(
+                parsed.commands[0].command, expected,
+                "Wrong command for '{}'",
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn test_oh_as_zero() {
+        let test_cases = [
+            (
+                "Lufthansa 123, climb flight level two oh oh",
+                AviationCommandPart::ChangeAltitude {
+                    altitude: Altitude::FlightLevel(200),
+                    maintain: false,
+                    discretion: false,
+                    turn_direction: Some(VerticalDirection::Climb),
+                },
+            ),
+            (
+                "Lufthansa 123, fly heading oh niner oh",
+                AviationCommandPart::FlyHeading {
+                    heading: HeadingDirection::Heading(Heading::new(90.0)),
+                    turn_direction: None,
+                },
+            ),
+        ];
+        for (text, expected) in test_cases {
+            let result = COMMAND_PARSER.parse_transmission_enhanced(text);
+            let ParseResult::Success(parsed) = result else {
+                panic!("Expected success for '{}', got: {:?}", text, result);
+            };
+            assert_eq!(parsed.commands.len(), 1, "Wrong commands for '{}'", text);
+            assert_eq!(parsed.commands[0].command, expected);
+        }
+
+        assert_eq!(
+            COMMAND_PARSER.convert_spoken_to_digits("one oh nine"),
+            "1 0 9"
+        );
+        // "oh" is only a digit next to another digit
+        assert_eq!(
+            COMMAND_PARSER.convert_spoken_to_digits("oh well, say heading"),
+            "oh well, say heading"
+        );
+    }
+
+    #[test]
+    fn test_number_homophones() {
+        let text = "Lufthansa 123, turn left heading two for oh";
+        let expected = AviationCommandPart::FlyHeading {
+            heading: HeadingDirection::Heading(Heading::new(240.0)),
+            turn_direction: Some(TurnDirection::Left),
+        };
+
+        let mut parser = COMMAND_PARSER.clone();
+        let config = ParserConfig::load_default().expect("Default config should load");
+        parser.add_number_homophones(&config.number_homophones);
+        parser.add_number_homophone("for", 4);
+        let result = parser.parse_transmission_enhanced(text);
+        let ParseResult::Success(parsed) = result else {
+            panic!("Expected success for '{}', got: {:?}", text, result);
+        };
+        assert_eq!(parsed.commands.len(), 1, "Wrong commands for '{}'", text);
+        assert_eq!(parsed.commands[0].command, expected);
+
+        // Without the homophone, "for" isn't a digit
+        assert_eq!(
+            COMMAND_PARSER.convert_spoken_to_digits("two for oh"),
+            "2 for oh"
+        );
+        // Only next to a digit
+        assert_eq!(
+            parser.convert_spoken_to_digits("looking for traffic"),
+            "looking for traffic"
+        );
+        // "ate" comes from the config
+        assert_eq!(parser.convert_spoken_to_digits("one ate zero"), "1 8 0");
+        assert_eq!(
+            COMMAND_PARSER.convert_spoken_to_digits("one ate zero"),
+            "1 ate 0"
+        );
+        assert_eq!(
+            COMMAND_PARSER.convert_spoken_to_digits("fiver thousand"),
+            "5 thousand"
+        );
+    }
+
+    #[test]
+    fn test_command_group_round_trip() {
+        let group = AviationCommandGroup {
+            target: Some(CommunicationEntity::Aircraft {
+                full_name: "DLH123".to_string(),
+            }),
+            parts: vec![
+                AviationCommandPart::FlyHeading {
+                    heading: HeadingDirection::Heading(Heading::new(270.0)),
+                    turn_direction: None,
+                },
+                AviationCommandPart::ChangeAltitude {
+                    altitude: Altitude::FlightLevel(350),
+                    maintain: true,
+                    discretion: false,
+                    turn_direction: Some(VerticalDirection::Climb),
+                },
+            ],
+        };
+        let expected = ParsedCommand::from_command_group(&group);
+        assert_eq!(expected.callsign, "DLH123");
+        assert_eq!(expected.to_command_group(), group);
+
+        let result = COMMAND_PARSER.parse_transmission_enhanced(
+            "Lufthansa 123, fly heading 270, climb and maintain flight level 350",
+        );
+        let ParseResult::Success(parsed) = result else {
+            panic!("Expected success, got: {:?}", result);
+        };
+        assert_eq!(parsed.to_command_group(), expected.to_command_group());
+    }
+
+    #[test]
+    fn test_max_commands() {
+        let text = "Lufthansa 123, turn left heading 270, climb and maintain flight level 350, \
+                    contact tower 118.1, squawk 7700";
+        let ParseResult::Success(unlimited) = COMMAND_PARSER.parse_transmission_enhanced(text)
+        else {
+            panic!("Expected success for '{}'", text);
+        };
+        assert_eq!(unlimited.commands.len(), 4);
+
+        let mut parser = COMMAND_PARSER.clone();
+        parser.set_max_commands(Some(2));
+        let result = parser.parse_transmission_enhanced(text);
+        let ParseResult::PartialSuccess {
+            parsed,
+            unparsed_parts,
+        } = result
+        else {
+            panic!("Expected partial success, got: {:?}", result);
+        };
+        assert_eq!(parsed.commands.len(), 2);
+        assert_eq!(unparsed_parts.len(), 2);
+
+        // The most confident commands are kept in their order
+        let mut expected = unlimited.commands.clone();
+        expected.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+        let min_kept_confidence = expected[1].confidence;
+        assert!(
+            parsed
+                .commands
+                .iter()
+                .all(|command| command.confidence >= min_kept_confidence)
+        );
+        let kept_positions: Vec<_> = parsed
+            .commands
+            .iter()
+            .map(|kept| {
+                unlimited
+                    .commands
+                    .iter()
+                    .position(|command| command.source_text == kept.source_text)
+                    .unwrap()
+            })
+            .collect();
+        assert!(kept_positions[0] < kept_positions[1]);
+    }
+
+    #[test]
+    fn test_parse_pilots_discretion() {
+        let expected = AviationCommandPart::ChangeAltitude {
+            altitude: Altitude::FlightLevel(100),
+            maintain: false,
+            discretion: true,
+            turn_direction: Some(VerticalDirection::Descend),
+        };
+        for text in [
+            "Lufthansa 123, descend at pilot's discretion flight level 100",
+            "Lufthansa 123, descend flight level 100 at pilots discretion",
+        ] {
+            let result = COMMAND_PARSER.parse_transmission_enhanced(text);
+            let ParseResult::Success(parsed) = result else {
+                panic!("Expected success for '{}', got: {:?}", text, result);
+            };
+            assert_eq!(parsed.commands.len(), 1, "Wrong commands for '{}'", text);
+            assert_eq!(parsed.commands[0].command, expected);
+        }
+
+        let result =
+            COMMAND_PARSER.parse_transmission_enhanced("Lufthansa 123, descend flight level 100");
+        let ParseResult::Success(parsed) = result else {
+            panic!("Expected success, got: {:?}", result);
+        };
+        assert!(matches!(
+            parsed.commands[0].command,
+            AviationCommandPart::ChangeAltitude {
+                discretion: false,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_parse_altimeter() {
+        let test_cases = [
+            ("Lufthansa 123, QNH 1013", Some(1013), None),
+            ("Lufthansa 123, QNH niner niner eight", Some(998), None),
+            ("Lufthansa 123, altimeter 29.92", None, Some(2992)),
+            ("Lufthansa 123, altimeter 3 0 decimal 1 2", None, Some(3012)),
+            ("Lufthansa 123, altimeter 2992", None, Some(2992)),
+        ];
+
+        for (text, hpa, inhg) in test_cases {
+            let result = COMMAND_PARSER.parse_transmission_enhanced(text);
+            let ParseResult::Success(parsed) = result else {
+                panic!("Expected success for '{}', got: {:?}", text, result);
+            };
+            assert_eq!(
+                parsed.commands[0].command,
+                AviationCommandPart::Altimeter { hpa, inhg },
+                "Wrong altimeter setting for '{}'",
+                text
+            );
+        }
+
+        // Out of range for both units
+        let result = COMMAND_PARSER.parse_transmission_enhanced("Lufthansa 123, QNH 500");
+        assert!(!matches!(result, ParseResult::Success(_)));
+    }
+
+    #[test]
+    fn test_parse_vectors_for_ils() {
+        let test_cases = [
+            ("Lufthansa 123, vectors for ILS runway 27", "27"),
+            (
+                "Lufthansa 123, vectors to the ILS approach runway two five left",
+                "25L",
+            ),
+        ];
+
+        for (text, runway) in test_cases {
+            let result = COMMAND_PARSER.parse_transmission_enhanced(text);
+            let ParseResult::Success(parsed) = result else {
+                panic!("Expected success for '{}', got: {:?}", text, result);
+            };
+            assert_eq!(parsed.callsign, "DLH123");
+            assert_eq!(
+                parsed
+                    .commands
+                    .into_iter()
+                    .map(|c| c.command)
+                    .collect::<Vec<_>>(),
+                vec![AviationCommandPart::VectorsForIls {
+                    runway: runway.to_string()
+                }]
+            );
+        }
+
+        // Vectors after another command are a separate command
+        let result = COMMAND_PARSER.parse_transmission_enhanced(
+            "Lufthansa 123, turn left heading 180, vectors for ILS runway 9",
+        );
+        let ParseResult::Success(parsed) = result else {
+            panic!("Expected success, got: {:?}", result);
+        };
+        assert_eq!(parsed.commands.len(), 2);
+        assert_eq!(
+            parsed.commands[1].command,
+            AviationCommandPart::VectorsForIls {
+                runway: "09".to_string()
+            }
+        );
     }
 
-    /// Parse radar contact command with confidence scoring
-    fn parse_radar_contact_with_confidence(
-        &self,
-        text: &str,
-    ) -> Option<(AviationCommandPart, f32)> {
-        // Only match exact "radar contact" or very short variants
-        if text == "radar contact" {
-            return Some((
-                AviationCommandPart::RadarContact,
-                0.98, // Perfect match
-            ));
-        }
+    #[test]
+    fn test_normalize_callsign_keeps_suffix_letter() {
+        assert_eq!(COMMAND_PARSER.normalize_callsign("Lufthansa 123 alpha"), "DLH123A");
+        assert_eq!(COMMAND_PARSER.normalize_callsign("DLH 456B"), "DLH456B");
+    }
 
-        // Allow some short variations but not too much extra text
-        if text.contains("radar")
-            && text.contains("contact")
-            && text.split_whitespace().count() <= 3
-        {
-            return Some((
-                AviationCommandPart::RadarContact,
-                0.85, // Good match but with extra words
-            ));
+    #[test]
+    fn test_normalize_spelled_icao_callsign() {
+        let without_airlines = AviationCommandParser::new(Airlines(Vec::new()));
+        for parser in [&*COMMAND_PARSER, &without_airlines] {
+            assert_eq!(parser.normalize_callsign("delta alpha lima 123"), "DAL123");
+            assert_eq!(parser.normalize_callsign("delta-alfa-lima 123"), "DAL123");
+            assert_eq!(
+                parser.normalize_callsign("delta lima hotel one two three"),
+                "DLH123"
+            );
         }
-
-        None
     }
 
-    /// Get information about a matched airline
-    pub fn get_airline_info(&self, callsign: &str) -> Option<CallsignMatch> {
-        let parts: Vec<&str> = callsign.split_whitespace().collect();
-        if parts.len() < 2 {
-            return None;
-        }
+    #[test]
+    fn test_parse_random_transmissions() {
+        use crate::test_utils::random_transmission;
+        use rand::{SeedableRng, rngs::StdRng};
 
-        // Try different combinations of the first parts as airline name
-        for i in 1..parts.len() {
-            let airline_part = parts[..i].join(" ");
+        let airline = |id, name: &str, icao: &str, callsign: &str| AirlineEntry {
+            id,
+            name: name.to_string(),
+            alias: None,
+            iata: None,
+            icao: Some(icao.to_string()),
+            callsign: Some(callsign.to_string()),
+            country: "Test".to_string(),
+            active: true,
+        };
+        let airlines = Airlines(vec![
+            airline(1, "Lufthansa", "DLH", "Lufthansa"),
+            airline(2, "British Airways", "BAW", "Speedbird"),
+            airline(3, "Delta Air Lines", "DAL", "Delta"),
+            airline(4, "Ryanair", "RYR", "Ryanair"),
+            airline(5, "easyJet", "EZY", "Easy"),
+        ]);
+        let parser = AviationCommandParser::new(airlines.clone());
+        let mut rng = StdRng::seed_from_u64(835);
+
+        for _ in 0..500 {
+            let (text, expected) = random_transmission(&mut rng, &airlines);
+
+            let result = parser.parse_transmission_enhanced(&text);
+            let ParseResult::Success(parsed) = result else {
+                panic!("Expected success for '{}', got: {:?}", text, result);
+            };
+            assert_eq!(
+                parsed.to_command_group(),
+                expected,
+                "Wrong parse of '{}'",
+                text
+            );
+        }
+    }
 
-            // Try direct name match
-            if let Some(icao) = self.match_airline_name(&airline_part) {
-                return Some(CallsignMatch {
-                    icao_code: icao.to_uppercase(),
-                    confidence: 0.9,
-                });
-            }
+    #[test]
+    fn test_parse_spoken_thousands() {
+        let test_cases = [
+            (
+                "Lufthansa 123, descend to six thousand feet",
+                Altitude::Feet(6000.0),
+                VerticalDirection::Descend,
+            ),
+            (
+                "Lufthansa 123, climb to one thousand five hundred",
+                Altitude::Feet(1500.0),
+                VerticalDirection::Climb,
+            ),
+        ];
 
-            // Try phonetic alphabet conversion
-            let phonetic_converted = self.convert_phonetic_callsign(&airline_part);
-            if let Some(icao) = self.match_airline_name(&phonetic_converted) {
-                return Some(CallsignMatch {
-                    icao_code: icao.to_uppercase(),
-                    confidence: 0.85,
-                });
-            }
+        for (text, altitude, direction) in test_cases {
+            let result = COMMAND_PARSER.parse_transmission_enhanced(text);
+            let ParseResult::Success(parsed) = result else {
+                panic!("Expected success for '{}', got: {:?}", text, result);
+            };
+            assert_eq!(
+                parsed
+                    .commands
+                    .into_iter()
+                    .map(|c| c.command)
+                    .collect::<Vec<_>>(),
+                vec![AviationCommandPart::ChangeAltitude {
+                    altitude,
+                    maintain: false,
+                    discretion: false,
+                    turn_direction: Some(direction),
+                }],
+                "Wrong command for '{}'",
+                text
+            );
         }
+    }
 
-        None
+    #[test]
+    fn test_parse_climb_and_maintain_vs_climb_to() {
+        let parse_maintain = |text| {
+            let result = COMMAND_PARSER.parse_transmission_enhanced(text);
+            let ParseResult::Success(parsed) = result else {
+                panic!("Expected success for '{}', got: {:?}", text, result);
+            };
+            let [command] = &parsed.commands[..] else {
+                panic!(
+                    "Expected one command for '{}', got: {:?}",
+                    text, parsed.commands
+                );
+            };
+            let AviationCommandPart::ChangeAltitude {
+                altitude,
+                maintain,
+                turn_direction,
+                ..
+            } = &command.command
+            else {
+                panic!(
+                    "Expected altitude command for '{}', got: {:?}",
+                    text, command
+                );
+            };
+            assert_eq!(*altitude, Altitude::FlightLevel(210));
+            assert_eq!(*turn_direction, Some(VerticalDirection::Climb));
+            *maintain
+        };
+
+        assert!(parse_maintain(
+            "Lufthansa 123, climb and maintain flight level two one zero"
+        ));
+        assert!(!parse_maintain(
+            "Lufthansa 123, climb to flight level two one zero"
+        ));
     }
 
-    /// Check if a callsign matches a known airline pattern
-    pub fn is_known_airline(&self, callsign: &str) -> bool {
-        self.get_airline_info(callsign).is_some()
+    #[test]
+    fn test_parse_trace_finds_failing_token() {
+        let (result, trace) = COMMAND_PARSER.parse_transmission_traced(
+            "Lufthansa 123, fly heading 090, climb flight lebel 350",
+            0.5,
+        );
+        assert!(
+            matches!(result, ParseResult::PartialSuccess { .. }),
+            "Expected partial success, got: {result:?}"
+        );
+        assert_eq!(trace.tokens[0].matched.as_deref(), Some("callsign"));
+        assert!(
+            trace
+                .tokens
+                .iter()
+                .any(|token| token.matched.as_deref() == Some("FlyHeading"))
+        );
+
+        let failing = trace
+            .first_failing_token()
+            .unwrap_or_else(|| panic!("Expected a failing token, got: {trace:?}"));
+        assert_eq!(failing.token, "climb");
+        assert!(
+            failing
+                .misses
+                .iter()
+                .any(|miss| miss.matcher == "altitude" && miss.reason == MissReason::NoMatch),
+            "Expected the altitude matcher to miss, got: {:?}",
+            failing.misses
+        );
     }
 
-    /// Get all possible airline matches for debugging
-    pub fn get_all_airline_matches(&self, name: &str) -> Vec<(String, String)> {
-        let mut matches = Vec::new();
-        let name_key = name.to_lowercase().replace(" ", "");
+    #[test]
+    fn test_parse_maintain_without_unit() {
+        let parse_altitude = |text| {
+            let result = COMMAND_PARSER.parse_transmission_enhanced(text);
+            let ParseResult::Success(parsed) = result else {
+                panic!("Expected success for '{text}', got: {result:?}");
+            };
+            let [command] = &parsed.commands[..] else {
+                panic!(
+                    "Expected one command for '{text}', got: {:?}",
+                    parsed.commands
+                );
+            };
+            let AviationCommandPart::ChangeAltitude {
+                altitude,
+                turn_direction,
+                ..
+            } = &command.command
+            else {
+                panic!("Expected altitude command for '{text}', got: {command:?}");
+            };
+            (*altitude, *turn_direction, command.confidence)
+        };
 
-        for (stored_name, icao) in &self.airline_name_to_icao {
-            if stored_name.contains(&name_key) || name_key.contains(stored_name) {
-                matches.push((icao.to_uppercase(), stored_name.clone()));
-            }
+        let (altitude, _, confidence) = parse_altitude("Lufthansa 123, maintain one zero zero");
+        assert_eq!(altitude, Altitude::FlightLevel(100));
+        let (altitude, direction, _) = parse_altitude("Lufthansa 123, descend and maintain 3000");
+        assert_eq!(altitude, Altitude::Feet(3000.));
+        assert_eq!(direction, Some(VerticalDirection::Descend));
+        let (altitude, _, _) = parse_altitude("Lufthansa 123, maintain 3000");
+        assert_eq!(altitude, Altitude::Feet(3000.));
+
+        // The unit was guessed, so the command is less confident than with the unit
+        let (_, _, confidence_with_unit) =
+            parse_altitude("Lufthansa 123, maintain flight level one zero zero");
+        assert!(confidence < confidence_with_unit);
+
+        // The number is kept for resolving it with the present altitude of the aircraft
+        assert_eq!(
+            COMMAND_PARSER.unitless_altitude("maintain 1 0 0"),
+            Some(100)
+        );
+        assert_eq!(
+            COMMAND_PARSER.unitless_altitude("maintain flight level 100"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_parse_block_altitude() {
+        let expected = AviationCommandPart::MaintainBlock {
+            lower: Altitude::FlightLevel(100),
+            upper: Altitude::FlightLevel(120),
+        };
+        for text in [
+            "Lufthansa 123, maintain block flight level 100 to 120",
+            "Lufthansa 123, maintain block flight level one zero zero to one two zero",
+            "Lufthansa 123, climb and maintain block flight level 120 to flight level 100",
+        ] {
+            let result = COMMAND_PARSER.parse_transmission_enhanced(text);
+            let ParseResult::Success(parsed) = result else {
+                panic!("Expected success for '{text}', got: {result:?}");
+            };
+            let commands: Vec<_> = parsed.commands.iter().map(|c| &c.command).collect();
+            assert_eq!(commands, vec![&expected], "{text}");
         }
 
-        matches
+        // Without "block", it's an ordinary altitude clearance
+        let result =
+            COMMAND_PARSER.parse_transmission_enhanced("Lufthansa 123, maintain flight level 100");
+        let ParseResult::Success(parsed) = result else {
+            panic!("Expected success, got: {result:?}");
+        };
+        assert_eq!(parsed.commands[0].command.name(), "ChangeAltitude");
     }
-}
 
-impl Default for AviationCommandParser {
-    fn default() -> Self {
-        // Load airlines from the default path for default constructor
-        let file =
-            std::fs::File::open("crates/aviation_helper_rs/resources/known_strings/airlines.json")
-                .expect("Failed to open airlines database");
-        let reader = std::io::BufReader::new(file);
-        let airlines = Airlines::load_airlines(reader).expect("Failed to load airlines database");
-        Self::new(airlines)
+    #[test]
+    fn test_parse_without_airlines_database() {
+        let parsers = [
+            AviationCommandParser::new_empty(),
+            AviationCommandParser::load_airlines_or_empty("not an airlines database".as_bytes()),
+        ];
+
+        for parser in parsers {
+            let result = parser.parse_transmission_enhanced("DLH 123, fly heading 090");
+            let ParseResult::Success(parsed) = result else {
+                panic!("Expected success, got: {:?}", result);
+            };
+            assert_eq!(parsed.callsign, "DLH123");
+            assert_eq!(
+                parsed
+                    .commands
+                    .into_iter()
+                    .map(|c| c.command)
+                    .collect::<Vec<_>>(),
+                vec![AviationCommandPart::FlyHeading {
+                    heading: HeadingDirection::Heading(Heading::new(90.0)),
+                    turn_direction: None,
+                }]
+            );
+
+            // Without database the airline is unknown, so the confidence is lower
+            let parsed = parser
+                .parse_transmission("Lufthansa 123, fly heading 090")
+                .unwrap();
+            assert_eq!(parsed.callsign, "LUFTHANSA123");
+            assert!(parsed.callsign_confidence < 0.95);
+            assert!(!parser.is_known_airline("Lufthansa 123"));
+        }
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use std::sync::LazyLock;
+    #[test]
+    fn test_parse_queries() {
+        let test_cases = [
+            ("Lufthansa 123, report heading", QueryField::Heading),
+            ("Lufthansa 123, say altitude", QueryField::Altitude),
+            ("Lufthansa 123, say your speed", QueryField::Speed),
+        ];
 
-    use super::*;
-    use aviation_helper_rs::clearance::airlines::AirlineEntry;
+        for (text, field) in test_cases {
+            let result = COMMAND_PARSER.parse_transmission_enhanced(text);
+            let ParseResult::Success(parsed) = result else {
+                panic!("Expected success for '{}', got: {:?}", text, result);
+            };
+            assert_eq!(parsed.callsign, "DLH123");
+            assert_eq!(
+                parsed
+                    .commands
+                    .into_iter()
+                    .map(|c| c.command)
+                    .collect::<Vec<_>>(),
+                vec![AviationCommandPart::Query { field }]
+            );
+        }
 
-    static AIRLINES: LazyLock<Airlines> =
-        LazyLock::new(|| Airlines::load_airlines_from_file().unwrap());
+        let parsed = COMMAND_PARSER
+            .parse_transmission("Lufthansa 123, fly heading 090, report altitude")
+            .unwrap();
+        assert_eq!(parsed.commands.len(), 2);
+        assert_eq!(
+            parsed.commands[1].command,
+            AviationCommandPart::Query {
+                field: QueryField::Altitude
+            }
+        );
+    }
 
-    static COMMAND_PARSER: LazyLock<AviationCommandParser> =
-        LazyLock::new(|| AviationCommandParser::new(AIRLINES.clone()));
+    #[test]
+    fn test_parse_disregard_and_standby() {
+        let test_cases = [
+            ("Lufthansa 123, disregard", AviationCommandPart::Disregard),
+            ("Lufthansa 123, standby", AviationCommandPart::Standby),
+        ];
+
+        for (text, command) in test_cases {
+            let result = COMMAND_PARSER.parse_transmission_enhanced(text);
+            let ParseResult::Success(parsed) = result else {
+                panic!("Expected success for '{}', got: {:?}", text, result);
+            };
+            assert_eq!(parsed.callsign, "DLH123");
+            assert_eq!(parsed.commands.len(), 1);
+            assert_eq!(parsed.commands[0].command, command);
+        }
+
+        let result =
+            COMMAND_PARSER.parse_transmission_enhanced("Lufthansa 123, fly heading 090, disregard");
+        let ParseResult::Success(parsed) = result else {
+            panic!("Expected success, got: {:?}", result);
+        };
+        assert_eq!(
+            parsed
+                .commands
+                .into_iter()
+                .map(|c| c.command)
+                .collect::<Vec<_>>(),
+            vec![
+                AviationCommandPart::FlyHeading {
+                    heading: HeadingDirection::Heading(Heading::new(90.0)),
+                    turn_direction: None,
+                },
+                AviationCommandPart::Disregard,
+            ]
+        );
+    }
 
     #[test]
-    fn test_parse_complete_transmission() {
-        let result = COMMAND_PARSER.parse_transmission("Lufthansa 123, turn left 30 degrees");
+    fn test_parse_with_min_confidence() {
+        // "radar contact" with an extra word is parsed with lower confidence
+        let text = "Lufthansa 123, fly heading 090, radar contact established";
 
-        assert!(result.is_some());
-        let parsed = result.unwrap();
-        assert_eq!(parsed.callsign, "DLH123"); // Expect ICAO code without space: DLH123
+        let result = COMMAND_PARSER.parse_transmission_enhanced_with_min_confidence(text, 0.0);
+        let ParseResult::Success(parsed) = result else {
+            panic!("Expected success at min confidence 0.0, got: {:?}", result);
+        };
+        assert_eq!(parsed.commands.len(), 2);
+        assert!(parsed.commands[1].confidence < 0.9);
 
+        let result = COMMAND_PARSER.parse_transmission_enhanced_with_min_confidence(text, 0.9);
+        let ParseResult::PartialSuccess {
+            parsed,
+            unparsed_parts,
+        } = result
+        else {
+            panic!(
+                "Expected partial success at min confidence 0.9, got: {:?}",
+                result
+            );
+        };
         assert_eq!(
-            &parsed
+            parsed
                 .commands
                 .into_iter()
                 .map(|c| c.command)
-                .collect::<Vec<_>>()[..],
-            &[AviationCommandPart::TurnBy {
-                turn_direction: Some(TurnDirection::Left),
-                degrees: Degrees(30.0)
-            }],
-            "Expected: \"turn left heading 270\""
+                .collect::<Vec<_>>(),
+            vec![AviationCommandPart::FlyHeading {
+                heading: HeadingDirection::Heading(Heading::new(90.0)),
+                turn_direction: None,
+            }]
+        );
+        assert_eq!(unparsed_parts, vec!["radar contact established"]);
+
+        let result = COMMAND_PARSER.parse_transmission_enhanced_with_min_confidence(
+            "Lufthansa 123, radar contact established",
+            0.9,
+        );
+        assert!(
+            matches!(result, ParseResult::Failed { .. }),
+            "Expected failure at min confidence 0.9, got: {:?}",
+            result
+        );
+    }
+
+    #[test]
+    fn test_parse_callsign_without_comma() {
+        let test_cases = [
+            "lufthansa one two three turn left heading two seven zero",
+            "delta lima hotel one two three turn left heading two seven zero",
+            "Lufthansa 123 turn left heading 270",
+        ];
+
+        for text in test_cases {
+            let result = COMMAND_PARSER.parse_transmission_enhanced(text);
+            let ParseResult::Success(parsed) = result else {
+                panic!("Expected success for '{}', got: {:?}", text, result);
+            };
+            assert_eq!(parsed.callsign, "DLH123", "Wrong callsign for '{}'", text);
+            assert_eq!(
+                parsed
+                    .commands
+                    .into_iter()
+                    .map(|c| c.command)
+                    .collect::<Vec<_>>(),
+                vec![AviationCommandPart::FlyHeading {
+                    heading: HeadingDirection::Heading(Heading::new(270.0)),
+                    turn_direction: None,
+                }],
+                "Wrong commands for '{}'",
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn test_verify_word_maps() {
+        assert_eq!(COMMAND_PARSER.verify_word_maps(), Ok(()));
+
+        let mut parser = COMMAND_PARSER.clone();
+        parser.phonetic_alphabet.remove("xray");
+        parser.number_words.remove("niner");
+        parser.add_filler_word("Climb");
+        let problems = parser.verify_word_maps().unwrap_err();
+        // "nine" still covers 9
+        assert_eq!(
+            problems,
+            vec![
+                "No phonetic word for letter X".to_string(),
+                "'climb' is used as altitude word and as filler word".to_string(),
+                "'climb' is used as command keyword and as filler word".to_string(),
+            ]
         );
+
+        parser.number_words.remove("nine");
+        parser.number_words.remove("9");
+        let problems = parser.verify_word_maps().unwrap_err();
+        assert!(problems.contains(&"No number word for digit 9".to_string()));
     }
 
     #[test]
@@ -1308,7 +4170,7 @@ mod tests {
                 &cmd_with_conf.command
             {
                 assert_eq!(frequency.num, 121);
-                assert_eq!(frequency.dec, 5);
+                assert_eq!(frequency.dec, 500);
                 assert_eq!(station, &Some("tower".to_string()));
             } else {
                 panic!("Expected frequency command");
@@ -1438,7 +4300,7 @@ mod tests {
                         &cmd_with_conf.command
                     {
                         assert_eq!(frequency.num, 121);
-                        assert_eq!(frequency.dec, 5);
+                        assert_eq!(frequency.dec, 500);
                     } else {
                         panic!(
                             "Expected ContactFrequency command, got: {:?}",
@@ -1584,7 +4446,9 @@ mod tests {
         if let Some((callsign, _commands)) = extraction {
             let normalized = COMMAND_PARSER.normalize_callsign(&callsign);
             println!("Normalized callsign: '{}'", normalized);
-            let confidence = COMMAND_PARSER.calculate_callsign_confidence(&callsign);
+            let confidence = COMMAND_PARSER
+                .calculate_callsign_confidence(&callsign)
+                .confidence;
             println!("Confidence: {}", confidence);
 
             assert_eq!(normalized, "DLH123");
@@ -1671,6 +4535,41 @@ mod tests {
         assert!(parser.is_known_airline("Super Long 456"));
     }
 
+    #[test]
+    fn test_airline_matches_are_sorted() {
+        let airline = |id, name: &str, icao: &str, callsign: &str| AirlineEntry {
+            id,
+            name: name.to_string(),
+            alias: None,
+            iata: None,
+            icao: Some(icao.to_string()),
+            callsign: Some(callsign.to_string()),
+            country: "Test Country".to_string(),
+            active: true,
+        };
+        let mut parser = COMMAND_PARSER.clone();
+        parser.load_airlines(Airlines(vec![
+            airline(1, "Sky Express", "SEH", "Air Crete"),
+            airline(2, "Sky Airline", "SKU", "Aerosky"),
+            airline(3, "Skyways", "SKX", "Skyways"),
+        ]));
+
+        let expected = [
+            ("SKU", "skyairline"),
+            ("SEH", "skyexpress"),
+            ("SKU", "aerosky"),
+            ("SKX", "skyways"),
+        ];
+        for _ in 0..3 {
+            let matches = parser.get_all_airline_matches("Sky");
+            let matches: Vec<_> = matches
+                .iter()
+                .map(|(icao, name)| (icao.as_str(), name.as_str()))
+                .collect();
+            assert_eq!(matches, expected);
+        }
+    }
+
     #[test]
     fn test_phonetic_conversion_debug() {
         // Test phonetic conversion function directly
@@ -1928,4 +4827,45 @@ mod tests {
             other => panic!("Expected success or partial success, got: {:?}", other),
         }
     }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_parse_result_json_round_trip() {
+        let result = COMMAND_PARSER.parse_transmission_enhanced(
+            "Lufthansa 123, fly heading 090, climb and maintain flight level 350",
+        );
+        assert!(matches!(result, ParseResult::Success(_)));
+
+        let json = serde_json::to_string(&result).unwrap();
+        assert!(json.contains(r#""callsign":"DLH123""#));
+
+        let deserialized: ParseResult = serde_json::from_str(&json).unwrap();
+        let ParseResult::Success(parsed) = deserialized else {
+            panic!("Expected success after round trip: {json}");
+        };
+        assert_eq!(parsed.callsign, "DLH123");
+        assert_eq!(
+            parsed
+                .commands
+                .iter()
+                .map(|c| c.command.clone())
+                .collect::<Vec<_>>(),
+            vec![
+                AviationCommandPart::FlyHeading {
+                    heading: HeadingDirection::Heading(Heading::new(90.0)),
+                    turn_direction: None,
+                },
+                AviationCommandPart::ChangeAltitude {
+                    altitude: Altitude::FlightLevel(350),
+                    maintain: true,
+                    discretion: false,
+                    turn_direction: Some(VerticalDirection::Climb),
+                },
+            ]
+        );
+        assert_eq!(
+            serde_json::to_string(&ParseResult::Success(parsed)).unwrap(),
+            json
+        );
+    }
 }
@@ -4,6 +4,7 @@
 //! This module orchestrates the speech-to-text and command parsing components.
 
 use std::{
+    collections::HashMap,
     sync::{Arc, Mutex, mpsc},
     thread,
     time::Duration,
@@ -12,37 +13,107 @@ use std::{
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
 use ringbuf::{
     HeapRb,
-    traits::{Consumer, Producer, Split},
+    traits::{Consumer, Observer, Producer, Split},
 };
 use rubato::Resampler;
 
-use crate::{AviationCommandParser, Error, SpeechToTextConfig, SpeechToText, create_resampler};
+use crate::{
+    AviationCommandParser, Error, OverflowPolicy, RecognitionConfig, SentenceBoundary, SentenceCut,
+    SpeechToText, SpeechToTextConfig, Transcribe, TranscriptionResult, create_resampler,
+    graph_parser::preprocess_whisper_text, to_f32_samples,
+};
 use aviation_helper_rs::clearance::{airlines::Airlines, aviation_command::AviationCommandPart};
 
 const SAMPLE_RATE_HZ: u32 = 16000;
 
+/// Rewrites the transcribed text before it is parsed, e.g. to expand local abbreviations or fix
+/// names. The parser still applies its own normalization to the result.
+pub type TextPreprocessor = Box<dyn Fn(&str) -> String + Send>;
+
 /// Voice recognizer that captures audio and converts it to aviation commands
 /// This is the main orchestrator that combines speech-to-text and command parsing
-pub struct VoiceRecognizer {
-    config: SpeechToTextConfig,
-    speech_to_text: SpeechToText,
+pub struct VoiceRecognizer<T = SpeechToText> {
+    speech_to_text: T,
     parser: AviationCommandParser,
+    overflow_policy: OverflowPolicy,
+    recognition_config: RecognitionConfig,
+    sentence_boundary: SentenceBoundary,
+    text_preprocessor: TextPreprocessor,
+    /// Corrections of the parser config, that run after the text preprocessor
+    recognition_corrections: HashMap<String, String>,
+}
+
+/// Keeps the audio capture of `VoiceRecognizer::spawn_recognition` running
+pub struct RecognitionHandle {
+    _input_stream: cpal::Stream,
+}
+
+/// Text of the streaming recognition
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RecognitionEvent {
+    /// Text of audio, that is transcribed again together with the following audio
+    Partial(String),
+    /// Text up to a sentence boundary, that doesn't change anymore
+    Final(String),
 }
 
 impl VoiceRecognizer {
     pub fn new(config: SpeechToTextConfig, airlines: Airlines) -> Result<Self, Error> {
-        let speech_to_text = SpeechToText::new(config.clone())?;
-        let parser = AviationCommandParser::new(airlines);
+        let speech_to_text = SpeechToText::new(config)?;
+        Ok(Self::with_transcriber(speech_to_text, airlines))
+    }
+}
 
-        Ok(Self {
-            config,
+impl<T: Transcribe> VoiceRecognizer<T> {
+    /// Recognizer, that uses another speech-to-text engine than Whisper, e.g. a stub in tests
+    pub fn with_transcriber(speech_to_text: T, airlines: Airlines) -> Self {
+        Self {
             speech_to_text,
-            parser,
-        })
+            parser: AviationCommandParser::new(airlines),
+            overflow_policy: OverflowPolicy::default(),
+            recognition_config: RecognitionConfig::default(),
+            sentence_boundary: SentenceBoundary::default(),
+            text_preprocessor: Box::new(str::to_owned),
+            recognition_corrections: HashMap::new(),
+        }
+    }
+
+    /// Set what happens to the audio, if the recognition can't keep up
+    pub fn with_overflow_policy(mut self, overflow_policy: OverflowPolicy) -> Self {
+        self.overflow_policy = overflow_policy;
+        self
+    }
+
+    /// Set the window length and timing of the streaming recognition
+    pub fn with_recognition_config(mut self, recognition_config: RecognitionConfig) -> Self {
+        self.recognition_config = recognition_config;
+        self
+    }
+
+    /// Set where the streaming recognition cuts the transcribed audio
+    pub fn with_sentence_boundary(mut self, sentence_boundary: SentenceBoundary) -> Self {
+        self.sentence_boundary = sentence_boundary;
+        self
+    }
+
+    /// Set the corrections of common misrecognitions, usually the ones of the `ParserConfig`.
+    /// They are applied after the text preprocessor.
+    pub fn with_recognition_corrections(
+        mut self,
+        recognition_corrections: HashMap<String, String>,
+    ) -> Self {
+        self.recognition_corrections = recognition_corrections;
+        self
+    }
+
+    /// Set the rewrite of the transcribed text, that is applied before parsing. Replaces the
+    /// previous one.
+    pub fn set_text_preprocessor(&mut self, text_preprocessor: TextPreprocessor) {
+        self.text_preprocessor = text_preprocessor;
     }
 
     /// Get a reference to the speech-to-text component
-    pub fn speech_to_text(&self) -> &SpeechToText {
+    pub fn speech_to_text(&self) -> &T {
         &self.speech_to_text
     }
 
@@ -52,9 +123,37 @@ impl VoiceRecognizer {
     }
 
     /// Start continuous voice recognition with a callback for each recognized command
-    pub fn start_continuous_recognition<F>(self, callback: F) -> Result<(), Error>
+    pub fn start_continuous_recognition<F>(mut self, callback: F) -> Result<(), Error>
     where
+        T: Send + 'static,
         F: Fn(AviationCommandPart) + Send + 'static,
+    {
+        let parser = std::mem::replace(&mut self.parser, AviationCommandParser::new_empty());
+        let _recognition = self.spawn_recognition(move |text| {
+            println!("Received recognized text: {}", text);
+
+            if let Some(command) = parser.parse(&text) {
+                println!("Parsed command: {:?}", command);
+                callback(command);
+            } else {
+                println!("No valid command found in: {}", text);
+            }
+        })?;
+        println!("Voice recognition started. Speak aviation commands...");
+
+        // Keep the main thread alive
+        loop {
+            thread::sleep(Duration::from_secs(1));
+        }
+    }
+
+    /// Start the streaming recognition in the background and pass each final text to `on_text`,
+    /// after it was preprocessed, e.g. to parse it in the game. Audio is captured, until the
+    /// returned handle is dropped.
+    pub fn spawn_recognition<F>(self, on_text: F) -> Result<RecognitionHandle, Error>
+    where
+        T: Send + 'static,
+        F: Fn(String) + Send + 'static,
     {
         let cpal_host = cpal::default_host();
         let input_device = cpal_host
@@ -66,17 +165,25 @@ impl VoiceRecognizer {
             println!("Using input device: {}", name);
         }
 
-        let config: cpal::StreamConfig = input_device.default_input_config()?.into();
+        // Devices deliver their own sample format, which is converted to f32 in the callback
+        let supported_config = input_device.default_input_config()?;
+        let sample_format = supported_config.sample_format();
+        let config: cpal::StreamConfig = supported_config.into();
         let sample_rate_in = config.sample_rate.0;
         let channel_count_in = config.channels;
 
-        let latency_samples = SAMPLE_RATE_HZ * self.config.window_len_seconds;
-        let ring = HeapRb::<f32>::new(latency_samples as usize * 8);
+        let recognition_config = self.recognition_config.clone();
+        let ring = HeapRb::<f32>::new(recognition_config.ring_capacity());
         let (mut producer, consumer) = ring.split();
+        let consumer = Arc::new(Mutex::new(SharedConsumer::new(consumer)));
+        let consumer_clone = consumer.clone();
+        let overflow_policy = self.overflow_policy;
 
         let resample_buffer: Arc<Mutex<[Vec<f32>; 1]>> = Arc::new(Mutex::new([vec![]]));
 
-        let input_data_fn = move |data: &[f32], _: &cpal::InputCallbackInfo| {
+        let input_data_fn = move |data: &cpal::Data, _: &cpal::InputCallbackInfo| {
+            let samples = to_f32_samples(data.bytes(), sample_format);
+            let data = &samples[..];
             let Ok(mut rb) = resample_buffer.lock() else {
                 eprintln!("Could not lock mutex");
                 return;
@@ -107,103 +214,117 @@ impl VoiceRecognizer {
                 data
             };
 
-            let len = data2.len();
-            let pushed_count = producer.push_slice(data2);
-            if len - pushed_count != 0 {
+            let plan = overflow_policy.plan(
+                producer.capacity().get(),
+                producer.vacant_len(),
+                data2.len(),
+            );
+            if plan.skip_buffered > 0 {
+                let Ok(mut cons) = consumer.lock() else {
+                    eprintln!("Could not lock mutex");
+                    return;
+                };
+                cons.drop_oldest(plan.skip_buffered);
+            }
+            producer.push_slice(&data2[plan.push_range.clone()]);
+            if plan.dropped_len(data2.len()) != 0 {
                 eprintln!("Mic buffer overflow");
             }
         };
 
-        let input_stream = input_device.build_input_stream(&config, input_data_fn, err_fn, None)?;
-        let consumer_clone = Arc::new(Mutex::new(consumer));
-
-        let (tx, rx) = mpsc::channel::<String>();
-        let parser = self.parser;
-        let config = self.config.clone();
-
-        // Recognition thread - simplified for now to use test commands
+        let input_stream = input_device.build_input_stream_raw(
+            &config,
+            sample_format,
+            input_data_fn,
+            err_fn,
+            None,
+        )?;
+
+        let (tx, rx) = mpsc::channel::<RecognitionEvent>();
+        let text_preprocessor = self.text_preprocessor;
+        let recognition_corrections = self.recognition_corrections;
+        let mut speech_to_text = self.speech_to_text;
+        let sentence_boundary = self.sentence_boundary;
+
+        // Recognition thread
         thread::spawn(move || {
-            // Note: In a full implementation, this would use the SpeechToText component
-            // For now, we continue with the test simulation
-
-            let mut audio_buffer =
-                vec![0.0f32; SAMPLE_RATE_HZ as usize * config.window_len_seconds as usize];
+            let mut audio_buffer = vec![0.0f32; recognition_config.window_len_samples()];
 
             loop {
-                let read_samples_len = {
+                let (read_samples_len, dropped_at_peek) = {
                     let cons = consumer_clone.lock().unwrap();
-                    cons.peek_slice(&mut audio_buffer)
+                    cons.peek(&mut audio_buffer)
                 };
 
-                if read_samples_len < SAMPLE_RATE_HZ as usize {
-                    thread::sleep(Duration::from_millis(config.check_interval_ms));
+                let Some(snippet_len) = recognition_config.snippet_len(read_samples_len) else {
+                    thread::sleep(recognition_config.check_interval());
                     continue;
-                }
-
-                // For now, use test commands until we solve the whisper API issue
-                let test_commands = [
-                    "turn left heading two seven zero",
-                    "climb and maintain flight level three five zero",
-                    "contact tower on frequency one two one point five",
-                    "descend to four thousand feet",
-                ];
-
-                static mut COMMAND_INDEX: usize = 0;
-                let test_text = unsafe {
-                    let cmd = test_commands[COMMAND_INDEX % test_commands.len()].to_string();
-                    COMMAND_INDEX += 1;
-                    cmd
                 };
 
-                println!("Test recognition: {}", test_text);
-                let _ = tx.send(test_text);
+                let (event, consumed_len) = recognize_snippet(
+                    &mut speech_to_text,
+                    &audio_buffer[..snippet_len],
+                    &recognition_config,
+                    sentence_boundary,
+                );
+                if let Some(event) = event {
+                    if let RecognitionEvent::Final(text) = &event {
+                        println!("Recognized: {text}");
+                    }
+                    let _ = tx.send(event);
+                }
 
-                let mut cons = consumer_clone.lock().unwrap();
-                cons.skip(read_samples_len);
-                drop(cons);
+                if consumed_len > 0 {
+                    let mut cons = consumer_clone.lock().unwrap();
+                    cons.skip_recognized(consumed_len, dropped_at_peek);
+                }
 
-                thread::sleep(Duration::from_millis(config.check_interval_ms));
+                thread::sleep(recognition_config.check_interval());
             }
         });
 
-        // Command processing thread
+        // Text processing thread
         thread::spawn(move || {
-            while let Ok(recognized_text) = rx.recv() {
-                println!("Received recognized text: {}", recognized_text);
-
-                if let Some(command) = parser.parse(&recognized_text) {
-                    println!("Parsed command: {:?}", command);
-                    callback(command);
-                } else {
-                    println!("No valid command found in: {}", recognized_text);
-                }
+            while let Ok(event) = rx.recv() {
+                let recognized_text = match event {
+                    RecognitionEvent::Partial(text) => {
+                        println!("Partially recognized: {text}");
+                        continue;
+                    }
+                    RecognitionEvent::Final(text) => text,
+                };
+                on_text(preprocess_whisper_text(
+                    &text_preprocessor(&recognized_text),
+                    &recognition_corrections,
+                ));
             }
         });
 
         input_stream.play()?;
-        println!("Voice recognition started. Speak aviation commands...");
-
-        // Keep the main thread alive
-        loop {
-            thread::sleep(Duration::from_secs(1));
-        }
+        Ok(RecognitionHandle {
+            _input_stream: input_stream,
+        })
     }
 
     /// Process audio samples and return the recognized text and parsed commands
     /// Accepts a slice of f32 audio samples (expected to be 16kHz mono)
     pub fn process_audio_samples(
-        &self,
+        &mut self,
         samples: &[f32],
     ) -> Result<(String, Option<crate::parser::ParsedCommand>), Error> {
         println!("Processing {} audio samples", samples.len());
 
         // Use the speech-to-text component for transcription
-        let transcribed_text = self.speech_to_text.transcribe_with_whisper(samples)?;
+        let transcribed_text = self.speech_to_text.transcribe(samples)?.text;
 
         println!("Transcribed text: '{}'", transcribed_text);
 
         // Parse the transcribed text with our aviation command parser
-        let parsed_command = match self.parser.parse_transmission_enhanced(&transcribed_text) {
+        let preprocessed_text = preprocess_whisper_text(
+            &(self.text_preprocessor)(&transcribed_text),
+            &self.recognition_corrections,
+        );
+        let parsed_command = match self.parser.parse_transmission_enhanced(&preprocessed_text) {
             crate::parser::ParseResult::Success(parsed) => Some(parsed),
             crate::parser::ParseResult::PartialSuccess { parsed, .. } => Some(parsed),
             _ => None,
@@ -213,6 +334,315 @@ impl VoiceRecognizer {
     }
 }
 
+/// Consumer of the mic samples, that the audio callback and the recognition thread share. The
+/// callback drops the oldest samples on overflow, while the recognition thread transcribes a
+/// copy of them, so it must not skip them again afterwards.
+struct SharedConsumer<C> {
+    consumer: C,
+    /// Number of samples, that the callback dropped so far. Wraps around.
+    dropped_len: usize,
+}
+
+impl<C: Consumer<Item = f32>> SharedConsumer<C> {
+    fn new(consumer: C) -> Self {
+        Self {
+            consumer,
+            dropped_len: 0,
+        }
+    }
+
+    /// Drops the oldest samples to make room for new ones
+    fn drop_oldest(&mut self, len: usize) {
+        let dropped_len = self.consumer.skip(len);
+        self.dropped_len = self.dropped_len.wrapping_add(dropped_len);
+    }
+
+    /// Copies the oldest samples into `buffer`. Returns their number and the mark, that is passed
+    /// to `skip_recognized`.
+    fn peek(&self, buffer: &mut [f32]) -> (usize, usize) {
+        (self.consumer.peek_slice(buffer), self.dropped_len)
+    }
+
+    /// Skips the recognized samples from the start of a peek, apart from the ones, that were
+    /// dropped since then
+    fn skip_recognized(&mut self, consumed_len: usize, dropped_at_peek: usize) {
+        let dropped_since_peek = self.dropped_len.wrapping_sub(dropped_at_peek);
+        self.consumer
+            .skip(consumed_len.saturating_sub(dropped_since_peek));
+    }
+}
+
+/// Transcribes a snippet of the streaming recognition. Returns the text as event, if there is
+/// any, and the number of samples, that are consumed from the ring buffer.
+fn recognize_snippet<T: Transcribe>(
+    speech_to_text: &mut T,
+    snippet: &[f32],
+    recognition_config: &RecognitionConfig,
+    sentence_boundary: SentenceBoundary,
+) -> (Option<RecognitionEvent>, usize) {
+    let snippet_len = snippet.len();
+    let result = speech_to_text.transcribe(snippet).unwrap_or_else(|err| {
+        eprintln!("Transcription failed: {err}");
+        TranscriptionResult::default()
+    });
+    // Without a boundary, the snippet is kept until it can't grow anymore
+    let cut = sentence_boundary.cut(&result, snippet_len).or_else(|| {
+        SentenceBoundary::FullBuffer
+            .cut(&result, snippet_len)
+            .filter(|_| snippet_len >= recognition_config.max_snippet_len())
+    });
+    let Some(SentenceCut { consumed_len, text }) = cut else {
+        return (result.into_text().map(RecognitionEvent::Partial), 0);
+    };
+    // The next utterance starts without the context of this one
+    if recognition_config.reset_state_each_utterance {
+        speech_to_text.reset();
+    }
+    let event = (!text.is_empty()).then_some(RecognitionEvent::Final(text));
+    (event, consumed_len)
+}
+
 fn err_fn(err: cpal::StreamError) {
     eprintln!("Audio stream error: {}", err);
 }
+
+#[cfg(test)]
+mod tests {
+    use aviation_helper_rs::clearance::{
+        airlines::Airlines,
+        aviation_command::{AviationCommandPart, HeadingDirection},
+    };
+    use aviation_helper_rs::types::heading::Heading;
+
+    use std::collections::{HashMap, VecDeque};
+
+    use ringbuf::{
+        HeapRb,
+        traits::{Consumer, Producer, Split},
+    };
+
+    use super::{RecognitionEvent, SharedConsumer, VoiceRecognizer, recognize_snippet};
+    use crate::test_utils::StubSpeechToText;
+    use crate::{Error, RecognitionConfig, SentenceBoundary, Transcribe, TranscriptionResult};
+
+    #[test]
+    fn test_process_audio_samples_with_stub() {
+        let samples = vec![0.1, -0.2, 0.3, -0.4];
+        let stub = StubSpeechToText::default()
+            .with_transcription(samples.clone(), "Lufthansa 123, turn left heading 270");
+        let airlines = Airlines::load_airlines_from_file().unwrap();
+        let mut recognizer = VoiceRecognizer::with_transcriber(stub, airlines);
+
+        let (text, parsed) = recognizer.process_audio_samples(&samples).unwrap();
+        assert_eq!(text, "Lufthansa 123, turn left heading 270");
+        let parsed = parsed.expect("Expected a parsed transmission");
+        assert_eq!(parsed.callsign, "DLH123");
+        assert_eq!(
+            parsed
+                .commands
+                .into_iter()
+                .map(|c| c.command)
+                .collect::<Vec<_>>(),
+            vec![AviationCommandPart::FlyHeading {
+                heading: HeadingDirection::Heading(Heading::from(270.)),
+                turn_direction: None,
+            }]
+        );
+
+        // Audio without a transcription is not parsed
+        let (text, parsed) = recognizer.process_audio_samples(&[0.5; 4]).unwrap();
+        assert_eq!(text, "");
+        assert!(parsed.is_none());
+    }
+
+    #[test]
+    fn test_text_preprocessor_runs_before_parsing() {
+        let samples = vec![0.1, -0.2, 0.3, -0.4];
+        let stub = StubSpeechToText::default()
+            .with_transcription(samples.clone(), "Lufthansa 123, proceed direct KILO ALPHA");
+        let airlines = Airlines::load_airlines_from_file().unwrap();
+        let mut recognizer = VoiceRecognizer::with_transcriber(stub, airlines);
+        recognizer.set_text_preprocessor(Box::new(|text| text.replace("KILO ALPHA", "KOLAX")));
+
+        let (text, parsed) = recognizer.process_audio_samples(&samples).unwrap();
+        // The transcription is returned as it was recognized
+        assert_eq!(text, "Lufthansa 123, proceed direct KILO ALPHA");
+        let parsed = parsed.expect("Expected a parsed transmission");
+        // The callsign is still normalized by the parser
+        assert_eq!(parsed.callsign, "DLH123");
+        assert_eq!(
+            parsed
+                .commands
+                .into_iter()
+                .map(|c| c.command)
+                .collect::<Vec<_>>(),
+            vec![AviationCommandPart::ProceedDirect("KOLAX".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_recognition_corrections_run_after_text_preprocessor() {
+        let samples = vec![0.1, -0.2, 0.3, -0.4];
+        let stub = StubSpeechToText::default()
+            .with_transcription(samples.clone(), "Lufthansa 123, turn left hdg 270");
+        let airlines = Airlines::load_airlines_from_file().unwrap();
+        let mut recognizer =
+            VoiceRecognizer::with_transcriber(stub, airlines).with_recognition_corrections(
+                HashMap::from([("hedding".to_string(), "heading".to_string())]),
+            );
+        recognizer.set_text_preprocessor(Box::new(|text| text.replace("hdg", "hedding")));
+
+        let (_, parsed) = recognizer.process_audio_samples(&samples).unwrap();
+        let parsed = parsed.expect("Expected a parsed transmission");
+        assert_eq!(parsed.callsign, "DLH123");
+        assert_eq!(
+            parsed
+                .commands
+                .into_iter()
+                .map(|c| c.command)
+                .collect::<Vec<_>>(),
+            vec![AviationCommandPart::FlyHeading {
+                heading: HeadingDirection::Heading(Heading::from(270.)),
+                turn_direction: None,
+            }]
+        );
+    }
+
+    /// Backend, that recognizes the next of its texts in every snippet
+    struct ScriptedBackend {
+        texts: VecDeque<&'static str>,
+    }
+
+    impl Transcribe for ScriptedBackend {
+        fn transcribe(&mut self, _samples: &[f32]) -> Result<TranscriptionResult, Error> {
+            Ok(self
+                .texts
+                .pop_front()
+                .map(TranscriptionResult::new)
+                .unwrap_or_default())
+        }
+    }
+
+    #[test]
+    fn test_recognizer_with_other_backend() {
+        let backend = ScriptedBackend {
+            texts: VecDeque::from(["Lufthansa 123, squawk 7700", "Lufthansa 123, standby"]),
+        };
+        let airlines = Airlines::load_airlines_from_file().unwrap();
+        let mut recognizer = VoiceRecognizer::with_transcriber(backend, airlines);
+
+        let mut next_commands = || {
+            let (_, parsed) = recognizer.process_audio_samples(&[0.0; 16]).unwrap();
+            parsed.map(|parsed| {
+                parsed
+                    .commands
+                    .into_iter()
+                    .map(|c| c.command)
+                    .collect::<Vec<_>>()
+            })
+        };
+        assert_eq!(
+            next_commands(),
+            Some(vec![AviationCommandPart::Squawk { code: 7700 }])
+        );
+        assert_eq!(next_commands(), Some(vec![AviationCommandPart::Standby]));
+        assert_eq!(next_commands(), None);
+    }
+
+    /// Backend, that leaks the previous text into the next transcription like a Whisper state,
+    /// that takes it as context
+    #[derive(Default)]
+    struct ContextBackend {
+        texts: VecDeque<&'static str>,
+        context: Option<&'static str>,
+    }
+
+    impl Transcribe for ContextBackend {
+        fn transcribe(&mut self, _samples: &[f32]) -> Result<TranscriptionResult, Error> {
+            let Some(text) = self.texts.pop_front() else {
+                return Ok(TranscriptionResult::default());
+            };
+            let result = match self.context {
+                Some(context) => format!("{context} {text}"),
+                None => text.to_string(),
+            };
+            self.context = Some(text);
+            Ok(TranscriptionResult::new(result))
+        }
+
+        fn reset(&mut self) {
+            self.context = None;
+        }
+    }
+
+    #[test]
+    fn test_reset_state_each_utterance() {
+        let finals = |reset_state_each_utterance| {
+            let mut backend = ContextBackend {
+                texts: VecDeque::from(["Lufthansa 123, squawk 7700.", "Ryanair 456, standby."]),
+                ..Default::default()
+            };
+            let config = RecognitionConfig {
+                reset_state_each_utterance,
+                ..Default::default()
+            };
+            (0..2)
+                .map(|_| {
+                    let (event, consumed_len) = recognize_snippet(
+                        &mut backend,
+                        &[0.0; 16],
+                        &config,
+                        SentenceBoundary::FullBuffer,
+                    );
+                    assert_eq!(consumed_len, 16);
+                    event
+                })
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            finals(true),
+            vec![
+                Some(RecognitionEvent::Final(
+                    "Lufthansa 123, squawk 7700.".to_string()
+                )),
+                Some(RecognitionEvent::Final("Ryanair 456, standby.".to_string())),
+            ]
+        );
+        // Without the reset, the first utterance leaks into the second
+        assert_eq!(
+            finals(false)[1],
+            Some(RecognitionEvent::Final(
+                "Lufthansa 123, squawk 7700. Ryanair 456, standby.".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_samples_dropped_during_recognition_are_not_skipped_again() {
+        let (mut producer, consumer) = HeapRb::<f32>::new(8).split();
+        let mut consumer = SharedConsumer::new(consumer);
+        producer.push_slice(&[1., 2., 3., 4., 5., 6.]);
+        let mut buffer = [0.; 8];
+        let (read_len, dropped_at_peek) = consumer.peek(&mut buffer);
+        assert_eq!(read_len, 6);
+
+        // The callback overflows, while the snippet is transcribed
+        consumer.drop_oldest(2);
+        producer.push_slice(&[7., 8., 9., 10.]);
+
+        // Samples 1 to 4 were recognized, but 1 and 2 are gone already
+        consumer.skip_recognized(4, dropped_at_peek);
+        assert_eq!(
+            consumer.consumer.pop_iter().collect::<Vec<_>>(),
+            [5., 6., 7., 8., 9., 10.]
+        );
+
+        // More dropped samples than recognized ones
+        producer.push_slice(&[11., 12., 13.]);
+        let (_, dropped_at_peek) = consumer.peek(&mut buffer);
+        consumer.drop_oldest(2);
+        consumer.skip_recognized(1, dropped_at_peek);
+        assert_eq!(consumer.consumer.pop_iter().collect::<Vec<_>>(), [13.]);
+    }
+}
@@ -3,15 +3,75 @@
 //! This module handles the low-level speech recognition functionality,
 //! providing a clean interface for converting audio samples to text.
 
-use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters};
+use whisper_rs::{
+    FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters, WhisperState,
+};
 
 use crate::{Error, SpeechToTextConfig};
 
 const SAMPLE_RATE_HZ: u32 = 16000;
 
+/// Converts audio samples (16kHz mono) to text. Implemented by the Whisper engine and, for
+/// tests without a model, by `test_utils::StubSpeechToText`. Other backends only need to
+/// implement this to be used by the recognizer.
+pub trait Transcribe {
+    fn transcribe(&mut self, samples: &[f32]) -> Result<TranscriptionResult, Error>;
+
+    /// Forget the previous transcriptions. Called after each final text, if
+    /// `RecognitionConfig::reset_state_each_utterance` is set. Stateless backends don't need to
+    /// implement it.
+    fn reset(&mut self) {}
+}
+
+/// Text, that was recognized in a snippet of audio
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TranscriptionResult {
+    /// Recognized text without special tokens. Empty, if nothing was recognized.
+    pub text: String,
+    /// Average probability of the recognized tokens, if the backend reports it
+    pub avg_probability: Option<f32>,
+    /// Single words with their probabilities, if the backend reports them
+    pub words: Vec<TranscribedWord>,
+    /// Parts of the text with their timing, if the backend reports them
+    pub segments: Vec<TranscribedSegment>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TranscribedWord {
+    pub text: String,
+    pub probability: f32,
+}
+
+/// Part of a transcription, e.g. a sentence, with its position in the snippet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TranscribedSegment {
+    pub text: String,
+    /// Start, relative to the beginning of the snippet
+    pub start_ms: u32,
+    /// End, relative to the beginning of the snippet
+    pub end_ms: u32,
+}
+
+impl TranscriptionResult {
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            ..Default::default()
+        }
+    }
+
+    /// The text. None, if nothing was recognized.
+    pub fn into_text(self) -> Option<String> {
+        (!self.text.is_empty()).then_some(self.text)
+    }
+}
+
 /// Speech-to-text engine using Whisper for transcription
 pub struct SpeechToText {
     pub whisper_context: WhisperContext,
+    /// State of `Transcribe::transcribe`. It is reused until the next reset instead of being
+    /// allocated for every snippet.
+    state: Option<WhisperState>,
 }
 
 impl SpeechToText {
@@ -23,7 +83,10 @@ impl SpeechToText {
             WhisperContextParameters::default(),
         )
         .map_err(|e| Error::WhisperError(format!("Failed to create Whisper context: {}", e)))?;
-        Ok(Self { whisper_context })
+        Ok(Self {
+            whisper_context,
+            state: None,
+        })
     }
 
     /// Get the expected sample rate for this speech-to-text engine
@@ -31,35 +94,120 @@ impl SpeechToText {
         SAMPLE_RATE_HZ
     }
 
+    /// Transcribe the samples without special tokens. Empty, if nothing else was recognized.
+    /// Unlike `Transcribe::transcribe`, it uses a new state every time.
     pub fn transcribe_with_whisper(&self, samples: &[f32]) -> Result<String, Error> {
-        let mut state = self
-            .whisper_context
+        let mut state = self.create_state()?;
+        let segments = whisper_segments(&mut state, samples)?;
+        let text: String = segments
+            .iter()
+            .map(|segment| segment.text.as_str())
+            .collect();
+        Ok(strip_special_tokens(&text))
+    }
+
+    fn create_state(&self) -> Result<WhisperState, Error> {
+        self.whisper_context
             .create_state()
-            .map_err(|e| Error::WhisperError(format!("Failed to create Whisper state: {}", e)))?;
-
-        let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
-        params.set_n_threads(crate::WHISPER_NUM_THREADS);
-        params.set_translate(false);
-        params.set_language(Some("en"));
-        params.set_print_special(false);
-        params.set_print_progress(false);
-        params.set_print_realtime(false);
-        params.set_print_timestamps(false);
-
-        // Run inference
-        state
-            .full(params, samples)
-            .map_err(|e| Error::WhisperError(format!("Whisper inference failed: {}", e)))?;
-
-        // Get transcribed text by concatenating all segments
-        let mut result = String::new();
-        for segment in state.as_iter() {
-            let Ok(segment_text) = segment.to_str() else {
-                continue;
-            };
-            result.push_str(segment_text);
-        }
-        // Trim whitespace and return
-        Ok(result)
+            .map_err(|e| Error::WhisperError(format!("Failed to create Whisper state: {}", e)))
+    }
+}
+
+impl Transcribe for SpeechToText {
+    /// Transcribe the samples. Empty, if Whisper recognized nothing but special tokens.
+    fn transcribe(&mut self, samples: &[f32]) -> Result<TranscriptionResult, Error> {
+        let state = match &mut self.state {
+            Some(state) => state,
+            None => self.state.insert(self.create_state()?),
+        };
+        let segments = whisper_segments(state, samples)?;
+        let text: String = segments
+            .iter()
+            .map(|segment| segment.text.as_str())
+            .collect();
+        Ok(TranscriptionResult {
+            text: strip_special_tokens(&text),
+            segments: segments
+                .into_iter()
+                .map(|segment| TranscribedSegment {
+                    text: strip_special_tokens(&segment.text),
+                    ..segment
+                })
+                .filter(|segment| !segment.text.is_empty())
+                .collect(),
+            ..Default::default()
+        })
+    }
+
+    fn reset(&mut self) {
+        self.state = None;
+    }
+}
+
+/// Segments, that Whisper recognized, including special tokens. The text of the previous call
+/// isn't used as context, because the snippets of the streaming recognition overlap, so the
+/// reused state would transcribe the same audio twice.
+fn whisper_segments(
+    state: &mut WhisperState,
+    samples: &[f32],
+) -> Result<Vec<TranscribedSegment>, Error> {
+    let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
+    params.set_n_threads(crate::WHISPER_NUM_THREADS);
+    params.set_translate(false);
+    params.set_language(Some("en"));
+    params.set_no_context(true);
+    params.set_print_special(false);
+    params.set_print_progress(false);
+    params.set_print_realtime(false);
+    params.set_print_timestamps(false);
+
+    // Run inference
+    state
+        .full(params, samples)
+        .map_err(|e| Error::WhisperError(format!("Whisper inference failed: {}", e)))?;
+
+    // Timestamps are in centiseconds
+    let to_ms = |timestamp: i64| (timestamp * 10).max(0) as u32;
+    Ok(state
+        .as_iter()
+        .filter_map(|segment| {
+            Some(TranscribedSegment {
+                text: segment.to_str().ok()?.to_string(),
+                start_ms: to_ms(segment.start_timestamp()),
+                end_ms: to_ms(segment.end_timestamp()),
+            })
+        })
+        .collect())
+}
+
+/// Removes special tokens like "[_BEG_]" or "[_TT_150]" and collapses the remaining whitespace
+fn strip_special_tokens(text: &str) -> String {
+    let mut result = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("[_") {
+        result.push_str(&rest[..start]);
+        rest = match rest[start..].find(']') {
+            Some(end) => &rest[start + end + 1..],
+            None => "",
+        };
+    }
+    result.push_str(rest);
+    result.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::strip_special_tokens;
+
+    #[test]
+    fn test_strip_special_tokens() {
+        assert_eq!(strip_special_tokens("[_BEG_] [_TT_50][_TT_150]"), "");
+        assert_eq!(strip_special_tokens("  "), "");
+        assert_eq!(
+            strip_special_tokens("[_BEG_] Lufthansa 123,[_TT_50] turn left[_TT_150]"),
+            "Lufthansa 123, turn left"
+        );
+        // An unterminated token is dropped until the end
+        assert_eq!(strip_special_tokens("descend [_TT_"), "descend");
     }
 }
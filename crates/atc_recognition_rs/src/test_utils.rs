@@ -1,9 +1,169 @@
-//! Test utilities for WAV file processing
+//! Test utilities for WAV file processing and synthetic transmissions
 //! Available only when testing feature is enabled
 use std::path::Path;
 
+use aviation_helper_rs::{
+    clearance::{
+        airlines::Airlines,
+        aviation_command::{
+            AviationCommandGroup, CommunicationEntity, Frequency, HeadingDirection,
+        },
+    },
+    types::{altitude::Altitude, altitude::VerticalDirection, heading::Heading},
+};
+use rand::{Rng, seq::SliceRandom};
+
 use super::*;
 
+const STATIONS: [&str; 5] = ["tower", "ground", "approach", "departure", "center"];
+const RUNWAY_SIDES: [(&str, &str); 4] =
+    [("", ""), (" left", "L"), (" right", "R"), (" center", "C")];
+
+/// Build a random, grammatically valid transmission (callsign + 1-3 commands) together
+/// with the command group the parser is expected to produce for it.
+/// The callsign is taken from an active airline with a single-word callsign.
+pub fn random_transmission(
+    rng: &mut impl Rng,
+    airlines: &Airlines,
+) -> (String, AviationCommandGroup) {
+    let candidates: Vec<(&str, &str)> = airlines
+        .0
+        .iter()
+        .filter(|airline| airline.active)
+        .filter_map(|airline| Some((airline.callsign.as_deref()?, airline.icao.as_deref()?)))
+        .filter(|(callsign, icao)| {
+            !callsign.is_empty()
+                && callsign.chars().all(|c| c.is_ascii_alphabetic())
+                && icao.len() == 3
+                && icao.chars().all(|c| c.is_ascii_alphabetic())
+        })
+        .collect();
+    let (callsign, icao) = *candidates
+        .choose(rng)
+        .expect("Airlines contain no usable callsign");
+    let flight_number = rng.gen_range(1..=9999);
+
+    let command_count = rng.gen_range(1..=3);
+    let (phrases, parts): (Vec<String>, Vec<AviationCommandPart>) =
+        (0..command_count).map(|_| random_command(rng)).unzip();
+
+    let text = format!("{} {}, {}", callsign, flight_number, phrases.join(", "));
+    let command = AviationCommandGroup {
+        target: Some(CommunicationEntity::Aircraft {
+            full_name: format!("{}{}", icao.to_uppercase(), flight_number),
+        }),
+        parts,
+    };
+    (text, command)
+}
+
+fn random_command(rng: &mut impl Rng) -> (String, AviationCommandPart) {
+    match rng.gen_range(0..6) {
+        0 => {
+            let heading = rng.gen_range(1..=35) * 10;
+            (
+                format!("fly heading {heading:03}"),
+                AviationCommandPart::FlyHeading {
+                    heading: HeadingDirection::Heading(Heading::from(heading as f64)),
+                    turn_direction: None,
+                },
+            )
+        }
+        1 => {
+            let (word, direction) = random_vertical_direction(rng);
+            let flight_level = rng.gen_range(10..=40) * 10;
+            (
+                format!("{word} and maintain flight level {flight_level}"),
+                AviationCommandPart::ChangeAltitude {
+                    altitude: Altitude::FlightLevel(flight_level),
+                    maintain: true,
+                    discretion: false,
+                    turn_direction: Some(direction),
+                },
+            )
+        }
+        2 => {
+            let (word, direction) = random_vertical_direction(rng);
+            let feet = rng.gen_range(2..=9) * 1000;
+            (
+                format!("{word} and maintain {feet} feet"),
+                AviationCommandPart::ChangeAltitude {
+                    altitude: Altitude::Feet(feet as f64),
+                    maintain: true,
+                    discretion: false,
+                    turn_direction: Some(direction),
+                },
+            )
+        }
+        3 => {
+            let station = *STATIONS.choose(rng).unwrap();
+            let num = rng.gen_range(118..=136);
+            // 25 kHz channel spacing
+            let dec = rng.gen_range(0..40) * 25;
+            let decimals = format!("{dec:03}");
+            let decimals = match decimals.trim_end_matches('0') {
+                "" => "0",
+                trimmed => trimmed,
+            };
+            (
+                format!("contact {station} {num}.{decimals}"),
+                AviationCommandPart::ContactFrequency {
+                    frequency: Frequency { num, dec },
+                    station: Some(station.to_owned()),
+                },
+            )
+        }
+        4 => {
+            let number = rng.gen_range(1..=36);
+            let (spoken_side, side) = *RUNWAY_SIDES.choose(rng).unwrap();
+            (
+                format!("cleared to land runway {number}{spoken_side}"),
+                AviationCommandPart::ClearedToLand {
+                    runway: format!("{number:02}{side}"),
+                },
+            )
+        }
+        _ => (
+            "radar contact".to_owned(),
+            AviationCommandPart::RadarContact,
+        ),
+    }
+}
+
+fn random_vertical_direction(rng: &mut impl Rng) -> (&'static str, VerticalDirection) {
+    if rng.gen_bool(0.5) {
+        ("climb", VerticalDirection::Climb)
+    } else {
+        ("descend", VerticalDirection::Descend)
+    }
+}
+
+/// Speech-to-text, that returns scripted transcriptions instead of running Whisper. Samples
+/// without a script are recognized as nothing.
+#[derive(Debug, Clone, Default)]
+pub struct StubSpeechToText {
+    transcriptions: Vec<(Vec<f32>, String)>,
+}
+
+impl StubSpeechToText {
+    /// Transcribe exactly these samples to the text
+    pub fn with_transcription(mut self, samples: Vec<f32>, text: impl Into<String>) -> Self {
+        self.transcriptions.push((samples, text.into()));
+        self
+    }
+}
+
+impl Transcribe for StubSpeechToText {
+    fn transcribe(&mut self, samples: &[f32]) -> Result<TranscriptionResult, Error> {
+        Ok(self
+            .transcriptions
+            .iter()
+            .find(|(scripted, _)| scripted.as_slice() == samples)
+            .map(|(_, text)| TranscriptionResult::new(text.as_str()))
+            .unwrap_or_default())
+    }
+}
+
 /// Process a WAV file and return the recognized text and parsed commands
 /// This function is only available for tests
 pub fn process_wav_file_for_test(
@@ -22,7 +22,7 @@ pub struct AirlineEntry {
     pub active: bool,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Default, Deserialize)]
 pub struct Airlines(pub Vec<AirlineEntry>);
 
 impl Airlines {
@@ -5,7 +5,7 @@ use serde::{Deserialize, Serialize};
 use crate::{
     errors::Error,
     types::{
-        altitude::{Altitude, VerticalDirection},
+        altitude::{Altitude, AltitudeConstraint, VerticalDirection},
         heading::{CardinalDirection, Degrees, Heading, TurnDirection},
     },
 };
@@ -15,14 +15,45 @@ pub enum HeadingDirection {
     RunwayHeading,
     CardinalDirection(CardinalDirection),
     Heading(Heading),
+    /// The heading, that the aircraft is flying, e.g. "continue present heading"
+    PresentHeading,
+}
+
+/// State of the aircraft, that the controller asks the pilot to report
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum QueryField {
+    Heading,
+    Altitude,
+    Speed,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
 pub struct Frequency {
+    /// MHz part
     pub num: u32,
+    /// kHz part, e.g. 500 for 121.5
     pub dec: u32,
 }
 
+impl Frequency {
+    /// Creates a frequency from the MHz part and the digits after the decimal point
+    pub fn from_parts(num: u32, decimal_digits: &str) -> Result<Self, Error> {
+        if decimal_digits.len() > 3 || !decimal_digits.chars().all(|c| c.is_ascii_digit()) {
+            return Err(Error::InvalidFrequency(format!("{num}.{decimal_digits}")));
+        }
+        // "5" -> 500, "05" -> 50, "905" -> 905
+        let dec = format!("{decimal_digits:0<3}")
+            .parse()
+            .map_err(|_| Error::InvalidFrequency(format!("{num}.{decimal_digits}")))?;
+        Ok(Self { num, dec })
+    }
+
+    /// Formats the frequency with all three decimals, e.g. "121.500"
+    pub fn to_display_string(&self) -> String {
+        format!("{}.{:03}", self.num, self.dec)
+    }
+}
+
 impl FromStr for Frequency {
     type Err = Error;
     fn from_str(val: &str) -> Result<Self, Self::Err> {
@@ -32,12 +63,8 @@ impl FromStr for Frequency {
                 let num: u32 = num
                     .parse()
                     .map_err(|_| Error::InvalidFrequency(val.to_owned()))?;
-                let dec: u32 = dec
-                    .map(|d| d.parse())
-                    .transpose()
-                    .map_err(|_| Error::InvalidFrequency(val.to_owned()))?
-                    .unwrap_or_default();
-                Ok(Self { num, dec })
+                Self::from_parts(num, dec.unwrap_or_default())
+                    .map_err(|_| Error::InvalidFrequency(val.to_owned()))
             }
             _ => Err(Error::InvalidFrequency(val.to_owned())),
         }
@@ -56,16 +83,112 @@ pub enum AviationCommandPart {
         turn_direction: Option<TurnDirection>,
     },
     ProceedDirect(String),
+    /// Direct to the waypoint, once the aircraft is at or abeam the fix, e.g. "until ALPHA then
+    /// direct BRAVO"
+    DirectAfterFix {
+        fix: String,
+        waypoint: String,
+    },
     ChangeAltitude {
         altitude: Altitude,
         #[serde(default)]
         maintain: bool,
+        /// "descend at pilot's discretion", the pilot chooses when and how fast
+        #[serde(default)]
+        discretion: bool,
         turn_direction: Option<VerticalDirection>,
     },
+    /// Block altitude, e.g. "maintain block flight level 100 to 120". The aircraft may fly at
+    /// any altitude between the two.
+    MaintainBlock {
+        lower: Altitude,
+        upper: Altitude,
+    },
+    /// Crossing restriction, e.g. "cross ALPHA at or above 6000"
+    CrossFix {
+        fix: String,
+        constraint: AltitudeConstraint,
+        altitude: Altitude,
+    },
+    /// Indicated airspeed in knots, e.g. "reduce speed to 210 knots"
+    ChangeSpeed {
+        knots: u32,
+    },
+    /// Cancels the speed restriction, e.g. "resume normal speed"
+    ResumeNormalSpeed,
     ContactFrequency {
         frequency: Frequency,
         station: Option<String>,
     },
+    ClearedToLand {
+        runway: String,
+    },
+    ClearedForTheOption {
+        runway: String,
+    },
+    /// Radar vectors to intercept the localizer of the runway
+    VectorsForIls {
+        runway: String,
+    },
+    /// Approach with visual reference to the runway, flown directly to the threshold
+    ClearedVisualApproach {
+        runway: String,
+    },
+    Query {
+        field: QueryField,
+    },
+    /// Ends radar vectors, e.g. after a direct-to
+    ResumeOwnNavigation,
+    /// Altimeter setting, either in hPa ("QNH 1013") or in hundredths of inHg ("altimeter 29.92")
+    Altimeter {
+        hpa: Option<u32>,
+        inhg: Option<u32>,
+    },
+    /// Transponder code, four octal digits, e.g. 7700
+    Squawk {
+        code: u16,
+    },
+    DeclareEmergency,
+    /// Follow another aircraft with enough spacing, e.g. "sequence behind Ryanair 456". The
+    /// callsign is normalized like the one of the transmission, e.g. "RYR456".
+    SequenceBehind {
+        callsign: String,
+    },
+    /// Cancels the most recent instruction
+    Disregard,
+    /// Acknowledgement without an instruction
+    Standby,
+}
+
+impl AviationCommandPart {
+    /// Name of the variant, e.g. "FlyHeading"
+    pub fn name(&self) -> &'static str {
+        match self {
+            AviationCommandPart::RadarContact => "RadarContact",
+            AviationCommandPart::TurnBy { .. } => "TurnBy",
+            AviationCommandPart::FlyHeading { .. } => "FlyHeading",
+            AviationCommandPart::ProceedDirect(_) => "ProceedDirect",
+            AviationCommandPart::DirectAfterFix { .. } => "DirectAfterFix",
+            AviationCommandPart::ChangeAltitude { .. } => "ChangeAltitude",
+            AviationCommandPart::MaintainBlock { .. } => "MaintainBlock",
+            AviationCommandPart::CrossFix { .. } => "CrossFix",
+            AviationCommandPart::ChangeSpeed { .. } => "ChangeSpeed",
+            AviationCommandPart::ResumeNormalSpeed => "ResumeNormalSpeed",
+            AviationCommandPart::ContactFrequency { .. } => "ContactFrequency",
+            AviationCommandPart::ClearedToLand { .. } => "ClearedToLand",
+            AviationCommandPart::ClearedForTheOption { .. } => "ClearedForTheOption",
+            AviationCommandPart::VectorsForIls { .. } => "VectorsForIls",
+            AviationCommandPart::ClearedVisualApproach { .. } => "ClearedVisualApproach",
+            AviationCommandPart::Query { .. } => "Query",
+            AviationCommandPart::ResumeOwnNavigation => "ResumeOwnNavigation",
+            AviationCommandPart::Altimeter { .. } => "Altimeter",
+            AviationCommandPart::Squawk { .. } => "Squawk",
+            AviationCommandPart::DeclareEmergency => "DeclareEmergency",
+            AviationCommandPart::SequenceBehind { .. } => "SequenceBehind",
+            AviationCommandPart::Disregard => "Disregard",
+            AviationCommandPart::Standby => "Standby",
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
@@ -80,3 +203,16 @@ pub struct AviationCommandGroup {
     pub target: Option<CommunicationEntity>,
     pub parts: Vec<AviationCommandPart>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Frequency;
+
+    #[test]
+    fn test_frequency_display_string() {
+        let display = |val: &str| val.parse::<Frequency>().unwrap().to_display_string();
+        assert_eq!(display("121.5"), "121.500");
+        assert_eq!(display("121.05"), "121.050");
+        assert_eq!(display("121.905"), "121.905");
+    }
+}
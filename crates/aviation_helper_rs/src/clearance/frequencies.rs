@@ -0,0 +1,113 @@
+use serde::{Deserialize, Deserializer};
+
+use crate::{clearance::aviation_command::Frequency, errors::Error};
+
+/// Rough conventions, the first matching entry wins
+const DEFAULT_FREQUENCY_STATIONS: [(&str, &str, &str); 6] = [
+    ("121.500", "121.500", "emergency"),
+    ("118.000", "121.475", "tower"),
+    ("121.600", "121.975", "ground"),
+    ("122.000", "123.975", "unicom"),
+    ("124.000", "128.975", "approach"),
+    ("129.000", "136.975", "center"),
+];
+
+/// Station, that is responsible for a range of frequencies (both ends inclusive)
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct FrequencyStation {
+    #[serde(deserialize_with = "deserialize_frequency")]
+    pub from: Frequency,
+    #[serde(deserialize_with = "deserialize_frequency")]
+    pub to: Frequency,
+    pub station: String,
+}
+
+/// Maps frequencies to the kind of station, e.g. 121.5 to "emergency"
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct FrequencyTable(pub Vec<FrequencyStation>);
+
+impl FrequencyTable {
+    pub fn load_frequency_table<R>(reader: R) -> Result<Self, Error>
+    where
+        R: std::io::Read,
+    {
+        let res = serde_json::from_reader(reader)?;
+        Ok(res)
+    }
+
+    pub fn station(&self, frequency: &Frequency) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|entry| (entry.from..=entry.to).contains(frequency))
+            .map(|entry| entry.station.as_str())
+    }
+}
+
+impl Default for FrequencyTable {
+    fn default() -> Self {
+        Self(
+            DEFAULT_FREQUENCY_STATIONS
+                .iter()
+                .map(|(from, to, station)| FrequencyStation {
+                    from: from.parse().expect("valid default frequency"),
+                    to: to.parse().expect("valid default frequency"),
+                    station: station.to_string(),
+                })
+                .collect(),
+        )
+    }
+}
+
+/// Kind of station of the frequency according to the default table
+pub fn frequency_to_station(freq: &Frequency) -> Option<&'static str> {
+    DEFAULT_FREQUENCY_STATIONS
+        .iter()
+        .find(|(from, to, _)| {
+            let (Ok(from), Ok(to)) = (from.parse::<Frequency>(), to.parse::<Frequency>()) else {
+                return false;
+            };
+            (from..=to).contains(freq)
+        })
+        .map(|(_, _, station)| *station)
+}
+
+fn deserialize_frequency<'de, D>(d: D) -> Result<Frequency, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s: String = Deserialize::deserialize(d)?;
+    s.parse().map_err(serde::de::Error::custom)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FrequencyTable, frequency_to_station};
+    use crate::clearance::aviation_command::Frequency;
+
+    #[test]
+    fn test_frequency_to_station() {
+        let station = |val: &str| frequency_to_station(&val.parse::<Frequency>().unwrap());
+        assert_eq!(station("121.5"), Some("emergency"));
+        assert_eq!(station("118.1"), Some("tower"));
+        assert_eq!(station("121.9"), Some("ground"));
+        assert_eq!(station("127.85"), Some("approach"));
+        assert_eq!(station("108.5"), None);
+    }
+
+    #[test]
+    fn test_load_frequency_table() {
+        let json = r#"[
+            {"from": "121.5", "to": "121.5", "station": "guard"},
+            {"from": "119.1", "to": "119.3", "station": "tower"}
+        ]"#;
+        let table = FrequencyTable::load_frequency_table(json.as_bytes()).unwrap();
+        let station = |val: &str| table.station(&val.parse::<Frequency>().unwrap());
+        assert_eq!(station("121.5"), Some("guard"));
+        assert_eq!(station("119.25"), Some("tower"));
+        assert_eq!(station("118.1"), None);
+        assert_eq!(
+            FrequencyTable::default().station(&"121.5".parse().unwrap()),
+            Some("emergency")
+        );
+    }
+}
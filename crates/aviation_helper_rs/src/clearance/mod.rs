@@ -1,2 +1,3 @@
 pub mod airlines;
 pub mod aviation_command;
+pub mod frequencies;
@@ -2,6 +2,16 @@ pub const MILES_PER_SECONDS_TO_KNOTS: f64 = 3128.30820878;
 pub const KNOTS_TO_MILES_PER_SECOND: f64 = 0.000319662;
 pub const RADIANS_TO_DEGREES: f64 = 180.0 / std::f64::consts::PI;
 pub const DEGREES_TO_RADIANS: f64 = std::f64::consts::PI / 180.0;
+pub const HPA_PER_INHG: f64 = 33.8639;
+/// Pressure of the standard atmosphere at sea level
+pub const STANDARD_PRESSURE_HPA: f64 = 1013.25;
+/// Change of the pressure altitude per hPa close to sea level
+pub const FEET_PER_HPA: f64 = 27.;
+
+/// Converts an altimeter setting in hundredths of inHg, e.g. 2992, to hPa
+pub fn inhg_hundredths_to_hpa(inhg: u32) -> f64 {
+    inhg as f64 / 100. * HPA_PER_INHG
+}
 
 pub fn aviation_degrees_to_bevy_rotation(degrees: f64) -> f64 {
     (90.0 - degrees).to_radians()
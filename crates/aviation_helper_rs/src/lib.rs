@@ -1,4 +1,5 @@
 pub mod clearance;
 pub mod conversions;
 pub mod errors;
+pub mod metar;
 pub mod types;
@@ -0,0 +1,191 @@
+use serde::{Deserialize, Serialize};
+
+/// Visibility, below which visual approaches aren't allowed (about 3 statute miles)
+pub const VISUAL_APPROACH_MIN_VISIBILITY_M: u32 = 4800;
+/// Ceiling, below which visual approaches aren't allowed
+pub const VISUAL_APPROACH_MIN_CEILING_FT: u32 = 1000;
+
+const METERS_PER_STATUTE_MILE: f64 = 1609.344;
+const KNOTS_PER_METER_PER_SECOND: f64 = 1.943_844;
+
+/// Wind, visibility and ceiling of a weather report
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub struct MetarData {
+    /// Direction, from which the wind blows. None, if the wind is variable.
+    #[serde(default)]
+    pub wind_dir: Option<u32>,
+    /// Knots
+    pub wind_speed: u32,
+    /// Knots
+    #[serde(default)]
+    pub gust: Option<u32>,
+    /// 9999 means 10 km or more
+    pub visibility_m: u32,
+    /// Height of the lowest broken or overcast layer. None, if there is no such layer.
+    #[serde(default)]
+    pub ceiling_ft: Option<u32>,
+}
+
+impl Default for MetarData {
+    /// Calm wind and no weather
+    fn default() -> Self {
+        Self {
+            wind_dir: None,
+            wind_speed: 0,
+            gust: None,
+            visibility_m: 9999,
+            ceiling_ft: None,
+        }
+    }
+}
+
+impl MetarData {
+    pub fn allows_visual_approach(&self) -> bool {
+        self.visibility_m >= VISUAL_APPROACH_MIN_VISIBILITY_M
+            && self
+                .ceiling_ft
+                .is_none_or(|ceiling| ceiling >= VISUAL_APPROACH_MIN_CEILING_FT)
+    }
+}
+
+/// Reads wind, visibility and ceiling of a METAR, e.g.
+/// "EDDF 121250Z 25012G22KT 9999 BKN035 12/05 Q1013 NOSIG". Unknown groups are skipped and
+/// missing values stay at their defaults. The trend and the remarks are ignored.
+pub fn parse_metar(metar: &str) -> MetarData {
+    let mut data = MetarData::default();
+    let mut whole_miles = None;
+    for group in metar.split_whitespace() {
+        if matches!(group, "RMK" | "NOSIG" | "BECMG" | "TEMPO") {
+            break;
+        }
+        if group == "CAVOK" {
+            data.visibility_m = 9999;
+            data.ceiling_ft = None;
+        } else if let Some((wind_dir, wind_speed, gust)) = parse_wind(group) {
+            data.wind_dir = wind_dir;
+            data.wind_speed = wind_speed;
+            data.gust = gust;
+        } else if let Some(miles) = group.strip_suffix("SM").and_then(parse_miles) {
+            let miles = miles + whole_miles.unwrap_or(0.);
+            data.visibility_m = (miles * METERS_PER_STATUTE_MILE).round() as u32;
+        } else if group.len() == 4 && group.chars().all(|c| c.is_ascii_digit()) {
+            data.visibility_m = group.parse().unwrap_or(data.visibility_m);
+        } else if let Some(height_ft) = parse_ceiling(group) {
+            data.ceiling_ft = Some(data.ceiling_ft.map_or(height_ft, |c| c.min(height_ft)));
+        }
+        // Whole miles of a visibility like "2 1/2SM"
+        whole_miles = group.parse::<f64>().ok().filter(|_| group.len() == 1);
+    }
+    data
+}
+
+/// Direction, speed and gust in knots of a wind group like "25012G22KT" or "VRB03MPS"
+fn parse_wind(group: &str) -> Option<(Option<u32>, u32, Option<u32>)> {
+    let (group, factor) = match group.strip_suffix("KT") {
+        Some(group) => (group, 1.),
+        None => (group.strip_suffix("MPS")?, KNOTS_PER_METER_PER_SECOND),
+    };
+    let direction = group.get(..3)?;
+    let direction = match direction {
+        "VRB" => None,
+        _ => Some(direction.parse().ok()?),
+    };
+    let (speed, gust) = match group[3..].split_once('G') {
+        Some((speed, gust)) => (speed, Some(gust)),
+        None => (&group[3..], None),
+    };
+    let to_knots = |value: &str| {
+        value
+            .parse::<u32>()
+            .ok()
+            .map(|value| (value as f64 * factor).round() as u32)
+    };
+    let gust = match gust {
+        Some(gust) => Some(to_knots(gust)?),
+        None => None,
+    };
+    Some((direction, to_knots(speed)?, gust))
+}
+
+/// Statute miles like "10", "P6" or "1/2"
+fn parse_miles(miles: &str) -> Option<f64> {
+    let miles = miles.strip_prefix('P').unwrap_or(miles);
+    match miles.split_once('/') {
+        Some((numerator, denominator)) => {
+            Some(numerator.parse::<f64>().ok()? / denominator.parse::<f64>().ok()?)
+        }
+        None => miles.parse().ok(),
+    }
+}
+
+/// Height in feet of a broken or overcast layer or of the vertical visibility, e.g. "BKN035"
+fn parse_ceiling(group: &str) -> Option<u32> {
+    let hundreds = ["BKN", "OVC", "VV"]
+        .iter()
+        .find_map(|cover| group.strip_prefix(cover))?;
+    let hundreds = hundreds.get(..3)?.parse::<u32>().ok()?;
+    Some(hundreds * 100)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MetarData, parse_metar};
+
+    #[test]
+    fn test_parse_metar() {
+        for (metar, expected) in [
+            (
+                "EDDF 121250Z 25012G22KT 220V280 9999 FEW020 BKN035 OVC080 12/05 Q1013 NOSIG",
+                MetarData {
+                    wind_dir: Some(250),
+                    wind_speed: 12,
+                    gust: Some(22),
+                    visibility_m: 9999,
+                    ceiling_ft: Some(3500),
+                },
+            ),
+            (
+                "METAR KJFK 121251Z 04008KT 2 1/2SM BR OVC006 08/07 A2992 RMK AO2 SLP132",
+                MetarData {
+                    wind_dir: Some(40),
+                    wind_speed: 8,
+                    gust: None,
+                    visibility_m: 4023,
+                    ceiling_ft: Some(600),
+                },
+            ),
+            (
+                "LOWW 121250Z VRB02KT CAVOK 18/09 Q1020",
+                MetarData {
+                    wind_dir: None,
+                    wind_speed: 2,
+                    gust: None,
+                    visibility_m: 9999,
+                    ceiling_ft: None,
+                },
+            ),
+            (
+                "UUEE 121300Z 27007MPS 0800 FG VV002 M02/M02 Q1008 TEMPO 0300",
+                MetarData {
+                    wind_dir: Some(270),
+                    wind_speed: 14,
+                    gust: None,
+                    visibility_m: 800,
+                    ceiling_ft: Some(200),
+                },
+            ),
+        ] {
+            assert_eq!(parse_metar(metar), expected, "{metar}");
+        }
+    }
+
+    #[test]
+    fn test_visual_approach_minima() {
+        let parse = |metar| parse_metar(metar).allows_visual_approach();
+        assert!(parse("EDDF 121250Z 25012KT 9999 SCT008 BKN035 Q1013"));
+        assert!(parse("KJFK 121251Z 04008KT 10SM FEW250 A2992"));
+        assert!(!parse("KJFK 121251Z 04008KT 2 1/2SM BR OVC006 A2992"));
+        assert!(!parse("EDDF 121250Z 25012KT 9999 OVC008 Q1013"));
+        assert!(!parse("UUEE 121300Z 27007MPS 0800 FG VV002 Q1008"));
+    }
+}
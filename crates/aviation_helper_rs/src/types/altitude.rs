@@ -1,11 +1,26 @@
+use std::ops::RangeInclusive;
+
 use serde::{Deserialize, Serialize};
 
+/// Flight levels, that a number without unit can stand for, e.g. "maintain 100"
+const UNITLESS_FLIGHT_LEVELS: RangeInclusive<u32> = 10..=600;
+/// Lowest altitude in feet, that a number without unit can stand for, e.g. "maintain 3000"
+const MIN_UNITLESS_FEET: u32 = 500;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
 pub enum VerticalDirection {
     Climb,
     Descend,
 }
 
+/// How the altitude of a crossing restriction has to be met, e.g. "cross ALPHA at or above 6000"
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum AltitudeConstraint {
+    At,
+    AtOrAbove,
+    AtOrBelow,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Deserialize, Serialize)]
 pub enum Altitude {
     Feet(f64),
@@ -23,4 +38,133 @@ impl Altitude {
             Altitude::FlightLevel(fl) => (fl * 100) as f64,
         }
     }
+
+    /// Altitude of a number, that was cleared without "feet" or "flight level", e.g.
+    /// "maintain 100". Small numbers are flight levels and whole hundreds from 500 up are feet,
+    /// which become a flight level at or above the transition altitude. If both fit, the one
+    /// closer to the present altitude wins. Without it, the flight level wins, if it is above
+    /// the transition altitude. None, if the number is neither.
+    pub fn from_unitless(
+        value: u32,
+        transition_altitude_feet: f64,
+        present_altitude_feet: Option<f64>,
+    ) -> Option<Altitude> {
+        let flight_level = UNITLESS_FLIGHT_LEVELS
+            .contains(&value)
+            .then_some(Altitude::FlightLevel(value));
+        let feet = (value >= MIN_UNITLESS_FEET && value % 100 == 0).then(|| {
+            if value as f64 >= transition_altitude_feet {
+                Altitude::FlightLevel(value / 100)
+            } else {
+                Altitude::Feet(value as f64)
+            }
+        });
+        match (flight_level, feet) {
+            (Some(flight_level), Some(feet)) => {
+                let flight_level_wins = match present_altitude_feet {
+                    Some(present) => {
+                        (flight_level.as_feet() - present).abs() <= (feet.as_feet() - present).abs()
+                    }
+                    None => flight_level.as_feet() >= transition_altitude_feet,
+                };
+                Some(if flight_level_wins {
+                    flight_level
+                } else {
+                    feet
+                })
+            }
+            (flight_level, feet) => flight_level.or(feet),
+        }
+    }
+
+    /// Whether a number without unit could be both a flight level and feet, so that
+    /// `from_unitless` had to guess
+    pub fn is_unitless_ambiguous(value: u32) -> bool {
+        UNITLESS_FLIGHT_LEVELS.contains(&value) && value >= MIN_UNITLESS_FEET && value % 100 == 0
+    }
+
+    /// Altitude as read back by a pilot, e.g. "flight level three five zero" or
+    /// "four thousand five hundred feet". Feet are rounded to hundreds.
+    pub fn to_spoken(self) -> String {
+        match self {
+            Altitude::FlightLevel(fl) => {
+                format!("flight level {}", super::spoken_digits(&format!("{fl:03}")))
+            }
+            Altitude::Feet(feet) => {
+                let hundreds_total = (feet.max(0.) / 100.).round() as u32;
+                let (thousands, hundreds) = (hundreds_total / 10, hundreds_total % 10);
+                let mut parts = Vec::new();
+                if thousands > 0 {
+                    parts.push(format!(
+                        "{} thousand",
+                        super::spoken_digits(&thousands.to_string())
+                    ));
+                }
+                if hundreds > 0 || thousands == 0 {
+                    parts.push(format!(
+                        "{} hundred",
+                        super::spoken_digits(&hundreds.to_string())
+                    ));
+                }
+                format!("{} feet", parts.join(" "))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Altitude;
+
+    #[test]
+    fn test_altitude_to_spoken() {
+        assert_eq!(
+            Altitude::FlightLevel(350).to_spoken(),
+            "flight level three five zero"
+        );
+        assert_eq!(
+            Altitude::FlightLevel(90).to_spoken(),
+            "flight level zero niner zero"
+        );
+        assert_eq!(
+            Altitude::Feet(4480.).to_spoken(),
+            "four thousand five hundred feet"
+        );
+        assert_eq!(Altitude::Feet(11000.).to_spoken(), "one one thousand feet");
+    }
+
+    #[test]
+    fn test_altitude_from_unitless() {
+        let transition = 18000.;
+        assert_eq!(
+            Altitude::from_unitless(100, transition, None),
+            Some(Altitude::FlightLevel(100))
+        );
+        assert_eq!(
+            Altitude::from_unitless(3000, transition, None),
+            Some(Altitude::Feet(3000.))
+        );
+        assert_eq!(
+            Altitude::from_unitless(24000, transition, None),
+            Some(Altitude::FlightLevel(240))
+        );
+        assert_eq!(Altitude::from_unitless(5, transition, None), None);
+        assert_eq!(Altitude::from_unitless(2550, transition, None), None);
+
+        // 500 is either flight level 500 or 500 feet, the present altitude decides
+        assert!(Altitude::is_unitless_ambiguous(500));
+        assert!(!Altitude::is_unitless_ambiguous(100));
+        assert_eq!(
+            Altitude::from_unitless(500, transition, Some(1500.)),
+            Some(Altitude::Feet(500.))
+        );
+        assert_eq!(
+            Altitude::from_unitless(500, transition, Some(41000.)),
+            Some(Altitude::FlightLevel(500))
+        );
+        assert_eq!(
+            Altitude::from_unitless(500, transition, None),
+            Some(Altitude::FlightLevel(500))
+        );
+    }
 }
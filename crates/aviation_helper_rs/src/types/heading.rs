@@ -86,6 +86,11 @@ impl Heading {
     pub fn get(&self) -> f64 {
         self.0
     }
+
+    /// Heading as read back by a pilot, e.g. "two seven zero"
+    pub fn to_spoken(&self) -> String {
+        super::spoken_digits(&self.to_string())
+    }
 }
 
 impl Display for Heading {
@@ -136,3 +141,25 @@ impl Deref for Degrees {
         &self.0
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Heading;
+
+    #[test]
+    fn test_heading_to_spoken() {
+        assert_eq!(Heading::new(270.).to_spoken(), "two seven zero");
+        assert_eq!(Heading::new(95.).to_spoken(), "zero niner five");
+        assert_eq!(Heading::new(0.).to_spoken(), "three six zero");
+    }
+
+    #[test]
+    fn test_heading_360_is_north() {
+        let north = Heading::from(360.);
+        assert_eq!(north, Heading::from(0.));
+        assert_eq!(north.to_string(), "360");
+        assert_eq!(Heading::from(350.).required_change(north), 10.);
+        assert_eq!(Heading::from(10.).required_change(north), -10.);
+        assert_eq!(north.required_change(Heading::from(360.)), 0.);
+    }
+}
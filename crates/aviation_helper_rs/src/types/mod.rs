@@ -1,2 +1,23 @@
 pub mod altitude;
 pub mod heading;
+
+/// Spell out digits as they are spoken on the radio, e.g. "270" -> "two seven zero"
+pub fn spoken_digits(digits: &str) -> String {
+    digits
+        .chars()
+        .filter_map(|digit| match digit {
+            '0' => Some("zero"),
+            '1' => Some("one"),
+            '2' => Some("two"),
+            '3' => Some("three"),
+            '4' => Some("four"),
+            '5' => Some("five"),
+            '6' => Some("six"),
+            '7' => Some("seven"),
+            '8' => Some("eight"),
+            '9' => Some("niner"),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
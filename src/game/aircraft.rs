@@ -1,7 +1,11 @@
+use aviation_helper_rs::clearance::aviation_command::QueryField;
+use aviation_helper_rs::types::altitude::Altitude;
 use aviation_helper_rs::types::heading::{Heading, TurnDirection};
+use aviation_helper_rs::types::spoken_digits;
 use bevy::asset::Asset;
 use bevy::dev_tools::states::log_transitions;
 use bevy::input::common_conditions::input_just_pressed;
+use bevy::math::DVec2;
 use bevy::platform::collections::hash_map::HashMap;
 use bevy::prelude::*;
 use bevy::reflect::TypePath;
@@ -10,17 +14,20 @@ use bevy_common_assets::ron::RonAssetPlugin;
 use bevy_prng::WyRand;
 use bevy_rand::global::GlobalEntropy;
 use rand_core::RngCore;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 use crate::APP_CONFIG;
+use crate::game::color_theme::{ColorTheme, recolor};
 use crate::game::loading::{PendingLoadingPlugins, PluginLoadingFinishedEvent};
+use crate::game::separation::WakeCategory;
+use crate::game::wind::Wind;
 use crate::game::{GameState, Z_AIRCRAFT};
-use crate::util::consts::PIXEL_PER_KNOT_SECOND;
+use crate::util::units::knots_to_pixels_per_second;
 
 #[derive(Resource, Default)]
 pub struct AircraftTypeStore(pub HashMap<String, Handle<AircraftType>>);
 
-#[derive(Resource, Default)]
+#[derive(Resource, Clone, Default)]
 pub struct AircraftMeshMaterials {
     pub mesh: Handle<Mesh>,
     pub material: Handle<ColorMaterial>,
@@ -43,6 +50,9 @@ const AIRCRAFT_SCALE_MAX: f32 = 2.5;
 const ZOOM_SCALE_MIN: f32 = 0.5; // Camera zoom at which elements reach max scale
 const ZOOM_SCALE_MAX: f32 = 4.0; // Camera zoom at which elements reach min scale
 
+/// Share of the maximum climb and descent rate, that is used at pilot's discretion
+const DISCRETION_ALTITUDE_RATE_FACTOR: f64 = 0.5;
+
 pub struct AircraftPlugin;
 
 impl Plugin for AircraftPlugin {
@@ -54,6 +64,7 @@ impl Plugin for AircraftPlugin {
         .add_event::<AircraftJustSpawned>()
         .init_resource::<AircraftTypeStore>()
         .init_resource::<AircraftMeshMaterials>()
+        .init_resource::<Wind>()
         .add_systems(Startup, on_startup)
         .add_systems(OnEnter(GameState::Loading), (setup, setup_aircraft_assets))
         .add_systems(
@@ -72,10 +83,18 @@ impl Plugin for AircraftPlugin {
                 poll_aircraft_types_loaded.run_if(in_state(LoadingState::LoadingHandles)),
                 update_aircraft_scale.run_if(in_state(GameState::Running)),
                 update_speed_indicators.run_if(in_state(GameState::Running)),
+                recolor_aircraft_material.run_if(resource_changed::<ColorTheme>),
             ),
         )
         .init_state::<LoadingState>();
 
+        if APP_CONFIG.dev_gui {
+            app.add_systems(
+                Update,
+                retune_modified_aircraft_types.run_if(in_state(GameState::Running)),
+            );
+        }
+
         if APP_CONFIG.log_state_transitions {
             app.add_systems(Update, (log_transitions::<LoadingState>,));
         }
@@ -152,6 +171,8 @@ pub fn spawn_aircraft(
         aircraft_type_id: "a320".to_owned(),
         call_sign: "Mayday321".to_owned(),
         cleared_altitude_feet: None,
+        cleared_altitude_discretion: false,
+        cleared_altitude_block_feet: None,
         wanted_altitude_feet: 30000.,
         cleared_heading: Some(Heading::from(30.)),
         cleared_speed_knots: None,
@@ -204,6 +225,8 @@ fn spawn_aircraft_at_mouse(
         aircraft_type_id: aircraft_type,
         call_sign,
         cleared_altitude_feet: None,
+        cleared_altitude_discretion: false,
+        cleared_altitude_block_feet: None,
         wanted_altitude_feet: 30000.0,
         cleared_heading: Some(Heading::from(heading)),
         cleared_speed_knots: None,
@@ -240,7 +263,7 @@ fn create_aircraft_bundle(
 }
 
 /// Spawnt ein Flugzeug mit einem Speed-Indikator als Child-Entity
-fn spawn_aircraft_with_speed_indicator(
+pub fn spawn_aircraft_with_speed_indicator(
     commands: &mut Commands,
     aircraft: Aircraft,
     world_pos: Vec2,
@@ -263,11 +286,43 @@ fn spawn_aircraft_with_speed_indicator(
         .id()
 }
 
+/// Applies edits of aircraft type files while the game is running. `update_aircrafts` reads the
+/// new values on its own, but rates above the new maximums are clamped right away.
+fn retune_modified_aircraft_types(
+    mut events: EventReader<AssetEvent<AircraftType>>,
+    aircraft_types: Res<AircraftTypeStore>,
+    aircraft_type_assets: Res<Assets<AircraftType>>,
+    mut q_aircraft: Query<&mut Aircraft>,
+) {
+    for event in events.read() {
+        let AssetEvent::Modified { id } = event else {
+            continue;
+        };
+        let Some(aircraft_type) = aircraft_type_assets.get(*id) else {
+            continue;
+        };
+        let Some(type_id) = aircraft_types
+            .0
+            .iter()
+            .find_map(|(type_id, handle)| (handle.id() == *id).then_some(type_id))
+        else {
+            continue;
+        };
+        info!("Aircraft type {type_id} was modified, retuning aircraft");
+        for mut aircraft in &mut q_aircraft {
+            if aircraft.aircraft_type_id == *type_id {
+                aircraft.retune(aircraft_type);
+            }
+        }
+    }
+}
+
 pub fn update_aircrafts(
     query: Query<(&mut Aircraft, &mut Transform)>,
     time: Res<Time>,
     aircraft_types: Res<AircraftTypeStore>,
     aircraft_type_assets: Res<Assets<AircraftType>>,
+    wind: Res<Wind>,
 ) {
     let delta_seconds = time.delta_secs_f64();
     for (mut aircraft, mut transform) in query {
@@ -277,113 +332,149 @@ pub fn update_aircrafts(
         let Some(aircraft_type) = aircraft_type_assets.get(handle) else {
             continue;
         };
-        // Parameter aus AircraftType
-        let heading_accuracy_degrees = aircraft_type.heading_accuracy_degrees;
-        let max_delta_heading_degrees_per_second =
-            aircraft_type.max_delta_heading_degrees_per_second;
-        let delta_heading_acceleration_degrees_per_second =
-            aircraft_type.delta_heading_acceleration_degrees_per_second;
-        let speed_accuracy_knots = aircraft_type.speed_accuracy_knots;
-        let max_delta_speed_knots_per_second = aircraft_type.max_delta_speed_knots_per_second;
-        let delta_speed_acceleration_knots_per_second =
-            aircraft_type.delta_speed_acceleration_knots_per_second;
-        let altitude_accuracy_feet = aircraft_type.altitude_accuracy_feet;
-        let max_delta_altitude_feet_per_second = aircraft_type.max_delta_altitude_feet_per_second;
-        let delta_altitude_acceleration_feet_per_second =
-            aircraft_type.delta_altitude_acceleration_feet_per_second;
-
-        // heading
-        let wanted = aircraft.cleared_heading.unwrap_or(aircraft.heading);
-        let required_change_u = required_heading_change(
-            aircraft.heading,
-            wanted,
-            aircraft.cleared_heading_change_direction,
+        simulate_aircraft(
+            &mut aircraft,
+            &mut transform,
+            aircraft_type,
+            &wind,
+            delta_seconds,
         );
-        if aircraft.heading_change_degrees_per_second != 0. || required_change_u != 0. {
-            let params = MoveSmoothParams {
-                delta_seconds,
-                val_remaining_u: required_change_u,
-                accuracy_u: heading_accuracy_degrees,
-                max_delta_val_u_per_second: max_delta_heading_degrees_per_second,
-                delta_val_acceleration_u_per_second2: delta_heading_acceleration_degrees_per_second,
-                delta_val_u_per_second: aircraft.heading_change_degrees_per_second,
-            };
-            let MoveSmoothReturn {
-                finished_moving,
-                delta_val_u_per_second,
-            } = move_smooth(params);
-            aircraft.heading_change_degrees_per_second = delta_val_u_per_second;
-            if finished_moving {
-                aircraft.heading = wanted;
-                let rotation_radians = wanted.to_bevy_rotation() as f32;
-                transform.rotation = Quat::from_rotation_z(rotation_radians);
-            }
-        }
-        if aircraft.heading_change_degrees_per_second != 0. {
-            aircraft.heading =
-                aircraft.heading + (delta_seconds * aircraft.heading_change_degrees_per_second);
-            let rotation_radians = aircraft.heading.to_bevy_rotation() as f32;
+    }
+}
+
+/// Moves the aircraft by one step towards its clearances. Only touches the aircraft and its
+/// transform, so it can also run on copies, e.g. to predict the path.
+pub fn simulate_aircraft(
+    aircraft: &mut Aircraft,
+    transform: &mut Transform,
+    aircraft_type: &AircraftType,
+    wind: &Wind,
+    delta_seconds: f64,
+) {
+    // Parameter aus AircraftType
+    let heading_accuracy_degrees = aircraft_type.heading_accuracy_degrees;
+    let max_delta_heading_degrees_per_second = aircraft_type.max_delta_heading_degrees_per_second;
+    let delta_heading_acceleration_degrees_per_second =
+        aircraft_type.delta_heading_acceleration_degrees_per_second;
+    let speed_accuracy_knots = aircraft_type.speed_accuracy_knots;
+    let max_delta_speed_knots_per_second = aircraft_type.max_delta_speed_knots_per_second;
+    let delta_speed_acceleration_knots_per_second =
+        aircraft_type.delta_speed_acceleration_knots_per_second;
+    let altitude_accuracy_feet = aircraft_type.altitude_accuracy_feet;
+    let mut max_delta_altitude_feet_per_second = aircraft_type.max_delta_altitude_feet_per_second;
+    if aircraft.cleared_altitude_discretion {
+        max_delta_altitude_feet_per_second *= DISCRETION_ALTITUDE_RATE_FACTOR;
+    }
+    let delta_altitude_acceleration_feet_per_second =
+        aircraft_type.delta_altitude_acceleration_feet_per_second;
+    let capture_band_multiplier = aircraft_type.capture_band_multiplier;
+
+    // heading
+    let wanted = aircraft.cleared_heading.unwrap_or(aircraft.heading);
+    let required_change_u = required_heading_change(
+        aircraft.heading,
+        wanted,
+        aircraft.cleared_heading_change_direction,
+    );
+    if aircraft.heading_change_degrees_per_second != 0. || required_change_u != 0. {
+        let params = MoveSmoothParams {
+            delta_seconds,
+            val_remaining_u: required_change_u,
+            accuracy_u: heading_accuracy_degrees,
+            max_delta_val_u_per_second: max_delta_heading_degrees_per_second,
+            delta_val_acceleration_u_per_second2: delta_heading_acceleration_degrees_per_second,
+            delta_val_u_per_second: aircraft.heading_change_degrees_per_second,
+            capture_band_multiplier,
+        };
+        let MoveSmoothReturn {
+            finished_moving,
+            delta_val_u_per_second,
+        } = move_smooth(params);
+        aircraft.heading_change_degrees_per_second = delta_val_u_per_second;
+        if finished_moving {
+            aircraft.heading = wanted;
+            let rotation_radians = wanted.to_bevy_rotation() as f32;
             transform.rotation = Quat::from_rotation_z(rotation_radians);
         }
+    }
+    if aircraft.heading_change_degrees_per_second != 0. {
+        aircraft.heading =
+            aircraft.heading + (delta_seconds * aircraft.heading_change_degrees_per_second);
+        let rotation_radians = aircraft.heading.to_bevy_rotation() as f32;
+        transform.rotation = Quat::from_rotation_z(rotation_radians);
+    }
 
-        // speed
-        let wanted = aircraft
-            .cleared_speed_knots
-            .unwrap_or(aircraft.wanted_speed_knots);
-        let required_change_u = -aircraft.speed_knots + wanted;
-        if required_change_u != 0. || aircraft.acceleration_knots_per_second != 0. {
-            let params = MoveSmoothParams {
-                delta_seconds,
-                val_remaining_u: required_change_u,
-                accuracy_u: speed_accuracy_knots,
-                max_delta_val_u_per_second: max_delta_speed_knots_per_second,
-                delta_val_acceleration_u_per_second2: delta_speed_acceleration_knots_per_second,
-                delta_val_u_per_second: aircraft.acceleration_knots_per_second,
-            };
-            let MoveSmoothReturn {
-                finished_moving,
-                delta_val_u_per_second,
-            } = move_smooth(params);
-            aircraft.acceleration_knots_per_second = delta_val_u_per_second;
-            if finished_moving {
-                aircraft.speed_knots = wanted;
-            }
+    // speed
+    let wanted = aircraft
+        .cleared_speed_knots
+        .unwrap_or(aircraft.wanted_speed_knots);
+    let required_change_u = -aircraft.speed_knots + wanted;
+    if required_change_u != 0. || aircraft.acceleration_knots_per_second != 0. {
+        let params = MoveSmoothParams {
+            delta_seconds,
+            val_remaining_u: required_change_u,
+            accuracy_u: speed_accuracy_knots,
+            max_delta_val_u_per_second: max_delta_speed_knots_per_second,
+            delta_val_acceleration_u_per_second2: delta_speed_acceleration_knots_per_second,
+            delta_val_u_per_second: aircraft.acceleration_knots_per_second,
+            capture_band_multiplier,
+        };
+        let MoveSmoothReturn {
+            finished_moving,
+            delta_val_u_per_second,
+        } = move_smooth(params);
+        aircraft.acceleration_knots_per_second = delta_val_u_per_second;
+        if finished_moving {
+            aircraft.speed_knots = wanted;
         }
-        aircraft.speed_knots += aircraft.acceleration_knots_per_second * delta_seconds;
-
-        // Move Aircraft in x-y plane
-        let heading_radians = aircraft.heading.to_bevy_rotation() as f32;
+    }
+    aircraft.speed_knots += aircraft.acceleration_knots_per_second * delta_seconds;
 
-        let movement_distance =
-            (aircraft.speed_knots * delta_seconds * PIXEL_PER_KNOT_SECOND) as f32;
+    // Move Aircraft in x-y plane, drifting with the wind
+    let ground_velocity_knots = aircraft.ground_velocity_knots(wind);
+    let movement = ground_velocity_knots.normalize_or_zero()
+        * knots_to_pixels_per_second(ground_velocity_knots.length())
+        * delta_seconds;
 
-        transform.translation += Vec2::from_angle(heading_radians).extend(0.) * movement_distance;
+    transform.translation += movement.as_vec2().extend(0.);
 
-        // altitude
-        let wanted = aircraft
+    // altitude
+    let wanted = match aircraft.cleared_altitude_block_feet {
+        Some((lower, upper)) => aircraft.altitude_feet.clamp(lower, upper),
+        None => aircraft
             .cleared_altitude_feet
-            .unwrap_or(aircraft.wanted_altitude_feet);
-        let required_change_u = -aircraft.altitude_feet + wanted;
-        if aircraft.altitude_change_feet_per_second != 0. || required_change_u != 0. {
-            let params = MoveSmoothParams {
-                delta_seconds,
-                val_remaining_u: required_change_u,
-                accuracy_u: altitude_accuracy_feet,
-                max_delta_val_u_per_second: max_delta_altitude_feet_per_second,
-                delta_val_acceleration_u_per_second2: delta_altitude_acceleration_feet_per_second,
-                delta_val_u_per_second: aircraft.altitude_change_feet_per_second,
-            };
-            let MoveSmoothReturn {
-                finished_moving,
-                delta_val_u_per_second,
-            } = move_smooth(params);
-            aircraft.altitude_change_feet_per_second = delta_val_u_per_second;
-            if finished_moving {
-                aircraft.altitude_feet = wanted;
-            }
+            .unwrap_or_else(|| uncommanded_altitude_feet(aircraft, aircraft_type)),
+    };
+    let required_change_u = -aircraft.altitude_feet + wanted;
+    if aircraft.altitude_change_feet_per_second != 0. || required_change_u != 0. {
+        let params = MoveSmoothParams {
+            delta_seconds,
+            val_remaining_u: required_change_u,
+            accuracy_u: altitude_accuracy_feet,
+            max_delta_val_u_per_second: max_delta_altitude_feet_per_second,
+            delta_val_acceleration_u_per_second2: delta_altitude_acceleration_feet_per_second,
+            delta_val_u_per_second: aircraft.altitude_change_feet_per_second,
+            capture_band_multiplier,
+        };
+        let MoveSmoothReturn {
+            finished_moving,
+            delta_val_u_per_second,
+        } = move_smooth(params);
+        aircraft.altitude_change_feet_per_second = delta_val_u_per_second;
+        if finished_moving {
+            aircraft.altitude_feet = wanted;
         }
-        aircraft.altitude_feet += aircraft.altitude_change_feet_per_second * delta_seconds;
     }
+    aircraft.altitude_feet += aircraft.altitude_change_feet_per_second * delta_seconds;
+}
+
+/// Altitude, that an aircraft without altitude clearance flies to. It doesn't climb above the
+/// optimal cruising altitude of its type, and levels off, if it is above it already.
+fn uncommanded_altitude_feet(aircraft: &Aircraft, aircraft_type: &AircraftType) -> f64 {
+    let cruising_altitude_feet = aircraft_type
+        .optimal_cruising_altitude_feet
+        .max(aircraft.altitude_feet);
+    aircraft.wanted_altitude_feet.min(cruising_altitude_feet)
 }
 
 pub fn move_smooth(params: MoveSmoothParams) -> MoveSmoothReturn {
@@ -394,9 +485,27 @@ pub fn move_smooth(params: MoveSmoothParams) -> MoveSmoothReturn {
         max_delta_val_u_per_second,
         delta_val_acceleration_u_per_second2,
         delta_val_u_per_second,
+        capture_band_multiplier,
     } = params;
+    debug_assert!(
+        [
+            delta_seconds,
+            val_remaining_u,
+            accuracy_u,
+            max_delta_val_u_per_second,
+            delta_val_acceleration_u_per_second2,
+            delta_val_u_per_second,
+            capture_band_multiplier,
+        ]
+        .iter()
+        .all(|value| value.is_finite()),
+        "move_smooth with non-finite input {params:?}"
+    );
 
-    let finished_moving = val_remaining_u.abs() <= accuracy_u;
+    // The faster the value changes, the wider the band, in which it counts as arrived
+    let capture_band_u =
+        accuracy_u + capture_band_multiplier * delta_val_u_per_second.abs() * delta_seconds;
+    let finished_moving = val_remaining_u.abs() <= capture_band_u;
 
     if finished_moving {
         return MoveSmoothReturn {
@@ -469,10 +578,11 @@ fn setup_aircraft_assets(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
+    color_theme: Res<ColorTheme>,
 ) {
     // Einfaches Quadrat-Mesh für das Flugzeug
     let aircraft_mesh = meshes.add(Rectangle::new(AIRCRAFT_SIZE, AIRCRAFT_SIZE));
-    let aircraft_material = materials.add(ColorMaterial::from(Color::Srgba(AIRCRAFT_COLOR)));
+    let aircraft_material = materials.add(ColorMaterial::from(color_theme.aircraft));
     // Speed-Indikator - wir erstellen eine 1x1 Rechteck und skalieren es dynamisch
     let speed_indicator_mesh = meshes.add(Rectangle::new(1.0, SPEED_INDICATOR_WIDTH));
 
@@ -484,6 +594,19 @@ fn setup_aircraft_assets(
     });
 }
 
+/// Aircraft share one material, so that spawned and existing aircraft follow a theme switch
+fn recolor_aircraft_material(
+    mesh_materials: Res<AircraftMeshMaterials>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    color_theme: Res<ColorTheme>,
+) {
+    recolor(
+        &mut materials,
+        &mesh_materials.material,
+        color_theme.aircraft,
+    );
+}
+
 /// Update aircraft scale based on camera zoom level
 /// Aircraft get larger when zooming out, smaller when zooming in, with min/max limits
 pub fn update_aircraft_scale(
@@ -514,21 +637,21 @@ pub fn update_aircraft_scale(
 }
 
 /// Update speed indicators to show the distance the aircraft would travel in one minute
-/// The length represents how far the aircraft will fly in one minute at current speed
+/// The length represents how far the aircraft will fly in one minute at current ground speed
 /// The indicator does NOT scale with camera zoom - it maintains absolute size
 pub fn update_speed_indicators(
     query: Query<(&Aircraft, &Children, &Transform), With<Aircraft>>,
     mut q_indicators: Query<&mut Transform, (With<SpeedIndicator>, Without<Aircraft>)>,
+    wind: Res<Wind>,
 ) {
     for (aircraft, children, aircraft_transform) in query.iter() {
         // Find the speed indicator child
         for child in children.iter() {
             if let Ok(mut indicator_transform) = q_indicators.get_mut(child) {
                 // Calculate distance in pixels for one minute flight
-                // Speed is in knots, PIXEL_PER_KNOT_SECOND converts knots/second to pixels/second
-                // Multiply by 60 to get pixels for one minute
+                // Multiply pixels per second by 60 to get pixels for one minute
                 let distance_in_one_minute =
-                    (aircraft.speed_knots * PIXEL_PER_KNOT_SECOND * 60.0) as f32;
+                    (knots_to_pixels_per_second(aircraft.ground_speed_knots(&wind)) * 60.0) as f32;
 
                 // Compensate for aircraft scaling to maintain absolute indicator size
                 // The aircraft's scale is used for camera zoom, but we want the indicator
@@ -567,11 +690,17 @@ pub enum LoadingState {
     Finished,
 }
 
-#[derive(Clone, Debug, Component)]
+#[derive(Clone, Debug, PartialEq, Component, Serialize, Deserialize)]
 pub struct Aircraft {
     pub aircraft_type_id: String,
     pub call_sign: String,
     pub cleared_altitude_feet: Option<f64>,
+    /// The pilot may change the altitude at a lower rate, when it suits them
+    pub cleared_altitude_discretion: bool,
+    /// Lower and upper limit of a block altitude. Anywhere in between is fine, so the aircraft
+    /// only climbs or descends, until it is inside.
+    #[serde(default)]
+    pub cleared_altitude_block_feet: Option<(f64, f64)>,
     pub wanted_altitude_feet: f64,
     pub cleared_heading: Option<Heading>,
     pub cleared_heading_change_direction: Option<TurnDirection>,
@@ -585,6 +714,53 @@ pub struct Aircraft {
     pub acceleration_knots_per_second: f64,
 }
 
+impl Aircraft {
+    /// Velocity over ground in knots, in world coordinates. `speed_knots` is the airspeed.
+    pub fn ground_velocity_knots(&self, wind: &Wind) -> DVec2 {
+        wind.ground_velocity_knots(self.heading, self.speed_knots)
+    }
+
+    pub fn ground_speed_knots(&self, wind: &Wind) -> f64 {
+        self.ground_velocity_knots(wind).length()
+    }
+
+    /// Limit the current rates of change to the maximums of the aircraft type
+    pub fn retune(&mut self, aircraft_type: &AircraftType) {
+        let clamp = |val: f64, max: f64| val.clamp(-max, max);
+        self.heading_change_degrees_per_second = clamp(
+            self.heading_change_degrees_per_second,
+            aircraft_type.max_delta_heading_degrees_per_second,
+        );
+        self.acceleration_knots_per_second = clamp(
+            self.acceleration_knots_per_second,
+            aircraft_type.max_delta_speed_knots_per_second,
+        );
+        self.altitude_change_feet_per_second = clamp(
+            self.altitude_change_feet_per_second,
+            aircraft_type.max_delta_altitude_feet_per_second,
+        );
+    }
+
+    /// Pilot response to a query of the controller, based on the current state
+    pub fn query_response(&self, field: QueryField, transition_altitude_feet: f64) -> String {
+        match field {
+            QueryField::Heading => format!("heading {}", self.heading.to_spoken()),
+            QueryField::Altitude => {
+                let altitude = if self.altitude_feet < transition_altitude_feet {
+                    Altitude::Feet(self.altitude_feet)
+                } else {
+                    Altitude::FlightLevel((self.altitude_feet / 100.).round() as u32)
+                };
+                altitude.to_spoken()
+            }
+            QueryField::Speed => format!(
+                "{} knots",
+                spoken_digits(&(self.speed_knots.round() as u32).to_string())
+            ),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Deserialize, Asset, TypePath)]
 pub struct AircraftTypeMeta {
     pub id: String,
@@ -612,15 +788,27 @@ pub struct AircraftType {
     pub altitude_accuracy_feet: f64,
     pub max_delta_altitude_feet_per_second: f64,
     pub delta_altitude_acceleration_feet_per_second: f64,
+    /// Widens the accuracy band by this many ticks of the current rate of change, so that fast
+    /// changes are captured instead of overshooting the target. 0 disables it.
+    pub capture_band_multiplier: f64,
     pub optimal_cruising_altitude_feet: f64,
 }
 
-const AIRCRAFT_COLOR: Srgba = Srgba {
-    red: 0.,
-    green: 0.4,
-    blue: 0.3,
-    alpha: 1.0,
-};
+impl AircraftType {
+    /// Wake turbulence category, derived from the characteristics. Defaults to medium.
+    pub fn wake_category(&self) -> WakeCategory {
+        let has = |characteristic| self.characteristics.contains(&characteristic);
+        if has(AircraftCharacteristic::Super) {
+            WakeCategory::Super
+        } else if has(AircraftCharacteristic::Heavy) {
+            WakeCategory::Heavy
+        } else if has(AircraftCharacteristic::Light) {
+            WakeCategory::Light
+        } else {
+            WakeCategory::Medium
+        }
+    }
+}
 
 const SPEED_INDICATOR_COLOR: Srgba = Srgba {
     red: 0.8,
@@ -629,9 +817,11 @@ const SPEED_INDICATOR_COLOR: Srgba = Srgba {
     alpha: 0.8,
 };
 
-#[derive(Debug, Clone, Deserialize, Reflect)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Reflect)]
 pub enum AircraftCharacteristic {
+    Light,
     Heavy,
+    Super,
 }
 
 #[derive(Clone, Debug, Event)]
@@ -648,6 +838,7 @@ pub struct MoveSmoothParams {
     pub max_delta_val_u_per_second: f64,
     pub delta_val_acceleration_u_per_second2: f64,
     pub delta_val_u_per_second: f64,
+    pub capture_band_multiplier: f64,
 }
 
 #[derive(Debug)]
@@ -656,9 +847,448 @@ pub struct MoveSmoothReturn {
     pub delta_val_u_per_second: f64,
 }
 
+/// Aircraft in level flight at 5000 ft and 250 kt, heading 090, without clearances
+#[cfg(test)]
+pub fn test_aircraft() -> Aircraft {
+    Aircraft {
+        aircraft_type_id: "a320".to_owned(),
+        call_sign: "DLH123".to_owned(),
+        cleared_altitude_feet: None,
+        cleared_altitude_discretion: false,
+        cleared_altitude_block_feet: None,
+        wanted_altitude_feet: 5000.,
+        cleared_heading: None,
+        cleared_heading_change_direction: None,
+        cleared_speed_knots: None,
+        wanted_speed_knots: 250.,
+        altitude_feet: 5000.,
+        altitude_change_feet_per_second: 0.,
+        heading: Heading::from(90.),
+        heading_change_degrees_per_second: 0.,
+        speed_knots: 250.,
+        acceleration_knots_per_second: 0.,
+    }
+}
+
+/// The A320 of the test aircraft
+#[cfg(test)]
+pub fn test_aircraft_type() -> AircraftType {
+    AircraftType {
+        id: "a320".to_owned(),
+        name: "Airbus A320".to_owned(),
+        characteristics: vec![],
+        heading_accuracy_degrees: 0.2,
+        max_delta_heading_degrees_per_second: 2.1,
+        delta_heading_acceleration_degrees_per_second: 0.45,
+        speed_accuracy_knots: 0.2,
+        max_delta_speed_knots_per_second: 2.1,
+        delta_speed_acceleration_knots_per_second: 0.12,
+        altitude_accuracy_feet: 10.0,
+        max_delta_altitude_feet_per_second: 110.0,
+        delta_altitude_acceleration_feet_per_second: 5.5,
+        capture_band_multiplier: 1.0,
+        optimal_cruising_altitude_feet: 37000.0,
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{MoveSmoothParams, MoveSmoothReturn, move_smooth};
+    use std::time::Duration;
+
+    use aviation_helper_rs::clearance::aviation_command::QueryField;
+    use aviation_helper_rs::types::heading::Heading;
+    use bevy::platform::collections::hash_map::HashMap;
+    use bevy::prelude::*;
+
+    use super::{
+        Aircraft, AircraftMeshMaterials, AircraftType, AircraftTypeStore, MoveSmoothParams,
+        MoveSmoothReturn, move_smooth, recolor_aircraft_material, retune_modified_aircraft_types,
+        setup_aircraft_assets, simulate_aircraft, spawn_aircraft_with_speed_indicator,
+        test_aircraft, test_aircraft_type, update_aircrafts,
+    };
+    use crate::game::clearance::{
+        ClearanceLog, ClearanceTrigger, CommandQueue, QueuedClearance, fire_queued_clearances,
+    };
+    use crate::game::color_theme::ColorTheme;
+    use crate::game::wind::Wind;
+
+    #[test]
+    fn test_modified_aircraft_type_is_used_by_update_aircrafts() {
+        let mut app = App::new();
+        app.insert_resource(Time::<()>::default())
+            .init_resource::<Wind>()
+            .add_systems(Update, update_aircrafts);
+
+        let mut assets = Assets::<AircraftType>::default();
+        let handle = assets.add(test_aircraft_type());
+        app.insert_resource(assets)
+            .insert_resource(AircraftTypeStore(HashMap::from_iter([(
+                "a320".to_owned(),
+                handle.clone(),
+            )])));
+
+        let aircraft = Aircraft {
+            cleared_speed_knots: Some(250.),
+            speed_knots: 200.,
+            ..test_aircraft()
+        };
+        let accelerate_for_one_second = |app: &mut App| {
+            let entity = app
+                .world_mut()
+                .spawn((aircraft.clone(), Transform::default()))
+                .id();
+            app.world_mut()
+                .resource_mut::<Time>()
+                .advance_by(Duration::from_secs(1));
+            app.update();
+            let acceleration = app
+                .world()
+                .get::<Aircraft>(entity)
+                .unwrap()
+                .acceleration_knots_per_second;
+            app.world_mut().despawn(entity);
+            acceleration
+        };
+
+        assert_eq!(accelerate_for_one_second(&mut app), 0.12);
+
+        app.world_mut()
+            .resource_mut::<Assets<AircraftType>>()
+            .get_mut(&handle)
+            .unwrap()
+            .delta_speed_acceleration_knots_per_second = 1.0;
+        assert_eq!(accelerate_for_one_second(&mut app), 1.0);
+    }
+
+    #[test]
+    fn test_modified_aircraft_type_retunes_its_aircraft() {
+        let mut app = App::new();
+        app.add_event::<AssetEvent<AircraftType>>()
+            .add_systems(Update, retune_modified_aircraft_types);
+
+        let mut assets = Assets::<AircraftType>::default();
+        let handle = assets.add(test_aircraft_type());
+        app.insert_resource(assets)
+            .insert_resource(AircraftTypeStore(HashMap::from_iter([(
+                "a320".to_owned(),
+                handle.clone(),
+            )])));
+
+        let accelerating = |aircraft_type_id: &str| Aircraft {
+            aircraft_type_id: aircraft_type_id.to_owned(),
+            acceleration_knots_per_second: 2.,
+            ..test_aircraft()
+        };
+        let modified = app.world_mut().spawn(accelerating("a320")).id();
+        let other = app.world_mut().spawn(accelerating("b738")).id();
+
+        app.world_mut()
+            .resource_mut::<Assets<AircraftType>>()
+            .get_mut(&handle)
+            .unwrap()
+            .max_delta_speed_knots_per_second = 1.;
+        app.world_mut()
+            .send_event(AssetEvent::<AircraftType>::Modified { id: handle.id() });
+        app.update();
+
+        let acceleration = |entity| {
+            app.world()
+                .get::<Aircraft>(entity)
+                .unwrap()
+                .acceleration_knots_per_second
+        };
+        assert_eq!(acceleration(modified), 1.);
+        assert_eq!(acceleration(other), 2.);
+    }
+
+    #[test]
+    fn test_same_clearances_give_same_state_at_any_frame_rate() {
+        use aviation_helper_rs::clearance::aviation_command::{
+            AviationCommandPart, HeadingDirection,
+        };
+        use aviation_helper_rs::types::altitude::Altitude;
+        use bevy::time::{TimePlugin, TimeUpdateStrategy};
+
+        let simulate_ten_seconds = |frames_per_second: u32| {
+            let mut app = App::new();
+            app.add_plugins(TimePlugin)
+                .insert_resource(TimeUpdateStrategy::ManualDuration(
+                    Duration::from_secs(1) / frames_per_second,
+                ))
+                .init_resource::<ClearanceLog>()
+                .init_resource::<Wind>()
+                .add_systems(
+                    FixedUpdate,
+                    (fire_queued_clearances, update_aircrafts).chain(),
+                );
+            let mut assets = Assets::<AircraftType>::default();
+            let handle = assets.add(test_aircraft_type());
+            app.insert_resource(assets)
+                .insert_resource(AircraftTypeStore(HashMap::from_iter([(
+                    "a320".to_owned(),
+                    handle,
+                )])));
+            let clearance = |seconds, command| QueuedClearance {
+                trigger: (seconds > 0)
+                    .then(|| ClearanceTrigger::AtTime(Duration::from_secs(seconds))),
+                command,
+                coalesce: false,
+            };
+            let entity = app
+                .world_mut()
+                .spawn((
+                    test_aircraft(),
+                    Transform::default(),
+                    CommandQueue(vec![
+                        clearance(
+                            0,
+                            AviationCommandPart::FlyHeading {
+                                heading: HeadingDirection::Heading(Heading::from(180.)),
+                                turn_direction: None,
+                            },
+                        ),
+                        clearance(
+                            2,
+                            AviationCommandPart::ChangeAltitude {
+                                altitude: Altitude::Feet(8000.),
+                                maintain: true,
+                                discretion: false,
+                                turn_direction: None,
+                            },
+                        ),
+                        clearance(5, AviationCommandPart::ChangeSpeed { knots: 220 }),
+                        // A right click on the card, that is undone
+                        clearance(6, AviationCommandPart::ResumeNormalSpeed),
+                        clearance(7, AviationCommandPart::Disregard),
+                        clearance(8, AviationCommandPart::ResumeOwnNavigation),
+                    ]),
+                ))
+                .id();
+            // The first update only starts the clock
+            for _ in 0..=(10 * frames_per_second) {
+                app.update();
+            }
+            let world = app.world();
+            assert_eq!(
+                world.resource::<Time<Fixed>>().elapsed(),
+                Duration::from_secs(10)
+            );
+            (
+                world.get::<Aircraft>(entity).unwrap().clone(),
+                *world.get::<Transform>(entity).unwrap(),
+            )
+        };
+
+        let (aircraft, transform) = simulate_ten_seconds(50);
+        assert_eq!(aircraft.cleared_altitude_feet, Some(8000.));
+        assert_eq!(aircraft.cleared_speed_knots, Some(220.));
+        assert_eq!(aircraft.cleared_heading, None);
+        assert_eq!((aircraft, transform), simulate_ten_seconds(128));
+    }
+
+    #[test]
+    fn test_query_response() {
+        let aircraft = Aircraft {
+            wanted_altitude_feet: 35000.,
+            altitude_feet: 34980.,
+            heading: Heading::from(270.),
+            speed_knots: 249.6,
+            ..test_aircraft()
+        };
+        let response = |field| aircraft.query_response(field, 18000.);
+
+        assert_eq!(response(QueryField::Heading), "heading two seven zero");
+        assert_eq!(
+            response(QueryField::Altitude),
+            "flight level three five zero"
+        );
+        assert_eq!(response(QueryField::Speed), "two five zero knots");
+        assert_eq!(
+            Aircraft {
+                altitude_feet: 4500.,
+                ..aircraft.clone()
+            }
+            .query_response(QueryField::Altitude, 18000.),
+            "four thousand five hundred feet"
+        );
+    }
+
+    #[test]
+    fn test_discretion_descends_slower() {
+        let aircraft_type = test_aircraft_type();
+        let seconds_to_descend = |discretion| {
+            let mut aircraft = Aircraft {
+                cleared_altitude_feet: Some(5000.),
+                cleared_altitude_discretion: discretion,
+                wanted_altitude_feet: 10000.,
+                altitude_feet: 10000.,
+                ..test_aircraft()
+            };
+            let mut transform = Transform::default();
+            let delta_seconds = 1. / 64.;
+            let mut seconds = 0.;
+            while aircraft.altitude_feet != 5000. {
+                simulate_aircraft(
+                    &mut aircraft,
+                    &mut transform,
+                    &aircraft_type,
+                    &Wind::default(),
+                    delta_seconds,
+                );
+                seconds += delta_seconds;
+                assert!(seconds < 600., "Didn't reach the cleared altitude");
+            }
+            seconds
+        };
+
+        let normal = seconds_to_descend(false);
+        let discretion = seconds_to_descend(true);
+        assert!(
+            discretion > normal * 1.5,
+            "Descent took {discretion}s at pilot's discretion and {normal}s normally"
+        );
+    }
+
+    #[test]
+    fn test_block_altitude_is_kept_inside() {
+        let aircraft_type = test_aircraft_type();
+        let altitude_after_five_minutes = |altitude_feet| {
+            let mut aircraft = Aircraft {
+                cleared_altitude_block_feet: Some((10000., 12000.)),
+                wanted_altitude_feet: 30000.,
+                altitude_feet,
+                ..test_aircraft()
+            };
+            let mut transform = Transform::default();
+            for _ in 0..(300 * 64) {
+                simulate_aircraft(
+                    &mut aircraft,
+                    &mut transform,
+                    &aircraft_type,
+                    &Wind::default(),
+                    1. / 64.,
+                );
+            }
+            aircraft.altitude_feet
+        };
+
+        // Inside the block, the aircraft stays, where it is
+        assert_eq!(altitude_after_five_minutes(11000.), 11000.);
+        // Outside, it flies to the nearest limit
+        assert_eq!(altitude_after_five_minutes(14000.), 12000.);
+        assert_eq!(altitude_after_five_minutes(8000.), 10000.);
+    }
+
+    #[test]
+    fn test_uncommanded_aircraft_levels_off_at_cruise() {
+        let aircraft_type = test_aircraft_type();
+        let mut aircraft = Aircraft {
+            wanted_altitude_feet: 41000.,
+            altitude_feet: 30000.,
+            ..test_aircraft()
+        };
+        let mut transform = Transform::default();
+        let mut simulate = |aircraft: &mut Aircraft, seconds: usize| {
+            for _ in 0..(seconds * 64) {
+                simulate_aircraft(
+                    aircraft,
+                    &mut transform,
+                    &aircraft_type,
+                    &Wind::default(),
+                    1. / 64.,
+                );
+            }
+        };
+
+        simulate(&mut aircraft, 300);
+        assert_eq!(aircraft.altitude_feet, 37000.);
+        simulate(&mut aircraft, 300);
+        assert_eq!(aircraft.altitude_feet, 37000.);
+        assert_eq!(aircraft.altitude_change_feet_per_second, 0.);
+
+        // A clearance still takes it higher
+        aircraft.cleared_altitude_feet = Some(39000.);
+        simulate(&mut aircraft, 300);
+        assert_eq!(aircraft.altitude_feet, 39000.);
+    }
+
+    #[test]
+    fn test_spawned_aircraft_use_the_color_theme() {
+        let mut app = App::new();
+        app.init_resource::<Assets<Mesh>>()
+            .init_resource::<Assets<ColorMaterial>>()
+            .init_resource::<ColorTheme>()
+            .add_systems(Startup, setup_aircraft_assets)
+            .add_systems(
+                Update,
+                recolor_aircraft_material.run_if(resource_changed::<ColorTheme>),
+            );
+        app.update();
+        let spawned_aircraft_color = |app: &mut App| {
+            let mesh_materials = app.world().resource::<AircraftMeshMaterials>().clone();
+            let aircraft = test_aircraft();
+            let world = app.world_mut();
+            let entity = spawn_aircraft_with_speed_indicator(
+                &mut world.commands(),
+                aircraft,
+                Vec2::ZERO,
+                &mesh_materials,
+            );
+            world.flush();
+            let material = &world
+                .get::<MeshMaterial2d<ColorMaterial>>(entity)
+                .unwrap()
+                .0;
+            world
+                .resource::<Assets<ColorMaterial>>()
+                .get(material)
+                .unwrap()
+                .color
+        };
+        assert_eq!(
+            spawned_aircraft_color(&mut app),
+            ColorTheme::default().aircraft
+        );
+
+        app.insert_resource(ColorTheme::colorblind());
+        app.update();
+
+        assert_eq!(
+            spawned_aircraft_color(&mut app),
+            ColorTheme::colorblind().aircraft
+        );
+    }
+
+    #[test]
+    fn test_turn_to_heading_360() {
+        let aircraft_type = test_aircraft_type();
+        for start in [330., 30.] {
+            let mut aircraft = Aircraft {
+                cleared_heading: Some(Heading::from(360.)),
+                heading: Heading::from(start),
+                ..test_aircraft()
+            };
+            let mut transform = Transform::default();
+            for _ in 0..(120 * 64) {
+                simulate_aircraft(
+                    &mut aircraft,
+                    &mut transform,
+                    &aircraft_type,
+                    &Wind::default(),
+                    1. / 64.,
+                );
+                // The aircraft takes the short way over north
+                let off_north = Heading::from(360.).required_change(aircraft.heading).abs();
+                assert!(off_north <= 31., "Turned away to {}", aircraft.heading);
+            }
+            assert_eq!(aircraft.heading, Heading::from(360.));
+            let direction = transform.rotation * Vec3::X;
+            assert!(
+                direction.truncate().distance(Vec2::Y) < 1e-3,
+                "Not pointing north: {direction}"
+            );
+        }
+    }
 
     #[test]
     fn test_move_ascend_over() {
@@ -670,6 +1300,7 @@ mod tests {
             max_delta_val_u_per_second: 2.,
             delta_val_acceleration_u_per_second2: 0.1,
             delta_val_u_per_second: *delta_val,
+            capture_band_multiplier: 0.,
         };
         let MoveSmoothReturn {
             delta_val_u_per_second,
@@ -690,6 +1321,7 @@ mod tests {
             max_delta_val_u_per_second: 2.,
             delta_val_acceleration_u_per_second2: 0.1,
             delta_val_u_per_second: *delta_val,
+            capture_band_multiplier: 0.,
         };
         let MoveSmoothReturn {
             delta_val_u_per_second,
@@ -699,4 +1331,45 @@ mod tests {
         dbg!(delta_val_u_per_second);
         assert!(delta_val_u_per_second < 2. && delta_val_u_per_second > -2.);
     }
+
+    /// Counts how often the rate of change reverses while moving towards the target with a
+    /// tick length, in which the value can step over the accuracy band
+    fn count_direction_reversals(capture_band_multiplier: f64) -> usize {
+        let delta_seconds = 0.5;
+        let target = 1000.;
+        let mut val = 0.;
+        let mut delta_val_u_per_second = 100.;
+        let mut reversals = 0;
+        for _ in 0..200 {
+            let MoveSmoothReturn {
+                finished_moving,
+                delta_val_u_per_second: new_delta_val,
+            } = move_smooth(MoveSmoothParams {
+                delta_seconds,
+                val_remaining_u: target - val,
+                accuracy_u: 10.,
+                max_delta_val_u_per_second: 110.,
+                delta_val_acceleration_u_per_second2: 5.5,
+                delta_val_u_per_second,
+                capture_band_multiplier,
+            });
+            if finished_moving {
+                val = target;
+            }
+            if new_delta_val * delta_val_u_per_second < 0. {
+                reversals += 1;
+            }
+            if new_delta_val != 0. {
+                delta_val_u_per_second = new_delta_val;
+            }
+            val += new_delta_val * delta_seconds;
+        }
+        reversals
+    }
+
+    #[test]
+    fn test_capture_band_prevents_oscillation() {
+        assert!(count_direction_reversals(0.) > 0);
+        assert_eq!(count_direction_reversals(1.), 0);
+    }
 }
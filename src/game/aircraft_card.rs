@@ -1,37 +1,38 @@
+use std::time::Duration;
+
+use crate::APP_CONFIG;
 use crate::game::aircraft::AircraftJustSpawned;
 use crate::game::run_conditions::was_mouse_wheel_used;
+use aviation_helper_rs::clearance::aviation_command::{AviationCommandPart, HeadingDirection};
+use aviation_helper_rs::types::altitude::Altitude;
 use aviation_helper_rs::types::heading::Heading;
 use bevy::ecs::component::Component;
 use bevy::input::common_conditions::input_just_pressed;
 use bevy::input::mouse::{AccumulatedMouseScroll, MouseScrollUnit};
-use bevy::picking::events::{Drag, DragEnd, DragStart, Pointer};
+use bevy::picking::events::{Click, Drag, DragEnd, DragStart, Pointer};
 use bevy::prelude::*;
+use bevy_common_assets::ron::RonAssetPlugin;
+use serde::Deserialize;
 use strum::EnumIter;
 
-use super::aircraft::Aircraft;
+use super::aircraft::{Aircraft, SpeedIndicator};
+use super::clearance::{QueuedClearance, queue_clearance};
+use super::color_theme::{ColorTheme, recolor};
 use super::control::{
     ControlMode, ControlState, control_mode_is_clearance_selection, control_mode_is_normal,
 };
-use super::{GameState, Z_AIRCRAFT_CARD};
+use super::handoff::HandoffDue;
+use super::history_panel::HistoryEntry;
+use super::radar_coverage::{Coasting, radar_position};
+use super::separation::{ConflictAlertState, SeparationConflict};
+use super::{GameState, GameVariables, Z_AIRCRAFT_CARD};
+use crate::util::units::format_feet;
 
-const AIRCRAFT_CARD_COLOR: Srgba = Srgba {
-    red: 0.1,
-    green: 0.3,
-    blue: 0.1,
-    alpha: 0.5,
-};
-const NORMAL_AIRCRAFT_CARD_COLOR: Srgba = Srgba {
-    red: 0.1,
-    green: 0.1,
-    blue: 0.1,
-    alpha: 0.7,
-};
-const SELECTED_AIRCRAFT_CARD_COLOR: Srgba = Srgba {
-    red: 0.5,
-    green: 0.5,
-    blue: 0.1,
-    alpha: 0.7,
-};
+/// Time, that the card of an aircraft in a new conflict is shown in each of the two colors
+const CONFLICT_FLASH_SECONDS: f32 = 0.5;
+
+/// Fraction of their overlap, by which overlapping cards are pushed apart per second
+const DECLUTTER_RATE_PER_SECOND: f32 = 5.0;
 
 // Drag distance scaling constants
 const DRAG_DISTANCE_BASE: f32 = 200.0; // Base distance in pixels
@@ -52,22 +53,45 @@ const STEP_SPEED: f64 = 10.;
 const STEP_SPEED_ACCEL: f64 = 50.;
 const STEP_ALTITUDE: f64 = 500.;
 const STEP_ALTITUDE_ACCEL: f64 = 5000.;
+/// Scroll steps on the same field, that are closer together, belong to one gesture
+const SCROLL_GESTURE_TIMEOUT: Duration = Duration::from_millis(500);
+
+// Rates, below which the card shows no trend
+const TREND_DEAD_BAND_DEGREES_PER_SECOND: f64 = 0.1;
+const TREND_DEAD_BAND_FEET_PER_SECOND: f64 = 1.;
 
 #[derive(Clone, Debug)]
 pub struct AircraftCardPlugin;
 
 impl Plugin for AircraftCardPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, setup_aircraft_card_display_materials)
+        app.add_plugins(RonAssetPlugin::<CardLayout>::new(&["ron"]))
+            .init_resource::<CardLayout>()
+            .add_systems(
+                Startup,
+                (setup_aircraft_card_display_materials, load_card_layout),
+            )
+            .add_systems(
+                Update,
+                (
+                    apply_loaded_card_layout,
+                    recolor_aircraft_card_display_materials.run_if(resource_changed::<ColorTheme>),
+                ),
+            )
             .add_systems(
                 Update,
                 (
                     handle_escape_clear_selected.run_if(input_just_pressed(KeyCode::Escape)),
+                    cycle_selected_aircraft.run_if(input_just_pressed(KeyCode::Tab)),
                     handle_clear_selected_on_any_click,
                     update_aircraft_card,
+                    tint_cards,
                     update_card_scale,
                     handle_aircraft_just_spawned,
+                    declutter_cards.before(update_pinned),
                     update_pinned,
+                    draw_leader_lines.after(update_pinned),
+                    despawn_orphaned_cards,
                     (
                         handle_aircraft_card_display_press.run_if(control_mode_is_normal),
                         handle_card_scroll
@@ -83,7 +107,7 @@ impl Plugin for AircraftCardPlugin {
 #[derive(Clone, Debug, Component)]
 pub struct AircraftCard;
 
-#[derive(Debug, Clone, Copy, Component, EnumIter, PartialEq)]
+#[derive(Debug, Clone, Copy, Component, EnumIter, PartialEq, Deserialize)]
 pub enum AircraftCardDisplay {
     Callsign,
     ClearedHeading,
@@ -94,6 +118,53 @@ pub enum AircraftCardDisplay {
     Altitude,
 }
 
+/// Fields of the card and their positions. New cards are built from it.
+#[derive(Resource, Asset, TypePath, Deserialize, Clone, Debug)]
+pub struct CardLayout {
+    /// Size of the card background
+    pub size: Vec2,
+    pub slots: Vec<CardSlot>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct CardSlot {
+    pub display: AircraftCardDisplay,
+    /// Position of the slot center relative to the card center
+    pub offset: Vec2,
+    pub size: Vec2,
+}
+
+impl Default for CardLayout {
+    /// Callsign, heading, speed and altitude with their cleared values
+    fn default() -> Self {
+        use AircraftCardDisplay::*;
+        let slot = |display, x, y| CardSlot {
+            display,
+            offset: Vec2::new(x, y),
+            size: Vec2::new(23., 11.),
+        };
+        Self {
+            size: Vec2::new(74., 50.),
+            slots: vec![
+                CardSlot {
+                    display: Callsign,
+                    offset: Vec2::new(0., 18.),
+                    size: Vec2::new(71., 11.),
+                },
+                slot(ClearedHeading, -24., 6.),
+                slot(Heading, 0., 6.),
+                slot(ClearedSpeed, -24., -6.),
+                slot(Speed, 0., -6.),
+                slot(ClearedAltitude, -24., -18.),
+                slot(Altitude, 0., -18.),
+            ],
+        }
+    }
+}
+
+#[derive(Resource)]
+struct CardLayoutHandle(Handle<CardLayout>);
+
 #[derive(Component)]
 pub struct PinnedTo {
     pub entity: Entity,
@@ -112,18 +183,39 @@ pub struct AircraftCardDisplayMaterials {
 fn setup_aircraft_card_display_materials(
     mut commands: Commands,
     mut materials: ResMut<Assets<ColorMaterial>>,
+    color_theme: Res<ColorTheme>,
 ) {
-    let normal = materials.add(Color::Srgba(NORMAL_AIRCRAFT_CARD_COLOR));
-    let selected = materials.add(Color::Srgba(SELECTED_AIRCRAFT_CARD_COLOR));
+    let normal = materials.add(color_theme.card_display);
+    let selected = materials.add(color_theme.selected_card_display);
     commands.insert_resource(AircraftCardDisplayMaterials { normal, selected });
 }
 
+fn recolor_aircraft_card_display_materials(
+    display_materials: Res<AircraftCardDisplayMaterials>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    color_theme: Res<ColorTheme>,
+) {
+    recolor(
+        &mut materials,
+        &display_materials.normal,
+        color_theme.card_display,
+    );
+    recolor(
+        &mut materials,
+        &display_materials.selected,
+        color_theme.selected_card_display,
+    );
+}
+
+/// Cards of coasting aircraft keep the values of the last radar return
 pub fn update_aircraft_card(
     q_aircraft_card: Query<(&Children, &PinnedTo), With<AircraftCard>>,
-    q_aircraft: Query<&Aircraft>,
+    q_aircraft: Query<&Aircraft, Without<Coasting>>,
     q_card_children: Query<(&AircraftCardDisplay, &Children)>,
     mut q_text: Query<&mut Text2d>,
+    variables: Res<GameVariables>,
 ) {
+    let transition_altitude_feet = variables.transition_level_feet();
     for (
         card_children,
         PinnedTo {
@@ -149,17 +241,35 @@ pub fn update_aircraft_card(
                             .as_ref()
                             .map(Heading::to_string)
                             .unwrap_or_default(),
-                        AircraftCardDisplay::Heading => Heading::to_string(&aircraft.heading),
+                        AircraftCardDisplay::Heading => format!(
+                            "{}{}",
+                            aircraft.heading,
+                            heading_trend_glyph(aircraft.heading_change_degrees_per_second)
+                        ),
                         AircraftCardDisplay::ClearedSpeed => aircraft
                             .cleared_speed_knots
                             .map(display_speed)
                             .unwrap_or_default(),
                         AircraftCardDisplay::Speed => display_speed(aircraft.speed_knots),
-                        AircraftCardDisplay::ClearedAltitude => aircraft
-                            .cleared_altitude_feet
-                            .map(display_altitude)
-                            .unwrap_or_default(),
-                        AircraftCardDisplay::Altitude => display_altitude(aircraft.altitude_feet),
+                        AircraftCardDisplay::ClearedAltitude => {
+                            match aircraft.cleared_altitude_block_feet {
+                                // Like "FL100-FL120"
+                                Some((lower, upper)) => format!(
+                                    "{}-{}",
+                                    format_feet(lower, transition_altitude_feet),
+                                    format_feet(upper, transition_altitude_feet)
+                                ),
+                                None => aircraft
+                                    .cleared_altitude_feet
+                                    .map(|altitude| format_feet(altitude, transition_altitude_feet))
+                                    .unwrap_or_default(),
+                            }
+                        }
+                        AircraftCardDisplay::Altitude => format!(
+                            "{}{}",
+                            format_feet(aircraft.altitude_feet, transition_altitude_feet),
+                            altitude_trend_glyph(aircraft.altitude_change_feet_per_second)
+                        ),
                     };
                 }
             }
@@ -167,12 +277,104 @@ pub fn update_aircraft_card(
     }
 }
 
+/// Tints the cards of aircraft in conflict and of aircraft, that have to be handed off soon, and
+/// restores the others. Cards of new conflicts flash first, priority conflicts with an aircraft
+/// in an emergency keep flashing in the emergency color.
+fn tint_cards(
+    q_cards: Query<(&PinnedTo, &MeshMaterial2d<ColorMaterial>), With<AircraftCard>>,
+    q_handoff_due: Query<(), With<HandoffDue>>,
+    q_conflict: Query<&SeparationConflict>,
+    alert_state: Res<ConflictAlertState>,
+    time: Res<Time>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    color_theme: Res<ColorTheme>,
+) {
+    let highlighted = (time.elapsed_secs() / CONFLICT_FLASH_SECONDS) as u32 % 2 == 0;
+    for (PinnedTo { entity, .. }, material) in &q_cards {
+        let color = match q_conflict.get(*entity) {
+            Ok(SeparationConflict { priority: true }) if highlighted => color_theme.emergency,
+            Ok(SeparationConflict { priority: false })
+                if highlighted || !alert_state.is_flashing(*entity, time.elapsed()) =>
+            {
+                color_theme.conflict_card
+            }
+            _ if q_handoff_due.contains(*entity) => color_theme.handoff_due_card,
+            _ => color_theme.aircraft_card,
+        };
+        // Only changed materials are marked as modified
+        if materials
+            .get(&material.0)
+            .is_some_and(|material| material.color != color)
+        {
+            if let Some(material) = materials.get_mut(&material.0) {
+                material.color = color;
+            }
+        }
+    }
+}
+
 fn display_speed(speed_knots: f64) -> String {
     speed_knots.floor().to_string()
 }
 
-fn display_altitude(altitude_feet: f64) -> String {
-    (altitude_feet as i32 / 100).to_string()
+/// ">" while turning right, "<" while turning left
+fn heading_trend_glyph(heading_change_degrees_per_second: f64) -> &'static str {
+    trend_glyph(
+        heading_change_degrees_per_second,
+        TREND_DEAD_BAND_DEGREES_PER_SECOND,
+        (">", "<"),
+    )
+}
+
+/// "^" while climbing, "v" while descending
+fn altitude_trend_glyph(altitude_change_feet_per_second: f64) -> &'static str {
+    trend_glyph(
+        altitude_change_feet_per_second,
+        TREND_DEAD_BAND_FEET_PER_SECOND,
+        ("^", "v"),
+    )
+}
+
+/// Glyph for an increasing or decreasing value. Empty, if the rate is within the dead band.
+fn trend_glyph(
+    rate: f64,
+    dead_band: f64,
+    (increasing, decreasing): (&'static str, &'static str),
+) -> &'static str {
+    if rate > dead_band {
+        increasing
+    } else if rate < -dead_band {
+        decreasing
+    } else {
+        ""
+    }
+}
+
+fn load_card_layout(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let handle = asset_server.load(format!("card_layouts/{}.ron", APP_CONFIG.card_layout));
+    commands.insert_resource(CardLayoutHandle(handle));
+}
+
+/// Replaces the default layout, once the layout file is loaded or edited. Existing cards keep
+/// their layout.
+fn apply_loaded_card_layout(
+    mut events: EventReader<AssetEvent<CardLayout>>,
+    card_layouts: Res<Assets<CardLayout>>,
+    handle: Res<CardLayoutHandle>,
+    mut card_layout: ResMut<CardLayout>,
+) {
+    for event in events.read() {
+        let (AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id }) = event
+        else {
+            continue;
+        };
+        if *id != handle.0.id() {
+            continue;
+        }
+        if let Some(loaded) = card_layouts.get(*id) {
+            *card_layout = loaded.clone();
+        }
+    }
 }
 
 pub fn handle_aircraft_just_spawned(
@@ -181,42 +383,26 @@ pub fn handle_aircraft_just_spawned(
     mut materials: ResMut<Assets<ColorMaterial>>,
     mut commands: Commands,
     card_materials: Res<AircraftCardDisplayMaterials>,
+    card_layout: Res<CardLayout>,
+    color_theme: Res<ColorTheme>,
 ) {
-    use AircraftCardDisplay::*;
     for event in events.read() {
         let AircraftJustSpawned(aircraft_entity) = event;
-        let mut children = Vec::new();
-        let card_display = create_card_display_bundle(
-            Callsign,
-            meshes.add(Rectangle::new(71., 11.)),
-            card_materials.normal.clone(),
-            0.,
-            18.,
-            0.5,
-        );
-        let child_entity = commands.spawn(card_display).id();
-        children.push(child_entity);
-        let mesh = meshes.add(Rectangle::new(23.0, 11.0));
-        let display_coords = [
-            (ClearedHeading, -24., 6.),
-            (Heading, 0., 6.),
-            (ClearedSpeed, -24., -6.),
-            (Speed, 0., -6.),
-            (ClearedAltitude, -24., -18.),
-            (Altitude, 0., -18.),
-        ];
-        for (display, x, y) in display_coords {
-            let card_display = create_card_display_bundle(
-                display,
-                mesh.clone(),
-                card_materials.normal.clone(),
-                x,
-                y,
-                0.5,
-            );
-            let child_entity = commands.spawn(card_display).id();
-            children.push(child_entity);
-        }
+        let children: Vec<Entity> = card_layout
+            .slots
+            .iter()
+            .map(|slot| {
+                let card_display = create_card_display_bundle(
+                    slot.display,
+                    meshes.add(Rectangle::from_size(slot.size)),
+                    card_materials.normal.clone(),
+                    slot.offset.x,
+                    slot.offset.y,
+                    0.5,
+                );
+                commands.spawn(card_display).id()
+            })
+            .collect();
         let relative_translation = Vec3::new(-80., 0., 0.);
         let mut entity = commands.spawn((
             AircraftCard,
@@ -224,8 +410,8 @@ pub fn handle_aircraft_just_spawned(
                 entity: *aircraft_entity,
                 relative_translation,
             },
-            Mesh2d(meshes.add(Rectangle::new(74., 50.))),
-            MeshMaterial2d(materials.add(Color::Srgba(AIRCRAFT_CARD_COLOR))),
+            Mesh2d(meshes.add(Rectangle::from_size(card_layout.size))),
+            MeshMaterial2d(materials.add(color_theme.aircraft_card)),
             Transform::from_xyz(0., 0., Z_AIRCRAFT_CARD),
             Visibility::Visible,
         ));
@@ -235,6 +421,8 @@ pub fn handle_aircraft_just_spawned(
             .observe(on_card_drag)
             .observe(on_card_drag_end);
         entity.add_children(&children);
+
+        commands.entity(*aircraft_entity).observe(on_aircraft_click);
     }
 }
 
@@ -269,10 +457,11 @@ fn create_card_display_bundle(
 
 #[allow(clippy::too_many_arguments)]
 pub fn handle_aircraft_card_display_press(
+    mut commands: Commands,
     mut events: EventReader<Pointer<Pressed>>,
     q_card_display: Query<(Entity, &AircraftCardDisplay, &ChildOf)>,
     q_card: Query<&PinnedTo, With<AircraftCard>>,
-    mut q_aircraft: Query<&mut Aircraft>,
+    q_aircraft: Query<&Aircraft>,
     mut q_display: Query<(&AircraftCardDisplay, &mut MeshMaterial2d<ColorMaterial>)>,
     card_materials: Res<AircraftCardDisplayMaterials>,
     mut control_state: ResMut<ControlState>,
@@ -291,13 +480,16 @@ pub fn handle_aircraft_card_display_press(
         };
         // Rechtsklick: Clearance entfernen
         if event.button == PointerButton::Secondary {
-            if let Ok(mut aircraft) = q_aircraft.get_mut(*aircraft_entity) {
-                match display {
-                    AircraftCardDisplay::ClearedHeading => aircraft.cleared_heading = None,
-                    AircraftCardDisplay::ClearedSpeed => aircraft.cleared_speed_knots = None,
-                    AircraftCardDisplay::ClearedAltitude => aircraft.cleared_altitude_feet = None,
-                    _ => {}
-                }
+            let command = q_aircraft
+                .get(*aircraft_entity)
+                .ok()
+                .and_then(|aircraft| cancelling_command(*display, aircraft));
+            if let Some(command) = command {
+                queue_clearance(
+                    &mut commands,
+                    *aircraft_entity,
+                    QueuedClearance::untriggered(&command),
+                );
             }
             continue; // Keine Auswahl setzen
         }
@@ -313,16 +505,164 @@ pub fn handle_aircraft_card_display_press(
     }
 }
 
+/// Command, that cancels the clearance shown by the display. There is no phraseology, that
+/// cancels an altitude, so the aircraft is cleared to the altitude, that it wants anyway.
+fn cancelling_command(
+    display: AircraftCardDisplay,
+    aircraft: &Aircraft,
+) -> Option<AviationCommandPart> {
+    match display {
+        AircraftCardDisplay::ClearedHeading => Some(AviationCommandPart::ResumeOwnNavigation),
+        AircraftCardDisplay::ClearedSpeed => Some(AviationCommandPart::ResumeNormalSpeed),
+        AircraftCardDisplay::ClearedAltitude => Some(AviationCommandPart::ChangeAltitude {
+            altitude: Altitude::Feet(aircraft.wanted_altitude_feet),
+            maintain: true,
+            discretion: false,
+            turn_direction: None,
+        }),
+        _ => None,
+    }
+}
+
+/// Selects the cleared heading of an aircraft, when the aircraft itself is clicked. Clicks on the
+/// speed indicator bubble up to the aircraft.
+fn on_aircraft_click(
+    mut trigger: Trigger<Pointer<Click>>,
+    q_card: Query<(&PinnedTo, &Children), With<AircraftCard>>,
+    mut q_display: Query<(&AircraftCardDisplay, &mut MeshMaterial2d<ColorMaterial>)>,
+    card_materials: Res<AircraftCardDisplayMaterials>,
+    mut control_state: ResMut<ControlState>,
+) {
+    if trigger.event().button != PointerButton::Primary {
+        return;
+    }
+    trigger.propagate(false);
+    select_card_display(
+        trigger.target(),
+        AircraftCardDisplay::ClearedHeading,
+        &q_card,
+        &mut q_display,
+        &card_materials,
+        &mut control_state,
+    );
+}
+
+/// Tab selects the next aircraft by call sign, Shift-Tab the previous one. The same field of
+/// the card stays selected, the cleared heading if none was.
+fn cycle_selected_aircraft(
+    input: Res<ButtonInput<KeyCode>>,
+    q_aircraft: Query<(Entity, &Aircraft)>,
+    q_card: Query<(&PinnedTo, &Children), With<AircraftCard>>,
+    mut q_display: Query<(&AircraftCardDisplay, &mut MeshMaterial2d<ColorMaterial>)>,
+    card_materials: Res<AircraftCardDisplayMaterials>,
+    mut control_state: ResMut<ControlState>,
+) {
+    let mut aircraft: Vec<(Entity, &str)> = q_aircraft
+        .iter()
+        .map(|(entity, aircraft)| (entity, aircraft.call_sign.as_str()))
+        .collect();
+    aircraft.sort_by(|(_, a), (_, b)| a.cmp(b));
+    let (current, display) = match &control_state.mode {
+        ControlMode::ClearanceSelection {
+            aircraft_entity,
+            display,
+            ..
+        } => (
+            aircraft
+                .iter()
+                .position(|(entity, _)| entity == aircraft_entity),
+            *display,
+        ),
+        ControlMode::Normal | ControlMode::HeadingDrag { .. } => {
+            (None, AircraftCardDisplay::ClearedHeading)
+        }
+    };
+    let backwards = input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    let Some(next) = cycle_index(current, aircraft.len(), backwards) else {
+        return;
+    };
+    select_card_display(
+        aircraft[next].0,
+        display,
+        &q_card,
+        &mut q_display,
+        &card_materials,
+        &mut control_state,
+    );
+}
+
+/// Index of the next or previous item of a list, wrapping around at the ends. Without a
+/// current item, it is the first or the last one.
+fn cycle_index(current: Option<usize>, len: usize, backwards: bool) -> Option<usize> {
+    if len == 0 {
+        return None;
+    }
+    Some(match (current.filter(|index| *index < len), backwards) {
+        (None, false) => 0,
+        (None, true) => len - 1,
+        (Some(index), false) => (index + 1) % len,
+        (Some(index), true) => (index + len - 1) % len,
+    })
+}
+
+/// Selects the field of the card of the aircraft and highlights it instead of the previously
+/// selected one
+pub fn select_card_display(
+    aircraft_entity: Entity,
+    display: AircraftCardDisplay,
+    q_card: &Query<(&PinnedTo, &Children), With<AircraftCard>>,
+    q_display: &mut Query<(&AircraftCardDisplay, &mut MeshMaterial2d<ColorMaterial>)>,
+    card_materials: &AircraftCardDisplayMaterials,
+    control_state: &mut ControlState,
+) {
+    let Some(display_entity) = q_card
+        .iter()
+        .find(|(PinnedTo { entity, .. }, _)| *entity == aircraft_entity)
+        .and_then(|(_, children)| {
+            children.iter().find(|child| {
+                q_display
+                    .get(*child)
+                    .is_ok_and(|(child_display, _)| *child_display == display)
+            })
+        })
+    else {
+        return;
+    };
+    if let ControlMode::ClearanceSelection {
+        display_entity: previous_display_entity,
+        ..
+    } = &control_state.mode
+    {
+        if let Ok((_, mut display_material)) = q_display.get_mut(*previous_display_entity) {
+            display_material.0 = card_materials.normal.clone();
+        }
+    }
+    control_state.mode = ControlMode::ClearanceSelection {
+        aircraft_entity,
+        display_entity,
+        display,
+    };
+    if let Ok((_, mut display_material)) = q_display.get_mut(display_entity) {
+        display_material.0 = card_materials.selected.clone();
+    }
+}
+
+/// Presses on an aircraft or a history entry don't clear the selection, because the click on it
+/// selects the aircraft after the press.
 pub fn handle_clear_selected_on_any_click(
     mut events: EventReader<Pointer<Pressed>>,
+    q_aircraft_parts: Query<(), Or<(With<Aircraft>, With<SpeedIndicator>, With<HistoryEntry>)>>,
     mut q_display: Query<&mut MeshMaterial2d<ColorMaterial>>,
     card_materials: Res<AircraftCardDisplayMaterials>,
     mut control_state: ResMut<ControlState>,
 ) {
-    if events.is_empty() {
+    let pressed_elsewhere = events
+        .read()
+        .filter(|event| !q_aircraft_parts.contains(event.target))
+        .count();
+    if pressed_elsewhere == 0 {
         return;
     }
-    events.clear();
     if let ControlMode::ClearanceSelection { display_entity, .. } = &control_state.mode {
         if let Ok(mut display_material) = q_display.get_mut(*display_entity) {
             display_material.0 = card_materials.normal.clone();
@@ -344,12 +684,27 @@ pub fn handle_escape_clear_selected(
     control_state.mode = ControlMode::Normal;
 }
 
+/// Last step of a scroll gesture. Steps are queued, so the cleared value of the aircraft lags
+/// behind until the next fixed update. The next step of the gesture starts from this value.
+#[derive(Debug, Clone, Copy)]
+pub struct ScrollGesture {
+    aircraft: Entity,
+    display: AircraftCardDisplay,
+    value: f64,
+    at: Duration,
+}
+
 // Scroll-System: Greift auf ControlMode::ClearanceSelection zu
+/// Every step is queued as a clearance, like a transmission. The steps of a gesture are logged
+/// as one clearance, so that the gesture is undone at once.
 pub fn handle_card_scroll(
+    mut commands: Commands,
+    time: Res<Time>,
     accumulated_mouse_scroll: Res<AccumulatedMouseScroll>,
     control_state: Res<ControlState>,
-    mut q_aircraft: Query<&mut Aircraft>,
+    q_aircraft: Query<&Aircraft>,
     input: Res<ButtonInput<KeyCode>>,
+    mut gesture: Local<Option<ScrollGesture>>,
 ) {
     let ControlMode::ClearanceSelection {
         aircraft_entity,
@@ -359,39 +714,61 @@ pub fn handle_card_scroll(
     else {
         return;
     };
-    let Ok(mut aircraft) = q_aircraft.get_mut(*aircraft_entity) else {
+    let Ok(aircraft) = q_aircraft.get(*aircraft_entity) else {
         return;
     };
+    let pending = gesture
+        .filter(|gesture| {
+            gesture.aircraft == *aircraft_entity
+                && gesture.display == *display
+                && time.elapsed().saturating_sub(gesture.at) <= SCROLL_GESTURE_TIMEOUT
+        })
+        .map(|gesture| gesture.value);
     let delta: f64 = if accumulated_mouse_scroll.unit == MouseScrollUnit::Line {
         accumulated_mouse_scroll.delta.y as f64
     } else {
         (accumulated_mouse_scroll.delta.y / 100.).round() as f64
     };
     let ctrl = input.pressed(KeyCode::ControlLeft) || input.pressed(KeyCode::ControlRight);
-    match display {
+    let (value, command) = match display {
         AircraftCardDisplay::ClearedHeading => {
             let step = if ctrl {
                 STEP_HEADING_ACCEL
             } else {
                 STEP_HEADING
             };
-            let new_val = calculate_cleared_value(
+            let Some(new_val) = calculate_cleared_value(
                 aircraft.heading.get(),
-                aircraft.cleared_heading.map(|h| h.get()),
+                pending.or(aircraft.cleared_heading.map(|h| h.get())),
                 delta,
                 step,
-            );
-            aircraft.cleared_heading = Some(Heading::from(new_val));
+            ) else {
+                return;
+            };
+            (
+                new_val,
+                AviationCommandPart::FlyHeading {
+                    heading: HeadingDirection::Heading(Heading::from(new_val)),
+                    turn_direction: aircraft.cleared_heading_change_direction,
+                },
+            )
         }
         AircraftCardDisplay::ClearedSpeed => {
             let step = if ctrl { STEP_SPEED_ACCEL } else { STEP_SPEED };
-            let new_val = calculate_cleared_value(
+            let Some(new_val) = calculate_cleared_value(
                 aircraft.speed_knots,
-                aircraft.cleared_speed_knots,
+                pending.or(aircraft.cleared_speed_knots),
                 delta,
                 step,
-            );
-            aircraft.cleared_speed_knots = Some(new_val);
+            ) else {
+                return;
+            };
+            (
+                new_val,
+                AviationCommandPart::ChangeSpeed {
+                    knots: new_val.round() as u32,
+                },
+            )
         }
         AircraftCardDisplay::ClearedAltitude => {
             let step = if ctrl {
@@ -399,21 +776,55 @@ pub fn handle_card_scroll(
             } else {
                 STEP_ALTITUDE
             };
-            let new_val = calculate_cleared_value(
+            let Some(new_val) = calculate_cleared_value(
                 aircraft.altitude_feet,
-                aircraft.cleared_altitude_feet,
+                pending.or(aircraft.cleared_altitude_feet),
                 delta,
                 step,
-            );
-            aircraft.cleared_altitude_feet = Some(new_val);
+            ) else {
+                return;
+            };
+            (
+                new_val,
+                AviationCommandPart::ChangeAltitude {
+                    altitude: Altitude::Feet(new_val),
+                    maintain: true,
+                    discretion: false,
+                    turn_direction: None,
+                },
+            )
+        }
+        _ => return,
+    };
+    *gesture = Some(ScrollGesture {
+        aircraft: *aircraft_entity,
+        display: *display,
+        value,
+        at: time.elapsed(),
+    });
+    let queued = match pending {
+        Some(_) => QueuedClearance::coalesced(&command),
+        None => QueuedClearance::untriggered(&command),
+    };
+    queue_clearance(&mut commands, *aircraft_entity, queued);
+}
+
+/// Removes cards of aircraft, that don't exist anymore (e.g. after landing)
+fn despawn_orphaned_cards(
+    mut commands: Commands,
+    q_aircraft_card: Query<(Entity, &PinnedTo), With<AircraftCard>>,
+    q_aircraft: Query<(), With<Aircraft>>,
+) {
+    for (card_entity, PinnedTo { entity, .. }) in &q_aircraft_card {
+        if !q_aircraft.contains(*entity) {
+            commands.entity(card_entity).despawn();
         }
-        _ => {}
     }
 }
 
 pub fn update_pinned(
     mut q_pinned: Query<(&mut PinnedTo, &mut Transform), Without<BeingDragged>>,
-    q_target: Query<&Transform, Without<PinnedTo>>,
+    q_target: Query<(&Transform, Option<&Coasting>), Without<PinnedTo>>,
     camera_projection: Single<&Projection, With<Camera2d>>,
 ) {
     let scale = if let Projection::Orthographic(ortho) = &**camera_projection {
@@ -431,13 +842,10 @@ pub fn update_pinned(
     let max_drag_distance = DRAG_DISTANCE_BASE * distance_scale_factor;
 
     for (mut pinned_to, mut pinned_by_transform) in &mut q_pinned {
-        let Ok(Transform {
-            translation: target_translation,
-            ..
-        }) = q_target.get(pinned_to.entity)
-        else {
+        let Ok((target_transform, coasting)) = q_target.get(pinned_to.entity) else {
             continue;
         };
+        let target_translation = radar_position(target_transform, coasting);
 
         // Check if current relative distance exceeds max drag distance
         let current_distance = (pinned_to.relative_translation.x
@@ -456,7 +864,78 @@ pub fn update_pinned(
     }
 }
 
-fn calculate_cleared_value(current: f64, cleared: Option<f64>, delta: f64, step: f64) -> f64 {
+/// Push of the card at `position` away from the card at `other`, that separates them, if both
+/// have the size and overlap. Pushing both cards by half of it in opposite directions, along
+/// the axis with less overlap, resolves the overlap.
+fn separation_nudge(position: Vec2, other: Vec2, card_size: Vec2) -> Vec2 {
+    let offset = position - other;
+    let overlap = card_size - offset.abs();
+    if overlap.x <= 0. || overlap.y <= 0. {
+        return Vec2::ZERO;
+    }
+    // Cards at the same position are pushed apart in arbitrary, but opposite directions
+    let direction = offset.signum();
+    if overlap.x < overlap.y {
+        Vec2::new(direction.x * overlap.x / 2., 0.)
+    } else {
+        Vec2::new(0., direction.y * overlap.y / 2.)
+    }
+}
+
+/// Gradually moves overlapping cards apart. Only the position relative to the aircraft
+/// changes, so the cards stay pinned and are clamped by `update_pinned` afterwards.
+fn declutter_cards(
+    time: Res<Time>,
+    card_layout: Res<CardLayout>,
+    mut q_cards: Query<(&mut PinnedTo, &Transform, Has<BeingDragged>), With<AircraftCard>>,
+) {
+    let factor = (DECLUTTER_RATE_PER_SECOND * time.delta_secs()).min(1.);
+    let mut combinations = q_cards.iter_combinations_mut();
+    while let Some([a, b]) = combinations.fetch_next() {
+        let (mut pinned_a, transform_a, dragged_a) = a;
+        let (mut pinned_b, transform_b, dragged_b) = b;
+        let card_size = card_layout.size * transform_a.scale.truncate();
+        let nudge = separation_nudge(
+            transform_a.translation.truncate(),
+            transform_b.translation.truncate(),
+            card_size,
+        ) * factor;
+        // The card in the hand of the user stays where it is
+        if !dragged_a {
+            pinned_a.relative_translation += nudge.extend(0.);
+        }
+        if !dragged_b {
+            pinned_b.relative_translation -= nudge.extend(0.);
+        }
+    }
+}
+
+/// Connects each card with its aircraft
+fn draw_leader_lines(
+    mut gizmos: Gizmos,
+    q_cards: Query<(&PinnedTo, &Transform), With<AircraftCard>>,
+    q_aircraft: Query<(&Transform, Option<&Coasting>), (With<Aircraft>, Without<AircraftCard>)>,
+    color_theme: Res<ColorTheme>,
+) {
+    for (pinned_to, card_transform) in &q_cards {
+        let Ok((aircraft_transform, coasting)) = q_aircraft.get(pinned_to.entity) else {
+            continue;
+        };
+        gizmos.line_2d(
+            radar_position(aircraft_transform, coasting).truncate(),
+            card_transform.translation.truncate(),
+            color_theme.leader_line,
+        );
+    }
+}
+
+/// Next value on the grid of the step. None, if it isn't finite, e.g. for a step of zero.
+fn calculate_cleared_value(
+    current: f64,
+    cleared: Option<f64>,
+    delta: f64,
+    step: f64,
+) -> Option<f64> {
     let base = cleared.unwrap_or(current);
     let idx = base / step;
     let is_on_grid = (base % step).abs() == 0.;
@@ -469,7 +948,12 @@ fn calculate_cleared_value(current: f64, cleared: Option<f64>, delta: f64, step:
         (false, false) => idx.ceil(),
     };
     // TODO: Delta (scroll delta) is not factored in yet
-    new_idx * step
+    let value = new_idx * step;
+    if !value.is_finite() {
+        warn!("Scrolled to {value} from {base} with a step of {step}, ignored");
+        return None;
+    }
+    Some(value)
 }
 
 fn on_card_drag(
@@ -568,3 +1052,351 @@ pub fn update_card_scale(
         transform.scale = Vec3::new(card_scale_factor, card_scale_factor, 1.0);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use aviation_helper_rs::types::heading::Heading;
+    use bevy::input::mouse::{AccumulatedMouseScroll, MouseScrollUnit};
+    use bevy::picking::backend::HitData;
+    use bevy::picking::events::{Click, Pointer, Pressed};
+    use bevy::picking::pointer::{Location, PointerButton, PointerId};
+    use bevy::prelude::*;
+    use bevy::render::camera::{ManualTextureViewHandle, NormalizedRenderTarget};
+
+    use super::{
+        AircraftCard, AircraftCardDisplay, AircraftCardDisplayMaterials, CardLayout, CardSlot,
+        PinnedTo, SCROLL_GESTURE_TIMEOUT, altitude_trend_glyph, calculate_cleared_value,
+        cycle_index, handle_aircraft_card_display_press, handle_aircraft_just_spawned,
+        handle_card_scroll, heading_trend_glyph, on_aircraft_click, separation_nudge,
+    };
+    use crate::game::aircraft::{Aircraft, AircraftJustSpawned, SpeedIndicator, test_aircraft};
+    use crate::game::clearance::{ClearanceLog, fire_queued_clearances};
+    use crate::game::color_theme::ColorTheme;
+    use crate::game::control::{ControlMode, ControlState};
+
+    fn click(target: Entity, button: PointerButton) -> Pointer<Click> {
+        Pointer::new(
+            PointerId::Mouse,
+            Location {
+                target: NormalizedRenderTarget::TextureView(ManualTextureViewHandle(0)),
+                position: Vec2::ZERO,
+            },
+            target,
+            Click {
+                button,
+                hit: HitData::new(Entity::PLACEHOLDER, 0., None, None),
+                duration: Duration::from_millis(100),
+            },
+        )
+    }
+
+    #[test]
+    fn test_separation_nudge() {
+        let card_size = Vec2::new(74., 50.);
+        let a = Vec2::new(0., 0.);
+        let b = Vec2::new(20., 10.);
+
+        // Less overlap vertically (40) than horizontally (54), so they are pushed apart vertically
+        let nudge = separation_nudge(a, b, card_size);
+        assert_eq!(nudge, Vec2::new(0., -20.));
+        assert_eq!(separation_nudge(b, a, card_size), -nudge);
+        let (a, b) = (a + nudge, b - nudge);
+        assert_eq!(separation_nudge(a, b, card_size), Vec2::ZERO);
+
+        assert_eq!(
+            separation_nudge(Vec2::ZERO, Vec2::new(70., 0.), card_size),
+            Vec2::new(-2., 0.)
+        );
+        assert_eq!(
+            separation_nudge(Vec2::ZERO, Vec2::new(80., 0.), card_size),
+            Vec2::ZERO
+        );
+    }
+
+    #[test]
+    fn test_cycle_index_wraps_around() {
+        assert_eq!(cycle_index(Some(0), 3, false), Some(1));
+        assert_eq!(cycle_index(Some(2), 3, false), Some(0));
+        assert_eq!(cycle_index(Some(0), 3, true), Some(2));
+        assert_eq!(cycle_index(Some(2), 3, true), Some(1));
+        // Nothing selected yet, or the selected aircraft is gone
+        assert_eq!(cycle_index(None, 3, false), Some(0));
+        assert_eq!(cycle_index(None, 3, true), Some(2));
+        assert_eq!(cycle_index(Some(5), 3, false), Some(0));
+        assert_eq!(cycle_index(Some(0), 1, true), Some(0));
+        assert_eq!(cycle_index(None, 0, false), None);
+    }
+
+    #[test]
+    fn test_cleared_value_must_be_finite() {
+        assert_eq!(calculate_cleared_value(95., None, 1., 10.), Some(100.));
+        assert_eq!(
+            calculate_cleared_value(95., Some(100.), -1., 10.),
+            Some(90.)
+        );
+        assert_eq!(calculate_cleared_value(95., None, 1., 0.), None);
+        assert_eq!(calculate_cleared_value(f64::NAN, None, 1., 10.), None);
+    }
+
+    #[test]
+    fn test_right_click_cancels_the_clearance_through_the_log() {
+        let mut app = App::new();
+        app.insert_resource(Time::<()>::default())
+            .init_resource::<ClearanceLog>()
+            .init_resource::<ControlState>()
+            .insert_resource(AircraftCardDisplayMaterials {
+                normal: Handle::default(),
+                selected: Handle::default(),
+            })
+            .add_event::<Pointer<Pressed>>()
+            .add_systems(
+                Update,
+                (handle_aircraft_card_display_press, fire_queued_clearances).chain(),
+            );
+        let aircraft = Aircraft {
+            cleared_heading: Some(Heading::from(270.)),
+            cleared_speed_knots: Some(210.),
+            cleared_altitude_feet: Some(5000.),
+            ..test_aircraft()
+        };
+        let wanted_altitude_feet = aircraft.wanted_altitude_feet;
+        let entity = app.world_mut().spawn((aircraft, Transform::default())).id();
+        let card = app
+            .world_mut()
+            .spawn((
+                AircraftCard,
+                PinnedTo {
+                    entity,
+                    relative_translation: Vec3::ZERO,
+                },
+            ))
+            .id();
+        for display in [
+            AircraftCardDisplay::ClearedHeading,
+            AircraftCardDisplay::ClearedSpeed,
+            AircraftCardDisplay::ClearedAltitude,
+        ] {
+            let display_entity = app.world_mut().spawn((display, ChildOf(card))).id();
+            app.world_mut().send_event(Pointer::new(
+                PointerId::Mouse,
+                Location {
+                    target: NormalizedRenderTarget::TextureView(ManualTextureViewHandle(0)),
+                    position: Vec2::ZERO,
+                },
+                display_entity,
+                Pressed {
+                    button: PointerButton::Secondary,
+                    hit: HitData::new(Entity::PLACEHOLDER, 0., None, None),
+                },
+            ));
+        }
+        app.update();
+
+        let aircraft = app.world().get::<Aircraft>(entity).unwrap();
+        assert_eq!(aircraft.cleared_heading, None);
+        assert_eq!(aircraft.cleared_speed_knots, None);
+        assert_eq!(aircraft.cleared_altitude_feet, Some(wanted_altitude_feet));
+        assert_eq!(app.world().resource::<ClearanceLog>().entries.len(), 3);
+    }
+
+    #[test]
+    fn test_scroll_gesture_is_logged_once() {
+        let mut app = App::new();
+        app.insert_resource(Time::<()>::default())
+            .init_resource::<ClearanceLog>()
+            .init_resource::<ButtonInput<KeyCode>>()
+            .insert_resource(AccumulatedMouseScroll {
+                unit: MouseScrollUnit::Line,
+                delta: Vec2::Y,
+            })
+            .add_systems(Update, (handle_card_scroll, fire_queued_clearances).chain());
+        let entity = app
+            .world_mut()
+            .spawn((test_aircraft(), Transform::default()))
+            .id();
+        app.insert_resource(ControlState {
+            mode: ControlMode::ClearanceSelection {
+                aircraft_entity: entity,
+                display_entity: Entity::PLACEHOLDER,
+                display: AircraftCardDisplay::ClearedHeading,
+            },
+        });
+        let cleared_heading =
+            |app: &App| app.world().get::<Aircraft>(entity).unwrap().cleared_heading;
+
+        for _ in 0..3 {
+            app.update();
+        }
+        assert_eq!(cleared_heading(&app), Some(Heading::from(105.)));
+        assert_eq!(app.world().resource::<ClearanceLog>().entries.len(), 1);
+
+        // A pause starts a new gesture
+        app.world_mut()
+            .resource_mut::<Time>()
+            .advance_by(SCROLL_GESTURE_TIMEOUT * 2);
+        app.update();
+        assert_eq!(cleared_heading(&app), Some(Heading::from(110.)));
+        assert_eq!(app.world().resource::<ClearanceLog>().entries.len(), 2);
+
+        // Undoing the first gesture goes back to no cleared heading at all
+        app.world_mut()
+            .resource_scope(|world, mut log: Mut<ClearanceLog>| {
+                let mut aircraft = world.get_mut::<Aircraft>(entity).unwrap();
+                log.revert_last(entity, &mut aircraft);
+                log.revert_last(entity, &mut aircraft);
+            });
+        assert_eq!(cleared_heading(&app), None);
+    }
+
+    #[test]
+    fn test_trend_glyphs() {
+        assert_eq!(altitude_trend_glyph(25.), "^");
+        assert_eq!(altitude_trend_glyph(-25.), "v");
+        assert_eq!(altitude_trend_glyph(0.), "");
+        // Within the dead band
+        assert_eq!(altitude_trend_glyph(0.5), "");
+        assert_eq!(altitude_trend_glyph(-0.5), "");
+
+        assert_eq!(heading_trend_glyph(3.), ">");
+        assert_eq!(heading_trend_glyph(-3.), "<");
+        assert_eq!(heading_trend_glyph(0.), "");
+        assert_eq!(heading_trend_glyph(0.05), "");
+    }
+
+    #[test]
+    fn test_click_on_aircraft_selects_cleared_heading() {
+        let mut app = App::new();
+        let normal = Handle::weak_from_u128(1);
+        let selected = Handle::weak_from_u128(2);
+        app.init_resource::<ControlState>()
+            .insert_resource(AircraftCardDisplayMaterials {
+                normal: normal.clone(),
+                selected: selected.clone(),
+            });
+        let aircraft = app
+            .world_mut()
+            .spawn_empty()
+            .observe(on_aircraft_click)
+            .id();
+        let speed_indicator = app
+            .world_mut()
+            .spawn((SpeedIndicator, ChildOf(aircraft)))
+            .id();
+        let card = app
+            .world_mut()
+            .spawn((
+                AircraftCard,
+                PinnedTo {
+                    entity: aircraft,
+                    relative_translation: Vec3::ZERO,
+                },
+            ))
+            .id();
+        let cleared_speed = app
+            .world_mut()
+            .spawn((
+                AircraftCardDisplay::ClearedSpeed,
+                MeshMaterial2d(selected.clone()),
+                ChildOf(card),
+            ))
+            .id();
+        let cleared_heading = app
+            .world_mut()
+            .spawn((
+                AircraftCardDisplay::ClearedHeading,
+                MeshMaterial2d(normal.clone()),
+                ChildOf(card),
+            ))
+            .id();
+        app.world_mut().resource_mut::<ControlState>().mode = ControlMode::ClearanceSelection {
+            aircraft_entity: aircraft,
+            display_entity: cleared_speed,
+            display: AircraftCardDisplay::ClearedSpeed,
+        };
+
+        // Secondary clicks don't change the selection
+        app.world_mut()
+            .trigger_targets(click(aircraft, PointerButton::Secondary), aircraft);
+        assert_eq!(
+            app.world().resource::<ControlState>().mode,
+            ControlMode::ClearanceSelection {
+                aircraft_entity: aircraft,
+                display_entity: cleared_speed,
+                display: AircraftCardDisplay::ClearedSpeed,
+            }
+        );
+
+        // The click on the speed indicator bubbles up to the aircraft
+        app.world_mut().trigger_targets(
+            click(speed_indicator, PointerButton::Primary),
+            speed_indicator,
+        );
+        assert_eq!(
+            app.world().resource::<ControlState>().mode,
+            ControlMode::ClearanceSelection {
+                aircraft_entity: aircraft,
+                display_entity: cleared_heading,
+                display: AircraftCardDisplay::ClearedHeading,
+            }
+        );
+        let material = |app: &App, entity| {
+            app.world()
+                .get::<MeshMaterial2d<ColorMaterial>>(entity)
+                .unwrap()
+                .0
+                .clone()
+        };
+        assert_eq!(material(&app, cleared_heading), selected);
+        assert_eq!(material(&app, cleared_speed), normal);
+    }
+
+    #[test]
+    fn test_card_is_built_from_layout() {
+        let mut app = App::new();
+        app.init_resource::<Assets<Mesh>>()
+            .init_resource::<Assets<ColorMaterial>>()
+            .insert_resource(AircraftCardDisplayMaterials {
+                normal: Handle::weak_from_u128(1),
+                selected: Handle::weak_from_u128(2),
+            })
+            .init_resource::<ColorTheme>()
+            .insert_resource(CardLayout {
+                size: Vec2::new(50., 26.),
+                slots: vec![
+                    CardSlot {
+                        display: AircraftCardDisplay::Callsign,
+                        offset: Vec2::new(0., 6.),
+                        size: Vec2::new(47., 11.),
+                    },
+                    CardSlot {
+                        display: AircraftCardDisplay::Altitude,
+                        offset: Vec2::new(0., -6.),
+                        size: Vec2::new(23., 11.),
+                    },
+                ],
+            })
+            .add_event::<AircraftJustSpawned>()
+            .add_systems(Update, handle_aircraft_just_spawned);
+        let aircraft = app.world_mut().spawn_empty().id();
+
+        app.world_mut().send_event(AircraftJustSpawned(aircraft));
+        app.update();
+
+        let card_children = app
+            .world_mut()
+            .query_filtered::<&Children, With<AircraftCard>>()
+            .single(app.world())
+            .unwrap()
+            .to_vec();
+        let displays: Vec<AircraftCardDisplay> = card_children
+            .into_iter()
+            .filter_map(|child| app.world().get::<AircraftCardDisplay>(child).copied())
+            .collect();
+        assert_eq!(
+            displays,
+            vec![AircraftCardDisplay::Callsign, AircraftCardDisplay::Altitude]
+        );
+    }
+}
@@ -0,0 +1,272 @@
+use aviation_helper_rs::clearance::aviation_command::AviationCommandPart;
+use aviation_helper_rs::types::heading::Heading;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::game::GameState;
+use crate::game::aircraft::Aircraft;
+use crate::game::level::Runway;
+use crate::util::units::{nm_to_pixels, pixels_to_nm};
+
+/// Angle between the intercept heading and the localizer
+const INTERCEPT_ANGLE_DEGREES: f64 = 30.;
+/// Distance from the localizer, at which aircraft start to turn onto it. Closer aircraft fly a
+/// proportionally smaller intercept angle, so they roll out on the localizer.
+const LOCALIZER_CAPTURE_DISTANCE_NM: f64 = 2.;
+/// Distance of the short final of a visual approach from the threshold. Aircraft fly directly
+/// to it and from there straight to the threshold.
+const VISUAL_FINAL_DISTANCE_NM: f64 = 2.;
+/// Descent on a visual approach, about a 3° glide path
+const VISUAL_DESCENT_FEET_PER_NM: f64 = 300.;
+
+pub struct ApproachPlugin;
+
+impl Plugin for ApproachPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            (update_ils_intercepts, update_visual_approaches).run_if(in_state(GameState::Running)),
+        );
+    }
+}
+
+/// Aircraft, that is vectored onto the localizer of a runway. The cleared heading follows the
+/// intercept heading until the aircraft gets another heading clearance.
+#[derive(Component, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IlsIntercept {
+    pub runway: String,
+}
+
+impl IlsIntercept {
+    pub fn from_command(command: &AviationCommandPart) -> Option<Self> {
+        match command {
+            AviationCommandPart::VectorsForIls { runway } => Some(Self {
+                runway: runway.clone(),
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Aircraft, that flies a visual approach to a runway. Unlike the ILS, it doesn't need to
+/// intercept the localizer, but flies directly to the threshold and descends on its own.
+#[derive(Component, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VisualApproach {
+    pub runway: String,
+}
+
+impl VisualApproach {
+    pub fn from_command(command: &AviationCommandPart) -> Option<Self> {
+        match command {
+            AviationCommandPart::ClearedVisualApproach { runway } => Some(Self {
+                runway: runway.clone(),
+            }),
+            _ => None,
+        }
+    }
+}
+
+pub fn update_ils_intercepts(
+    q_aircraft: Query<(&mut Aircraft, &Transform, &IlsIntercept)>,
+    q_runways: Query<&Runway>,
+) {
+    for (mut aircraft, transform, IlsIntercept { runway }) in q_aircraft {
+        let Some(runway) = q_runways.iter().find(|r| r.name == *runway) else {
+            continue;
+        };
+        let heading = intercept_heading(transform.translation.truncate(), runway);
+        if aircraft.cleared_heading != Some(heading) {
+            aircraft.cleared_heading = Some(heading);
+            aircraft.cleared_heading_change_direction = None;
+        }
+    }
+}
+
+pub fn update_visual_approaches(
+    q_aircraft: Query<(&mut Aircraft, &Transform, &VisualApproach)>,
+    q_runways: Query<&Runway>,
+) {
+    for (mut aircraft, transform, VisualApproach { runway }) in q_aircraft {
+        let Some(runway) = q_runways.iter().find(|r| r.name == *runway) else {
+            continue;
+        };
+        let (heading, altitude_feet) =
+            visual_approach_guidance(transform.translation.truncate(), runway);
+        if aircraft.cleared_heading != Some(heading) {
+            aircraft.cleared_heading = Some(heading);
+            aircraft.cleared_heading_change_direction = None;
+        }
+        // Aircraft below the glide path level off instead of climbing
+        let altitude_feet = altitude_feet.min(aircraft.altitude_feet);
+        if aircraft.cleared_altitude_feet != Some(altitude_feet) {
+            aircraft.cleared_altitude_feet = Some(altitude_feet);
+            aircraft.cleared_altitude_discretion = false;
+            aircraft.cleared_altitude_block_feet = None;
+        }
+    }
+}
+
+/// Heading and altitude on a visual approach to the runway: Directly to the short final, until
+/// the aircraft is that close to the threshold, then directly to the threshold
+pub fn visual_approach_guidance(position: Vec2, runway: &Runway) -> (Heading, f64) {
+    let direction = (runway.end - runway.threshold).normalize_or_zero();
+    let along_nm = pixels_to_nm(direction.dot(position - runway.threshold));
+    let (target, remaining_nm) = if along_nm < -VISUAL_FINAL_DISTANCE_NM {
+        let short_final = runway.threshold - direction * nm_to_pixels(VISUAL_FINAL_DISTANCE_NM);
+        (
+            short_final,
+            pixels_to_nm(position.distance(short_final)) + VISUAL_FINAL_DISTANCE_NM,
+        )
+    } else {
+        (
+            runway.threshold,
+            pixels_to_nm(position.distance(runway.threshold)),
+        )
+    };
+    let heading = Heading::from_bevy_rotation((target - position).to_angle() as f64);
+    (
+        heading,
+        runway.elevation_feet + remaining_nm * VISUAL_DESCENT_FEET_PER_NM,
+    )
+}
+
+/// Heading, that intercepts the localizer of the runway at `INTERCEPT_ANGLE_DEGREES` and turns
+/// into the runway heading on the localizer
+pub fn intercept_heading(position: Vec2, runway: &Runway) -> Heading {
+    let direction = (runway.end - runway.threshold).normalize_or_zero();
+    // Positive, if the aircraft is left of the localizer when looking towards the threshold
+    let offset_nm = pixels_to_nm(direction.perp_dot(position - runway.threshold));
+    let intercept_angle =
+        INTERCEPT_ANGLE_DEGREES * (offset_nm / LOCALIZER_CAPTURE_DISTANCE_NM).clamp(-1., 1.);
+    runway.heading() + intercept_angle
+}
+
+#[cfg(test)]
+mod tests {
+    use aviation_helper_rs::types::heading::Heading;
+    use bevy::input::mouse::{AccumulatedMouseScroll, MouseScrollUnit};
+    use bevy::prelude::*;
+
+    use super::{IlsIntercept, intercept_heading, update_ils_intercepts, visual_approach_guidance};
+    use crate::game::aircraft::{Aircraft, test_aircraft};
+    use crate::game::aircraft_card::{AircraftCardDisplay, handle_card_scroll};
+    use crate::game::clearance::{ClearanceLog, fire_queued_clearances};
+    use crate::game::control::{ControlMode, ControlState};
+    use crate::game::landing::is_touching_down;
+    use crate::game::level::Runway;
+    use crate::util::units::nm_to_pixels;
+
+    #[test]
+    fn test_intercept_heading() {
+        let runway = Runway {
+            name: "09".to_owned(),
+            threshold: Vec2::ZERO,
+            end: Vec2::new(100., 0.),
+            elevation_feet: 0.,
+        };
+        let at = |along_nm: f64, left_nm: f64| {
+            intercept_heading(
+                Vec2::new(nm_to_pixels(along_nm), nm_to_pixels(left_nm)),
+                &runway,
+            )
+        };
+
+        // Far from the localizer at the full intercept angle
+        assert_eq!(at(-10., 5.), 120.);
+        assert_eq!(at(-10., -5.), 60.);
+        // Half the intercept angle halfway into the capture distance
+        assert_eq!(at(-10., 1.), 105.);
+        assert_eq!(at(-5., -1.), 75.);
+        // Established on the localizer
+        assert_eq!(at(-8., 0.), 90.);
+    }
+
+    #[test]
+    fn test_visual_approach_reaches_threshold() {
+        let runway = Runway {
+            name: "09".to_owned(),
+            threshold: Vec2::ZERO,
+            end: Vec2::new(100., 0.),
+            elevation_feet: 500.,
+        };
+        // Abeam the final, heading away from the runway, far outside the localizer
+        let mut position = Vec2::new(nm_to_pixels(-6.), nm_to_pixels(4.));
+        let mut aircraft = Aircraft {
+            wanted_altitude_feet: 3500.,
+            wanted_speed_knots: 160.,
+            altitude_feet: 3500.,
+            heading: Heading::from(360.),
+            speed_knots: 160.,
+            ..test_aircraft()
+        };
+
+        // Follow the guidance in small steps, without turn or descent limits
+        let step = nm_to_pixels(0.05);
+        for _ in 0..400 {
+            if is_touching_down(&aircraft, position, &runway) {
+                return;
+            }
+            let (heading, altitude_feet) = visual_approach_guidance(position, &runway);
+            aircraft.heading = heading;
+            aircraft.altitude_feet = aircraft.altitude_feet.min(altitude_feet);
+            position += Vec2::from_angle(heading.to_bevy_rotation() as f32) * step;
+        }
+        panic!("Did not reach the threshold, ended at {position} with {aircraft:?}");
+    }
+
+    #[test]
+    fn test_scrolled_heading_ends_the_intercept() {
+        let mut app = App::new();
+        app.insert_resource(Time::<()>::default())
+            .init_resource::<ClearanceLog>()
+            .init_resource::<ButtonInput<KeyCode>>()
+            .insert_resource(AccumulatedMouseScroll {
+                unit: MouseScrollUnit::Line,
+                delta: Vec2::Y,
+            })
+            .add_systems(
+                Update,
+                (
+                    handle_card_scroll,
+                    fire_queued_clearances,
+                    update_ils_intercepts,
+                )
+                    .chain(),
+            );
+        app.world_mut().spawn(Runway {
+            name: "09".to_owned(),
+            threshold: Vec2::ZERO,
+            end: Vec2::new(100., 0.),
+            elevation_feet: 0.,
+        });
+        let entity = app
+            .world_mut()
+            .spawn((
+                test_aircraft(),
+                Transform::from_xyz(nm_to_pixels(-10.), nm_to_pixels(5.), 0.),
+                IlsIntercept {
+                    runway: "09".to_owned(),
+                },
+            ))
+            .id();
+        app.insert_resource(ControlState {
+            mode: ControlMode::ClearanceSelection {
+                aircraft_entity: entity,
+                display_entity: Entity::PLACEHOLDER,
+                display: AircraftCardDisplay::ClearedHeading,
+            },
+        });
+        let cleared_heading =
+            |app: &App| app.world().get::<Aircraft>(entity).unwrap().cleared_heading;
+
+        // One step up from the present heading of 090
+        app.update();
+        assert_eq!(cleared_heading(&app), Some(Heading::from(95.)));
+        assert!(app.world().get::<IlsIntercept>(entity).is_none());
+
+        // The intercept heading doesn't override the scrolled heading anymore
+        app.world_mut().resource_mut::<ControlState>().mode = ControlMode::Normal;
+        app.update();
+        assert_eq!(cleared_heading(&app), Some(Heading::from(95.)));
+    }
+}
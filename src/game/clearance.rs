@@ -0,0 +1,1498 @@
+use std::time::Duration;
+
+use aviation_helper_rs::clearance::aviation_command::{AviationCommandPart, HeadingDirection};
+use aviation_helper_rs::types::altitude::AltitudeConstraint;
+use aviation_helper_rs::types::heading::{Heading, TurnDirection};
+use bevy::input::common_conditions::input_just_pressed;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::game::GameState;
+use crate::game::aircraft::{Aircraft, AircraftType, AircraftTypeStore, update_aircrafts};
+use crate::game::approach::{IlsIntercept, VisualApproach};
+use crate::game::control::{ControlMode, ControlState, control_mode_is_clearance_selection};
+use crate::game::level::Waypoint;
+use crate::util::units::{nm_to_pixels, pixels_to_nm};
+
+/// Distance to a waypoint, at which an aircraft counts as being at the waypoint
+const WAYPOINT_TRIGGER_RADIUS_NM: f64 = 0.5;
+/// Difference to an altitude, at which an aircraft counts as being at the altitude
+const ALTITUDE_TRIGGER_TOLERANCE_FEET: f64 = 100.;
+
+pub struct ClearancePlugin;
+
+impl Plugin for ClearancePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ClearanceLog>()
+            .add_systems(
+                FixedUpdate,
+                fire_queued_clearances
+                    .before(update_aircrafts)
+                    .run_if(in_state(GameState::Running)),
+            )
+            .add_systems(
+                Update,
+                undo_last_clearance.run_if(
+                    in_state(GameState::Running)
+                        .and(control_mode_is_clearance_selection)
+                        .and(input_just_pressed(KeyCode::KeyZ)),
+                ),
+            )
+            .add_systems(
+                PostUpdate,
+                record_applied_clearances.run_if(resource_changed::<ClearanceLog>),
+            );
+    }
+}
+
+/// The cleared values of an aircraft at one point in time
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClearedValues {
+    pub altitude_feet: Option<f64>,
+    pub altitude_discretion: bool,
+    pub altitude_block_feet: Option<(f64, f64)>,
+    pub heading: Option<Heading>,
+    pub heading_change_direction: Option<TurnDirection>,
+    pub speed_knots: Option<f64>,
+}
+
+impl ClearedValues {
+    pub fn of(aircraft: &Aircraft) -> Self {
+        Self {
+            altitude_feet: aircraft.cleared_altitude_feet,
+            altitude_discretion: aircraft.cleared_altitude_discretion,
+            altitude_block_feet: aircraft.cleared_altitude_block_feet,
+            heading: aircraft.cleared_heading,
+            heading_change_direction: aircraft.cleared_heading_change_direction,
+            speed_knots: aircraft.cleared_speed_knots,
+        }
+    }
+
+    /// Whether the cleared values, that differ from the previous ones, are finite. NaN or infinity
+    /// would propagate into the transform of the aircraft. Unchanged values aren't checked.
+    pub fn changes_are_finite(&self, previous: &Self) -> bool {
+        let values = |values: &Self| {
+            let (lower, upper) = values.altitude_block_feet.unwrap_or_default();
+            [
+                values.altitude_feet.unwrap_or_default(),
+                lower,
+                upper,
+                values
+                    .heading
+                    .map(|heading| heading.get())
+                    .unwrap_or_default(),
+                values.speed_knots.unwrap_or_default(),
+            ]
+        };
+        values(self)
+            .into_iter()
+            .zip(values(previous))
+            .all(|(value, previous)| value.to_bits() == previous.to_bits() || value.is_finite())
+    }
+
+    pub fn restore(&self, aircraft: &mut Aircraft) {
+        aircraft.cleared_altitude_feet = self.altitude_feet;
+        aircraft.cleared_altitude_discretion = self.altitude_discretion;
+        aircraft.cleared_altitude_block_feet = self.altitude_block_feet;
+        aircraft.cleared_heading = self.heading;
+        aircraft.cleared_heading_change_direction = self.heading_change_direction;
+        aircraft.cleared_speed_knots = self.speed_knots;
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ClearanceLogEntry {
+    /// Increases with every entry and is never reused, even after entries are removed
+    pub sequence: u64,
+    /// Elapsed game time, when the clearance was applied
+    pub applied_at: Duration,
+    pub aircraft: Entity,
+    pub command: AviationCommandPart,
+    /// Cleared values of the aircraft before the clearance was applied
+    pub previous: ClearedValues,
+}
+
+/// All clearances, that changed the cleared values of an aircraft, oldest first
+#[derive(Resource, Debug, Clone, Default)]
+pub struct ClearanceLog {
+    pub entries: Vec<ClearanceLogEntry>,
+    /// Aircraft and sequence of the entries, that were removed again, oldest first
+    pub removed: Vec<(Entity, u64)>,
+    next_sequence: u64,
+}
+
+impl ClearanceLog {
+    pub fn push(
+        &mut self,
+        aircraft: Entity,
+        command: &AviationCommandPart,
+        previous: ClearedValues,
+        applied_at: Duration,
+    ) {
+        self.entries.push(ClearanceLogEntry {
+            sequence: self.next_sequence,
+            applied_at,
+            aircraft,
+            command: command.clone(),
+            previous,
+        });
+        self.next_sequence += 1;
+    }
+
+    /// Removes the most recent clearance of the aircraft and restores the cleared values from
+    /// before it. Returns the removed entry.
+    pub fn revert_last(
+        &mut self,
+        entity: Entity,
+        aircraft: &mut Aircraft,
+    ) -> Option<ClearanceLogEntry> {
+        let index = self
+            .entries
+            .iter()
+            .rposition(|entry| entry.aircraft == entity)?;
+        let entry = self.entries.remove(index);
+        entry.previous.restore(aircraft);
+        self.removed.push((entity, entry.sequence));
+        Some(entry)
+    }
+
+    /// Merges the most recent clearance of the aircraft into the one before, if both are of the
+    /// same kind, so that they are undone at once
+    pub fn coalesce_last(&mut self, entity: Entity) {
+        let mut indices = self
+            .entries
+            .iter()
+            .enumerate()
+            .rev()
+            .filter(|(_, entry)| entry.aircraft == entity)
+            .map(|(index, _)| index);
+        let (Some(last), Some(before)) = (indices.next(), indices.next()) else {
+            return;
+        };
+        if std::mem::discriminant(&self.entries[last].command)
+            != std::mem::discriminant(&self.entries[before].command)
+        {
+            return;
+        }
+        let before = self.entries.remove(before);
+        self.entries[last - 1].previous = before.previous;
+        self.removed.push((entity, before.sequence));
+    }
+
+    /// Forgets all entries, e.g. when the aircraft are replaced by a snapshot. New entries
+    /// continue after `last_sequence`, so that they don't reuse the sequence of a restored
+    /// `AppliedClearances`. `removed` is kept, because its followers only read what was added.
+    pub fn clear(&mut self, last_sequence: Option<u64>) {
+        self.entries.clear();
+        if let Some(last_sequence) = last_sequence {
+            self.next_sequence = self.next_sequence.max(last_sequence + 1);
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TimestampedClearance {
+    /// Sequence of the entry in the `ClearanceLog`
+    pub sequence: u64,
+    /// Elapsed game time, when the clearance was applied
+    pub applied_at: Duration,
+    pub command: AviationCommandPart,
+}
+
+/// The clearances of the `ClearanceLog`, that were applied to the aircraft, oldest first
+#[derive(Component, Debug, Clone, Default, PartialEq)]
+pub struct AppliedClearances(pub Vec<TimestampedClearance>);
+
+/// The most recent clearance of each kind, that is in effect
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EffectiveClearances {
+    /// None, after the aircraft resumed own navigation
+    pub heading: Option<AviationCommandPart>,
+    pub altitude: Option<AviationCommandPart>,
+    /// None, after the speed restriction was cancelled
+    pub speed: Option<AviationCommandPart>,
+}
+
+impl AppliedClearances {
+    pub fn effective(&self) -> EffectiveClearances {
+        let mut effective = EffectiveClearances::default();
+        for TimestampedClearance { command, .. } in &self.0 {
+            match command {
+                AviationCommandPart::FlyHeading { .. } | AviationCommandPart::TurnBy { .. } => {
+                    effective.heading = Some(command.clone());
+                }
+                AviationCommandPart::ChangeAltitude { .. }
+                | AviationCommandPart::MaintainBlock { .. }
+                | AviationCommandPart::CrossFix { .. } => {
+                    effective.altitude = Some(command.clone());
+                }
+                AviationCommandPart::ChangeSpeed { .. } => {
+                    effective.speed = Some(command.clone());
+                }
+                AviationCommandPart::ResumeNormalSpeed => effective.speed = None,
+                AviationCommandPart::ResumeOwnNavigation => effective.heading = None,
+                _ => {}
+            }
+        }
+        effective
+    }
+}
+
+/// Aircraft start turns onto a direct-to early, so that they roll out on the track to the
+/// waypoint instead of overshooting it
+#[derive(Debug, Clone, PartialEq)]
+pub struct TurnAnticipation {
+    pub turn_rate_degrees_per_second: f64,
+}
+
+impl TurnAnticipation {
+    /// Turns at the maximum turn rate of the aircraft type, like `simulate_aircraft`. None, if
+    /// the aircraft type can't turn.
+    pub fn of(aircraft_type: &AircraftType) -> Option<Self> {
+        let turn_rate_degrees_per_second = aircraft_type.max_delta_heading_degrees_per_second;
+        (turn_rate_degrees_per_second > 0.).then_some(Self {
+            turn_rate_degrees_per_second,
+        })
+    }
+
+    /// Radius of a turn at the turn rate, r = v / ω
+    pub fn turn_radius_nm(&self, speed_knots: f64) -> f64 {
+        speed_knots / 3600. / self.turn_rate_degrees_per_second.to_radians()
+    }
+
+    /// Distance before a fix, at which the turn onto the next track starts. Turns of more than
+    /// 90° start at most one turn radius before the fix.
+    pub fn lead_distance_nm(&self, speed_knots: f64, track_change_degrees: f64) -> f64 {
+        let track_change = track_change_degrees.abs().min(90.).to_radians();
+        self.turn_radius_nm(speed_knots) * (track_change / 2.).tan()
+    }
+
+    /// Heading, that ends the turn on a straight track to the target, instead of the heading to
+    /// the target from the present position
+    pub fn heading_to(
+        &self,
+        position: Vec2,
+        heading: Heading,
+        speed_knots: f64,
+        target: Vec2,
+    ) -> Heading {
+        let direct = heading_to(position, target);
+        let change = heading.required_change(direct);
+        if change == 0. {
+            return direct;
+        }
+        let radius = nm_to_pixels(self.turn_radius_nm(speed_knots));
+        let forward = Vec2::from_angle(heading.to_bevy_rotation() as f32);
+        // Right turns are clockwise, around a center right of the aircraft
+        let is_right_turn = change > 0.;
+        let normal = if is_right_turn {
+            Vec2::new(forward.y, -forward.x)
+        } else {
+            Vec2::new(-forward.y, forward.x)
+        };
+        let to_target = target - (position + normal * radius);
+        let distance = to_target.length();
+        // The target is inside of the turn
+        if distance <= radius {
+            return direct;
+        }
+        let tangent_offset = (radius / distance).asin();
+        let track = if is_right_turn {
+            to_target.to_angle() - tangent_offset
+        } else {
+            to_target.to_angle() + tangent_offset
+        };
+        Heading::from_bevy_rotation(track as f64)
+    }
+}
+
+/// Condition, under which a queued clearance is applied
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ClearanceTrigger {
+    /// The aircraft is at the waypoint with the given name
+    AtWaypoint(String),
+    /// The aircraft is at the waypoint or has passed it abeam, so that it is behind. Only counts
+    /// as passed, after the aircraft flew inbound, with the waypoint ahead and without turning.
+    AbeamWaypoint {
+        fix: String,
+        #[serde(default)]
+        inbound: bool,
+    },
+    /// The aircraft is at the altitude in feet
+    AtAltitude(f64),
+    /// The game time elapsed
+    AtTime(Duration),
+    /// "When able", the aircraft isn't turning anymore
+    WhenAble,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QueuedClearance {
+    /// Without a trigger, the clearance is applied in the next fixed update
+    pub trigger: Option<ClearanceTrigger>,
+    pub command: AviationCommandPart,
+    /// Step of a scroll gesture, that is merged with the step before in the log
+    #[serde(default)]
+    pub coalesce: bool,
+}
+
+impl QueuedClearance {
+    /// Clearance, that is applied in the next fixed update
+    pub fn untriggered(command: &AviationCommandPart) -> Self {
+        Self {
+            trigger: None,
+            command: command.clone(),
+            coalesce: false,
+        }
+    }
+
+    /// Clearance, that is applied in the next fixed update and replaces the previous clearance
+    /// of the same kind in the log
+    pub fn coalesced(command: &AviationCommandPart) -> Self {
+        Self {
+            trigger: None,
+            command: command.clone(),
+            coalesce: true,
+        }
+    }
+
+    /// Clearance, that the pilot applies, once the aircraft isn't turning anymore
+    pub fn when_able(command: &AviationCommandPart) -> Self {
+        Self {
+            trigger: Some(ClearanceTrigger::WhenAble),
+            command: command.clone(),
+            coalesce: false,
+        }
+    }
+
+    /// Clearance, that is applied, when the aircraft reaches the fix of a crossing restriction
+    /// or of a direct-to after the fix
+    pub fn from_command(command: &AviationCommandPart) -> Option<Self> {
+        match command {
+            AviationCommandPart::CrossFix { fix, .. } => Some(Self {
+                trigger: Some(ClearanceTrigger::AtWaypoint(fix.clone())),
+                command: command.clone(),
+                coalesce: false,
+            }),
+            AviationCommandPart::DirectAfterFix { fix, waypoint } => Some(Self {
+                trigger: Some(ClearanceTrigger::AbeamWaypoint {
+                    fix: fix.clone(),
+                    inbound: false,
+                }),
+                command: AviationCommandPart::ProceedDirect(waypoint.clone()),
+                coalesce: false,
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Clearances of an aircraft, that are applied later, e.g. "at ALPHA, descend FL100"
+#[derive(Component, Debug, Clone, Default)]
+pub struct CommandQueue(pub Vec<QueuedClearance>);
+
+/// Adds the clearance to the queue of the aircraft. Clearances given by the player are queued
+/// as well, so that they are applied in a fixed update, independently of the frame rate.
+pub fn queue_clearance(commands: &mut Commands, entity: Entity, queued: QueuedClearance) {
+    commands
+        .entity(entity)
+        .entry::<CommandQueue>()
+        .or_default()
+        .and_modify(move |mut queue| queue.0.push(queued));
+}
+
+/// Applies a command to the cleared values of the aircraft and records it in the log.
+/// `Disregard` reverts the most recent clearance of the aircraft. A heading ends the vectors for
+/// the ILS or the visual approach, and so does resuming own navigation. Returns false, if the command has no effect on the cleared
+/// values, or would clear a value, that isn't finite.
+pub fn apply_clearance(
+    commands: &mut Commands,
+    entity: Entity,
+    aircraft: &mut Aircraft,
+    command: &AviationCommandPart,
+    log: &mut ClearanceLog,
+    applied_at: Duration,
+) -> bool {
+    let previous = ClearedValues::of(aircraft);
+    match command {
+        AviationCommandPart::Disregard => {
+            return log
+                .revert_last(entity, aircraft)
+                .inspect(|entry| debug!("{} disregarded {:?}", aircraft.call_sign, entry.command))
+                .is_some();
+        }
+        AviationCommandPart::FlyHeading {
+            heading: HeadingDirection::Heading(heading),
+            turn_direction,
+        } => {
+            aircraft.cleared_heading = Some(*heading);
+            aircraft.cleared_heading_change_direction = *turn_direction;
+        }
+        AviationCommandPart::FlyHeading {
+            heading: HeadingDirection::PresentHeading,
+            ..
+        } => {
+            aircraft.cleared_heading = Some(aircraft.heading);
+            aircraft.cleared_heading_change_direction = None;
+        }
+        AviationCommandPart::TurnBy {
+            degrees,
+            turn_direction,
+        } => {
+            let heading = match turn_direction {
+                Some(TurnDirection::Left) => aircraft.heading - **degrees,
+                _ => aircraft.heading + **degrees,
+            };
+            aircraft.cleared_heading = Some(heading);
+            aircraft.cleared_heading_change_direction = *turn_direction;
+        }
+        AviationCommandPart::ChangeAltitude {
+            altitude,
+            discretion,
+            ..
+        } => {
+            aircraft.cleared_altitude_feet = Some(altitude.as_feet());
+            aircraft.cleared_altitude_discretion = *discretion;
+            aircraft.cleared_altitude_block_feet = None;
+        }
+        AviationCommandPart::MaintainBlock { lower, upper } => {
+            aircraft.cleared_altitude_feet = None;
+            aircraft.cleared_altitude_discretion = false;
+            aircraft.cleared_altitude_block_feet = Some((lower.as_feet(), upper.as_feet()));
+        }
+        AviationCommandPart::CrossFix {
+            constraint,
+            altitude,
+            ..
+        } => {
+            let altitude_feet = altitude.as_feet();
+            let cleared_feet = aircraft
+                .cleared_altitude_feet
+                .unwrap_or(aircraft.altitude_feet);
+            let is_met = match constraint {
+                AltitudeConstraint::At => cleared_feet == altitude_feet,
+                AltitudeConstraint::AtOrAbove => cleared_feet >= altitude_feet,
+                AltitudeConstraint::AtOrBelow => cleared_feet <= altitude_feet,
+            };
+            if is_met {
+                return false;
+            }
+            aircraft.cleared_altitude_feet = Some(altitude_feet);
+            aircraft.cleared_altitude_discretion = false;
+            aircraft.cleared_altitude_block_feet = None;
+        }
+        AviationCommandPart::ChangeSpeed { knots } => {
+            aircraft.cleared_speed_knots = Some(f64::from(*knots));
+        }
+        AviationCommandPart::ResumeNormalSpeed => {
+            if aircraft.cleared_speed_knots.is_none() {
+                return false;
+            }
+            aircraft.cleared_speed_knots = None;
+        }
+        AviationCommandPart::ResumeOwnNavigation => {
+            if aircraft.cleared_heading.is_none() {
+                return false;
+            }
+            aircraft.cleared_heading = None;
+            aircraft.cleared_heading_change_direction = None;
+        }
+        _ => return false,
+    }
+    if !ClearedValues::of(aircraft).changes_are_finite(&previous) {
+        warn!(
+            "{} rejected {command:?} with a non-finite value",
+            aircraft.call_sign
+        );
+        previous.restore(aircraft);
+        return false;
+    }
+    if matches!(
+        command,
+        AviationCommandPart::FlyHeading { .. }
+            | AviationCommandPart::TurnBy { .. }
+            | AviationCommandPart::ResumeOwnNavigation
+    ) {
+        commands
+            .entity(entity)
+            .remove::<(IlsIntercept, VisualApproach)>();
+    }
+    log.push(entity, command, previous, applied_at);
+    true
+}
+
+/// Ctrl+Z reverts the most recent clearance of the selected aircraft. Like a spoken "disregard",
+/// it is queued, so that it is reverted in a fixed update.
+fn undo_last_clearance(
+    mut commands: Commands,
+    input: Res<ButtonInput<KeyCode>>,
+    control_state: Res<ControlState>,
+    q_aircraft: Query<(), With<Aircraft>>,
+) {
+    if !input.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]) {
+        return;
+    }
+    let ControlMode::ClearanceSelection {
+        aircraft_entity, ..
+    } = control_state.mode
+    else {
+        return;
+    };
+    if q_aircraft.contains(aircraft_entity) {
+        queue_clearance(
+            &mut commands,
+            aircraft_entity,
+            QueuedClearance::untriggered(&AviationCommandPart::Disregard),
+        );
+    }
+}
+
+/// Mirrors the log into the `AppliedClearances` of each aircraft. Only the entries, that were
+/// added or removed since the last run, are looked at.
+fn record_applied_clearances(
+    mut commands: Commands,
+    log: Res<ClearanceLog>,
+    mut next_sequence: Local<u64>,
+    mut removed: Local<usize>,
+) {
+    let added = log
+        .entries
+        .iter()
+        .rev()
+        .take_while(|entry| entry.sequence >= *next_sequence)
+        .count();
+    for entry in &log.entries[log.entries.len() - added..] {
+        let applied = TimestampedClearance {
+            sequence: entry.sequence,
+            applied_at: entry.applied_at,
+            command: entry.command.clone(),
+        };
+        if let Ok(mut aircraft) = commands.get_entity(entry.aircraft) {
+            aircraft
+                .entry::<AppliedClearances>()
+                .or_default()
+                .and_modify(move |mut applied_clearances| applied_clearances.0.push(applied));
+        }
+    }
+    if let Some(last) = log.entries.last() {
+        *next_sequence = (*next_sequence).max(last.sequence + 1);
+    }
+    for &(aircraft, sequence) in &log.removed[*removed..] {
+        if let Ok(mut aircraft) = commands.get_entity(aircraft) {
+            aircraft
+                .entry::<AppliedClearances>()
+                .and_modify(move |mut applied| applied.0.retain(|c| c.sequence != sequence));
+        }
+    }
+    *removed = log.removed.len();
+}
+
+/// Whether the command changes the lateral path of the aircraft
+fn is_lateral(command: &AviationCommandPart) -> bool {
+    matches!(
+        command,
+        AviationCommandPart::FlyHeading { .. }
+            | AviationCommandPart::TurnBy { .. }
+            | AviationCommandPart::ProceedDirect(_)
+    )
+}
+
+/// Heading from the position straight to the waypoint
+fn heading_to(position: Vec2, waypoint_position: Vec2) -> Heading {
+    Heading::from_bevy_rotation((waypoint_position - position).to_angle() as f64)
+}
+
+/// Applies the clearances of the queues, whose trigger is met. A direct-to is flown as the
+/// heading towards the waypoint at that moment, with turn anticipation as the heading, that
+/// rolls out on the track to the waypoint. A heading or direct-to cancels the conditional ones,
+/// that were queued before it.
+pub fn fire_queued_clearances(
+    mut commands: Commands,
+    time: Res<Time>,
+    aircraft_types: Option<Res<AircraftTypeStore>>,
+    aircraft_type_assets: Option<Res<Assets<AircraftType>>>,
+    mut log: ResMut<ClearanceLog>,
+    q_aircraft: Query<(Entity, &mut Aircraft, &Transform, &mut CommandQueue)>,
+    q_waypoints: Query<(&Waypoint, &Transform)>,
+) {
+    let waypoint_position = |name: &str| {
+        q_waypoints
+            .iter()
+            .find(|(waypoint, _)| waypoint.name == name)
+            .map(|(_, transform)| transform.translation.truncate())
+    };
+    for (entity, mut aircraft, transform, mut queue) in q_aircraft {
+        let position = transform.translation.truncate();
+        let anticipation = aircraft_types
+            .as_ref()
+            .zip(aircraft_type_assets.as_ref())
+            .and_then(|(types, assets)| assets.get(types.0.get(&aircraft.aircraft_type_id)?))
+            .and_then(TurnAnticipation::of);
+        let is_at_waypoint = |waypoint_position: Vec2| {
+            pixels_to_nm(position.distance(waypoint_position)) <= WAYPOINT_TRIGGER_RADIUS_NM
+        };
+        let direction = Vec2::from_angle(aircraft.heading.to_bevy_rotation() as f32);
+        for queued in &mut queue.0 {
+            if let Some(ClearanceTrigger::AbeamWaypoint { fix, inbound }) = &mut queued.trigger {
+                *inbound |= aircraft.heading_change_degrees_per_second == 0.
+                    && waypoint_position(fix)
+                        .is_some_and(|fix| (fix - position).dot(direction) > 0.);
+            }
+        }
+        let is_triggered = |trigger: &ClearanceTrigger,
+                            command: &AviationCommandPart,
+                            aircraft: &Aircraft| match trigger {
+            ClearanceTrigger::AtWaypoint(name) => {
+                waypoint_position(name).is_some_and(is_at_waypoint)
+            }
+            ClearanceTrigger::AbeamWaypoint { fix, inbound } => {
+                waypoint_position(fix).is_some_and(|fix| {
+                    // The turn onto the direct-to after the fix starts before the fix
+                    let lead_nm = match (&anticipation, command) {
+                        (Some(anticipation), AviationCommandPart::ProceedDirect(next)) => {
+                            waypoint_position(next).map_or(0., |next| {
+                                let next_track = heading_to(fix, next);
+                                anticipation.lead_distance_nm(
+                                    aircraft.speed_knots,
+                                    aircraft.heading.required_change(next_track),
+                                )
+                            })
+                        }
+                        _ => 0.,
+                    };
+                    is_at_waypoint(fix)
+                        || pixels_to_nm(position.distance(fix)) <= lead_nm
+                        || (*inbound && (fix - position).dot(direction) <= 0.)
+                })
+            }
+            ClearanceTrigger::AtAltitude(altitude_feet) => {
+                (aircraft.altitude_feet - altitude_feet).abs() <= ALTITUDE_TRIGGER_TOLERANCE_FEET
+            }
+            ClearanceTrigger::AtTime(elapsed) => time.elapsed() >= *elapsed,
+            ClearanceTrigger::WhenAble => aircraft.heading_change_degrees_per_second == 0.,
+        };
+        let fires: Vec<bool> = queue
+            .0
+            .iter()
+            .map(
+                |QueuedClearance {
+                     trigger, command, ..
+                 }| {
+                    trigger
+                        .as_ref()
+                        .is_none_or(|trigger| is_triggered(trigger, command, &aircraft))
+                },
+            )
+            .collect();
+        let superseded = queue
+            .0
+            .iter()
+            .zip(&fires)
+            .rposition(|(queued, fires)| *fires && is_lateral(&queued.command))
+            .unwrap_or(0);
+        let mut index = 0;
+        queue.0.retain(|queued| {
+            let QueuedClearance {
+                trigger,
+                command,
+                coalesce,
+            } = queued;
+            index += 1;
+            if !fires[index - 1] {
+                let is_superseded =
+                    index - 1 < superseded && trigger.is_some() && is_lateral(command);
+                if is_superseded {
+                    debug!("{} cancelled {command:?}", aircraft.call_sign);
+                }
+                return !is_superseded;
+            }
+            let command = match command {
+                AviationCommandPart::ProceedDirect(name) => match waypoint_position(name) {
+                    Some(waypoint_position) => AviationCommandPart::FlyHeading {
+                        heading: HeadingDirection::Heading(match &anticipation {
+                            Some(anticipation) => anticipation.heading_to(
+                                position,
+                                aircraft.heading,
+                                aircraft.speed_knots,
+                                waypoint_position,
+                            ),
+                            None => heading_to(position, waypoint_position),
+                        }),
+                        turn_direction: None,
+                    },
+                    None => {
+                        warn!(
+                            "{} can't proceed direct to unknown {name}",
+                            aircraft.call_sign
+                        );
+                        return false;
+                    }
+                },
+                command => command.clone(),
+            };
+            if apply_clearance(
+                &mut commands,
+                entity,
+                &mut aircraft,
+                &command,
+                &mut log,
+                time.elapsed(),
+            ) && *coalesce
+            {
+                log.coalesce_last(entity);
+            }
+            false
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use aviation_helper_rs::clearance::aviation_command::{AviationCommandPart, HeadingDirection};
+    use aviation_helper_rs::types::altitude::Altitude;
+    use aviation_helper_rs::types::altitude::AltitudeConstraint;
+    use aviation_helper_rs::types::heading::Heading;
+    use bevy::prelude::*;
+
+    use super::{
+        AppliedClearances, ClearanceLog, ClearanceTrigger, CommandQueue, QueuedClearance,
+        TimestampedClearance, TurnAnticipation, apply_clearance, fire_queued_clearances,
+        heading_to, record_applied_clearances, undo_last_clearance,
+    };
+    use crate::game::aircraft::{Aircraft, simulate_aircraft, test_aircraft, test_aircraft_type};
+    use crate::game::aircraft_card::AircraftCardDisplay;
+    use crate::game::approach::VisualApproach;
+    use crate::game::control::{ControlMode, ControlState};
+    use crate::game::level::Waypoint;
+    use crate::game::wind::Wind;
+    use crate::util::units::nm_to_pixels;
+
+    fn fly_heading(heading: f64) -> AviationCommandPart {
+        AviationCommandPart::FlyHeading {
+            heading: HeadingDirection::Heading(Heading::from(heading)),
+            turn_direction: None,
+        }
+    }
+
+    #[test]
+    fn test_non_finite_clearances_are_rejected() {
+        let mut world = World::new();
+        let entity = world.spawn_empty().id();
+        let mut commands = world.commands();
+        let mut aircraft = test_aircraft();
+        let mut log = ClearanceLog::default();
+        assert!(apply_clearance(
+            &mut commands,
+            entity,
+            &mut aircraft,
+            &fly_heading(270.),
+            &mut log,
+            Duration::ZERO
+        ));
+
+        let non_finite = [
+            fly_heading(f64::NAN),
+            AviationCommandPart::ChangeAltitude {
+                altitude: Altitude::Feet(f64::INFINITY),
+                maintain: true,
+                discretion: false,
+                turn_direction: None,
+            },
+            AviationCommandPart::MaintainBlock {
+                lower: Altitude::Feet(5000.),
+                upper: Altitude::Feet(f64::NAN),
+            },
+        ];
+        for command in non_finite {
+            assert!(
+                !apply_clearance(
+                    &mut commands,
+                    entity,
+                    &mut aircraft,
+                    &command,
+                    &mut log,
+                    Duration::ZERO
+                ),
+                "{command:?} was applied"
+            );
+        }
+        assert_eq!(aircraft.cleared_heading, Some(Heading::from(270.)));
+        assert_eq!(aircraft.cleared_altitude_feet, None);
+        assert_eq!(aircraft.cleared_altitude_block_feet, None);
+        assert_eq!(log.entries.len(), 1);
+
+        // A broken value, that is already there, doesn't block other clearances
+        aircraft.cleared_speed_knots = Some(f64::NAN);
+        assert!(apply_clearance(
+            &mut commands,
+            entity,
+            &mut aircraft,
+            &fly_heading(180.),
+            &mut log,
+            Duration::ZERO
+        ));
+        assert_eq!(log.entries.len(), 2);
+    }
+
+    #[test]
+    fn test_resume_normal_speed_removes_cleared_speed() {
+        let aircraft_type = test_aircraft_type();
+        let mut world = World::new();
+        let entity = world.spawn_empty().id();
+        let mut commands = world.commands();
+        let mut aircraft = test_aircraft();
+        aircraft.speed_knots = 200.;
+        let mut log = ClearanceLog::default();
+        assert!(apply_clearance(
+            &mut commands,
+            entity,
+            &mut aircraft,
+            &AviationCommandPart::ChangeSpeed { knots: 200 },
+            &mut log,
+            Duration::ZERO
+        ));
+
+        assert!(apply_clearance(
+            &mut commands,
+            entity,
+            &mut aircraft,
+            &AviationCommandPart::ResumeNormalSpeed,
+            &mut log,
+            Duration::ZERO
+        ));
+        assert_eq!(aircraft.cleared_speed_knots, None);
+        // Without a restriction, there is nothing to resume
+        assert!(!apply_clearance(
+            &mut commands,
+            entity,
+            &mut aircraft,
+            &AviationCommandPart::ResumeNormalSpeed,
+            &mut log,
+            Duration::ZERO
+        ));
+
+        let mut transform = Transform::default();
+        for _ in 0..(120 * 64) {
+            simulate_aircraft(
+                &mut aircraft,
+                &mut transform,
+                &aircraft_type,
+                &Wind::default(),
+                1. / 64.,
+            );
+        }
+        assert!(
+            (aircraft.speed_knots - aircraft.wanted_speed_knots).abs() < 1.,
+            "Expected {} knots, got {}",
+            aircraft.wanted_speed_knots,
+            aircraft.speed_knots
+        );
+    }
+
+    #[test]
+    fn test_disregard_reverts_heading() {
+        let mut world = World::new();
+        let entity = world.spawn_empty().id();
+        let other = world.spawn_empty().id();
+        let mut commands = world.commands();
+        let mut aircraft = test_aircraft();
+        let mut other_aircraft = test_aircraft();
+        let mut log = ClearanceLog::default();
+
+        assert!(apply_clearance(
+            &mut commands,
+            entity,
+            &mut aircraft,
+            &fly_heading(180.),
+            &mut log,
+            Duration::ZERO
+        ));
+        assert!(apply_clearance(
+            &mut commands,
+            entity,
+            &mut aircraft,
+            &fly_heading(270.),
+            &mut log,
+            Duration::ZERO
+        ));
+        // Clearances of other aircraft are not affected
+        assert!(apply_clearance(
+            &mut commands,
+            other,
+            &mut other_aircraft,
+            &AviationCommandPart::ChangeAltitude {
+                altitude: Altitude::FlightLevel(100),
+                maintain: false,
+                discretion: false,
+                turn_direction: None,
+            },
+            &mut log,
+            Duration::ZERO
+        ));
+        // Standby doesn't change anything and is not logged
+        assert!(!apply_clearance(
+            &mut commands,
+            entity,
+            &mut aircraft,
+            &AviationCommandPart::Standby,
+            &mut log,
+            Duration::ZERO
+        ));
+        assert_eq!(aircraft.cleared_heading, Some(Heading::from(270.)));
+
+        assert!(apply_clearance(
+            &mut commands,
+            entity,
+            &mut aircraft,
+            &AviationCommandPart::Disregard,
+            &mut log,
+            Duration::ZERO
+        ));
+        assert_eq!(aircraft.cleared_heading, Some(Heading::from(180.)));
+        assert_eq!(other_aircraft.cleared_altitude_feet, Some(10000.));
+
+        assert!(apply_clearance(
+            &mut commands,
+            entity,
+            &mut aircraft,
+            &AviationCommandPart::Disregard,
+            &mut log,
+            Duration::ZERO
+        ));
+        assert_eq!(aircraft.cleared_heading, None);
+        // Nothing left to disregard
+        assert!(!apply_clearance(
+            &mut commands,
+            entity,
+            &mut aircraft,
+            &AviationCommandPart::Disregard,
+            &mut log,
+            Duration::ZERO
+        ));
+        assert_eq!(log.entries.len(), 1);
+    }
+
+    #[test]
+    fn test_undo_restores_previous_heading() {
+        let mut app = App::new();
+        app.insert_resource(Time::<()>::default())
+            .init_resource::<ClearanceLog>()
+            .init_resource::<ButtonInput<KeyCode>>()
+            .add_systems(
+                Update,
+                (undo_last_clearance, fire_queued_clearances).chain(),
+            );
+        let entity = app.world_mut().spawn(test_aircraft()).id();
+        app.insert_resource(ControlState {
+            mode: ControlMode::ClearanceSelection {
+                aircraft_entity: entity,
+                display_entity: Entity::PLACEHOLDER,
+                display: AircraftCardDisplay::ClearedHeading,
+            },
+        });
+        app.world_mut()
+            .resource_scope(|world, mut log: Mut<ClearanceLog>| {
+                let mut aircraft = world.get::<Aircraft>(entity).unwrap().clone();
+                let mut commands = world.commands();
+                apply_clearance(
+                    &mut commands,
+                    entity,
+                    &mut aircraft,
+                    &fly_heading(180.),
+                    &mut log,
+                    Duration::ZERO,
+                );
+                apply_clearance(
+                    &mut commands,
+                    entity,
+                    &mut aircraft,
+                    &fly_heading(270.),
+                    &mut log,
+                    Duration::ZERO,
+                );
+                commands.entity(entity).insert(aircraft);
+                world.flush();
+            });
+        let cleared_heading =
+            |app: &App| app.world().get::<Aircraft>(entity).unwrap().cleared_heading;
+
+        // Z alone doesn't undo
+        app.world_mut()
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .press(KeyCode::KeyZ);
+        app.update();
+        assert_eq!(cleared_heading(&app), Some(Heading::from(270.)));
+
+        app.world_mut()
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .press(KeyCode::ControlLeft);
+        app.update();
+        assert_eq!(cleared_heading(&app), Some(Heading::from(180.)));
+        assert_eq!(app.world().resource::<ClearanceLog>().entries.len(), 1);
+    }
+
+    #[test]
+    fn test_altitude_trigger_fires_once() {
+        let mut app = App::new();
+        app.insert_resource(Time::<()>::default())
+            .init_resource::<ClearanceLog>()
+            .add_systems(Update, fire_queued_clearances);
+        let entity = app
+            .world_mut()
+            .spawn((
+                test_aircraft(),
+                Transform::default(),
+                CommandQueue(vec![QueuedClearance {
+                    trigger: Some(ClearanceTrigger::AtAltitude(6000.)),
+                    command: fly_heading(180.),
+                    coalesce: false,
+                }]),
+            ))
+            .id();
+        let set_altitude = |app: &mut App, altitude_feet| {
+            app.world_mut()
+                .get_mut::<Aircraft>(entity)
+                .unwrap()
+                .altitude_feet = altitude_feet;
+        };
+        let cleared_heading =
+            |app: &App| app.world().get::<Aircraft>(entity).unwrap().cleared_heading;
+
+        app.update();
+        assert_eq!(cleared_heading(&app), None);
+
+        set_altitude(&mut app, 5950.);
+        app.update();
+        assert_eq!(cleared_heading(&app), Some(Heading::from(180.)));
+        assert!(
+            app.world()
+                .get::<CommandQueue>(entity)
+                .unwrap()
+                .0
+                .is_empty()
+        );
+
+        // The clearance is not applied again
+        app.world_mut()
+            .get_mut::<Aircraft>(entity)
+            .unwrap()
+            .cleared_heading = None;
+        set_altitude(&mut app, 6000.);
+        app.update();
+        assert_eq!(cleared_heading(&app), None);
+        assert_eq!(app.world().resource::<ClearanceLog>().entries.len(), 1);
+    }
+
+    #[test]
+    fn test_crossing_restriction_applies_at_fix() {
+        let mut app = App::new();
+        app.insert_resource(Time::<()>::default())
+            .init_resource::<ClearanceLog>()
+            .add_systems(Update, fire_queued_clearances);
+        app.world_mut().spawn((
+            Waypoint {
+                name: "BRAVO".to_owned(),
+            },
+            Transform::from_xyz(500., 0., 0.),
+        ));
+        let crossings = [
+            (AltitudeConstraint::AtOrAbove, Altitude::Feet(6000.)),
+            // Already met by the current altitude
+            (AltitudeConstraint::AtOrBelow, Altitude::Feet(7000.)),
+        ];
+        let queue = crossings
+            .into_iter()
+            .map(|(constraint, altitude)| {
+                QueuedClearance::from_command(&AviationCommandPart::CrossFix {
+                    fix: "BRAVO".to_owned(),
+                    constraint,
+                    altitude,
+                })
+                .unwrap()
+            })
+            .collect();
+        let entity = app
+            .world_mut()
+            .spawn((test_aircraft(), Transform::default(), CommandQueue(queue)))
+            .id();
+        let cleared_altitude = |app: &App| {
+            app.world()
+                .get::<Aircraft>(entity)
+                .unwrap()
+                .cleared_altitude_feet
+        };
+
+        app.update();
+        assert_eq!(cleared_altitude(&app), None);
+
+        app.world_mut()
+            .get_mut::<Transform>(entity)
+            .unwrap()
+            .translation
+            .x = 490.;
+        app.update();
+        assert_eq!(cleared_altitude(&app), Some(6000.));
+        assert!(
+            app.world()
+                .get::<CommandQueue>(entity)
+                .unwrap()
+                .0
+                .is_empty()
+        );
+        assert_eq!(app.world().resource::<ClearanceLog>().entries.len(), 1);
+    }
+
+    #[test]
+    fn test_direct_after_fix_fires_abeam_the_fix() {
+        let mut app = App::new();
+        app.insert_resource(Time::<()>::default())
+            .init_resource::<ClearanceLog>()
+            .add_systems(Update, fire_queued_clearances);
+        // Beside the track of the aircraft, which never comes close to it
+        app.world_mut().spawn((
+            Waypoint {
+                name: "ALPHA".to_owned(),
+            },
+            Transform::from_xyz(500., 300., 0.),
+        ));
+        let bravo = Vec2::new(0., 1000.);
+        app.world_mut().spawn((
+            Waypoint {
+                name: "BRAVO".to_owned(),
+            },
+            Transform::from_translation(bravo.extend(0.)),
+        ));
+        let queue = vec![
+            QueuedClearance::untriggered(&fly_heading(90.)),
+            QueuedClearance::from_command(&AviationCommandPart::DirectAfterFix {
+                fix: "ALPHA".to_owned(),
+                waypoint: "BRAVO".to_owned(),
+            })
+            .unwrap(),
+        ];
+        let entity = app
+            .world_mut()
+            .spawn((test_aircraft(), Transform::default(), CommandQueue(queue)))
+            .id();
+        let move_to = |app: &mut App, x: f32| {
+            app.world_mut()
+                .get_mut::<Transform>(entity)
+                .unwrap()
+                .translation
+                .x = x;
+            app.update();
+            app.world().get::<Aircraft>(entity).unwrap().cleared_heading
+        };
+
+        assert_eq!(move_to(&mut app, 0.), Some(Heading::from(90.)));
+        assert_eq!(move_to(&mut app, 490.), Some(Heading::from(90.)));
+        // ALPHA is behind the aircraft
+        assert_eq!(
+            move_to(&mut app, 510.),
+            Some(heading_to(Vec2::new(510., 0.), bravo))
+        );
+        assert!(
+            app.world()
+                .get::<CommandQueue>(entity)
+                .unwrap()
+                .0
+                .is_empty()
+        );
+        assert_eq!(app.world().resource::<ClearanceLog>().entries.len(), 2);
+    }
+
+    #[test]
+    fn test_heading_cancels_earlier_direct_after_fix() {
+        let mut app = App::new();
+        app.insert_resource(Time::<()>::default())
+            .init_resource::<ClearanceLog>()
+            .add_systems(Update, fire_queued_clearances);
+        for (name, position) in [
+            ("ALPHA", Vec3::new(500., 0., 0.)),
+            ("BRAVO", Vec3::Y * 1000.),
+        ] {
+            app.world_mut().spawn((
+                Waypoint {
+                    name: name.to_owned(),
+                },
+                Transform::from_translation(position),
+            ));
+        }
+        let queue = vec![
+            QueuedClearance::from_command(&AviationCommandPart::DirectAfterFix {
+                fix: "ALPHA".to_owned(),
+                waypoint: "BRAVO".to_owned(),
+            })
+            .unwrap(),
+            QueuedClearance::untriggered(&fly_heading(180.)),
+        ];
+        let entity = app
+            .world_mut()
+            .spawn((test_aircraft(), Transform::default(), CommandQueue(queue)))
+            .id();
+
+        app.update();
+        assert!(
+            app.world()
+                .get::<CommandQueue>(entity)
+                .unwrap()
+                .0
+                .is_empty()
+        );
+        assert_eq!(
+            app.world().get::<Aircraft>(entity).unwrap().cleared_heading,
+            Some(Heading::from(180.))
+        );
+    }
+
+    #[test]
+    fn test_abeam_needs_the_aircraft_inbound_first() {
+        let mut app = App::new();
+        app.insert_resource(Time::<()>::default())
+            .init_resource::<ClearanceLog>()
+            .add_systems(Update, fire_queued_clearances);
+        app.world_mut().spawn((
+            Waypoint {
+                name: "ALPHA".to_owned(),
+            },
+            // Beside the track of the aircraft
+            Transform::from_xyz(-500., 300., 0.),
+        ));
+        app.world_mut().spawn((
+            Waypoint {
+                name: "BRAVO".to_owned(),
+            },
+            Transform::from_xyz(-500., 1000., 0.),
+        ));
+        let queue = vec![
+            QueuedClearance::from_command(&AviationCommandPart::DirectAfterFix {
+                fix: "ALPHA".to_owned(),
+                waypoint: "BRAVO".to_owned(),
+            })
+            .unwrap(),
+        ];
+        let entity = app
+            .world_mut()
+            .spawn((test_aircraft(), Transform::default(), CommandQueue(queue)))
+            .id();
+        let fly = |app: &mut App, heading: f64, turn_rate: f64, x: f32| {
+            let mut entity_mut = app.world_mut().entity_mut(entity);
+            entity_mut.get_mut::<Transform>().unwrap().translation.x = x;
+            let mut aircraft = entity_mut.get_mut::<Aircraft>().unwrap();
+            aircraft.heading = Heading::from(heading);
+            aircraft.heading_change_degrees_per_second = turn_rate;
+            app.update();
+            app.world().get::<Aircraft>(entity).unwrap().cleared_heading
+        };
+
+        // ALPHA is behind, but the aircraft never flew towards it
+        assert_eq!(fly(&mut app, 90., 0., 0.), None);
+        // Mid-turn, ALPHA is ahead only for a moment
+        assert_eq!(fly(&mut app, 270., -3., 0.), None);
+        assert_eq!(fly(&mut app, 90., 0., 0.), None);
+        // Inbound, then past ALPHA
+        assert_eq!(fly(&mut app, 270., 0., 0.), None);
+        assert_eq!(
+            fly(&mut app, 270., 0., -510.),
+            Some(heading_to(Vec2::new(-510., 0.), Vec2::new(-500., 1000.)))
+        );
+    }
+
+    #[test]
+    fn test_direct_to_ends_the_visual_approach() {
+        let mut app = App::new();
+        app.insert_resource(Time::<()>::default())
+            .init_resource::<ClearanceLog>()
+            .add_systems(Update, fire_queued_clearances);
+        let bravo = Vec2::new(0., 1000.);
+        app.world_mut().spawn((
+            Waypoint {
+                name: "BRAVO".to_owned(),
+            },
+            Transform::from_translation(bravo.extend(0.)),
+        ));
+        let entity = app
+            .world_mut()
+            .spawn((
+                test_aircraft(),
+                Transform::default(),
+                VisualApproach {
+                    runway: "09".to_owned(),
+                },
+                CommandQueue(vec![QueuedClearance::untriggered(
+                    &AviationCommandPart::ProceedDirect("BRAVO".to_owned()),
+                )]),
+            ))
+            .id();
+
+        app.update();
+        assert!(app.world().get::<VisualApproach>(entity).is_none());
+        assert!(
+            app.world()
+                .get::<Aircraft>(entity)
+                .unwrap()
+                .cleared_heading
+                .is_some()
+        );
+    }
+
+    #[test]
+    fn test_when_able_waits_for_the_turn_to_complete() {
+        let mut app = App::new();
+        app.insert_resource(Time::<()>::default())
+            .init_resource::<ClearanceLog>()
+            .add_systems(Update, fire_queued_clearances);
+        let mut aircraft = test_aircraft();
+        aircraft.cleared_heading = Some(Heading::from(180.));
+        aircraft.heading_change_degrees_per_second = 2.;
+        let descend = AviationCommandPart::ChangeAltitude {
+            altitude: Altitude::FlightLevel(40),
+            maintain: false,
+            discretion: false,
+            turn_direction: None,
+        };
+        let entity = app
+            .world_mut()
+            .spawn((
+                aircraft,
+                Transform::default(),
+                CommandQueue(vec![QueuedClearance::when_able(&descend)]),
+            ))
+            .id();
+
+        // Mid-turn
+        app.update();
+        assert_eq!(
+            app.world()
+                .get::<Aircraft>(entity)
+                .unwrap()
+                .cleared_altitude_feet,
+            None
+        );
+        assert_eq!(app.world().get::<CommandQueue>(entity).unwrap().0.len(), 1);
+
+        let mut aircraft = app.world_mut().get_mut::<Aircraft>(entity).unwrap();
+        aircraft.heading = Heading::from(180.);
+        aircraft.heading_change_degrees_per_second = 0.;
+        app.update();
+        assert_eq!(
+            app.world()
+                .get::<Aircraft>(entity)
+                .unwrap()
+                .cleared_altitude_feet,
+            Some(4000.)
+        );
+        assert!(
+            app.world()
+                .get::<CommandQueue>(entity)
+                .unwrap()
+                .0
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_applied_clearances_are_recorded_on_the_aircraft() {
+        let mut app = App::new();
+        app.insert_resource(Time::<()>::default())
+            .init_resource::<ClearanceLog>()
+            .add_systems(
+                Update,
+                (fire_queued_clearances, record_applied_clearances).chain(),
+            );
+        let climb = AviationCommandPart::ChangeAltitude {
+            altitude: Altitude::FlightLevel(100),
+            maintain: false,
+            discretion: false,
+            turn_direction: None,
+        };
+        let queue = vec![
+            QueuedClearance::untriggered(&fly_heading(180.)),
+            QueuedClearance::untriggered(&climb),
+        ];
+        let entity = app
+            .world_mut()
+            .spawn((test_aircraft(), Transform::default(), CommandQueue(queue)))
+            .id();
+        let other = app
+            .world_mut()
+            .spawn((test_aircraft(), Transform::default()))
+            .id();
+        // Stamped with the time of the fixed update, that applied them
+        app.world_mut()
+            .resource_mut::<Time>()
+            .advance_by(Duration::from_secs(2));
+        app.update();
+
+        let applied = app.world().get::<AppliedClearances>(entity).unwrap();
+        assert_eq!(
+            applied.0,
+            vec![
+                TimestampedClearance {
+                    sequence: 0,
+                    applied_at: Duration::from_secs(2),
+                    command: fly_heading(180.),
+                },
+                TimestampedClearance {
+                    sequence: 1,
+                    applied_at: Duration::from_secs(2),
+                    command: climb.clone(),
+                },
+            ]
+        );
+        let effective = applied.effective();
+        assert_eq!(effective.heading, Some(fly_heading(180.)));
+        assert_eq!(effective.altitude, Some(climb));
+        assert_eq!(effective.speed, None);
+        assert!(app.world().get::<AppliedClearances>(other).is_none());
+
+        // A disregarded clearance is removed again
+        app.world_mut()
+            .get_mut::<CommandQueue>(entity)
+            .unwrap()
+            .0
+            .push(QueuedClearance::untriggered(
+                &AviationCommandPart::Disregard,
+            ));
+        app.update();
+        let applied = app.world().get::<AppliedClearances>(entity).unwrap();
+        assert_eq!(applied.0.len(), 1);
+        assert_eq!(applied.effective().altitude, None);
+    }
+
+    #[test]
+    fn test_turn_anticipation_lead_distance() {
+        let anticipation = TurnAnticipation::of(&test_aircraft_type()).unwrap();
+        // 250 knots at 2.1° per second turn with a radius of about 1.9 NM
+        let radius = anticipation.turn_radius_nm(250.);
+        assert!((radius - 1.89).abs() < 0.01, "Radius {radius}");
+        assert_eq!(anticipation.lead_distance_nm(250., 0.), 0.);
+        assert!((anticipation.lead_distance_nm(250., 90.) - radius).abs() < 1e-9);
+        assert!(
+            (anticipation.lead_distance_nm(250., -60.) - radius * 30_f64.to_radians().tan()).abs()
+                < 1e-9
+        );
+        // Larger turns don't start earlier
+        assert_eq!(
+            anticipation.lead_distance_nm(250., 150.),
+            anticipation.lead_distance_nm(250., 90.)
+        );
+        // Slower aircraft turn tighter
+        assert!(anticipation.lead_distance_nm(180., 90.) < radius);
+
+        // Flying north, the target east of the aircraft. The turn ends north-east of the
+        // aircraft, so the track to the target points further south than the direct heading.
+        let target = Vec2::new(1000., 0.);
+        let direct = heading_to(Vec2::ZERO, target);
+        let anticipated = anticipation.heading_to(Vec2::ZERO, Heading::from(0.), 250., target);
+        assert!(direct.required_change(anticipated) > 5.);
+        let rollout = Vec2::splat(nm_to_pixels(radius));
+        let expected = heading_to(rollout, target);
+        assert!(
+            (anticipated - expected).min(360. - (anticipated - expected)) < 1.,
+            "Anticipated {anticipated:?}, expected about {expected:?}"
+        );
+    }
+}
@@ -0,0 +1,130 @@
+use bevy::prelude::*;
+use bevy_common_assets::ron::RonAssetPlugin;
+use serde::Deserialize;
+
+use crate::APP_CONFIG;
+
+/// Name of the theme with the original colors
+pub const DEFAULT_COLOR_THEME: &str = "default";
+/// Name of the built-in theme, that can be told apart with the common color vision deficiencies
+pub const COLORBLIND_COLOR_THEME: &str = "colorblind";
+
+/// Selects the color theme by `APP_CONFIG.color_theme`. A name, that isn't built in, is loaded
+/// from assets/color_themes.
+pub struct ColorThemePlugin;
+
+impl Plugin for ColorThemePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(RonAssetPlugin::<ColorTheme>::new(&["ron"]));
+        match ColorTheme::built_in(&APP_CONFIG.color_theme) {
+            Some(theme) => {
+                app.insert_resource(theme);
+            }
+            None => {
+                app.init_resource::<ColorTheme>()
+                    .add_systems(Startup, load_color_theme)
+                    .add_systems(Update, apply_loaded_color_theme);
+            }
+        }
+    }
+}
+
+/// Colors of the radar by their role. Systems, that created materials from it, recolor them,
+/// when the theme changes.
+#[derive(Resource, Asset, TypePath, Deserialize, Clone, Debug, PartialEq)]
+pub struct ColorTheme {
+    pub aircraft: Color,
+    /// Flashing color of aircraft in an emergency
+    pub emergency: Color,
+    /// Card background
+    pub aircraft_card: Color,
+    /// Card background of aircraft, that have to be handed off soon
+    pub handoff_due_card: Color,
+    /// Card background of aircraft in a separation conflict. New conflicts flash in it.
+    pub conflict_card: Color,
+    /// Background of the fields on the card
+    pub card_display: Color,
+    /// Background of the field, that is selected for a clearance
+    pub selected_card_display: Color,
+    /// Line from the card to its aircraft
+    pub leader_line: Color,
+}
+
+impl Default for ColorTheme {
+    fn default() -> Self {
+        Self {
+            aircraft: Color::srgb(0., 0.4, 0.3),
+            emergency: Color::srgb(1.0, 0.2, 0.2),
+            aircraft_card: Color::srgba(0.1, 0.3, 0.1, 0.5),
+            handoff_due_card: Color::srgba(0.5, 0.3, 0.05, 0.6),
+            conflict_card: Color::srgba(0.6, 0.1, 0.1, 0.7),
+            card_display: Color::srgba(0.1, 0.1, 0.1, 0.7),
+            selected_card_display: Color::srgba(0.5, 0.5, 0.1, 0.7),
+            leader_line: Color::srgba(0.6, 0.8, 0.6, 0.5),
+        }
+    }
+}
+
+impl ColorTheme {
+    /// Okabe-Ito palette. Roles, that have to be told apart, differ in brightness as well.
+    pub fn colorblind() -> Self {
+        Self {
+            aircraft: Color::srgb(0.34, 0.71, 0.91),
+            emergency: Color::srgb(0.84, 0.37, 0.),
+            aircraft_card: Color::srgba(0., 0.45, 0.7, 0.6),
+            handoff_due_card: Color::srgba(0.9, 0.62, 0., 0.7),
+            conflict_card: Color::srgba(0.84, 0.37, 0., 0.8),
+            card_display: Color::srgba(0.05, 0.05, 0.05, 0.8),
+            selected_card_display: Color::srgba(0.8, 0.47, 0.65, 0.8),
+            leader_line: Color::srgba(0.34, 0.71, 0.91, 0.6),
+        }
+    }
+
+    pub fn built_in(name: &str) -> Option<Self> {
+        match name {
+            DEFAULT_COLOR_THEME => Some(Self::default()),
+            COLORBLIND_COLOR_THEME => Some(Self::colorblind()),
+            _ => None,
+        }
+    }
+}
+
+/// Sets the color of a material, that was created from the theme
+pub fn recolor(
+    materials: &mut Assets<ColorMaterial>,
+    material: &Handle<ColorMaterial>,
+    color: Color,
+) {
+    if let Some(material) = materials.get_mut(material) {
+        material.color = color;
+    }
+}
+
+#[derive(Resource)]
+struct ColorThemeHandle(Handle<ColorTheme>);
+
+fn load_color_theme(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let handle = asset_server.load(format!("color_themes/{}.ron", APP_CONFIG.color_theme));
+    commands.insert_resource(ColorThemeHandle(handle));
+}
+
+/// Replaces the default theme, once the theme file is loaded or edited
+fn apply_loaded_color_theme(
+    mut events: EventReader<AssetEvent<ColorTheme>>,
+    color_themes: Res<Assets<ColorTheme>>,
+    handle: Res<ColorThemeHandle>,
+    mut color_theme: ResMut<ColorTheme>,
+) {
+    for event in events.read() {
+        let (AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id }) = event
+        else {
+            continue;
+        };
+        if *id != handle.0.id() {
+            continue;
+        }
+        if let Some(loaded) = color_themes.get(*id) {
+            *color_theme = loaded.clone();
+        }
+    }
+}
@@ -0,0 +1,754 @@
+use std::sync::mpsc;
+
+use atc_recognition_rs::graph_parser::ParserConfig;
+use atc_recognition_rs::{
+    AviationCommandParser, CommandWithConfidence, ParseResult, ParsedCommand, RecognitionHandle,
+    SpeechToTextConfig, VoiceRecognizer,
+};
+use aviation_helper_rs::clearance::airlines::Airlines;
+use aviation_helper_rs::clearance::aviation_command::AviationCommandPart;
+use aviation_helper_rs::conversions::inhg_hundredths_to_hpa;
+use aviation_helper_rs::types::altitude::Altitude;
+use bevy::prelude::*;
+use bevy_ui_text_input::{TextInputMode, TextInputNode, TextInputPrompt, TextSubmitEvent};
+
+use crate::APP_CONFIG;
+use crate::game::aircraft::Aircraft;
+use crate::game::approach::{IlsIntercept, VisualApproach};
+use crate::game::clearance::{QueuedClearance, queue_clearance};
+use crate::game::contact_label::ContactFrequencyCleared;
+use crate::game::emergency::{Emergency, apply_squawk};
+use crate::game::landing::LandingClearance;
+use crate::game::sequencing::{FollowAircraft, find_aircraft_by_call_sign};
+use crate::game::squawk::AssignedSquawk;
+use crate::game::wind::Metar;
+use crate::game::{GameState, GameVariables};
+
+/// Number of lines, that are kept in the console log
+const CONSOLE_LOG_MAX_LINES: usize = 8;
+const CONSOLE_BACKGROUND_COLOR: Color = Color::srgba(0.05, 0.05, 0.05, 0.8);
+const CONSOLE_LOG_COLOR: Color = Color::srgb(0.8, 0.8, 0.8);
+
+pub struct ConsolePlugin;
+
+impl Plugin for ConsolePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<TransmissionSubmitted>()
+            .add_event::<TransmissionParsed>()
+            .add_event::<ConsoleLogLine>()
+            .insert_resource(TransmissionParser(AviationCommandParser::default()))
+            .init_resource::<VoiceControlConfig>()
+            .add_systems(
+                OnEnter(GameState::Running),
+                (spawn_console, start_voice_recognition),
+            )
+            .add_systems(
+                Update,
+                (
+                    forward_console_input,
+                    forward_voice_transmissions,
+                    sync_parser_transition_level.run_if(resource_changed::<GameVariables>),
+                    handle_transmission_submitted,
+                    append_console_log_lines,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Running)),
+            );
+    }
+}
+
+/// Parser for typed transmissions
+#[derive(Resource)]
+pub struct TransmissionParser(pub AviationCommandParser);
+
+/// Confidences, below which transmissions recognized from voice aren't applied, but wait for
+/// the controller to confirm them in the history
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct VoiceControlConfig {
+    pub min_callsign_confidence: f32,
+    /// Floor for each command of the transmission
+    pub min_command_confidence: f32,
+}
+
+impl Default for VoiceControlConfig {
+    fn default() -> Self {
+        Self {
+            // Callsigns of unknown airlines are below
+            min_callsign_confidence: 0.9,
+            min_command_confidence: 0.7,
+        }
+    }
+}
+
+impl VoiceControlConfig {
+    /// Whether a transmission is applied without confirmation. `command_confidence` is the
+    /// lowest confidence of its commands.
+    pub fn accepts(&self, callsign_confidence: f32, command_confidence: f32) -> bool {
+        callsign_confidence >= self.min_callsign_confidence
+            && command_confidence >= self.min_command_confidence
+    }
+}
+
+/// Texts, that the voice recognition recognized in the background
+pub struct VoiceTransmissions {
+    receiver: mpsc::Receiver<String>,
+    /// Keeps the microphone open, None in tests
+    _recognition: Option<RecognitionHandle>,
+}
+
+/// A transmission of the controller as text, e.g. "Lufthansa 123, fly heading 270"
+#[derive(Event, Debug, Clone)]
+pub struct TransmissionSubmitted {
+    pub text: String,
+    /// Recognized from speech instead of typed
+    pub from_voice: bool,
+}
+
+/// A submitted transmission and how the parser understood it
+#[derive(Event, Debug, Clone)]
+pub struct TransmissionParsed {
+    pub text: String,
+    pub result: ParseResult,
+    /// Not applied, because it was recognized from voice with a low confidence
+    pub needs_confirmation: bool,
+}
+
+#[derive(Event, Debug, Clone)]
+pub struct ConsoleLogLine(pub String);
+
+#[derive(Component)]
+pub struct ConsoleInput;
+
+#[derive(Component)]
+pub struct ConsoleLog;
+
+fn spawn_console(mut commands: Commands) {
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            left: Val::Px(10.0),
+            bottom: Val::Px(10.0),
+            width: Val::Px(500.0),
+            flex_direction: FlexDirection::Column,
+            ..default()
+        },
+        BackgroundColor(CONSOLE_BACKGROUND_COLOR),
+        children![
+            (
+                ConsoleLog,
+                Node {
+                    flex_direction: FlexDirection::Column,
+                    ..default()
+                },
+            ),
+            (
+                ConsoleInput,
+                Node {
+                    width: Val::Percent(100.),
+                    height: Val::Px(30.),
+                    ..default()
+                },
+                TextInputNode {
+                    clear_on_submit: true,
+                    mode: TextInputMode::SingleLine,
+                    ..default()
+                },
+                TextInputPrompt {
+                    text: "Type a transmission, e.g. Lufthansa 123, fly heading 270".to_owned(),
+                    ..default()
+                },
+            ),
+        ],
+    ));
+}
+
+fn forward_console_input(
+    mut submit_events: EventReader<TextSubmitEvent>,
+    q_console_input: Query<(), With<ConsoleInput>>,
+    mut writer: EventWriter<TransmissionSubmitted>,
+) {
+    for TextSubmitEvent { entity, text } in submit_events.read() {
+        if q_console_input.contains(*entity) && !text.trim().is_empty() {
+            writer.write(TransmissionSubmitted {
+                text: text.clone(),
+                from_voice: false,
+            });
+        }
+    }
+}
+
+/// Starts recognizing transmissions from the microphone, if VOICE_CONTROL=1. Loading the Whisper
+/// model takes a while, so it happens once, when the first game starts.
+fn start_voice_recognition(world: &mut World) {
+    if !APP_CONFIG.voice_control || world.contains_non_send::<VoiceTransmissions>() {
+        return;
+    }
+    let recognition_corrections = ParserConfig::load_default()
+        .map(|config| config.recognition_corrections)
+        .unwrap_or_else(|err| {
+            warn!("No recognition corrections: {err}");
+            default()
+        });
+    let (sender, receiver) = mpsc::channel();
+    let recognition = Airlines::load_airlines_from_file()
+        .map_err(|err| err.to_string())
+        .and_then(|airlines| {
+            VoiceRecognizer::new(SpeechToTextConfig::default(), airlines)
+                .and_then(|recognizer| {
+                    recognizer
+                        .with_recognition_corrections(recognition_corrections)
+                        .spawn_recognition(move |text| {
+                            let _ = sender.send(text);
+                        })
+                })
+                .map_err(|err| err.to_string())
+        });
+    match recognition {
+        Ok(recognition) => world.insert_non_send_resource(VoiceTransmissions {
+            receiver,
+            _recognition: Some(recognition),
+        }),
+        Err(err) => error!("Voice control unavailable: {err}"),
+    }
+}
+
+/// Submits the texts, that the voice recognition recognized since the last frame
+fn forward_voice_transmissions(
+    voice_transmissions: Option<NonSend<VoiceTransmissions>>,
+    mut writer: EventWriter<TransmissionSubmitted>,
+) {
+    let Some(voice_transmissions) = voice_transmissions else {
+        return;
+    };
+    for text in voice_transmissions.receiver.try_iter() {
+        writer.write(TransmissionSubmitted {
+            text,
+            from_voice: true,
+        });
+    }
+}
+
+/// Altitudes without unit are resolved with the transition level, that follows the QNH
+pub fn sync_parser_transition_level(
+    variables: Res<GameVariables>,
+    mut parser: ResMut<TransmissionParser>,
+) {
+    parser
+        .0
+        .set_transition_altitude(variables.transition_level_feet());
+}
+
+/// Parses submitted transmissions and applies the commands to the addressed aircraft. Clearances
+/// are queued for the next fixed update. Transmissions recognized from voice with a low
+/// confidence are only shown in the history.
+#[allow(clippy::too_many_arguments)]
+pub fn handle_transmission_submitted(
+    mut commands: Commands,
+    mut events: EventReader<TransmissionSubmitted>,
+    parser: Res<TransmissionParser>,
+    voice_config: Res<VoiceControlConfig>,
+    mut variables: ResMut<GameVariables>,
+    metar: Option<Res<Metar>>,
+    q_aircraft: Query<(Entity, &Aircraft)>,
+    mut contact_writer: EventWriter<ContactFrequencyCleared>,
+    mut log_writer: EventWriter<ConsoleLogLine>,
+    mut parsed_writer: EventWriter<TransmissionParsed>,
+) {
+    let visual_approaches_allowed = metar.is_none_or(|metar| metar.0.allows_visual_approach());
+    for TransmissionSubmitted { text, from_voice } in events.read() {
+        log_writer.write(ConsoleLogLine(format!("> {text}")));
+        let result = parser.0.parse_transmission_enhanced(text);
+        let needs_confirmation = *from_voice
+            && match &result {
+                ParseResult::Success(parsed) | ParseResult::PartialSuccess { parsed, .. } => {
+                    let command_confidence = parsed
+                        .commands
+                        .iter()
+                        .map(|command| command.confidence)
+                        .fold(f32::INFINITY, f32::min);
+                    !voice_config.accepts(parsed.callsign_confidence, command_confidence)
+                }
+                _ => false,
+            };
+        parsed_writer.write(TransmissionParsed {
+            text: text.clone(),
+            result: result.clone(),
+            needs_confirmation,
+        });
+        let (parsed, unparsed_parts) = match result {
+            ParseResult::Success(parsed) => (parsed, Vec::new()),
+            ParseResult::PartialSuccess {
+                parsed,
+                unparsed_parts,
+            } => (parsed, unparsed_parts),
+            ParseResult::CallsignOnly(callsign) => {
+                log_writer.write(ConsoleLogLine(format!("{callsign}: no command recognized")));
+                continue;
+            }
+            ParseResult::Failed { reason, .. } => {
+                log_writer.write(ConsoleLogLine(reason));
+                continue;
+            }
+        };
+        if !unparsed_parts.is_empty() {
+            log_writer.write(ConsoleLogLine(format!(
+                "Unparsed: {}",
+                unparsed_parts.join(" ")
+            )));
+        }
+        if needs_confirmation {
+            log_writer.write(ConsoleLogLine(format!(
+                "{}: unsure, confirm in the history",
+                parsed.callsign
+            )));
+            continue;
+        }
+
+        if !parsed.has_flight_number {
+            log_writer.write(ConsoleLogLine(format!(
+                "{}: say again full callsign",
+                parsed.display_callsign
+            )));
+            continue;
+        }
+
+        let ParsedCommand {
+            callsign,
+            commands: parsed_commands,
+            ..
+        } = parsed;
+        let call_signs: Vec<(Entity, String)> = q_aircraft
+            .iter()
+            .map(|(entity, aircraft)| (entity, aircraft.call_sign.clone()))
+            .collect();
+        let call_signs = || {
+            call_signs
+                .iter()
+                .map(|(entity, call_sign)| (*entity, call_sign.as_str()))
+        };
+        let Some((entity, aircraft)) = find_aircraft_by_call_sign(call_signs(), &callsign)
+            .and_then(|entity| q_aircraft.get(entity).ok())
+        else {
+            log_writer.write(ConsoleLogLine(format!("{callsign}: unknown aircraft")));
+            continue;
+        };
+
+        let transition_altitude_feet = variables.transition_level_feet();
+        for command in &parsed_commands {
+            // The pilot chooses the moment of a "when able" clearance
+            let queued = if command.when_able {
+                QueuedClearance::when_able
+            } else {
+                QueuedClearance::untriggered
+            };
+            let command = &with_present_altitude(
+                &parser.0,
+                command,
+                aircraft.altitude_feet,
+                transition_altitude_feet,
+            );
+            log_writer.write(ConsoleLogLine(format!("{callsign}: {command:?}")));
+            match command {
+                AviationCommandPart::ClearedToLand { .. }
+                | AviationCommandPart::ClearedForTheOption { .. } => {
+                    if let Some(clearance) = LandingClearance::from_command(command) {
+                        commands.entity(entity).insert(clearance);
+                    }
+                }
+                AviationCommandPart::VectorsForIls { .. } => {
+                    if let Some(intercept) = IlsIntercept::from_command(command) {
+                        commands
+                            .entity(entity)
+                            .remove::<VisualApproach>()
+                            .insert(intercept);
+                    }
+                }
+                AviationCommandPart::ClearedVisualApproach { .. } => {
+                    if !visual_approaches_allowed {
+                        log_writer.write(ConsoleLogLine(format!(
+                            "{callsign}: unable visual approach, weather below minima"
+                        )));
+                    } else if let Some(approach) = VisualApproach::from_command(command) {
+                        commands
+                            .entity(entity)
+                            .remove::<IlsIntercept>()
+                            .insert(approach);
+                    }
+                }
+                AviationCommandPart::CrossFix { .. }
+                | AviationCommandPart::DirectAfterFix { .. } => {
+                    if let Some(queued) = QueuedClearance::from_command(command) {
+                        queue_clearance(&mut commands, entity, queued);
+                    }
+                }
+                AviationCommandPart::ContactFrequency { frequency, station } => {
+                    contact_writer.write(ContactFrequencyCleared {
+                        aircraft: entity,
+                        frequency: *frequency,
+                        station: station.clone(),
+                    });
+                }
+                AviationCommandPart::Query { field } => {
+                    let response =
+                        aircraft.query_response(*field, variables.transition_level_feet());
+                    log_writer.write(ConsoleLogLine(format!("{callsign}: {response}")));
+                }
+                AviationCommandPart::Altimeter { hpa, inhg } => {
+                    if let Some(qnh_hpa) = hpa.map(f64::from).or(inhg.map(inhg_hundredths_to_hpa)) {
+                        variables.qnh_hpa = qnh_hpa;
+                    }
+                }
+                AviationCommandPart::Squawk { code } => {
+                    apply_squawk(commands.entity(entity), *code);
+                    commands.entity(entity).insert(AssignedSquawk(*code));
+                }
+                AviationCommandPart::DeclareEmergency => {
+                    commands.entity(entity).insert(Emergency::General);
+                }
+                AviationCommandPart::SequenceBehind { callsign: leader } => {
+                    match FollowAircraft::from_command(command, call_signs()) {
+                        Some(follow) if follow.leader != entity => {
+                            commands.entity(entity).insert(follow);
+                        }
+                        _ => {
+                            log_writer.write(ConsoleLogLine(format!(
+                                "{callsign}: unable, no traffic {leader} in sight"
+                            )));
+                        }
+                    }
+                }
+                AviationCommandPart::ChangeSpeed { .. }
+                | AviationCommandPart::ResumeNormalSpeed => {
+                    // A speed clearance ends the sequencing
+                    commands.entity(entity).remove::<FollowAircraft>();
+                    queue_clearance(&mut commands, entity, queued(command));
+                }
+                _ => {
+                    queue_clearance(&mut commands, entity, queued(command));
+                }
+            }
+        }
+    }
+}
+
+/// The command, with an altitude, that was cleared without unit, resolved again with the
+/// present altitude of the aircraft, which the parser didn't know
+fn with_present_altitude(
+    parser: &AviationCommandParser,
+    command: &CommandWithConfidence,
+    present_altitude_feet: f64,
+    transition_altitude_feet: f64,
+) -> AviationCommandPart {
+    let mut resolved = command.command.clone();
+    if let AviationCommandPart::ChangeAltitude { altitude, .. } = &mut resolved {
+        if let Some(unitless) = parser
+            .unitless_altitude(&command.source_text)
+            .and_then(|value| {
+                Altitude::from_unitless(
+                    value,
+                    transition_altitude_feet,
+                    Some(present_altitude_feet),
+                )
+            })
+        {
+            *altitude = unitless;
+        }
+    }
+    resolved
+}
+
+fn append_console_log_lines(
+    mut commands: Commands,
+    mut events: EventReader<ConsoleLogLine>,
+    console_log: Single<(Entity, Option<&Children>), With<ConsoleLog>>,
+) {
+    let (console_log, children) = *console_log;
+    let mut lines: Vec<Entity> = children.map(|c| c.to_vec()).unwrap_or_default();
+    for ConsoleLogLine(line) in events.read() {
+        let line = commands
+            .spawn((
+                Text(line.clone()),
+                TextFont::from_font_size(14.0),
+                TextColor(CONSOLE_LOG_COLOR),
+                ChildOf(console_log),
+            ))
+            .id();
+        lines.push(line);
+    }
+    let excess = lines.len().saturating_sub(CONSOLE_LOG_MAX_LINES);
+    for line in lines.drain(..excess) {
+        commands.entity(line).despawn();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use atc_recognition_rs::{AviationCommandParser, ParseResult};
+    use aviation_helper_rs::clearance::aviation_command::AviationCommandPart;
+    use aviation_helper_rs::types::altitude::Altitude;
+    use aviation_helper_rs::types::heading::Heading;
+    use bevy::prelude::*;
+
+    use super::{
+        ConsoleLogLine, TransmissionParsed, TransmissionParser, TransmissionSubmitted,
+        VoiceControlConfig, VoiceTransmissions, forward_voice_transmissions,
+        handle_transmission_submitted, sync_parser_transition_level,
+    };
+    use crate::game::GameVariables;
+    use crate::game::aircraft::{Aircraft, test_aircraft};
+    use crate::game::clearance::{ClearanceLog, fire_queued_clearances};
+    use crate::game::contact_label::ContactFrequencyCleared;
+    use crate::game::landing::{LandingClearance, LandingClearanceKind};
+    use crate::menu::LevelMeta;
+
+    fn fixture_aircraft(call_sign: &str) -> Aircraft {
+        Aircraft {
+            call_sign: call_sign.to_owned(),
+            ..test_aircraft()
+        }
+    }
+
+    #[test]
+    fn test_submitted_transmission_is_applied_to_addressed_aircraft() {
+        let mut app = App::new();
+        app.add_event::<TransmissionSubmitted>()
+            .add_event::<TransmissionParsed>()
+            .add_event::<ConsoleLogLine>()
+            .add_event::<ContactFrequencyCleared>()
+            .insert_resource(TransmissionParser(AviationCommandParser::new_empty()))
+            .init_resource::<VoiceControlConfig>()
+            .insert_resource(GameVariables::new(LevelMeta {
+                file: "test.ron".to_owned(),
+                name: "Test".to_owned(),
+            }))
+            .init_resource::<ClearanceLog>()
+            .insert_resource(Time::<()>::default())
+            .add_systems(
+                Update,
+                (handle_transmission_submitted, fire_queued_clearances).chain(),
+            );
+        let addressed = app
+            .world_mut()
+            .spawn((fixture_aircraft("DLH123"), Transform::default()))
+            .id();
+        let other = app
+            .world_mut()
+            .spawn((fixture_aircraft("EZY2902"), Transform::default()))
+            .id();
+
+        app.world_mut().send_event(TransmissionSubmitted {
+            text: "DLH 123, fly heading 270, climb and maintain flight level 250, cleared to land runway 27"
+                .to_owned(),
+            from_voice: false,
+        });
+        app.update();
+
+        let aircraft = app.world().get::<Aircraft>(addressed).unwrap();
+        assert_eq!(aircraft.cleared_heading, Some(Heading::from(270.)));
+        assert_eq!(aircraft.cleared_altitude_feet, Some(25000.));
+        assert_eq!(
+            app.world().get::<LandingClearance>(addressed),
+            Some(&LandingClearance {
+                runway: "27".to_owned(),
+                kind: LandingClearanceKind::FullStop,
+            })
+        );
+        assert_eq!(app.world().resource::<ClearanceLog>().entries.len(), 2);
+
+        let other_aircraft = app.world().get::<Aircraft>(other).unwrap();
+        assert_eq!(other_aircraft.cleared_heading, None);
+        assert_eq!(other_aircraft.cleared_altitude_feet, None);
+        assert!(app.world().get::<LandingClearance>(other).is_none());
+    }
+
+    #[test]
+    fn test_altitude_without_unit_is_resolved_with_present_altitude() {
+        let mut app = App::new();
+        app.add_event::<TransmissionSubmitted>()
+            .add_event::<TransmissionParsed>()
+            .add_event::<ConsoleLogLine>()
+            .add_event::<ContactFrequencyCleared>()
+            .insert_resource(TransmissionParser(AviationCommandParser::new_empty()))
+            .init_resource::<VoiceControlConfig>()
+            .insert_resource(GameVariables::new(LevelMeta {
+                file: "test.ron".to_owned(),
+                name: "Test".to_owned(),
+            }))
+            .init_resource::<ClearanceLog>()
+            .insert_resource(Time::<()>::default())
+            .add_systems(
+                Update,
+                (handle_transmission_submitted, fire_queued_clearances).chain(),
+            );
+        let aircraft = app
+            .world_mut()
+            .spawn((fixture_aircraft("DLH123"), Transform::default()))
+            .id();
+        let mut cleared_altitude = |text: &str| {
+            app.world_mut().send_event(TransmissionSubmitted {
+                text: text.to_owned(),
+                from_voice: false,
+            });
+            app.update();
+            app.world()
+                .get::<Aircraft>(aircraft)
+                .unwrap()
+                .cleared_altitude_feet
+        };
+
+        assert_eq!(cleared_altitude("DLH 123, maintain 100"), Some(10000.));
+        assert_eq!(cleared_altitude("DLH 123, maintain 3000"), Some(3000.));
+        // Flight level 500 or 500 feet, the aircraft at 5000 feet makes feet more likely
+        assert_eq!(cleared_altitude("DLH 123, maintain 500"), Some(500.));
+    }
+
+    #[test]
+    fn test_parser_resolves_with_transition_level_of_the_game() {
+        let mut app = App::new();
+        app.add_event::<TransmissionSubmitted>()
+            .add_event::<TransmissionParsed>()
+            .add_event::<ConsoleLogLine>()
+            .add_event::<ContactFrequencyCleared>()
+            .insert_resource(TransmissionParser(AviationCommandParser::new_empty()))
+            .init_resource::<VoiceControlConfig>()
+            .insert_resource(GameVariables {
+                transition_altitude_feet: 5000.,
+                ..GameVariables::new(LevelMeta {
+                    file: "test.ron".to_owned(),
+                    name: "Test".to_owned(),
+                })
+            })
+            .add_systems(
+                Update,
+                (sync_parser_transition_level, handle_transmission_submitted).chain(),
+            );
+        app.world_mut()
+            .spawn((fixture_aircraft("DLH123"), Transform::default()));
+        app.world_mut().send_event(TransmissionSubmitted {
+            text: "DLH 123, maintain 6000".to_owned(),
+            from_voice: false,
+        });
+        app.update();
+
+        let events = app.world().resource::<Events<TransmissionParsed>>();
+        let parsed = events.iter_current_update_events().next().unwrap();
+        let (ParseResult::Success(parsed) | ParseResult::PartialSuccess { parsed, .. }) =
+            &parsed.result
+        else {
+            panic!("Not parsed: {:?}", parsed.result);
+        };
+        // Above the transition altitude of the level, not of the parser default
+        assert!(matches!(
+            parsed.commands[0].command,
+            AviationCommandPart::ChangeAltitude {
+                altitude: Altitude::FlightLevel(60),
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_recognized_texts_are_submitted_from_voice() {
+        let mut app = App::new();
+        let (sender, receiver) = std::sync::mpsc::channel();
+        app.add_event::<TransmissionSubmitted>()
+            .insert_non_send_resource(VoiceTransmissions {
+                receiver,
+                _recognition: None,
+            })
+            .add_systems(Update, forward_voice_transmissions);
+        sender
+            .send("lufthansa 123 descend flight level 100".to_owned())
+            .unwrap();
+        app.update();
+
+        let events = app.world().resource::<Events<TransmissionSubmitted>>();
+        let submitted: Vec<_> = events
+            .iter_current_update_events()
+            .map(|event| (event.text.as_str(), event.from_voice))
+            .collect();
+        assert_eq!(
+            submitted,
+            vec![("lufthansa 123 descend flight level 100", true)]
+        );
+    }
+
+    #[test]
+    fn test_voice_control_confidence_floors() {
+        let config = VoiceControlConfig {
+            min_callsign_confidence: 0.9,
+            min_command_confidence: 0.7,
+        };
+        assert!(config.accepts(0.95, 0.9));
+        assert!(config.accepts(0.9, 0.7));
+        // A callsign of an unknown airline
+        assert!(!config.accepts(0.8, 0.95));
+        assert!(!config.accepts(0.95, 0.5));
+        assert!(!config.accepts(0.3, 0.3));
+        // Without commands, the command confidence doesn't hold anything back
+        assert!(config.accepts(0.95, f32::INFINITY));
+    }
+
+    #[cfg(feature = "test-utils")]
+    #[test]
+    fn test_voice_transmission_is_applied_with_stub_transcriber() {
+        use atc_recognition_rs::VoiceRecognizer;
+        use atc_recognition_rs::test_utils::StubSpeechToText;
+        use aviation_helper_rs::clearance::airlines::{AirlineEntry, Airlines};
+
+        let airlines = Airlines(vec![AirlineEntry {
+            id: 1,
+            name: "Lufthansa".to_owned(),
+            alias: None,
+            iata: Some("LH".to_owned()),
+            icao: Some("DLH".to_owned()),
+            callsign: Some("LUFTHANSA".to_owned()),
+            country: "Germany".to_owned(),
+            active: true,
+        }]);
+        let samples = vec![0.1, -0.2, 0.3, -0.4];
+        let stub = StubSpeechToText::default()
+            .with_transcription(samples.clone(), "lufthansa 123 descend flight level 100");
+        let mut recognizer = VoiceRecognizer::with_transcriber(stub, airlines.clone());
+        let (text, parsed) = recognizer.process_audio_samples(&samples).unwrap();
+        assert_eq!(
+            parsed.map(|parsed| parsed.callsign),
+            Some("DLH123".to_owned())
+        );
+
+        let mut app = App::new();
+        app.add_event::<TransmissionSubmitted>()
+            .add_event::<TransmissionParsed>()
+            .add_event::<ConsoleLogLine>()
+            .add_event::<ContactFrequencyCleared>()
+            .insert_resource(TransmissionParser(AviationCommandParser::new(airlines)))
+            .init_resource::<VoiceControlConfig>()
+            .insert_resource(GameVariables::new(LevelMeta {
+                file: "test.ron".to_owned(),
+                name: "Test".to_owned(),
+            }))
+            .init_resource::<ClearanceLog>()
+            .insert_resource(Time::<()>::default())
+            .add_systems(
+                Update,
+                (handle_transmission_submitted, fire_queued_clearances).chain(),
+            );
+        let mut aircraft = fixture_aircraft("DLH123");
+        aircraft.altitude_feet = 15000.;
+        let aircraft = app.world_mut().spawn((aircraft, Transform::default())).id();
+
+        app.world_mut().send_event(TransmissionSubmitted {
+            text,
+            from_voice: true,
+        });
+        app.update();
+
+        assert_eq!(
+            app.world()
+                .get::<Aircraft>(aircraft)
+                .unwrap()
+                .cleared_altitude_feet,
+            Some(10000.)
+        );
+    }
+}
@@ -0,0 +1,70 @@
+use aviation_helper_rs::clearance::aviation_command::Frequency;
+use bevy::prelude::*;
+
+use crate::game::GameState;
+use crate::game::aircraft_card::PinnedTo;
+
+/// How long the label stays visible after the contact clearance
+const CONTACT_LABEL_SECONDS: f32 = 4.0;
+const CONTACT_LABEL_COLOR: Color = Color::srgb(0.4, 0.8, 1.0);
+
+pub struct ContactLabelPlugin;
+
+impl Plugin for ContactLabelPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ContactFrequencyCleared>().add_systems(
+            Update,
+            (spawn_contact_labels, despawn_expired_contact_labels)
+                .run_if(in_state(GameState::Running)),
+        );
+    }
+}
+
+/// An aircraft was told to contact another frequency
+#[derive(Event, Debug, Clone)]
+pub struct ContactFrequencyCleared {
+    pub aircraft: Entity,
+    pub frequency: Frequency,
+    /// Kind of station, e.g. "tower"
+    pub station: Option<String>,
+}
+
+#[derive(Component)]
+pub struct ContactLabel(pub Timer);
+
+fn spawn_contact_labels(mut commands: Commands, mut events: EventReader<ContactFrequencyCleared>) {
+    for ContactFrequencyCleared {
+        aircraft,
+        frequency,
+        station,
+    } in events.read()
+    {
+        let label = match station {
+            Some(station) => format!("→ {station} {}", frequency.to_display_string()),
+            None => format!("→ {}", frequency.to_display_string()),
+        };
+        commands.spawn((
+            ContactLabel(Timer::from_seconds(CONTACT_LABEL_SECONDS, TimerMode::Once)),
+            PinnedTo {
+                entity: *aircraft,
+                relative_translation: Vec3::new(0., -20., 1.),
+            },
+            Text2d(label),
+            TextFont::from_font_size(12.0),
+            TextColor(CONTACT_LABEL_COLOR),
+            Transform::default(),
+        ));
+    }
+}
+
+fn despawn_expired_contact_labels(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut q_labels: Query<(Entity, &mut ContactLabel)>,
+) {
+    for (entity, mut label) in &mut q_labels {
+        if label.0.tick(time.delta()).finished() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
@@ -10,6 +10,11 @@ pub enum ControlMode {
         display_entity: Entity,
         display: AircraftCardDisplay,
     },
+    /// Dragging from the aircraft to the cursor to set its heading
+    HeadingDrag {
+        aircraft_entity: Entity,
+        cursor: Vec2,
+    },
     // Weitere Modi können hier ergänzt werden
 }
 
@@ -0,0 +1,179 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::game::GameState;
+use crate::game::aircraft::{Aircraft, AircraftMeshMaterials};
+use crate::game::color_theme::{ColorTheme, recolor};
+
+/// Time, that an aircraft in an emergency is shown in each of the two colors
+const EMERGENCY_FLASH_SECONDS: f32 = 0.5;
+
+pub struct EmergencyPlugin;
+
+impl Plugin for EmergencyPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, setup_emergency_material)
+            .add_systems(
+                Update,
+                (
+                    flash_emergency_aircraft.run_if(in_state(GameState::Running)),
+                    recolor_emergency_material.run_if(resource_changed::<ColorTheme>),
+                ),
+            );
+    }
+}
+
+/// Aircraft, that squawks an emergency code or declared an emergency. Its conflicts are
+/// alerted with priority.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Emergency {
+    /// Squawk 7700
+    General,
+    /// Squawk 7600
+    RadioFailure,
+    /// Squawk 7500
+    Hijack,
+}
+
+impl Emergency {
+    pub fn from_squawk(code: u16) -> Option<Self> {
+        match code {
+            7700 => Some(Self::General),
+            7600 => Some(Self::RadioFailure),
+            7500 => Some(Self::Hijack),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Resource)]
+struct EmergencyMaterial(Handle<ColorMaterial>);
+
+/// Sets the emergency state of the aircraft according to the new squawk code. Any other code
+/// ends the emergency.
+pub fn apply_squawk(mut aircraft: EntityCommands, code: u16) {
+    match Emergency::from_squawk(code) {
+        Some(emergency) => aircraft.insert(emergency),
+        None => aircraft.remove::<Emergency>(),
+    };
+}
+
+fn setup_emergency_material(
+    mut commands: Commands,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    color_theme: Res<ColorTheme>,
+) {
+    let material = materials.add(color_theme.emergency);
+    commands.insert_resource(EmergencyMaterial(material));
+}
+
+fn recolor_emergency_material(
+    emergency_material: Res<EmergencyMaterial>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    color_theme: Res<ColorTheme>,
+) {
+    recolor(&mut materials, &emergency_material.0, color_theme.emergency);
+}
+
+/// Alternates the color of aircraft in an emergency and restores it, when the emergency ends.
+/// Other aircraft aren't touched.
+fn flash_emergency_aircraft(
+    time: Res<Time>,
+    aircraft_materials: Res<AircraftMeshMaterials>,
+    emergency_material: Res<EmergencyMaterial>,
+    mut ended: RemovedComponents<Emergency>,
+    q_emergencies: Query<Entity, (With<Aircraft>, With<Emergency>)>,
+    mut q_materials: Query<&mut MeshMaterial2d<ColorMaterial>, With<Aircraft>>,
+) {
+    let highlighted = (time.elapsed_secs() / EMERGENCY_FLASH_SECONDS) as u32 % 2 == 0;
+    let wanted = if highlighted {
+        &emergency_material.0
+    } else {
+        &aircraft_materials.material
+    };
+    for entity in &q_emergencies {
+        let Ok(mut material) = q_materials.get_mut(entity) else {
+            continue;
+        };
+        if material.0 != *wanted {
+            material.0 = wanted.clone();
+        }
+    }
+    for entity in ended.read() {
+        if let Ok(mut material) = q_materials.get_mut(entity) {
+            material.0 = aircraft_materials.material.clone();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+
+    use super::{Emergency, EmergencyMaterial, apply_squawk, flash_emergency_aircraft};
+    use crate::game::aircraft::{AircraftMeshMaterials, test_aircraft};
+
+    #[test]
+    fn test_squawk_sets_emergency() {
+        let mut world = World::new();
+        let entity = world.spawn_empty().id();
+        let mut squawk = |code| {
+            apply_squawk(world.commands().entity(entity), code);
+            world.flush();
+            world.get::<Emergency>(entity).copied()
+        };
+
+        assert_eq!(squawk(7700), Some(Emergency::General));
+        assert_eq!(squawk(1000), None);
+        assert_eq!(squawk(7700), Some(Emergency::General));
+        assert_eq!(squawk(7600), Some(Emergency::RadioFailure));
+        assert_eq!(squawk(7500), Some(Emergency::Hijack));
+        assert_eq!(squawk(2000), None);
+    }
+
+    #[test]
+    fn test_only_aircraft_in_an_emergency_are_flashed() {
+        let normal = Handle::<ColorMaterial>::weak_from_u128(1);
+        let emergency = Handle::<ColorMaterial>::weak_from_u128(2);
+        let other = Handle::<ColorMaterial>::weak_from_u128(3);
+        let mut app = App::new();
+        app.insert_resource(Time::<()>::default())
+            .insert_resource(AircraftMeshMaterials {
+                material: normal.clone(),
+                ..default()
+            })
+            .insert_resource(EmergencyMaterial(emergency.clone()))
+            .add_systems(Update, flash_emergency_aircraft);
+        let in_emergency = app
+            .world_mut()
+            .spawn((
+                test_aircraft(),
+                MeshMaterial2d(normal.clone()),
+                Emergency::General,
+            ))
+            .id();
+        // E.g. tinted by another system
+        let tinted = app
+            .world_mut()
+            .spawn((test_aircraft(), MeshMaterial2d(other.clone())))
+            .id();
+        let material = |app: &App, entity| {
+            app.world()
+                .get::<MeshMaterial2d<ColorMaterial>>(entity)
+                .unwrap()
+                .0
+                .clone()
+        };
+
+        app.update();
+        assert_eq!(material(&app, in_emergency), emergency);
+        assert_eq!(material(&app, tinted), other);
+
+        app.world_mut()
+            .entity_mut(in_emergency)
+            .remove::<Emergency>();
+        app.update();
+        assert_eq!(material(&app, in_emergency), normal);
+        assert_eq!(material(&app, tinted), other);
+    }
+}
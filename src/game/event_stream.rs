@@ -0,0 +1,208 @@
+use std::io::Write;
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::{Receiver, SyncSender, TrySendError, sync_channel};
+
+use aviation_helper_rs::clearance::aviation_command::AviationCommandPart;
+use bevy::prelude::*;
+use serde::Serialize;
+
+use crate::APP_CONFIG;
+use crate::game::GameState;
+use crate::game::aircraft::Aircraft;
+use crate::game::clearance::ClearanceLog;
+use crate::game::console::TransmissionSubmitted;
+
+/// Lines, that are buffered for the worker thread. Newer ones are dropped, if it falls behind.
+const EVENT_STREAM_BUFFER: usize = 256;
+
+/// Streams recognized transmissions and applied clearances as newline-delimited JSON to all
+/// clients connected over TCP
+pub struct EventStreamPlugin;
+
+impl Plugin for EventStreamPlugin {
+    fn build(&self, app: &mut App) {
+        let (sender, receiver) = sync_channel(EVENT_STREAM_BUFFER);
+        let address = &APP_CONFIG.event_stream_address;
+        match TcpListener::bind(address) {
+            Ok(listener) => {
+                info!("Streaming events on {address}");
+                std::thread::spawn(move || serve_clients(listener, receiver));
+            }
+            // The game runs on without the stream, the lines are dropped
+            Err(err) => error!("Failed to stream events on {address}: {err}"),
+        }
+        app.insert_resource(EventStream(sender)).add_systems(
+            Update,
+            (stream_transmissions, stream_clearances).run_if(in_state(GameState::Running)),
+        );
+    }
+}
+
+/// One line of the stream
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum StreamEvent {
+    Transmission {
+        text: String,
+    },
+    Clearance {
+        call_sign: String,
+        command: AviationCommandPart,
+    },
+}
+
+impl StreamEvent {
+    /// The event as JSON, terminated by a newline
+    pub fn to_json_line(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string(self).map(|json| json + "\n")
+    }
+}
+
+/// Sending end of the channel to the worker thread. Sending never blocks the game.
+#[derive(Resource, Debug, Clone)]
+pub struct EventStream(pub SyncSender<String>);
+
+impl EventStream {
+    pub fn send(&self, event: &StreamEvent) {
+        let line = match event.to_json_line() {
+            Ok(line) => line,
+            Err(err) => {
+                error!("Failed to serialize {event:?}: {err}");
+                return;
+            }
+        };
+        match self.0.try_send(line) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) => warn!("Event stream is behind, dropped {event:?}"),
+            // The listener couldn't be bound
+            Err(TrySendError::Disconnected(_)) => {}
+        }
+    }
+}
+
+/// Writes each line to all clients. New clients are accepted before each line, clients, that
+/// can't be written to, are dropped.
+fn serve_clients(listener: TcpListener, receiver: Receiver<String>) {
+    if let Err(err) = listener.set_nonblocking(true) {
+        error!("Failed to accept clients of the event stream: {err}");
+        return;
+    }
+    let mut clients: Vec<TcpStream> = Vec::new();
+    for line in receiver {
+        while let Ok((client, address)) = listener.accept() {
+            debug!("Event stream client {address} connected");
+            clients.push(client);
+        }
+        clients.retain_mut(|client| client.write_all(line.as_bytes()).is_ok());
+    }
+}
+
+fn stream_transmissions(mut events: EventReader<TransmissionSubmitted>, stream: Res<EventStream>) {
+    for TransmissionSubmitted { text, .. } in events.read() {
+        stream.send(&StreamEvent::Transmission { text: text.clone() });
+    }
+}
+
+/// Streams the entries, that were added to the log since the last run. Entries can be removed
+/// from the log, so the sequence of the next entry is remembered instead of an index.
+fn stream_clearances(
+    log: Res<ClearanceLog>,
+    mut next_sequence: Local<u64>,
+    q_aircraft: Query<&Aircraft>,
+    stream: Res<EventStream>,
+) {
+    if !log.is_changed() {
+        return;
+    }
+    let added = log
+        .entries
+        .iter()
+        .rev()
+        .take_while(|entry| entry.sequence >= *next_sequence)
+        .count();
+    for entry in &log.entries[log.entries.len() - added..] {
+        *next_sequence = entry.sequence + 1;
+        let Ok(aircraft) = q_aircraft.get(entry.aircraft) else {
+            continue;
+        };
+        stream.send(&StreamEvent::Clearance {
+            call_sign: aircraft.call_sign.clone(),
+            command: entry.command.clone(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc::sync_channel;
+    use std::time::Duration;
+
+    use aviation_helper_rs::clearance::aviation_command::{AviationCommandPart, HeadingDirection};
+    use aviation_helper_rs::types::heading::Heading;
+    use bevy::prelude::*;
+
+    use super::{EVENT_STREAM_BUFFER, EventStream, stream_clearances};
+    use crate::game::aircraft::{Aircraft, test_aircraft};
+    use crate::game::clearance::{ClearanceLog, apply_clearance};
+
+    #[test]
+    fn test_applied_clearance_is_streamed_as_json_line() {
+        let (sender, receiver) = sync_channel(EVENT_STREAM_BUFFER);
+        let mut app = App::new();
+        app.init_resource::<ClearanceLog>()
+            .insert_resource(EventStream(sender))
+            .add_systems(Update, stream_clearances);
+        let entity = app.world_mut().spawn(test_aircraft()).id();
+        let apply = |app: &mut App, commands: &[AviationCommandPart]| {
+            app.world_mut()
+                .resource_scope(|world, mut log: Mut<ClearanceLog>| {
+                    let mut aircraft = world.get::<Aircraft>(entity).unwrap().clone();
+                    let mut world_commands = world.commands();
+                    for command in commands {
+                        apply_clearance(
+                            &mut world_commands,
+                            entity,
+                            &mut aircraft,
+                            command,
+                            &mut log,
+                            Duration::ZERO,
+                        );
+                    }
+                    world_commands.entity(entity).insert(aircraft);
+                    world.flush();
+                });
+            app.update();
+        };
+        let fly_heading = |heading| AviationCommandPart::FlyHeading {
+            heading: HeadingDirection::Heading(Heading::from(heading)),
+            turn_direction: None,
+        };
+        app.update();
+        assert!(receiver.try_recv().is_err());
+
+        apply(&mut app, &[fly_heading(270.)]);
+
+        let line = receiver.try_recv().unwrap();
+        assert!(receiver.try_recv().is_err(), "Expected exactly one line");
+        assert_eq!(line.matches('\n').count(), 1);
+        assert!(line.ends_with('\n'));
+        let json: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(json["event"], "clearance");
+        assert_eq!(json["call_sign"], "DLH123");
+        assert!(json["command"].get("FlyHeading").is_some(), "{line}");
+
+        // Entries, that were already streamed, aren't sent again
+        app.world_mut().resource_mut::<ClearanceLog>().set_changed();
+        app.update();
+        assert!(receiver.try_recv().is_err());
+
+        // The log has the same length after a disregard and a new clearance
+        apply(
+            &mut app,
+            &[AviationCommandPart::Disregard, fly_heading(180.)],
+        );
+        let line = receiver.try_recv().unwrap();
+        assert!(receiver.try_recv().is_err(), "Expected exactly one line");
+        assert!(line.contains("180"), "{line}");
+    }
+}
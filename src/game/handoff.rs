@@ -0,0 +1,318 @@
+use aviation_helper_rs::clearance::aviation_command::Frequency;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::game::GameState;
+use crate::game::aircraft::Aircraft;
+use crate::game::contact_label::ContactFrequencyCleared;
+use crate::game::level::Sector;
+use crate::util::units::nm_to_pixels;
+
+/// Distance before the sector boundary, at which the controller is prompted to hand off
+/// outbound aircraft
+const DEFAULT_HANDOFF_PROMPT_DISTANCE_NM: f64 = 5.;
+
+pub struct HandoffPlugin;
+
+impl Plugin for HandoffPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<HandoffCompleted>()
+            .add_event::<AircraftLeftSector>()
+            .init_resource::<HandoffPrompt>()
+            .add_systems(
+                FixedUpdate,
+                (check_sector_exits, prompt_handoffs).run_if(in_state(GameState::Running)),
+            )
+            .add_systems(
+                Update,
+                (start_handoffs, log_sector_exits).run_if(in_state(GameState::Running)),
+            );
+    }
+}
+
+/// The aircraft was told to contact the next sector
+#[derive(Component, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Handoff {
+    pub frequency: Frequency,
+    /// The aircraft left the sector after the handoff
+    pub completed: bool,
+}
+
+/// Aircraft, that will leave the sector soon and wasn't handed off yet. Its card is tinted.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct HandoffDue;
+
+/// How early the controller is prompted to hand off aircraft, that fly out of the sector
+#[derive(Resource, Debug, Clone)]
+pub struct HandoffPrompt {
+    pub distance_nm: f64,
+}
+
+impl Default for HandoffPrompt {
+    fn default() -> Self {
+        Self {
+            distance_nm: DEFAULT_HANDOFF_PROMPT_DISTANCE_NM,
+        }
+    }
+}
+
+/// Aircraft, that is inside one of the sectors
+#[derive(Component, Debug, Clone, Copy)]
+pub struct InSector;
+
+/// An aircraft left the sector after it was handed off
+#[derive(Event, Debug, Clone)]
+pub struct HandoffCompleted(pub Entity);
+
+/// An aircraft left the sector without a handoff
+#[derive(Event, Debug, Clone)]
+pub struct AircraftLeftSector(pub Entity);
+
+fn start_handoffs(mut commands: Commands, mut events: EventReader<ContactFrequencyCleared>) {
+    for ContactFrequencyCleared {
+        aircraft,
+        frequency,
+        ..
+    } in events.read()
+    {
+        if let Ok(mut entity) = commands.get_entity(*aircraft) {
+            entity
+                .insert(Handoff {
+                    frequency: *frequency,
+                    completed: false,
+                })
+                .remove::<HandoffDue>();
+        }
+    }
+}
+
+/// Whether the aircraft is inside one of the sectors, but will be outside of all of them after
+/// flying `distance_nm` along its track
+pub fn is_approaching_exit(
+    sectors: &[&Sector],
+    position: Vec2,
+    altitude_feet: f64,
+    track: Vec2,
+    distance_nm: f64,
+) -> bool {
+    let ahead = position + track.normalize_or_zero() * nm_to_pixels(distance_nm);
+    let in_any_sector = |position| {
+        sectors
+            .iter()
+            .any(|sector| sector.contains(position, altitude_feet))
+    };
+    in_any_sector(position) && !in_any_sector(ahead)
+}
+
+fn prompt_handoffs(
+    mut commands: Commands,
+    prompt: Res<HandoffPrompt>,
+    q_aircraft: Query<(Entity, &Aircraft, &Transform, Has<HandoffDue>), Without<Handoff>>,
+    q_sectors: Query<&Sector>,
+) {
+    let sectors: Vec<&Sector> = q_sectors.iter().collect();
+    for (entity, aircraft, transform, was_due) in &q_aircraft {
+        let track = Vec2::from_angle(aircraft.heading.to_bevy_rotation() as f32);
+        let is_due = is_approaching_exit(
+            &sectors,
+            transform.translation.truncate(),
+            aircraft.altitude_feet,
+            track,
+            prompt.distance_nm,
+        );
+        match (was_due, is_due) {
+            (false, true) => {
+                commands.entity(entity).insert(HandoffDue);
+            }
+            (true, false) => {
+                commands.entity(entity).remove::<HandoffDue>();
+            }
+            _ => {}
+        }
+    }
+}
+
+fn check_sector_exits(
+    mut commands: Commands,
+    q_aircraft: Query<(
+        Entity,
+        &Aircraft,
+        &Transform,
+        Option<&mut Handoff>,
+        Has<InSector>,
+    )>,
+    q_sectors: Query<&Sector>,
+    mut handoff_completed_writer: EventWriter<HandoffCompleted>,
+    mut left_sector_writer: EventWriter<AircraftLeftSector>,
+) {
+    for (entity, aircraft, transform, handoff, was_in_sector) in q_aircraft {
+        let position = transform.translation.truncate();
+        let is_in_sector = q_sectors
+            .iter()
+            .any(|sector| sector.contains(position, aircraft.altitude_feet));
+        match (was_in_sector, is_in_sector) {
+            (false, true) => {
+                commands.entity(entity).insert(InSector);
+            }
+            (true, false) => {
+                commands.entity(entity).remove::<InSector>();
+                match handoff {
+                    Some(mut handoff) if !handoff.completed => {
+                        handoff.completed = true;
+                        handoff_completed_writer.write(HandoffCompleted(entity));
+                    }
+                    _ => {
+                        left_sector_writer.write(AircraftLeftSector(entity));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn log_sector_exits(
+    mut handoff_completed_reader: EventReader<HandoffCompleted>,
+    mut left_sector_reader: EventReader<AircraftLeftSector>,
+) {
+    for HandoffCompleted(entity) in handoff_completed_reader.read() {
+        info!("Aircraft {entity} was handed off");
+    }
+    for AircraftLeftSector(entity) in left_sector_reader.read() {
+        info!("Aircraft {entity} left the sector without a handoff");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use aviation_helper_rs::clearance::aviation_command::Frequency;
+    use bevy::prelude::*;
+
+    use super::{
+        AircraftLeftSector, Handoff, HandoffCompleted, check_sector_exits, is_approaching_exit,
+    };
+    use crate::game::aircraft::{Aircraft, test_aircraft};
+    use crate::game::level::Sector;
+    use crate::util::units::nm_to_pixels;
+
+    #[test]
+    fn test_leaving_the_sector_with_and_without_handoff() {
+        let mut app = App::new();
+        app.add_event::<HandoffCompleted>()
+            .add_event::<AircraftLeftSector>()
+            .add_systems(Update, check_sector_exits);
+        app.world_mut().spawn(Sector {
+            name: "SectorA".to_owned(),
+            min_altitude_feet: 2000.,
+            max_altitude_feet: 8000.,
+            boundary: vec![
+                Vec2::new(0., 0.),
+                Vec2::new(100., 0.),
+                Vec2::new(100., 100.),
+                Vec2::new(0., 100.),
+            ],
+        });
+        let handed_off = app
+            .world_mut()
+            .spawn((
+                test_aircraft(),
+                Transform::from_xyz(90., 50., 0.),
+                Handoff {
+                    frequency: "133.5".parse::<Frequency>().unwrap(),
+                    completed: false,
+                },
+            ))
+            .id();
+        let not_handed_off = app
+            .world_mut()
+            .spawn((test_aircraft(), Transform::from_xyz(50., 90., 0.)))
+            .id();
+
+        app.update();
+        assert!(
+            app.world()
+                .resource::<Events<HandoffCompleted>>()
+                .is_empty()
+        );
+        assert!(
+            app.world()
+                .resource::<Events<AircraftLeftSector>>()
+                .is_empty()
+        );
+
+        // Leaves laterally
+        app.world_mut()
+            .get_mut::<Transform>(handed_off)
+            .unwrap()
+            .translation
+            .x = 110.;
+        // Leaves through the top of the sector
+        app.world_mut()
+            .get_mut::<Aircraft>(not_handed_off)
+            .unwrap()
+            .altitude_feet = 9000.;
+        app.update();
+
+        let completed: Vec<_> = app
+            .world()
+            .resource::<Events<HandoffCompleted>>()
+            .iter_current_update_events()
+            .map(|HandoffCompleted(entity)| *entity)
+            .collect();
+        assert_eq!(completed, vec![handed_off]);
+        let left: Vec<_> = app
+            .world()
+            .resource::<Events<AircraftLeftSector>>()
+            .iter_current_update_events()
+            .map(|AircraftLeftSector(entity)| *entity)
+            .collect();
+        assert_eq!(left, vec![not_handed_off]);
+        assert!(app.world().get::<Handoff>(handed_off).unwrap().completed);
+    }
+
+    #[test]
+    fn test_approaching_exit_outbound() {
+        let size = nm_to_pixels(20.);
+        let sector = Sector {
+            name: "SectorA".to_owned(),
+            min_altitude_feet: 2000.,
+            max_altitude_feet: 8000.,
+            boundary: vec![
+                Vec2::new(0., 0.),
+                Vec2::new(size, 0.),
+                Vec2::new(size, size),
+                Vec2::new(0., size),
+            ],
+        };
+        let sectors = [&sector];
+        let near_east_boundary = Vec2::new(nm_to_pixels(17.), size / 2.);
+        let approaching =
+            |position, track| is_approaching_exit(&sectors, position, 5000., track, 5.);
+
+        assert!(approaching(near_east_boundary, Vec2::X));
+        // Inbound or along the boundary
+        assert!(!approaching(near_east_boundary, Vec2::NEG_X));
+        assert!(!approaching(near_east_boundary, Vec2::Y));
+        // Far from the boundary
+        assert!(!approaching(Vec2::splat(size / 2.), Vec2::X));
+        // Already outside
+        assert!(!approaching(Vec2::new(size + 10., size / 2.), Vec2::X));
+        // Adjacent sectors are one airspace
+        let east = Sector {
+            name: "SectorB".to_owned(),
+            boundary: sector
+                .boundary
+                .iter()
+                .map(|corner| *corner + Vec2::new(size, 0.))
+                .collect(),
+            ..sector.clone()
+        };
+        assert!(!is_approaching_exit(
+            &[&sector, &east],
+            near_east_boundary,
+            5000.,
+            Vec2::X,
+            5.
+        ));
+    }
+}
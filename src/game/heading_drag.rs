@@ -0,0 +1,242 @@
+use aviation_helper_rs::clearance::aviation_command::{AviationCommandPart, HeadingDirection};
+use aviation_helper_rs::types::heading::Heading;
+use bevy::prelude::*;
+
+use crate::game::GameState;
+use crate::game::aircraft::{Aircraft, AircraftJustSpawned};
+use crate::game::aircraft_card::BeingDragged;
+use crate::game::clearance::{QueuedClearance, queue_clearance};
+use crate::game::control::{ControlMode, ControlState};
+
+/// Dragged headings are rounded, like a controller would assign them
+const HEADING_STEP_DEGREES: f64 = 5.;
+/// Closer to the aircraft, the direction of the cursor is too jumpy to be a heading
+const MIN_DRAG_DISTANCE_PIXELS: f32 = 10.;
+const RUBBER_BAND_COLOR: Color = Color::srgb(0.3, 0.9, 0.9);
+
+/// Dragging from an aircraft on the radar clears it to fly the heading towards the cursor
+pub struct HeadingDragPlugin;
+
+impl Plugin for HeadingDragPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                observe_aircraft_drags,
+                end_drag_of_despawned_aircraft,
+                draw_rubber_band,
+            )
+                .run_if(in_state(GameState::Running)),
+        );
+    }
+}
+
+/// State before the drag. The control mode is restored, when the drag ends.
+#[derive(Component, Debug, Clone)]
+struct HeadingDragStart {
+    previous_mode: ControlMode,
+}
+
+/// Heading from the aircraft to the cursor, rounded to whole steps. None, if the cursor is too
+/// close to the aircraft.
+pub fn heading_from_drag(aircraft_position: Vec2, cursor: Vec2) -> Option<Heading> {
+    let offset = cursor - aircraft_position;
+    if offset.length() < MIN_DRAG_DISTANCE_PIXELS {
+        return None;
+    }
+    let degrees = Heading::from_bevy_rotation(offset.to_angle() as f64).get();
+    Some(Heading::from(
+        (degrees / HEADING_STEP_DEGREES).round() * HEADING_STEP_DEGREES,
+    ))
+}
+
+fn observe_aircraft_drags(mut commands: Commands, mut events: EventReader<AircraftJustSpawned>) {
+    for AircraftJustSpawned(aircraft_entity) in events.read() {
+        commands
+            .entity(*aircraft_entity)
+            .observe(on_aircraft_drag_start)
+            .observe(on_aircraft_drag)
+            .observe(on_aircraft_drag_end);
+    }
+}
+
+fn cursor_in_world(camera: (&Camera, &GlobalTransform), pointer_position: Vec2) -> Option<Vec2> {
+    let (camera, camera_transform) = camera;
+    camera
+        .viewport_to_world_2d(camera_transform, pointer_position)
+        .ok()
+}
+
+/// Only starts, if no card is dragged and no other drag is going on
+fn on_aircraft_drag_start(
+    mut trigger: Trigger<Pointer<DragStart>>,
+    mut commands: Commands,
+    q_aircraft: Query<&Aircraft>,
+    q_dragged_cards: Query<(), With<BeingDragged>>,
+    camera: Single<(&Camera, &GlobalTransform), With<Camera2d>>,
+    mut control_state: ResMut<ControlState>,
+) {
+    if trigger.event().button != PointerButton::Primary
+        || !q_dragged_cards.is_empty()
+        || matches!(control_state.mode, ControlMode::HeadingDrag { .. })
+    {
+        return;
+    }
+    trigger.propagate(false);
+    let aircraft_entity = trigger.target();
+    if !q_aircraft.contains(aircraft_entity) {
+        return;
+    }
+    let Some(cursor) = cursor_in_world(*camera, trigger.event().pointer_location.position) else {
+        return;
+    };
+    commands.entity(aircraft_entity).insert(HeadingDragStart {
+        previous_mode: control_state.mode.clone(),
+    });
+    control_state.mode = ControlMode::HeadingDrag {
+        aircraft_entity,
+        cursor,
+    };
+}
+
+/// The rubber band shows the heading, until the drag ends
+fn on_aircraft_drag(
+    mut trigger: Trigger<Pointer<Drag>>,
+    q_aircraft: Query<(), (With<Aircraft>, With<HeadingDragStart>)>,
+    camera: Single<(&Camera, &GlobalTransform), With<Camera2d>>,
+    mut control_state: ResMut<ControlState>,
+) {
+    trigger.propagate(false);
+    let aircraft_entity = trigger.target();
+    if !q_aircraft.contains(aircraft_entity) {
+        return;
+    }
+    let Some(cursor) = cursor_in_world(*camera, trigger.event().pointer_location.position) else {
+        return;
+    };
+    control_state.mode = ControlMode::HeadingDrag {
+        aircraft_entity,
+        cursor,
+    };
+}
+
+/// Queued like a typed heading, so that it can be disregarded or undone
+fn on_aircraft_drag_end(
+    mut trigger: Trigger<Pointer<DragEnd>>,
+    mut commands: Commands,
+    q_aircraft: Query<(&Aircraft, &Transform, &HeadingDragStart)>,
+    mut control_state: ResMut<ControlState>,
+) {
+    trigger.propagate(false);
+    let aircraft_entity = trigger.target();
+    let Ok((aircraft, transform, drag_start)) = q_aircraft.get(aircraft_entity) else {
+        return;
+    };
+    commands
+        .entity(aircraft_entity)
+        .remove::<HeadingDragStart>();
+    let ControlMode::HeadingDrag { cursor, .. } =
+        std::mem::replace(&mut control_state.mode, drag_start.previous_mode.clone())
+    else {
+        return;
+    };
+    let Some(heading) = heading_from_drag(transform.translation.truncate(), cursor) else {
+        return;
+    };
+    if aircraft.cleared_heading == Some(heading) {
+        return;
+    }
+    queue_clearance(
+        &mut commands,
+        aircraft_entity,
+        QueuedClearance::untriggered(&AviationCommandPart::FlyHeading {
+            heading: HeadingDirection::Heading(heading),
+            turn_direction: None,
+        }),
+    );
+}
+
+/// The drag end isn't observed anymore, after the aircraft was despawned mid-drag, e.g. after
+/// leaving the sector
+fn end_drag_of_despawned_aircraft(
+    mut control_state: ResMut<ControlState>,
+    q_aircraft: Query<(), With<Aircraft>>,
+) {
+    if let ControlMode::HeadingDrag {
+        aircraft_entity, ..
+    } = control_state.mode
+    {
+        if !q_aircraft.contains(aircraft_entity) {
+            control_state.mode = ControlMode::Normal;
+        }
+    }
+}
+
+fn draw_rubber_band(
+    mut gizmos: Gizmos,
+    control_state: Res<ControlState>,
+    q_aircraft: Query<&Transform, With<Aircraft>>,
+) {
+    let ControlMode::HeadingDrag {
+        aircraft_entity,
+        cursor,
+    } = control_state.mode
+    else {
+        return;
+    };
+    let Ok(transform) = q_aircraft.get(aircraft_entity) else {
+        return;
+    };
+    gizmos.line_2d(transform.translation.truncate(), cursor, RUBBER_BAND_COLOR);
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+
+    use super::{end_drag_of_despawned_aircraft, heading_from_drag};
+    use crate::game::aircraft::test_aircraft;
+    use crate::game::control::{ControlMode, ControlState};
+
+    #[test]
+    fn test_heading_from_drag() {
+        let aircraft = Vec2::new(100., -50.);
+        let heading = |offset: Vec2| heading_from_drag(aircraft, aircraft + offset);
+
+        assert_eq!(heading(Vec2::new(0., 200.)).unwrap().to_string(), "360");
+        assert_eq!(heading(Vec2::new(200., 0.)).unwrap().to_string(), "090");
+        assert_eq!(heading(Vec2::new(0., -200.)).unwrap().to_string(), "180");
+        assert_eq!(heading(Vec2::new(-200., 0.)).unwrap().to_string(), "270");
+        // Rounded to five degrees, 47 degrees right of north
+        let offset = Vec2::from_angle((90f32 - 47.).to_radians()) * 200.;
+        assert_eq!(heading(offset).unwrap().to_string(), "045");
+        // Too close to the aircraft for a direction
+        assert_eq!(heading(Vec2::new(3., 3.)), None);
+    }
+
+    #[test]
+    fn test_drag_ends_when_the_aircraft_despawns() {
+        let mut app = App::new();
+        app.add_systems(Update, end_drag_of_despawned_aircraft);
+        let entity = app.world_mut().spawn(test_aircraft()).id();
+        app.insert_resource(ControlState {
+            mode: ControlMode::HeadingDrag {
+                aircraft_entity: entity,
+                cursor: Vec2::new(100., 0.),
+            },
+        });
+
+        app.update();
+        assert!(matches!(
+            app.world().resource::<ControlState>().mode,
+            ControlMode::HeadingDrag { .. }
+        ));
+
+        app.world_mut().despawn(entity);
+        app.update();
+        assert_eq!(
+            app.world().resource::<ControlState>().mode,
+            ControlMode::Normal
+        );
+    }
+}
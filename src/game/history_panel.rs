@@ -0,0 +1,300 @@
+use atc_recognition_rs::ParseResult;
+use bevy::prelude::*;
+use bevy_simple_scroll_view::{ScrollView, ScrollableContent};
+
+use crate::game::GameState;
+use crate::game::aircraft::Aircraft;
+use crate::game::aircraft_card::{
+    AircraftCard, AircraftCardDisplay, AircraftCardDisplayMaterials, PinnedTo, select_card_display,
+};
+use crate::game::console::{TransmissionParsed, TransmissionSubmitted};
+use crate::game::control::ControlState;
+use crate::game::sequencing::find_aircraft_by_call_sign;
+
+/// Number of transmissions, that are kept in the history
+const HISTORY_MAX_ENTRIES: usize = 50;
+const HISTORY_BACKGROUND_COLOR: Color = Color::srgba(0.05, 0.05, 0.05, 0.8);
+const SUCCESS_COLOR: Color = Color::srgb(0.4, 0.9, 0.4);
+const PARTIAL_COLOR: Color = Color::srgb(0.95, 0.85, 0.3);
+const FAILED_COLOR: Color = Color::srgb(0.95, 0.35, 0.35);
+const UNCONFIRMED_COLOR: Color = Color::srgb(0.4, 0.7, 0.95);
+
+/// Lists the recent transmissions with their parse result, newest first. Clicking an entry
+/// selects the addressed aircraft, and applies an unconfirmed voice transmission.
+pub struct HistoryPanelPlugin;
+
+impl Plugin for HistoryPanelPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::Running), spawn_history_panel)
+            .add_systems(
+                Update,
+                append_history_entries.run_if(in_state(GameState::Running)),
+            );
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseStatus {
+    Success,
+    /// Some words weren't understood, or only the call sign was
+    Partial,
+    Failed,
+    /// Recognized from voice with a low confidence, not applied yet
+    Unconfirmed,
+}
+
+impl ParseStatus {
+    pub fn color(&self) -> Color {
+        match self {
+            ParseStatus::Success => SUCCESS_COLOR,
+            ParseStatus::Partial => PARTIAL_COLOR,
+            ParseStatus::Failed => FAILED_COLOR,
+            ParseStatus::Unconfirmed => UNCONFIRMED_COLOR,
+        }
+    }
+}
+
+/// What is shown for one transmission
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistoryRow {
+    pub text: String,
+    pub status: ParseStatus,
+    /// Call sign of the addressed aircraft, None if it wasn't recognized
+    pub call_sign: Option<String>,
+    /// Each recognized command, e.g. "FlyHeading 95%"
+    pub commands: Vec<String>,
+}
+
+impl HistoryRow {
+    pub fn from_parse_result(text: &str, result: &ParseResult) -> Self {
+        let (status, parsed) = match result {
+            ParseResult::Success(parsed) => (ParseStatus::Success, parsed),
+            ParseResult::PartialSuccess { parsed, .. } => (ParseStatus::Partial, parsed),
+            ParseResult::CallsignOnly(callsign) => {
+                return Self {
+                    text: text.to_owned(),
+                    status: ParseStatus::Partial,
+                    call_sign: Some(callsign.clone()),
+                    commands: Vec::new(),
+                };
+            }
+            ParseResult::Failed { reason, .. } => {
+                return Self {
+                    text: text.to_owned(),
+                    status: ParseStatus::Failed,
+                    call_sign: None,
+                    commands: vec![reason.clone()],
+                };
+            }
+        };
+        Self {
+            text: text.to_owned(),
+            status,
+            call_sign: Some(parsed.callsign.clone()),
+            commands: parsed
+                .commands
+                .iter()
+                .map(|command| {
+                    format!(
+                        "{} {:.0}%",
+                        command.command.name(),
+                        command.confidence * 100.
+                    )
+                })
+                .collect(),
+        }
+    }
+}
+
+/// An entry of the panel
+#[derive(Component, Debug, Clone)]
+pub struct HistoryEntry {
+    pub call_sign: Option<String>,
+    /// Text of an unconfirmed transmission, that is submitted again, when the entry is clicked
+    pub unconfirmed_text: Option<String>,
+}
+
+#[derive(Component)]
+struct HistoryEntries;
+
+fn spawn_history_panel(mut commands: Commands) {
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            left: Val::Px(10.0),
+            top: Val::Px(10.0),
+            width: Val::Px(400.0),
+            height: Val::Px(250.0),
+            overflow: Overflow::clip(),
+            ..default()
+        },
+        ScrollView {
+            scroll_speed: 1000.0,
+        },
+        BackgroundColor(HISTORY_BACKGROUND_COLOR),
+        children![(
+            HistoryEntries,
+            Node {
+                flex_direction: FlexDirection::Column,
+                width: Val::Percent(100.),
+                ..default()
+            },
+            ScrollableContent::default(),
+        )],
+    ));
+}
+
+fn append_history_entries(
+    mut commands: Commands,
+    mut events: EventReader<TransmissionParsed>,
+    history: Single<(Entity, Option<&Children>), With<HistoryEntries>>,
+) {
+    let (history, children) = *history;
+    let mut entries: Vec<Entity> = children.map(|c| c.to_vec()).unwrap_or_default();
+    for TransmissionParsed {
+        text,
+        result,
+        needs_confirmation,
+    } in events.read()
+    {
+        let mut row = HistoryRow::from_parse_result(text, result);
+        if *needs_confirmation {
+            row.status = ParseStatus::Unconfirmed;
+        }
+        let color = row.status.color();
+        let entry = commands
+            .spawn((
+                HistoryEntry {
+                    call_sign: row.call_sign,
+                    unconfirmed_text: needs_confirmation.then(|| text.clone()),
+                },
+                Node {
+                    flex_direction: FlexDirection::Column,
+                    padding: UiRect::all(Val::Px(4.0)),
+                    ..default()
+                },
+                children![
+                    (
+                        Text(format!("> {}", row.text)),
+                        TextFont::from_font_size(14.0),
+                        TextColor(color),
+                        Pickable::IGNORE,
+                    ),
+                    (
+                        Text(row.commands.join(", ")),
+                        TextFont::from_font_size(12.0),
+                        TextColor(color),
+                        Pickable::IGNORE,
+                    ),
+                ],
+            ))
+            .observe(on_history_entry_click)
+            .id();
+        commands.entity(history).insert_children(0, &[entry]);
+        entries.insert(0, entry);
+    }
+    for entry in entries.drain(HISTORY_MAX_ENTRIES.min(entries.len())..) {
+        commands.entity(entry).despawn();
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn on_history_entry_click(
+    trigger: Trigger<Pointer<Click>>,
+    mut q_entry: Query<&mut HistoryEntry>,
+    q_aircraft: Query<(Entity, &Aircraft)>,
+    q_card: Query<(&PinnedTo, &Children), With<AircraftCard>>,
+    mut q_display: Query<(&AircraftCardDisplay, &mut MeshMaterial2d<ColorMaterial>)>,
+    card_materials: Res<AircraftCardDisplayMaterials>,
+    mut control_state: ResMut<ControlState>,
+    mut submitted_writer: EventWriter<TransmissionSubmitted>,
+) {
+    if trigger.event().button != PointerButton::Primary {
+        return;
+    }
+    let Ok(mut entry) = q_entry.get_mut(trigger.target()) else {
+        return;
+    };
+    // Confirmed by the controller, as if it was typed
+    if let Some(text) = entry.unconfirmed_text.take() {
+        submitted_writer.write(TransmissionSubmitted {
+            text,
+            from_voice: false,
+        });
+    }
+    let Some(call_sign) = entry.call_sign.as_ref() else {
+        return;
+    };
+    let call_signs = q_aircraft
+        .iter()
+        .map(|(entity, aircraft)| (entity, aircraft.call_sign.as_str()));
+    let Some(aircraft_entity) = find_aircraft_by_call_sign(call_signs, call_sign) else {
+        return;
+    };
+    select_card_display(
+        aircraft_entity,
+        AircraftCardDisplay::ClearedHeading,
+        &q_card,
+        &mut q_display,
+        &card_materials,
+        &mut control_state,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use atc_recognition_rs::{CommandWithConfidence, ParseResult, ParsedCommand};
+    use aviation_helper_rs::clearance::aviation_command::{AviationCommandPart, HeadingDirection};
+    use aviation_helper_rs::types::heading::Heading;
+
+    use super::{HistoryRow, ParseStatus};
+
+    #[test]
+    fn test_history_row_from_parse_result() {
+        let parsed = ParsedCommand {
+            callsign: "DLH123".to_owned(),
+            display_callsign: "Lufthansa 123".to_owned(),
+            callsign_confidence: 1.0,
+            commands: vec![CommandWithConfidence {
+                command: AviationCommandPart::FlyHeading {
+                    heading: HeadingDirection::Heading(Heading::from(270.)),
+                    turn_direction: None,
+                },
+                confidence: 0.95,
+                source_text: "fly heading 270".to_owned(),
+                when_able: false,
+            }],
+            weight_suffix: None,
+            has_flight_number: true,
+        };
+        let row = |result: &ParseResult| HistoryRow::from_parse_result("text", result);
+
+        assert_eq!(
+            row(&ParseResult::Success(parsed.clone())),
+            HistoryRow {
+                text: "text".to_owned(),
+                status: ParseStatus::Success,
+                call_sign: Some("DLH123".to_owned()),
+                commands: vec!["FlyHeading 95%".to_owned()],
+            }
+        );
+        let partial = row(&ParseResult::PartialSuccess {
+            parsed,
+            unparsed_parts: vec!["blah".to_owned()],
+        });
+        assert_eq!(partial.status, ParseStatus::Partial);
+        assert_eq!(partial.commands, vec!["FlyHeading 95%".to_owned()]);
+
+        let callsign_only = row(&ParseResult::CallsignOnly("DLH123".to_owned()));
+        assert_eq!(callsign_only.status, ParseStatus::Partial);
+        assert_eq!(callsign_only.call_sign, Some("DLH123".to_owned()));
+
+        let failed = row(&ParseResult::Failed {
+            reason: "No callsign".to_owned(),
+            raw_text: "text".to_owned(),
+        });
+        assert_eq!(failed.status, ParseStatus::Failed);
+        assert_eq!(failed.call_sign, None);
+        assert_ne!(failed.status.color(), ParseStatus::Success.color());
+    }
+}
@@ -0,0 +1,289 @@
+use aviation_helper_rs::clearance::aviation_command::AviationCommandPart;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::game::GameState;
+use crate::game::aircraft::Aircraft;
+use crate::game::approach::{IlsIntercept, VisualApproach};
+use crate::game::level::Runway;
+use crate::util::units::pixels_to_nm;
+
+/// Distance from the threshold, at which an aircraft touches down
+const TOUCHDOWN_RADIUS_NM: f64 = 0.5;
+/// Maximum height above the runway, at which an aircraft touches down
+const TOUCHDOWN_MAX_HEIGHT_FEET: f64 = 300.;
+/// Maximum difference between aircraft heading and runway heading for a touchdown
+const TOUCHDOWN_MAX_HEADING_DIFFERENCE_DEGREES: f64 = 30.;
+/// Height above the runway, to which aircraft climb after a missed approach or a touch and go
+const GO_AROUND_HEIGHT_FEET: f64 = 3000.;
+
+pub struct LandingPlugin;
+
+impl Plugin for LandingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<Landed>()
+            .add_event::<MissedApproach>()
+            .add_systems(
+                FixedUpdate,
+                check_runway_thresholds.run_if(in_state(GameState::Running)),
+            )
+            .add_systems(
+                Update,
+                log_landing_events.run_if(in_state(GameState::Running)),
+            );
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LandingClearanceKind {
+    /// Cleared to land
+    FullStop,
+    /// Cleared for the option. The game treats it as a touch and go.
+    Option,
+}
+
+/// Permission to land on a runway. Without it, reaching the threshold leads to a missed approach.
+#[derive(Component, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LandingClearance {
+    pub runway: String,
+    pub kind: LandingClearanceKind,
+}
+
+impl LandingClearance {
+    pub fn from_command(command: &AviationCommandPart) -> Option<Self> {
+        match command {
+            AviationCommandPart::ClearedToLand { runway } => Some(Self {
+                runway: runway.clone(),
+                kind: LandingClearanceKind::FullStop,
+            }),
+            AviationCommandPart::ClearedForTheOption { runway } => Some(Self {
+                runway: runway.clone(),
+                kind: LandingClearanceKind::Option,
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Aircraft, that is climbing out after a missed approach or a touch and go.
+/// It is ignored by the runway until it left the touchdown area.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct ClimbingOut;
+
+#[derive(Event, Debug, Clone)]
+pub struct Landed {
+    pub entity: Entity,
+    pub runway: String,
+}
+
+#[derive(Event, Debug, Clone)]
+pub struct MissedApproach {
+    pub entity: Entity,
+    pub runway: String,
+}
+
+fn check_runway_thresholds(
+    mut commands: Commands,
+    q_aircraft: Query<(
+        Entity,
+        &mut Aircraft,
+        &Transform,
+        Option<&LandingClearance>,
+        Has<ClimbingOut>,
+    )>,
+    q_runways: Query<&Runway>,
+    mut landed_writer: EventWriter<Landed>,
+    mut missed_approach_writer: EventWriter<MissedApproach>,
+) {
+    for (entity, mut aircraft, transform, clearance, climbing_out) in q_aircraft {
+        let position = transform.translation.truncate();
+        let runway = q_runways
+            .iter()
+            .find(|runway| is_touching_down(&aircraft, position, runway));
+
+        let runway = match (runway, climbing_out) {
+            (None, true) => {
+                commands.entity(entity).remove::<ClimbingOut>();
+                continue;
+            }
+            (None, false) | (Some(_), true) => continue,
+            (Some(runway), false) => runway,
+        };
+
+        let clearance_kind = clearance
+            .filter(|clearance| clearance.runway == runway.name)
+            .map(|clearance| clearance.kind);
+        match clearance_kind {
+            Some(LandingClearanceKind::FullStop) => {
+                landed_writer.write(Landed {
+                    entity,
+                    runway: runway.name.clone(),
+                });
+                commands.entity(entity).despawn();
+            }
+            Some(LandingClearanceKind::Option) => {
+                landed_writer.write(Landed {
+                    entity,
+                    runway: runway.name.clone(),
+                });
+                go_around(&mut aircraft, runway);
+                commands
+                    .entity(entity)
+                    .remove::<(LandingClearance, IlsIntercept, VisualApproach)>()
+                    .insert(ClimbingOut);
+            }
+            None => {
+                missed_approach_writer.write(MissedApproach {
+                    entity,
+                    runway: runway.name.clone(),
+                });
+                go_around(&mut aircraft, runway);
+                commands
+                    .entity(entity)
+                    .remove::<(LandingClearance, IlsIntercept, VisualApproach)>()
+                    .insert(ClimbingOut);
+            }
+        }
+    }
+}
+
+fn log_landing_events(
+    mut landed_reader: EventReader<Landed>,
+    mut missed_approach_reader: EventReader<MissedApproach>,
+) {
+    for Landed { entity, runway } in landed_reader.read() {
+        info!("Aircraft {entity} landed on runway {runway}");
+    }
+    for MissedApproach { entity, runway } in missed_approach_reader.read() {
+        info!("Aircraft {entity} missed the approach to runway {runway}");
+    }
+}
+
+pub fn is_touching_down(aircraft: &Aircraft, position: Vec2, runway: &Runway) -> bool {
+    let distance_nm = pixels_to_nm(position.distance(runway.threshold));
+    let height_feet = aircraft.altitude_feet - runway.elevation_feet;
+    let heading_difference = aircraft.heading - runway.heading();
+    distance_nm <= TOUCHDOWN_RADIUS_NM
+        && height_feet <= TOUCHDOWN_MAX_HEIGHT_FEET
+        && heading_difference.min(360. - heading_difference)
+            <= TOUCHDOWN_MAX_HEADING_DIFFERENCE_DEGREES
+}
+
+/// Climb out on runway heading. The caller removes the approaches, that would steer the aircraft
+/// back to the threshold.
+fn go_around(aircraft: &mut Aircraft, runway: &Runway) {
+    aircraft.cleared_heading = Some(runway.heading());
+    aircraft.cleared_heading_change_direction = None;
+    aircraft.cleared_altitude_feet = Some(runway.elevation_feet + GO_AROUND_HEIGHT_FEET);
+    aircraft.cleared_altitude_block_feet = None;
+}
+
+#[cfg(test)]
+mod tests {
+    use aviation_helper_rs::types::heading::Heading;
+    use bevy::prelude::*;
+
+    use super::{
+        Landed, LandingClearance, LandingClearanceKind, MissedApproach, check_runway_thresholds,
+    };
+    use crate::game::aircraft::{Aircraft, test_aircraft};
+    use crate::game::approach::{
+        IlsIntercept, VisualApproach, update_ils_intercepts, update_visual_approaches,
+    };
+    use crate::game::level::Runway;
+
+    fn setup_app() -> App {
+        let mut app = App::new();
+        app.add_event::<Landed>()
+            .add_event::<MissedApproach>()
+            .add_systems(Update, check_runway_thresholds);
+        app.world_mut().spawn(Runway {
+            name: "09".to_owned(),
+            threshold: Vec2::ZERO,
+            end: Vec2::new(100., 0.),
+            elevation_feet: 500.,
+        });
+        app
+    }
+
+    fn landing_aircraft() -> (Aircraft, Transform) {
+        let aircraft = Aircraft {
+            wanted_altitude_feet: 500.,
+            wanted_speed_knots: 140.,
+            altitude_feet: 600.,
+            altitude_change_feet_per_second: -10.,
+            speed_knots: 140.,
+            ..test_aircraft()
+        };
+        (aircraft, Transform::from_xyz(-5., 0., 0.))
+    }
+
+    #[test]
+    fn test_landing_without_clearance_is_missed_approach() {
+        let mut app = setup_app();
+        let entity = app.world_mut().spawn(landing_aircraft()).id();
+
+        app.update();
+
+        assert!(app.world().resource::<Events<Landed>>().is_empty());
+        assert_eq!(app.world().resource::<Events<MissedApproach>>().len(), 1);
+        let aircraft = app.world().get::<Aircraft>(entity).unwrap();
+        assert_eq!(aircraft.cleared_altitude_feet, Some(3500.));
+    }
+
+    #[test]
+    fn test_go_around_ends_the_approach() {
+        let mut app = setup_app();
+        app.add_systems(
+            Update,
+            (update_ils_intercepts, update_visual_approaches).after(check_runway_thresholds),
+        );
+        let entity = app
+            .world_mut()
+            .spawn((
+                landing_aircraft(),
+                IlsIntercept {
+                    runway: "09".to_owned(),
+                },
+                VisualApproach {
+                    runway: "09".to_owned(),
+                },
+            ))
+            .id();
+
+        app.update();
+        assert_eq!(app.world().resource::<Events<MissedApproach>>().len(), 1);
+        // One more tick, in which the approach would steer the aircraft back to the threshold
+        app.update();
+
+        let aircraft = app.world().get::<Aircraft>(entity).unwrap();
+        assert_eq!(
+            aircraft.cleared_heading,
+            Some(Heading::from_bevy_rotation(0.))
+        );
+        assert_eq!(aircraft.cleared_altitude_feet, Some(3500.));
+        assert!(app.world().get::<IlsIntercept>(entity).is_none());
+        assert!(app.world().get::<VisualApproach>(entity).is_none());
+    }
+
+    #[test]
+    fn test_landing_with_clearance() {
+        let mut app = setup_app();
+        let entity = app
+            .world_mut()
+            .spawn((
+                landing_aircraft(),
+                LandingClearance {
+                    runway: "09".to_owned(),
+                    kind: LandingClearanceKind::FullStop,
+                },
+            ))
+            .id();
+
+        app.update();
+
+        assert_eq!(app.world().resource::<Events<Landed>>().len(), 1);
+        assert!(app.world().resource::<Events<MissedApproach>>().is_empty());
+        assert!(app.world().get_entity(entity).is_err());
+    }
+}
@@ -1,10 +1,16 @@
 use crate::{
     APP_CONFIG,
     game::{
-        GameState, Z_WAYPOINT,
+        GameState, GameVariables, Z_WAYPOINT,
         loading::{PendingLoadingPlugins, PluginLoadingFinishedEvent},
+        radar_coverage::RadarCoverage,
+        separation::SeparationMinima,
+        wind::{Metar, Wind},
     },
+    util::geo::{LatLon, MapProjection, Mercator},
 };
+use aviation_helper_rs::metar::MetarData;
+use aviation_helper_rs::types::heading::Heading;
 use bevy::{dev_tools::states::log_transitions, prelude::*};
 use bevy_common_assets::ron::RonAssetPlugin;
 use serde::Deserialize;
@@ -25,6 +31,7 @@ pub struct LevelPlugin;
 impl Plugin for LevelPlugin {
     fn build(&self, app: &mut App) {
         app.add_plugins(RonAssetPlugin::<LevelFile>::new(&["ron"]))
+            .init_resource::<MapProjection>()
             .add_systems(Startup, on_startup)
             .add_systems(OnEnter(GameState::Loading), setup)
             .add_systems(
@@ -34,6 +41,13 @@ impl Plugin for LevelPlugin {
             .add_systems(OnEnter(LoadingState::SpawningLevel), spawn_level)
             .init_state::<LoadingState>();
 
+        if APP_CONFIG.dev_gui {
+            app.add_systems(
+                Update,
+                respawn_modified_level.run_if(in_state(GameState::Running)),
+            );
+        }
+
         if APP_CONFIG.log_state_transitions {
             app.add_systems(Update, (log_transitions::<LoadingState>,));
         }
@@ -44,8 +58,8 @@ fn on_startup(mut pending: ResMut<PendingLoadingPlugins>) {
     pending.register_plugin(LEVEL_PLUGIN);
 }
 
-fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
-    let level_handle = asset_server.load::<LevelFile>("levels/example_level.ron");
+fn setup(mut commands: Commands, asset_server: Res<AssetServer>, variables: Res<GameVariables>) {
+    let level_handle = asset_server.load::<LevelFile>(format!("levels/{}", variables.level.file));
     commands.insert_resource(LevelHandle(level_handle));
     commands.set_state(LoadingState::LoadingHandles);
 }
@@ -67,20 +81,72 @@ fn spawn_level(
     mut materials: ResMut<Assets<ColorMaterial>>,
     level_assets: Res<Assets<LevelFile>>,
     level_handle: Res<LevelHandle>,
+    mut variables: ResMut<GameVariables>,
     mut next_loading_state: ResMut<NextState<LoadingState>>,
     mut event_writer: EventWriter<PluginLoadingFinishedEvent>,
 ) {
     let Some(level) = level_assets.get(&level_handle.0) else {
         unreachable!("Level asset not found!");
     };
+    spawn_level_entities(&mut commands, &mut meshes, &mut materials, level);
+    variables.separation = level.separation.unwrap_or_default();
+    next_loading_state.set(LoadingState::Finished);
+    event_writer.write(PluginLoadingFinishedEvent {
+        plugin: LEVEL_PLUGIN,
+    });
+}
+
+/// Replaces waypoints, runways and sectors, when the level file was edited while the game is running
+fn respawn_modified_level(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut events: EventReader<AssetEvent<LevelFile>>,
+    level_assets: Res<Assets<LevelFile>>,
+    level_handle: Res<LevelHandle>,
+    mut variables: ResMut<GameVariables>,
+    q_level_entities: Query<Entity, Or<(With<Waypoint>, With<Runway>, With<Sector>)>>,
+) {
+    let modified = events
+        .read()
+        .any(|event| event.is_modified(level_handle.0.id()));
+    if !modified {
+        return;
+    }
+    let Some(level) = level_assets.get(&level_handle.0) else {
+        return;
+    };
+    info!("Level file was modified, respawning waypoints, runways and sectors");
+    for entity in &q_level_entities {
+        commands.entity(entity).despawn();
+    }
+    spawn_level_entities(&mut commands, &mut meshes, &mut materials, level);
+    variables.separation = level.separation.unwrap_or_default();
+}
+
+fn spawn_level_entities(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<ColorMaterial>,
+    level: &LevelFile,
+) {
+    let projection = match level.projection_origin {
+        Some(origin) => MapProjection::Mercator(Mercator { origin }),
+        None => MapProjection::Planar,
+    };
+    commands.insert_resource(projection);
+    for problem in level.projection_problems() {
+        warn!("{problem}");
+    }
     for wp in &level.waypoints {
+        let pos = projection.world_position(wp.lat_lon, wp.pos);
         commands.spawn((
             Waypoint {
                 name: wp.name.clone(),
             },
             Mesh2d(meshes.add(Circle { radius: 10.0 })),
             MeshMaterial2d(materials.add(Color::srgb(0.5, 0.5, 0.5))),
-            Transform::from_xyz(wp.pos.x, wp.pos.y, Z_WAYPOINT),
+            Transform::from_xyz(pos.x, pos.y, Z_WAYPOINT),
             Name::new(wp.name.clone()),
             children![(
                 Text2d(wp.name.clone()),
@@ -95,17 +161,25 @@ fn spawn_level(
         ));
     }
     for rw in &level.runways {
-        let dir = rw.end - rw.start;
+        let start = projection.world_position(rw.start_lat_lon, rw.start);
+        let end = projection.world_position(rw.end_lat_lon, rw.end);
+        let dir = end - start;
         let length = dir.length();
         let angle = -dir.angle_to(Vec2::X);
         commands.spawn((
+            Runway {
+                name: rw.name.clone(),
+                threshold: start,
+                end,
+                elevation_feet: rw.elevation as f64,
+            },
             Mesh2d(meshes.add(Rectangle {
                 half_size: Vec2::new(length / 2.0, 5.0),
             })),
             MeshMaterial2d(materials.add(Color::srgb(0.3, 0.3, 0.3))),
             Transform::from_xyz(
-                (rw.start.x + rw.end.x) / 2.0,
-                (rw.start.y + rw.end.y) / 2.0,
+                (start.x + end.x) / 2.0,
+                (start.y + end.y) / 2.0,
                 crate::game::Z_RUNWAY,
             )
             .with_rotation(Quat::from_rotation_z(angle)),
@@ -113,11 +187,33 @@ fn spawn_level(
             Visibility::Visible,
         ));
     }
-    commands.remove_resource::<LevelHandle>();
-    next_loading_state.set(LoadingState::Finished);
-    event_writer.write(PluginLoadingFinishedEvent {
-        plugin: LEVEL_PLUGIN,
+    let wind = match (&level.metar, &level.wind) {
+        (Some(metar), _) => Wind::from_metar(metar),
+        (None, Some(wind)) => Wind {
+            direction_degrees: wind.direction as f64,
+            speed_knots: wind.speed as f64,
+        },
+        (None, None) => Wind::default(),
+    };
+    commands.insert_resource(wind);
+    match level.metar {
+        Some(metar) => commands.insert_resource(Metar(metar)),
+        None => commands.remove_resource::<Metar>(),
+    }
+    commands.insert_resource(RadarCoverage {
+        boundary: level.radar_coverage.iter().map(|point| point.pos).collect(),
     });
+    for sector in &level.sectors {
+        commands.spawn((
+            Sector {
+                name: sector.name.clone(),
+                min_altitude_feet: sector.min_altitude as f64,
+                max_altitude_feet: sector.max_altitude as f64,
+                boundary: sector.boundary.iter().map(|point| point.pos).collect(),
+            },
+            Name::new(format!("Sector {}", sector.name)),
+        ));
+    }
 }
 
 #[derive(Resource, Debug, Clone)]
@@ -127,23 +223,205 @@ struct LevelHandle(Handle<LevelFile>);
 pub struct LevelFile {
     pub waypoints: Vec<WaypointData>,
     pub runways: Vec<RunwayData>,
+    #[serde(default)]
+    pub sectors: Vec<SectorData>,
+    /// Calm, if missing
+    #[serde(default)]
+    pub wind: Option<WindData>,
+    /// Weather of the level. Its wind replaces `wind`.
+    #[serde(default)]
+    #[reflect(ignore)]
+    pub metar: Option<MetarData>,
+    /// Terminal minima below and en-route minima above the transition altitude, if missing
+    #[serde(default)]
+    pub separation: Option<SeparationMinima>,
+    /// Area, that the radar sees. Everywhere, if empty.
+    #[serde(default)]
+    pub radar_coverage: Vec<BoundaryPointData>,
+    /// Geographic positions are projected with Mercator around this origin, and distances are
+    /// measured on the sphere. Planar, if missing.
+    #[serde(default)]
+    pub projection_origin: Option<LatLon>,
+}
+
+impl LevelFile {
+    /// Positions, that fall back to their world position, because a geographic position or
+    /// the projection is missing
+    fn projection_problems(&self) -> Vec<String> {
+        let waypoints = self
+            .waypoints
+            .iter()
+            .map(|wp| (format!("Waypoint {}", wp.name), vec![wp.lat_lon]));
+        let runways = self.runways.iter().map(|rw| {
+            let lat_lons = vec![rw.start_lat_lon, rw.end_lat_lon];
+            (format!("Runway {}", rw.name), lat_lons)
+        });
+        waypoints
+            .chain(runways)
+            .filter_map(|(name, lat_lons)| {
+                if self.projection_origin.is_none() && lat_lons.iter().any(Option::is_some) {
+                    Some(format!(
+                        "{name} has a lat_lon, but the level has no projection_origin, using its pos"
+                    ))
+                } else if self.projection_origin.is_some() && lat_lons.iter().any(Option::is_none)
+                {
+                    Some(format!(
+                        "{name} lacks a lat_lon, but the level has a projection_origin, using its pos"
+                    ))
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+}
+
+#[derive(Deserialize, Clone, Debug, Reflect)]
+pub struct WindData {
+    /// Direction, from which the wind blows
+    pub direction: f32,
+    pub speed: f32,
 }
 
 #[derive(Deserialize, Clone, Debug, Reflect)]
 pub struct WaypointData {
     pub name: String,
+    #[serde(default)]
     pub pos: Vec2,
+    /// Replaces `pos`, if the level has a projection
+    #[serde(default)]
+    pub lat_lon: Option<LatLon>,
 }
 
 #[derive(Deserialize, Clone, Debug, Reflect)]
 pub struct RunwayData {
     pub name: String,
+    #[serde(default)]
     pub start: Vec2,
+    #[serde(default)]
     pub end: Vec2,
+    /// Replace `start` and `end`, if the level has a projection
+    #[serde(default)]
+    pub start_lat_lon: Option<LatLon>,
+    #[serde(default)]
+    pub end_lat_lon: Option<LatLon>,
     pub elevation: f32,
 }
 
+#[derive(Deserialize, Clone, Debug, Reflect)]
+pub struct SectorData {
+    pub name: String,
+    pub min_altitude: f32,
+    pub max_altitude: f32,
+    pub boundary: Vec<BoundaryPointData>,
+}
+
+#[derive(Deserialize, Clone, Debug, Reflect)]
+pub struct BoundaryPointData {
+    pub pos: Vec2,
+}
+
 #[derive(Component, Clone, Debug)]
 pub struct Waypoint {
     pub name: String,
 }
+
+#[derive(Component, Clone, Debug)]
+pub struct Runway {
+    pub name: String,
+    pub threshold: Vec2,
+    pub end: Vec2,
+    pub elevation_feet: f64,
+}
+
+impl Runway {
+    /// Heading when landing from the threshold towards the end
+    pub fn heading(&self) -> Heading {
+        Heading::from_bevy_rotation((self.end - self.threshold).to_angle() as f64)
+    }
+}
+
+/// Airspace controlled by the player
+#[derive(Component, Clone, Debug)]
+pub struct Sector {
+    pub name: String,
+    pub min_altitude_feet: f64,
+    pub max_altitude_feet: f64,
+    /// Corners of the lateral boundary
+    pub boundary: Vec<Vec2>,
+}
+
+impl Sector {
+    pub fn contains(&self, position: Vec2, altitude_feet: f64) -> bool {
+        (self.min_altitude_feet..=self.max_altitude_feet).contains(&altitude_feet)
+            && is_inside_polygon(position, &self.boundary)
+    }
+}
+
+/// Even-odd rule: a ray from the point crosses the edges of the polygon an odd number of times,
+/// if the point is inside
+pub fn is_inside_polygon(point: Vec2, polygon: &[Vec2]) -> bool {
+    let mut inside = false;
+    for (i, a) in polygon.iter().enumerate() {
+        let b = polygon[(i + 1) % polygon.len()];
+        if (a.y > point.y) != (b.y > point.y)
+            && point.x < a.x + (point.y - a.y) / (b.y - a.y) * (b.x - a.x)
+        {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::math::Vec2;
+
+    use super::{LevelFile, RunwayData, WaypointData};
+    use crate::util::geo::LatLon;
+
+    fn level(projection_origin: Option<LatLon>) -> LevelFile {
+        let lat_lon = LatLon { lat: 50., lon: 8. };
+        LevelFile {
+            waypoints: vec![
+                WaypointData {
+                    name: "PLANAR".to_string(),
+                    pos: Vec2::ZERO,
+                    lat_lon: None,
+                },
+                WaypointData {
+                    name: "GEO".to_string(),
+                    pos: Vec2::ZERO,
+                    lat_lon: Some(lat_lon),
+                },
+            ],
+            runways: vec![RunwayData {
+                name: "25".to_string(),
+                start: Vec2::ZERO,
+                end: Vec2::X,
+                start_lat_lon: Some(lat_lon),
+                end_lat_lon: None,
+                elevation: 0.,
+            }],
+            sectors: Vec::new(),
+            wind: None,
+            metar: None,
+            separation: None,
+            radar_coverage: Vec::new(),
+            projection_origin,
+        }
+    }
+
+    #[test]
+    fn test_projection_problems_name_the_positions_falling_back() {
+        let problems = level(None).projection_problems();
+        assert_eq!(problems.len(), 2, "{problems:?}");
+        assert!(problems[0].starts_with("Waypoint GEO has a lat_lon"));
+        assert!(problems[1].starts_with("Runway 25 has a lat_lon"));
+
+        let problems = level(Some(LatLon { lat: 50., lon: 8. })).projection_problems();
+        assert_eq!(problems.len(), 2, "{problems:?}");
+        assert!(problems[0].starts_with("Waypoint PLANAR lacks a lat_lon"));
+        assert!(problems[1].starts_with("Runway 25 lacks a lat_lon"));
+    }
+}
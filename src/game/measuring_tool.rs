@@ -0,0 +1,185 @@
+use aviation_helper_rs::types::heading::Heading;
+use bevy::input::common_conditions::input_just_pressed;
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+
+use crate::game::GameState;
+use crate::util::geo::MapProjection;
+
+const MEASURING_LINE_COLOR: Color = Color::srgb(0.9, 0.9, 0.3);
+const MEASURING_LABEL_Z: f32 = 10.;
+
+/// M starts measuring. The first click sets the start, the second one the end of the
+/// measurement, whose bearing and range are shown next to the line. M again ends measuring.
+pub struct MeasuringToolPlugin;
+
+impl Plugin for MeasuringToolPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MeasuringTool>()
+            .add_systems(OnEnter(GameState::Running), spawn_measurement_label)
+            .add_systems(
+                Update,
+                (
+                    toggle_measuring_tool.run_if(input_just_pressed(KeyCode::KeyM)),
+                    place_measuring_point.run_if(input_just_pressed(MouseButton::Left)),
+                    draw_measurement,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Running)),
+            );
+    }
+}
+
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq)]
+pub enum MeasuringTool {
+    #[default]
+    Inactive,
+    AwaitingStart,
+    /// Measuring from the point to the cursor
+    AwaitingEnd(Vec2),
+    Measured {
+        from: Vec2,
+        to: Vec2,
+    },
+}
+
+impl MeasuringTool {
+    /// State after a click on the point
+    fn place(self, point: Vec2) -> Self {
+        match self {
+            MeasuringTool::Inactive => MeasuringTool::Inactive,
+            MeasuringTool::AwaitingStart | MeasuringTool::Measured { .. } => {
+                MeasuringTool::AwaitingEnd(point)
+            }
+            MeasuringTool::AwaitingEnd(from) => MeasuringTool::Measured { from, to: point },
+        }
+    }
+}
+
+#[derive(Component)]
+struct MeasurementLabel;
+
+/// Bearing from one point to the other and the range between them in NM, on the sphere, if
+/// the level has a geographic projection
+pub fn bearing_and_range(projection: &MapProjection, from: Vec2, to: Vec2) -> (Heading, f64) {
+    (
+        projection.bearing(from, to),
+        projection.distance_nm(from, to),
+    )
+}
+
+fn spawn_measurement_label(mut commands: Commands) {
+    commands.spawn((
+        MeasurementLabel,
+        Text2d::default(),
+        TextFont::from_font_size(20.0),
+        TextColor(MEASURING_LINE_COLOR),
+        Transform::from_xyz(0., 0., MEASURING_LABEL_Z),
+        Visibility::Hidden,
+        Name::new("Measurement label"),
+    ));
+}
+
+fn toggle_measuring_tool(mut tool: ResMut<MeasuringTool>) {
+    *tool = match *tool {
+        MeasuringTool::Inactive => MeasuringTool::AwaitingStart,
+        _ => MeasuringTool::Inactive,
+    };
+}
+
+fn cursor_in_world(
+    window: &Window,
+    (camera, camera_transform): (&Camera, &GlobalTransform),
+) -> Option<Vec2> {
+    let cursor = window.cursor_position()?;
+    camera.viewport_to_world_2d(camera_transform, cursor).ok()
+}
+
+fn place_measuring_point(
+    mut tool: ResMut<MeasuringTool>,
+    window: Single<&Window, With<PrimaryWindow>>,
+    camera: Single<(&Camera, &GlobalTransform), With<Camera2d>>,
+) {
+    if *tool == MeasuringTool::Inactive {
+        return;
+    }
+    if let Some(point) = cursor_in_world(&window, *camera) {
+        *tool = tool.place(point);
+    }
+}
+
+fn draw_measurement(
+    mut gizmos: Gizmos,
+    tool: Res<MeasuringTool>,
+    projection: Res<MapProjection>,
+    window: Single<&Window, With<PrimaryWindow>>,
+    camera: Single<(&Camera, &GlobalTransform), With<Camera2d>>,
+    label: Single<(&mut Text2d, &mut Transform, &mut Visibility), With<MeasurementLabel>>,
+) {
+    let (mut text, mut transform, mut visibility) = label.into_inner();
+    let line = match *tool {
+        MeasuringTool::AwaitingEnd(from) => {
+            cursor_in_world(&window, *camera).map(|cursor| (from, cursor))
+        }
+        MeasuringTool::Measured { from, to } => Some((from, to)),
+        MeasuringTool::Inactive | MeasuringTool::AwaitingStart => None,
+    };
+    let Some((from, to)) = line else {
+        *visibility = Visibility::Hidden;
+        return;
+    };
+    gizmos.line_2d(from, to, MEASURING_LINE_COLOR);
+    let (bearing, range_nm) = bearing_and_range(&projection, from, to);
+    // Rounded, the display of headings cuts off the decimals
+    let bearing = Heading::from(bearing.get().round());
+    text.0 = format!("{bearing} / {range_nm:.1} NM");
+    transform.translation = ((from + to) / 2.).extend(MEASURING_LABEL_Z);
+    *visibility = Visibility::Visible;
+}
+
+#[cfg(test)]
+mod tests {
+    use aviation_helper_rs::types::heading::Heading;
+    use bevy::prelude::*;
+
+    use super::{MeasuringTool, bearing_and_range};
+    use crate::util::geo::MapProjection;
+    use crate::util::units::nm_to_pixels;
+
+    #[test]
+    fn test_bearing_and_range() {
+        let assert_measured = |from: Vec2, to: Vec2, bearing_degrees: f64, range_nm: f64| {
+            let (bearing, range) = bearing_and_range(&MapProjection::Planar, from, to);
+            let difference = bearing - Heading::from(bearing_degrees);
+            assert!(
+                difference.min(360. - difference) < 0.01,
+                "Bearing {bearing:?} from {from} to {to}"
+            );
+            assert!(
+                (range - range_nm).abs() < 1e-4,
+                "Range {range} from {from} to {to}"
+            );
+        };
+        assert_measured(Vec2::ZERO, Vec2::new(0., nm_to_pixels(3.)), 360., 3.);
+        assert_measured(
+            Vec2::new(-nm_to_pixels(2.), 20.),
+            Vec2::new(nm_to_pixels(8.), 20.),
+            90.,
+            10.,
+        );
+        // 3-4-5 triangle towards south-west
+        assert_measured(
+            Vec2::new(nm_to_pixels(6.), nm_to_pixels(8.)),
+            Vec2::ZERO,
+            216.87,
+            10.,
+        );
+
+        // A third click starts a new measurement
+        let tool = MeasuringTool::AwaitingStart
+            .place(Vec2::ZERO)
+            .place(Vec2::X)
+            .place(Vec2::Y);
+        assert_eq!(tool, MeasuringTool::AwaitingEnd(Vec2::Y));
+    }
+}
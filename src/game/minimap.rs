@@ -0,0 +1,212 @@
+use bevy::platform::collections::hash_map::HashMap;
+use bevy::prelude::*;
+
+use crate::game::GameState;
+use crate::game::aircraft::Aircraft;
+use crate::game::level::{Runway, Waypoint};
+
+const MINIMAP_DOT_SIZE: f32 = 4.0;
+const MINIMAP_BACKGROUND_COLOR: Color = Color::srgba(0.05, 0.05, 0.05, 0.8);
+const MINIMAP_VIEWPORT_COLOR: Color = Color::srgba(0.9, 0.9, 0.9, 0.8);
+const MINIMAP_AIRCRAFT_COLOR: Color = Color::srgb(0.0, 0.8, 0.6);
+const MINIMAP_WAYPOINT_COLOR: Color = Color::srgb(0.5, 0.5, 0.5);
+const MINIMAP_RUNWAY_COLOR: Color = Color::srgb(0.8, 0.8, 0.8);
+
+pub struct MinimapPlugin;
+
+impl Plugin for MinimapPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MinimapConfig>()
+            .add_systems(OnEnter(GameState::Running), spawn_minimap)
+            .add_systems(
+                Update,
+                (
+                    update_minimap_layout,
+                    sync_minimap_dots,
+                    update_minimap_viewport,
+                )
+                    .run_if(in_state(GameState::Running)),
+            );
+    }
+}
+
+/// Size and position of the minimap, and the world area it shows
+#[derive(Resource, Debug, Clone)]
+pub struct MinimapConfig {
+    /// Size of the minimap in logical pixels
+    pub size: Vec2,
+    /// Distance from the bottom right corner of the window in logical pixels
+    pub margin: Vec2,
+    /// The area of the world, that is shown on the minimap
+    pub world_bounds: Rect,
+}
+
+impl Default for MinimapConfig {
+    fn default() -> Self {
+        Self {
+            size: Vec2::new(200.0, 200.0),
+            margin: Vec2::new(10.0, 10.0),
+            world_bounds: Rect::from_center_half_size(Vec2::ZERO, Vec2::splat(1500.0)),
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct Minimap;
+
+#[derive(Component)]
+pub struct MinimapViewport;
+
+/// A dot on the minimap, that represents the target entity in the world
+#[derive(Component)]
+pub struct MinimapDot {
+    pub target: Entity,
+}
+
+/// Transforms a world position into a position on the minimap.
+/// The result is relative to the top left corner of the minimap, with y pointing down.
+pub fn world_to_minimap(world_pos: Vec2, world_bounds: Rect, minimap_size: Vec2) -> Vec2 {
+    let normalized = (world_pos - world_bounds.min) / world_bounds.size();
+    Vec2::new(normalized.x, 1.0 - normalized.y) * minimap_size
+}
+
+fn spawn_minimap(mut commands: Commands, config: Res<MinimapConfig>) {
+    commands.spawn((
+        Minimap,
+        Node {
+            position_type: PositionType::Absolute,
+            right: Val::Px(config.margin.x),
+            bottom: Val::Px(config.margin.y),
+            width: Val::Px(config.size.x),
+            height: Val::Px(config.size.y),
+            overflow: Overflow::clip(),
+            ..default()
+        },
+        BackgroundColor(MINIMAP_BACKGROUND_COLOR),
+        children![(
+            MinimapViewport,
+            Node {
+                position_type: PositionType::Absolute,
+                border: UiRect::all(Val::Px(1.0)),
+                ..default()
+            },
+            BorderColor(MINIMAP_VIEWPORT_COLOR),
+        )],
+    ));
+}
+
+fn update_minimap_layout(
+    config: Res<MinimapConfig>,
+    mut minimap: Single<&mut Node, With<Minimap>>,
+) {
+    if !config.is_changed() {
+        return;
+    }
+    minimap.right = Val::Px(config.margin.x);
+    minimap.bottom = Val::Px(config.margin.y);
+    minimap.width = Val::Px(config.size.x);
+    minimap.height = Val::Px(config.size.y);
+}
+
+fn sync_minimap_dots(
+    mut commands: Commands,
+    config: Res<MinimapConfig>,
+    minimap: Single<Entity, With<Minimap>>,
+    q_targets: Query<
+        (Entity, &GlobalTransform, Has<Aircraft>, Has<Runway>),
+        Or<(With<Aircraft>, With<Waypoint>, With<Runway>)>,
+    >,
+    mut q_dots: Query<(Entity, &MinimapDot, &mut Node)>,
+) {
+    let mut dots: HashMap<Entity, Entity> = HashMap::new();
+    for (dot_entity, dot, _) in &q_dots {
+        if q_targets.contains(dot.target) {
+            dots.insert(dot.target, dot_entity);
+        } else {
+            commands.entity(dot_entity).despawn();
+        }
+    }
+
+    for (target, transform, is_aircraft, is_runway) in &q_targets {
+        let pos = world_to_minimap(
+            transform.translation().truncate(),
+            config.world_bounds,
+            config.size,
+        ) - MINIMAP_DOT_SIZE / 2.0;
+        if let Some(dot_entity) = dots.get(&target) {
+            if let Ok((_, _, mut node)) = q_dots.get_mut(*dot_entity) {
+                node.left = Val::Px(pos.x);
+                node.top = Val::Px(pos.y);
+            }
+            continue;
+        }
+        let color = if is_aircraft {
+            MINIMAP_AIRCRAFT_COLOR
+        } else if is_runway {
+            MINIMAP_RUNWAY_COLOR
+        } else {
+            MINIMAP_WAYPOINT_COLOR
+        };
+        commands.spawn((
+            MinimapDot { target },
+            Node {
+                position_type: PositionType::Absolute,
+                left: Val::Px(pos.x),
+                top: Val::Px(pos.y),
+                width: Val::Px(MINIMAP_DOT_SIZE),
+                height: Val::Px(MINIMAP_DOT_SIZE),
+                ..default()
+            },
+            BackgroundColor(color),
+            ChildOf(*minimap),
+        ));
+    }
+}
+
+fn update_minimap_viewport(
+    config: Res<MinimapConfig>,
+    camera: Single<(&Transform, &Projection), With<Camera2d>>,
+    mut viewport: Single<&mut Node, With<MinimapViewport>>,
+) {
+    let (transform, projection) = *camera;
+    let Projection::Orthographic(projection) = projection else {
+        bevy::log::error!("Wrong camera projection. Expected orthographic!");
+        return;
+    };
+    let center = transform.translation.truncate();
+    // Top left and bottom right corner of the visible area
+    let top_left = world_to_minimap(
+        center + Vec2::new(projection.area.min.x, projection.area.max.y),
+        config.world_bounds,
+        config.size,
+    );
+    let bottom_right = world_to_minimap(
+        center + Vec2::new(projection.area.max.x, projection.area.min.y),
+        config.world_bounds,
+        config.size,
+    );
+    let size = bottom_right - top_left;
+    viewport.left = Val::Px(top_left.x);
+    viewport.top = Val::Px(top_left.y);
+    viewport.width = Val::Px(size.x);
+    viewport.height = Val::Px(size.y);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::world_to_minimap;
+    use bevy::math::{Rect, Vec2};
+
+    #[test]
+    fn test_world_to_minimap() {
+        let world_bounds = Rect::new(-1000.0, -500.0, 1000.0, 500.0);
+        let minimap_size = Vec2::new(200.0, 100.0);
+        let transform = |pos| world_to_minimap(pos, world_bounds, minimap_size);
+
+        assert_eq!(transform(Vec2::ZERO), Vec2::new(100.0, 50.0));
+        // World y points up, minimap y points down
+        assert_eq!(transform(Vec2::new(-1000.0, 500.0)), Vec2::ZERO);
+        assert_eq!(transform(Vec2::new(1000.0, -500.0)), minimap_size);
+        assert_eq!(transform(Vec2::new(500.0, 250.0)), Vec2::new(150.0, 25.0));
+    }
+}
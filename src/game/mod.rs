@@ -4,11 +4,32 @@
 use crate::game::{
     aircraft::AircraftPlugin,
     aircraft_card::AircraftCardPlugin,
+    approach::ApproachPlugin,
+    clearance::ClearancePlugin,
+    color_theme::ColorThemePlugin,
+    console::ConsolePlugin,
+    contact_label::ContactLabelPlugin,
+    emergency::EmergencyPlugin,
+    handoff::HandoffPlugin,
+    heading_drag::HeadingDragPlugin,
+    history_panel::HistoryPanelPlugin,
+    landing::LandingPlugin,
     level::LevelPlugin,
     loading::{LoadingFinishedEvent, LoadingPlugin},
+    measuring_tool::MeasuringToolPlugin,
+    minimap::MinimapPlugin,
+    predicted_path::PredictedPathPlugin,
+    radar_coverage::RadarCoveragePlugin,
+    separation::{SeparationMinima, SeparationPlugin},
+    sequencing::SequencingPlugin,
+    snapshot::SnapshotPlugin,
+    squawk::SquawkPlugin,
+    traffic::TrafficPlugin,
 };
+use aviation_helper_rs::conversions::{FEET_PER_HPA, STANDARD_PRESSURE_HPA};
 use bevy::{dev_tools::states::log_transitions, prelude::*};
 use camera::GameCameraPlugin;
+use serde::{Deserialize, Serialize};
 pub struct GamePlugin;
 
 use crate::{
@@ -16,16 +37,39 @@ use crate::{
     dev_gui::{DevGuiInputEvent, DevGuiStructTrait, DevGuiVariableUpdatedEvent},
     game::control::ControlPlugin,
     menu::LevelMeta,
-    util::reflect::try_apply_parsed,
+    util::{consts::DEFAULT_TRANSITION_ALTITUDE_FEET, reflect::try_apply_parsed},
 };
 
 mod aircraft;
 mod aircraft_card;
+mod approach;
 mod camera;
+mod clearance;
+mod color_theme;
+mod console;
+mod contact_label;
 mod control;
+mod emergency;
+#[cfg(feature = "event-stream")]
+mod event_stream;
+mod handoff;
+mod heading_drag;
+mod history_panel;
+mod landing;
 mod level;
 mod loading;
+mod measuring_tool;
+mod minimap;
+mod predicted_path;
+mod radar_coverage;
 pub mod run_conditions;
+mod separation;
+mod sequencing;
+mod snapshot;
+mod spatial_grid;
+mod squawk;
+mod traffic;
+mod wind;
 
 // Z-Index-Konstanten für die Spielobjekte
 pub const Z_BACKGROUND: f32 = 0.0;
@@ -34,9 +78,14 @@ pub const Z_WAYPOINT: f32 = 4.0;
 pub const Z_AIRCRAFT: f32 = 8.0;
 pub const Z_AIRCRAFT_CARD: f32 = 10.0;
 
+const DEFAULT_DIFFICULTY: f64 = 1.0;
+/// Flight levels, that can be the transition level, are this far apart
+const TRANSITION_LEVEL_STEP_FEET: f64 = 500.;
+
 impl Plugin for GamePlugin {
     fn build(&self, app: &mut App) {
         app.add_plugins((
+            ColorThemePlugin,
             ControlPlugin,
             GameCameraPlugin,
             AircraftCardPlugin,
@@ -44,6 +93,26 @@ impl Plugin for GamePlugin {
             LoadingPlugin,
             LevelPlugin,
             AircraftPlugin,
+            SeparationPlugin,
+            MinimapPlugin,
+            SnapshotPlugin,
+        ))
+        .add_plugins((
+            LandingPlugin,
+            ApproachPlugin,
+            ContactLabelPlugin,
+            HandoffPlugin,
+            ClearancePlugin,
+            ConsolePlugin,
+            EmergencyPlugin,
+            PredictedPathPlugin,
+            SquawkPlugin,
+            TrafficPlugin,
+            SequencingPlugin,
+            HeadingDragPlugin,
+            HistoryPanelPlugin,
+            RadarCoveragePlugin,
+            MeasuringToolPlugin,
         ))
         .register_type::<GameVariables>()
         .add_systems(OnEnter(AppState::Game), enter_loading_state)
@@ -61,6 +130,9 @@ impl Plugin for GamePlugin {
                 );
         }
 
+        #[cfg(feature = "event-stream")]
+        app.add_plugins(event_stream::EventStreamPlugin);
+
         if APP_CONFIG.log_state_transitions {
             app.add_systems(Update, (log_transitions::<GameState>,));
         }
@@ -80,16 +152,41 @@ fn handle_loading_finished(
     }
 }
 
-#[derive(Debug, Clone, Resource, Reflect)]
+#[derive(Debug, Clone, PartialEq, Resource, Reflect, Serialize, Deserialize)]
 pub struct GameVariables {
     pub level: LevelMeta,
+    pub transition_altitude_feet: f64,
+    /// Current altimeter setting, cleared with "QNH 1013"
+    pub qnh_hpa: f64,
+    /// Scales how often inbound aircraft arrive and how often they conflict. 0 stops them.
+    pub difficulty: f64,
+    /// Minima for separation conflicts, replaced by the ones of the level
+    #[serde(default)]
+    pub separation: SeparationMinima,
 }
 
 impl DevGuiStructTrait for GameVariables {}
 
 impl GameVariables {
     pub fn new(level: LevelMeta) -> Self {
-        Self { level }
+        Self {
+            level,
+            transition_altitude_feet: DEFAULT_TRANSITION_ALTITUDE_FEET,
+            qnh_hpa: STANDARD_PRESSURE_HPA,
+            difficulty: DEFAULT_DIFFICULTY,
+            separation: SeparationMinima::default(),
+        }
+    }
+
+    /// Lowest altitude, that is shown as flight level, the first flight level at or above the
+    /// transition altitude. A QNH below standard raises it, a QNH above standard never lowers it
+    /// below the transition altitude.
+    pub fn transition_level_feet(&self) -> f64 {
+        let pressure_correction_feet =
+            ((STANDARD_PRESSURE_HPA - self.qnh_hpa) * FEET_PER_HPA).max(0.);
+        ((self.transition_altitude_feet + pressure_correction_feet) / TRANSITION_LEVEL_STEP_FEET)
+            .ceil()
+            * TRANSITION_LEVEL_STEP_FEET
     }
 }
 
@@ -127,3 +224,37 @@ fn handle_dev_gui_events(
             .ok();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::GameVariables;
+    use crate::menu::LevelMeta;
+
+    fn variables_with_qnh(qnh_hpa: f64) -> GameVariables {
+        GameVariables {
+            transition_altitude_feet: 5000.,
+            qnh_hpa,
+            ..GameVariables::new(LevelMeta {
+                file: "test.ron".to_owned(),
+                name: "Test".to_owned(),
+            })
+        }
+    }
+
+    #[test]
+    fn test_transition_level_with_standard_qnh() {
+        assert_eq!(variables_with_qnh(1013.25).transition_level_feet(), 5000.);
+    }
+
+    #[test]
+    fn test_low_qnh_raises_transition_level() {
+        // 13 hPa below standard are 351 ft, rounded up to the next flight level
+        assert_eq!(variables_with_qnh(1000.25).transition_level_feet(), 5500.);
+        assert_eq!(variables_with_qnh(990.).transition_level_feet(), 6000.);
+    }
+
+    #[test]
+    fn test_high_qnh_keeps_transition_level_above_transition_altitude() {
+        assert_eq!(variables_with_qnh(1030.).transition_level_feet(), 5000.);
+    }
+}
@@ -0,0 +1,128 @@
+use bevy::prelude::*;
+
+use crate::game::GameState;
+use crate::game::aircraft::{Aircraft, AircraftType, AircraftTypeStore, simulate_aircraft};
+use crate::game::control::{ControlMode, ControlState, control_mode_is_clearance_selection};
+use crate::game::wind::Wind;
+
+/// How far ahead the path of the selected aircraft is predicted
+const PREDICTION_SECONDS: f64 = 60.0;
+/// Simulated time between two points of the predicted path
+const PREDICTION_STEP_SECONDS: f64 = 0.25;
+const PREDICTED_PATH_COLOR: Color = Color::srgba(0.4, 0.8, 1.0, 0.6);
+
+pub struct PredictedPathPlugin;
+
+impl Plugin for PredictedPathPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            draw_predicted_path
+                .run_if(in_state(GameState::Running).and(control_mode_is_clearance_selection)),
+        );
+    }
+}
+
+/// Positions of the aircraft over the next seconds, if it keeps following its current
+/// clearances. Starts with the current position.
+pub fn predict_path(
+    aircraft: &Aircraft,
+    transform: &Transform,
+    aircraft_type: &AircraftType,
+    wind: &Wind,
+    seconds: f64,
+    step_seconds: f64,
+) -> Vec<Vec2> {
+    let mut aircraft = aircraft.clone();
+    let mut transform = *transform;
+    let steps = (seconds / step_seconds).ceil() as usize;
+    let mut points = Vec::with_capacity(steps + 1);
+    points.push(transform.translation.truncate());
+    for _ in 0..steps {
+        simulate_aircraft(
+            &mut aircraft,
+            &mut transform,
+            aircraft_type,
+            wind,
+            step_seconds,
+        );
+        points.push(transform.translation.truncate());
+    }
+    points
+}
+
+fn draw_predicted_path(
+    mut gizmos: Gizmos,
+    control_state: Res<ControlState>,
+    q_aircraft: Query<(&Aircraft, &Transform)>,
+    aircraft_types: Res<AircraftTypeStore>,
+    aircraft_type_assets: Res<Assets<AircraftType>>,
+    wind: Res<Wind>,
+) {
+    let ControlMode::ClearanceSelection {
+        aircraft_entity, ..
+    } = control_state.mode
+    else {
+        return;
+    };
+    let Ok((aircraft, transform)) = q_aircraft.get(aircraft_entity) else {
+        return;
+    };
+    let Some(aircraft_type) = aircraft_types
+        .0
+        .get(&aircraft.aircraft_type_id)
+        .and_then(|handle| aircraft_type_assets.get(handle))
+    else {
+        return;
+    };
+    let points = predict_path(
+        aircraft,
+        transform,
+        aircraft_type,
+        &wind,
+        PREDICTION_SECONDS,
+        PREDICTION_STEP_SECONDS,
+    );
+    gizmos.linestrip_2d(points, PREDICTED_PATH_COLOR);
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+
+    use super::predict_path;
+    use crate::game::aircraft::{Aircraft, simulate_aircraft, test_aircraft, test_aircraft_type};
+    use crate::game::wind::Wind;
+    use crate::util::units::knots_to_pixels_per_second;
+
+    #[test]
+    fn test_straight_ahead_prediction() {
+        let aircraft_type = test_aircraft_type();
+        let aircraft = Aircraft {
+            wanted_speed_knots: 200.,
+            speed_knots: 200.,
+            ..test_aircraft()
+        };
+        let start = Transform::from_xyz(100., -50., 0.);
+        let wind = Wind::default();
+
+        let points = predict_path(&aircraft, &start, &aircraft_type, &wind, 60., 0.25);
+        assert_eq!(points.len(), 241);
+        assert_eq!(points[0], Vec2::new(100., -50.));
+
+        let mut simulated = aircraft.clone();
+        let mut transform = start;
+        for _ in 0..240 {
+            simulate_aircraft(&mut simulated, &mut transform, &aircraft_type, &wind, 0.25);
+        }
+        let end = *points.last().unwrap();
+        assert_eq!(end, transform.translation.truncate());
+
+        let expected =
+            Vec2::new(100., -50.) + Vec2::X * (knots_to_pixels_per_second(200.) * 60.) as f32;
+        assert!(
+            end.distance(expected) < 0.01,
+            "Expected {expected}, got {end}"
+        );
+    }
+}
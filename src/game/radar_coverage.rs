@@ -0,0 +1,179 @@
+use std::f32::consts::TAU;
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::game::GameState;
+use crate::game::aircraft::Aircraft;
+use crate::game::color_theme::ColorTheme;
+use crate::game::level::is_inside_polygon;
+use crate::game::wind::Wind;
+use crate::util::units::knots_to_pixels_per_second;
+
+/// Dashes of the circle around the extrapolated position
+const COASTING_DASHES: usize = 8;
+const COASTING_SYMBOL_RADIUS: f32 = 12.;
+/// Length of the dashed track ahead of the extrapolated position
+const COASTING_TRACK_SECONDS: f32 = 60.;
+
+/// Aircraft outside of the radar coverage of the level coast: their last track is extrapolated
+/// and drawn dashed, until they are covered again
+pub struct RadarCoveragePlugin;
+
+impl Plugin for RadarCoveragePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RadarCoverage>()
+            .add_systems(
+                FixedUpdate,
+                update_coasting.run_if(in_state(GameState::Running)),
+            )
+            .add_systems(
+                Update,
+                draw_coasting_aircraft.run_if(in_state(GameState::Running)),
+            );
+    }
+}
+
+/// Area, in which the radar sees aircraft. Everywhere, if the boundary is empty.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct RadarCoverage {
+    pub boundary: Vec<Vec2>,
+}
+
+impl RadarCoverage {
+    pub fn covers(&self, position: Vec2) -> bool {
+        self.boundary.is_empty() || is_inside_polygon(position, &self.boundary)
+    }
+}
+
+/// Radar contact is lost. The aircraft is shown at the position extrapolated from its last
+/// radar return.
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct Coasting {
+    pub last_position: Vec2,
+    pub velocity_pixels_per_second: Vec2,
+    /// Elapsed game time at the last radar return
+    pub since: Duration,
+}
+
+impl Coasting {
+    pub fn extrapolated_position(&self, elapsed: Duration) -> Vec2 {
+        let seconds = elapsed.saturating_sub(self.since).as_secs_f32();
+        self.last_position + self.velocity_pixels_per_second * seconds
+    }
+}
+
+/// Position of the aircraft, as far as the radar knows: the last radar return while coasting.
+/// Cards and leader lines stay there.
+pub fn radar_position(transform: &Transform, coasting: Option<&Coasting>) -> Vec3 {
+    coasting.map_or(transform.translation, |coasting| {
+        coasting.last_position.extend(transform.translation.z)
+    })
+}
+
+/// Hides aircraft, that left the coverage, behind their extrapolated symbol. Their cards are
+/// frozen at the last radar return.
+fn update_coasting(
+    mut commands: Commands,
+    time: Res<Time>,
+    coverage: Res<RadarCoverage>,
+    wind: Res<Wind>,
+    q_aircraft: Query<(Entity, &Aircraft, &Transform, Has<Coasting>)>,
+) {
+    for (entity, aircraft, transform, is_coasting) in &q_aircraft {
+        let position = transform.translation.truncate();
+        match (coverage.covers(position), is_coasting) {
+            (false, false) => {
+                let ground_velocity_knots = aircraft.ground_velocity_knots(&wind);
+                let velocity = ground_velocity_knots.normalize_or_zero()
+                    * knots_to_pixels_per_second(ground_velocity_knots.length());
+                commands.entity(entity).insert((
+                    Coasting {
+                        last_position: position,
+                        velocity_pixels_per_second: velocity.as_vec2(),
+                        since: time.elapsed(),
+                    },
+                    Visibility::Hidden,
+                ));
+            }
+            (true, true) => {
+                commands
+                    .entity(entity)
+                    .remove::<Coasting>()
+                    .insert(Visibility::Inherited);
+            }
+            _ => {}
+        }
+    }
+}
+
+fn draw_coasting_aircraft(
+    mut gizmos: Gizmos,
+    time: Res<Time>,
+    color_theme: Res<ColorTheme>,
+    q_coasting: Query<&Coasting>,
+) {
+    let dash_angle = TAU / (2 * COASTING_DASHES) as f32;
+    for coasting in &q_coasting {
+        let position = coasting.extrapolated_position(time.elapsed());
+        for dash in 0..COASTING_DASHES {
+            let rotation = Rot2::radians(2. * dash as f32 * dash_angle);
+            gizmos.arc_2d(
+                Isometry2d::new(position, rotation),
+                dash_angle,
+                COASTING_SYMBOL_RADIUS,
+                color_theme.aircraft,
+            );
+        }
+        let track = coasting.velocity_pixels_per_second * COASTING_TRACK_SECONDS;
+        let dashes = COASTING_DASHES * 2;
+        for dash in (0..dashes).step_by(2) {
+            let start = position + track * dash as f32 / dashes as f32;
+            let end = position + track * (dash + 1) as f32 / dashes as f32;
+            gizmos.line_2d(start, end, color_theme.aircraft);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::prelude::*;
+
+    use super::{Coasting, RadarCoverage, radar_position};
+
+    #[test]
+    fn test_radar_coverage_polygon() {
+        let coverage = RadarCoverage {
+            boundary: vec![
+                Vec2::new(0., 0.),
+                Vec2::new(1000., 0.),
+                Vec2::new(1000., 1000.),
+                Vec2::new(0., 1000.),
+            ],
+        };
+        assert!(coverage.covers(Vec2::new(500., 500.)));
+        assert!(!coverage.covers(Vec2::new(1500., 500.)));
+        assert!(!coverage.covers(Vec2::new(500., -1.)));
+        // Without a boundary, the radar sees everything
+        assert!(RadarCoverage::default().covers(Vec2::new(1e6, -1e6)));
+
+        let coasting = Coasting {
+            last_position: Vec2::new(1000., 500.),
+            velocity_pixels_per_second: Vec2::new(2., 0.),
+            since: Duration::from_secs(10),
+        };
+        assert_eq!(
+            coasting.extrapolated_position(Duration::from_secs(15)),
+            Vec2::new(1010., 500.)
+        );
+
+        let transform = Transform::from_xyz(1100., 500., 3.);
+        assert_eq!(
+            radar_position(&transform, Some(&coasting)),
+            Vec3::new(1000., 500., 3.)
+        );
+        assert_eq!(radar_position(&transform, None), transform.translation);
+    }
+}
@@ -0,0 +1,346 @@
+use std::time::Duration;
+
+use bevy::platform::collections::HashMap;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::game::aircraft::{Aircraft, AircraftType, AircraftTypeStore};
+use crate::game::emergency::Emergency;
+use crate::game::spatial_grid::{SpatialGrid, rebuild_spatial_grid};
+use crate::game::{GameState, GameVariables};
+use crate::util::units::{nm_to_pixels, pixels_to_nm};
+
+/// Minimum radar separation between two aircraft, if no wake turbulence rule applies
+pub const MIN_RADAR_SEPARATION_NM: f64 = 3.0;
+/// Minimum radar separation above the transition altitude, where en-route radars are used
+pub const MIN_EN_ROUTE_SEPARATION_NM: f64 = 5.0;
+/// Aircraft closer than this vertically are not separated by altitude
+pub const MIN_VERTICAL_SEPARATION_FEET: f64 = 1000.0;
+/// Largest spacing of all wake turbulence rules
+const MAX_REQUIRED_SPACING_NM: f64 = 8.0;
+/// Aircraft with headings closer than this are considered to be on the same track
+const SAME_TRACK_MAX_DIFFERENCE_DEGREES: f64 = 45.0;
+/// Time, that the cards of aircraft in a new conflict flash, before they settle to a steady
+/// warning color
+pub const CONFLICT_FLASH_DURATION: Duration = Duration::from_secs(4);
+
+pub struct SeparationPlugin;
+
+impl Plugin for SeparationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SpatialGrid>()
+            .init_resource::<ConflictAlertState>()
+            .add_systems(
+                FixedUpdate,
+                (rebuild_spatial_grid, check_separation)
+                    .chain()
+                    .run_if(in_state(GameState::Running)),
+            );
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Deserialize, Reflect)]
+pub enum WakeCategory {
+    Light,
+    #[default]
+    Medium,
+    Heavy,
+    Super,
+}
+
+impl WakeCategory {
+    /// Required distance of a follower behind a leader on the same track
+    pub fn required_spacing_nm(leader: WakeCategory, follower: WakeCategory) -> f64 {
+        use WakeCategory::*;
+        match (leader, follower) {
+            (Super, Super) => MIN_RADAR_SEPARATION_NM,
+            (Super, Heavy) => 6.0,
+            (Super, Medium) => 7.0,
+            (Super, Light) => MAX_REQUIRED_SPACING_NM,
+            (Heavy, Heavy) => 4.0,
+            (Heavy, Medium) => 5.0,
+            (Heavy, Light) => 6.0,
+            (Medium, Light) => 5.0,
+            _ => MIN_RADAR_SEPARATION_NM,
+        }
+    }
+}
+
+/// Distances, below which two aircraft are in conflict
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Reflect)]
+pub struct SeparationStandards {
+    pub horizontal_nm: f64,
+    pub vertical_ft: f64,
+}
+
+impl SeparationStandards {
+    pub const TERMINAL: Self = Self {
+        horizontal_nm: MIN_RADAR_SEPARATION_NM,
+        vertical_ft: MIN_VERTICAL_SEPARATION_FEET,
+    };
+    pub const EN_ROUTE: Self = Self {
+        horizontal_nm: MIN_EN_ROUTE_SEPARATION_NM,
+        vertical_ft: MIN_VERTICAL_SEPARATION_FEET,
+    };
+
+    /// Aircraft are in conflict, if they are neither separated vertically nor horizontally.
+    /// Wake turbulence spacing of aircraft on the same track applies, if it is larger than the
+    /// horizontal minimum.
+    pub fn is_conflict(
+        &self,
+        distance_nm: f64,
+        vertical_distance_feet: f64,
+        wake_spacing_nm: Option<f64>,
+    ) -> bool {
+        let required_nm = wake_spacing_nm.unwrap_or(0.).max(self.horizontal_nm);
+        vertical_distance_feet.abs() < self.vertical_ft && distance_nm < required_nm
+    }
+}
+
+/// Separation standards of the level, which differ below and above the transition altitude
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Reflect)]
+pub struct SeparationMinima {
+    pub below_transition: SeparationStandards,
+    pub above_transition: SeparationStandards,
+}
+
+impl Default for SeparationMinima {
+    fn default() -> Self {
+        Self {
+            below_transition: SeparationStandards::TERMINAL,
+            above_transition: SeparationStandards::EN_ROUTE,
+        }
+    }
+}
+
+impl SeparationMinima {
+    /// Standards for a pair of aircraft. The higher one decides, so that a pair, that is only
+    /// partly above the transition altitude, gets the larger en-route minima.
+    pub fn standards(
+        &self,
+        altitude_a_feet: f64,
+        altitude_b_feet: f64,
+        transition_altitude_feet: f64,
+    ) -> SeparationStandards {
+        if altitude_a_feet.max(altitude_b_feet) < transition_altitude_feet {
+            self.below_transition
+        } else {
+            self.above_transition
+        }
+    }
+
+    fn max_horizontal_nm(&self) -> f64 {
+        self.below_transition
+            .horizontal_nm
+            .max(self.above_transition.horizontal_nm)
+    }
+}
+
+/// Marks an aircraft that is not separated from another aircraft
+#[derive(Component, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SeparationConflict {
+    /// One of the aircraft is in an emergency. The conflict is alerted with priority.
+    pub priority: bool,
+}
+
+/// Pairs of aircraft in conflict and since when. A pair is only alerted again, after its conflict
+/// cleared and recurred, so that a persistent conflict doesn't keep flashing.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct ConflictAlertState {
+    since: HashMap<(Entity, Entity), Duration>,
+}
+
+impl ConflictAlertState {
+    /// Replaces the pairs, that are in conflict now. Pairs, that were in conflict before, keep
+    /// the time their conflict arose, the others are forgotten.
+    pub fn update(&mut self, pairs: impl IntoIterator<Item = (Entity, Entity)>, now: Duration) {
+        let previous = std::mem::take(&mut self.since);
+        self.since = pairs
+            .into_iter()
+            .map(|pair| (pair, previous.get(&pair).copied().unwrap_or(now)))
+            .collect();
+    }
+
+    /// Whether one of the conflicts of the aircraft arose less than `CONFLICT_FLASH_DURATION`
+    /// ago
+    pub fn is_flashing(&self, entity: Entity, now: Duration) -> bool {
+        self.since.iter().any(|(&(entity_a, entity_b), &since)| {
+            (entity_a == entity || entity_b == entity)
+                && now.saturating_sub(since) < CONFLICT_FLASH_DURATION
+        })
+    }
+}
+
+fn check_separation(
+    mut commands: Commands,
+    query: Query<(
+        Entity,
+        &Aircraft,
+        &Transform,
+        Option<&SeparationConflict>,
+        Has<Emergency>,
+    )>,
+    aircraft_types: Res<AircraftTypeStore>,
+    aircraft_type_assets: Res<Assets<AircraftType>>,
+    grid: Res<SpatialGrid>,
+    variables: Res<GameVariables>,
+    time: Res<Time>,
+    mut alert_state: ResMut<ConflictAlertState>,
+) {
+    let wake_category = |aircraft: &Aircraft| {
+        aircraft_types
+            .0
+            .get(&aircraft.aircraft_type_id)
+            .and_then(|handle| aircraft_type_assets.get(handle))
+            .map(AircraftType::wake_category)
+            .unwrap_or_default()
+    };
+
+    let minima = variables.separation;
+    let query_radius = nm_to_pixels(MAX_REQUIRED_SPACING_NM.max(minima.max_horizontal_nm()));
+    let mut in_conflict = HashMap::new();
+    let mut conflict_pairs = Vec::new();
+    let pairs = query.iter().flat_map(|(entity_a, _, transform_a, _, _)| {
+        grid.query_radius(transform_a.translation.truncate(), query_radius)
+            // Every pair only once
+            .filter(move |entity_b| entity_a < *entity_b)
+            .map(move |entity_b| (entity_a, entity_b))
+    });
+    for (entity_a, entity_b) in pairs {
+        let (
+            Ok((_, aircraft_a, transform_a, _, emergency_a)),
+            Ok((_, aircraft_b, transform_b, _, emergency_b)),
+        ) = (query.get(entity_a), query.get(entity_b))
+        else {
+            continue;
+        };
+        let standards = minima.standards(
+            aircraft_a.altitude_feet,
+            aircraft_b.altitude_feet,
+            variables.transition_altitude_feet,
+        );
+        let position_a = transform_a.translation.truncate();
+        let position_b = transform_b.translation.truncate();
+        let distance_nm = pixels_to_nm(position_a.distance(position_b));
+
+        let heading_difference = aircraft_a.heading - aircraft_b.heading;
+        let same_track =
+            heading_difference.min(360. - heading_difference) < SAME_TRACK_MAX_DIFFERENCE_DEGREES;
+        let wake_spacing_nm = same_track.then(|| {
+            // The leader is the one in front, seen in the direction of flight of b
+            let direction_b = Vec2::from_angle(aircraft_b.heading.to_bevy_rotation() as f32);
+            let (leader, follower) = if (position_a - position_b).dot(direction_b) > 0. {
+                (aircraft_a, aircraft_b)
+            } else {
+                (aircraft_b, aircraft_a)
+            };
+            WakeCategory::required_spacing_nm(wake_category(leader), wake_category(follower))
+        });
+
+        if standards.is_conflict(
+            distance_nm,
+            aircraft_a.altitude_feet - aircraft_b.altitude_feet,
+            wake_spacing_nm,
+        ) {
+            conflict_pairs.push((entity_a, entity_b));
+            let priority = emergency_a || emergency_b;
+            for entity in [entity_a, entity_b] {
+                let conflict: &mut SeparationConflict = in_conflict.entry(entity).or_default();
+                conflict.priority |= priority;
+            }
+        }
+    }
+
+    for (entity, _, _, current, _) in &query {
+        match (in_conflict.get(&entity), current) {
+            (Some(conflict), current) if current != Some(conflict) => {
+                commands.entity(entity).insert(*conflict);
+            }
+            (None, Some(_)) => {
+                commands.entity(entity).remove::<SeparationConflict>();
+            }
+            _ => {}
+        }
+    }
+    alert_state.update(conflict_pairs, time.elapsed());
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::prelude::*;
+
+    use super::{
+        CONFLICT_FLASH_DURATION, ConflictAlertState, MIN_RADAR_SEPARATION_NM, SeparationMinima,
+        SeparationStandards, WakeCategory,
+    };
+
+    #[test]
+    fn test_required_spacing_behind_heavier_aircraft() {
+        let spacing = WakeCategory::required_spacing_nm;
+        assert_eq!(
+            spacing(WakeCategory::Medium, WakeCategory::Medium),
+            MIN_RADAR_SEPARATION_NM
+        );
+        assert_eq!(spacing(WakeCategory::Heavy, WakeCategory::Heavy), 4.0);
+        assert_eq!(spacing(WakeCategory::Heavy, WakeCategory::Medium), 5.0);
+        assert_eq!(spacing(WakeCategory::Super, WakeCategory::Light), 8.0);
+        // A lighter leader never requires more than radar separation
+        assert_eq!(
+            spacing(WakeCategory::Light, WakeCategory::Super),
+            MIN_RADAR_SEPARATION_NM
+        );
+    }
+
+    #[test]
+    fn test_conflict_uses_configured_minima() {
+        let minima = SeparationMinima {
+            below_transition: SeparationStandards {
+                horizontal_nm: 2.5,
+                vertical_ft: 500.,
+            },
+            above_transition: SeparationStandards {
+                horizontal_nm: 10.,
+                vertical_ft: 2000.,
+            },
+        };
+        let transition_altitude_feet = 5000.;
+        let below = minima.standards(3000., 3600., transition_altitude_feet);
+        assert_eq!(below, minima.below_transition);
+        // 600 ft apart are separated vertically below, but not above the transition altitude
+        assert!(!below.is_conflict(1., 600., None));
+        assert!(below.is_conflict(2., 400., None));
+        assert!(!below.is_conflict(3., 0., None));
+        // Larger wake turbulence spacing still applies
+        assert!(below.is_conflict(3., 0., Some(4.)));
+
+        let above = minima.standards(4500., 6000., transition_altitude_feet);
+        assert_eq!(above, minima.above_transition);
+        assert!(above.is_conflict(8., -1500., None));
+        assert!(!above.is_conflict(10., 0., None));
+    }
+
+    #[test]
+    fn test_persistent_conflict_flashes_once() {
+        let mut world = World::new();
+        let (a, b) = (world.spawn_empty().id(), world.spawn_empty().id());
+        let seconds = Duration::from_secs;
+        let mut state = ConflictAlertState::default();
+
+        state.update([(a, b)], seconds(10));
+        assert!(state.is_flashing(a, seconds(10)));
+        assert!(state.is_flashing(b, seconds(10) + CONFLICT_FLASH_DURATION / 2));
+        // The same pair stays in conflict, its flash window doesn't restart
+        for second in 11..30 {
+            state.update([(a, b)], seconds(second));
+        }
+        assert!(!state.is_flashing(a, seconds(30)));
+        assert!(!state.is_flashing(b, seconds(30)));
+
+        // Cleared and recurred
+        state.update([], seconds(31));
+        state.update([(a, b)], seconds(32));
+        assert!(state.is_flashing(a, seconds(32)));
+    }
+}
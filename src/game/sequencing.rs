@@ -0,0 +1,160 @@
+use aviation_helper_rs::clearance::aviation_command::AviationCommandPart;
+use bevy::prelude::*;
+
+use crate::game::GameState;
+use crate::game::aircraft::Aircraft;
+use crate::game::console::ConsoleLogLine;
+use crate::util::units::pixels_to_nm;
+
+/// In-trail spacing, that "sequence behind" establishes
+pub const DEFAULT_SPACING_NM: f64 = 5.;
+/// Speed change per nautical mile, that the follower is too close or too far
+const SPEED_KNOTS_PER_NM_ERROR: f64 = 20.;
+/// The follower flies at most this much faster or slower than the leader
+const MAX_SPEED_DIFFERENCE_KNOTS: f64 = 40.;
+/// Cleared speeds are rounded, like a controller would assign them
+const SPEED_STEP_KNOTS: f64 = 10.;
+
+pub struct SequencingPlugin;
+
+impl Plugin for SequencingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            follow_leaders.run_if(in_state(GameState::Running)),
+        );
+    }
+}
+
+/// Aircraft, that adjusts its speed to stay `spacing_nm` behind the leader, until it gets
+/// another speed clearance
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct FollowAircraft {
+    pub leader: Entity,
+    pub spacing_nm: f64,
+}
+
+impl FollowAircraft {
+    /// Following the leader of a "sequence behind" command, if it is the call sign of one of
+    /// the aircraft
+    pub fn from_command<'a>(
+        command: &AviationCommandPart,
+        aircraft: impl IntoIterator<Item = (Entity, &'a str)>,
+    ) -> Option<Self> {
+        let AviationCommandPart::SequenceBehind { callsign } = command else {
+            return None;
+        };
+        Some(Self {
+            leader: find_aircraft_by_call_sign(aircraft, callsign)?,
+            spacing_nm: DEFAULT_SPACING_NM,
+        })
+    }
+}
+
+/// Aircraft with the call sign, ignoring the case
+pub fn find_aircraft_by_call_sign<'a>(
+    aircraft: impl IntoIterator<Item = (Entity, &'a str)>,
+    call_sign: &str,
+) -> Option<Entity> {
+    aircraft
+        .into_iter()
+        .find(|(_, other)| other.eq_ignore_ascii_case(call_sign))
+        .map(|(entity, _)| entity)
+}
+
+/// Speed, that keeps the follower `spacing_nm` behind the leader. The distance is measured
+/// along the track of the follower, so a leader behind it counts as too close.
+pub fn follow_speed_knots(
+    leader_position: Vec2,
+    leader_speed_knots: f64,
+    follower_position: Vec2,
+    follower_track: Vec2,
+    spacing_nm: f64,
+) -> f64 {
+    let distance_nm =
+        pixels_to_nm((leader_position - follower_position).dot(follower_track.normalize_or_zero()));
+    let speed_difference = ((distance_nm - spacing_nm) * SPEED_KNOTS_PER_NM_ERROR)
+        .clamp(-MAX_SPEED_DIFFERENCE_KNOTS, MAX_SPEED_DIFFERENCE_KNOTS);
+    leader_speed_knots + speed_difference
+}
+
+fn follow_leaders(
+    mut commands: Commands,
+    q_followers: Query<(Entity, &FollowAircraft, &Transform)>,
+    mut q_aircraft: Query<(&mut Aircraft, &Transform)>,
+    mut log_writer: EventWriter<ConsoleLogLine>,
+) {
+    for (entity, follow, transform) in &q_followers {
+        let Ok((leader, leader_transform)) = q_aircraft.get(follow.leader) else {
+            // The leader landed or left the sector
+            commands.entity(entity).remove::<FollowAircraft>();
+            if let Ok((aircraft, _)) = q_aircraft.get(entity) {
+                log_writer.write(ConsoleLogLine(format!(
+                    "{}: traffic to follow no longer in sight",
+                    aircraft.call_sign
+                )));
+            }
+            continue;
+        };
+        let leader_position = leader_transform.translation.truncate();
+        let leader_speed_knots = leader.speed_knots;
+        let Ok((mut aircraft, _)) = q_aircraft.get_mut(entity) else {
+            continue;
+        };
+        let track = Vec2::from_angle(aircraft.heading.to_bevy_rotation() as f32);
+        let speed_knots = follow_speed_knots(
+            leader_position,
+            leader_speed_knots,
+            transform.translation.truncate(),
+            track,
+            follow.spacing_nm,
+        );
+        let speed_knots = (speed_knots / SPEED_STEP_KNOTS).round() * SPEED_STEP_KNOTS;
+        if aircraft.cleared_speed_knots != Some(speed_knots) {
+            aircraft.cleared_speed_knots = Some(speed_knots);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+
+    use super::{MAX_SPEED_DIFFERENCE_KNOTS, follow_speed_knots};
+    use crate::util::units::nm_to_pixels;
+
+    #[test]
+    fn test_follow_speed_keeps_spacing() {
+        let leader_speed = 180.;
+        let track = Vec2::X;
+        let speed_at = |distance_nm: f64| {
+            follow_speed_knots(
+                Vec2::new(nm_to_pixels(distance_nm), 0.),
+                leader_speed,
+                Vec2::ZERO,
+                track,
+                5.,
+            )
+        };
+
+        // At the spacing, the follower flies the speed of the leader
+        assert!((speed_at(5.) - leader_speed).abs() < 1e-3);
+        // Too close, it slows down, too far, it catches up
+        assert!(speed_at(4.) < leader_speed);
+        assert!(speed_at(6.) > leader_speed);
+        assert!(speed_at(4.5) > speed_at(4.));
+        // But never by more than the maximum difference
+        assert_eq!(speed_at(30.), leader_speed + MAX_SPEED_DIFFERENCE_KNOTS);
+        assert_eq!(speed_at(0.), leader_speed - MAX_SPEED_DIFFERENCE_KNOTS);
+
+        // A leader abeam or behind the follower is too close
+        let abeam = follow_speed_knots(
+            Vec2::new(0., nm_to_pixels(5.)),
+            leader_speed,
+            Vec2::ZERO,
+            track,
+            5.,
+        );
+        assert_eq!(abeam, leader_speed - MAX_SPEED_DIFFERENCE_KNOTS);
+    }
+}
@@ -0,0 +1,443 @@
+use bevy::input::common_conditions::input_just_pressed;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::game::aircraft::{
+    Aircraft, AircraftJustSpawned, AircraftMeshMaterials, spawn_aircraft_with_speed_indicator,
+};
+use crate::game::approach::{IlsIntercept, VisualApproach};
+use crate::game::clearance::{
+    AppliedClearances, ClearanceLog, CommandQueue, QueuedClearance, TimestampedClearance,
+};
+use crate::game::emergency::Emergency;
+use crate::game::handoff::{Handoff, HandoffDue, InSector};
+use crate::game::landing::{ClimbingOut, LandingClearance};
+use crate::game::sequencing::FollowAircraft;
+use crate::game::squawk::AssignedSquawk;
+use crate::game::wind::Wind;
+use crate::game::{GameState, GameVariables};
+
+/// File, that F5 saves the game to and F9 restores it from
+const QUICK_SAVE_FILE: &str = "quicksave.ron";
+
+pub struct SnapshotPlugin;
+
+impl Plugin for SnapshotPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                quick_save.run_if(input_just_pressed(KeyCode::F5)),
+                quick_load.run_if(input_just_pressed(KeyCode::F9)),
+            )
+                .run_if(in_state(GameState::Running)),
+        );
+    }
+}
+
+/// State of a running game, from which it can be continued
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GameSnapshot {
+    pub variables: GameVariables,
+    pub wind: Wind,
+    /// Sorted by call sign
+    pub aircraft: Vec<AircraftSnapshot>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AircraftSnapshot {
+    pub aircraft: Aircraft,
+    pub position: Vec2,
+    #[serde(default)]
+    pub landing_clearance: Option<LandingClearance>,
+    #[serde(default)]
+    pub ils_intercept: Option<IlsIntercept>,
+    #[serde(default)]
+    pub visual_approach: Option<VisualApproach>,
+    #[serde(default)]
+    pub queued_clearances: Vec<QueuedClearance>,
+    #[serde(default)]
+    pub handoff: Option<Handoff>,
+    #[serde(default)]
+    pub emergency: Option<Emergency>,
+    #[serde(default)]
+    pub squawk: Option<u16>,
+    #[serde(default)]
+    pub following: Option<FollowedAircraft>,
+    #[serde(default)]
+    pub climbing_out: bool,
+    #[serde(default)]
+    pub in_sector: bool,
+    #[serde(default)]
+    pub handoff_due: bool,
+    #[serde(default)]
+    pub applied_clearances: Vec<TimestampedClearance>,
+}
+
+/// `FollowAircraft` with the call sign of the leader, because its entity changes on restore
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FollowedAircraft {
+    pub call_sign: String,
+    pub spacing_nm: f64,
+}
+
+impl GameSnapshot {
+    pub fn to_ron(&self) -> Result<String, ron::Error> {
+        ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+    }
+
+    pub fn from_ron(text: &str) -> Result<Self, ron::error::SpannedError> {
+        ron::from_str(text)
+    }
+}
+
+type SnapshotQueryData = (
+    &'static Aircraft,
+    &'static Transform,
+    Option<&'static LandingClearance>,
+    Option<&'static IlsIntercept>,
+    Option<&'static VisualApproach>,
+    Option<&'static CommandQueue>,
+    Option<&'static Handoff>,
+    Option<&'static Emergency>,
+    Option<&'static AssignedSquawk>,
+    Option<&'static FollowAircraft>,
+    Has<ClimbingOut>,
+    Has<InSector>,
+    Has<HandoffDue>,
+    Option<&'static AppliedClearances>,
+);
+
+pub fn snapshot_game(world: &mut World) -> GameSnapshot {
+    let mut q_call_signs = world.query::<&Aircraft>();
+    let mut q_aircraft = world.query::<SnapshotQueryData>();
+    let mut aircraft: Vec<AircraftSnapshot> = q_aircraft
+        .iter(world)
+        .map(
+            |(
+                aircraft,
+                transform,
+                landing_clearance,
+                ils_intercept,
+                visual_approach,
+                queue,
+                handoff,
+                emergency,
+                squawk,
+                follow,
+                climbing_out,
+                in_sector,
+                handoff_due,
+                applied_clearances,
+            )| AircraftSnapshot {
+                aircraft: aircraft.clone(),
+                position: transform.translation.truncate(),
+                landing_clearance: landing_clearance.cloned(),
+                ils_intercept: ils_intercept.cloned(),
+                visual_approach: visual_approach.cloned(),
+                queued_clearances: queue.map(|queue| queue.0.clone()).unwrap_or_default(),
+                handoff: handoff.cloned(),
+                emergency: emergency.copied(),
+                squawk: squawk.map(|AssignedSquawk(code)| *code),
+                following: follow.and_then(|follow| {
+                    let leader = q_call_signs.get(world, follow.leader).ok()?;
+                    Some(FollowedAircraft {
+                        call_sign: leader.call_sign.clone(),
+                        spacing_nm: follow.spacing_nm,
+                    })
+                }),
+                climbing_out,
+                in_sector,
+                handoff_due,
+                applied_clearances: applied_clearances
+                    .map(|applied| applied.0.clone())
+                    .unwrap_or_default(),
+            },
+        )
+        .collect();
+    aircraft.sort_by(|a, b| a.aircraft.call_sign.cmp(&b.aircraft.call_sign));
+    GameSnapshot {
+        variables: world.resource::<GameVariables>().clone(),
+        wind: *world.resource::<Wind>(),
+        aircraft,
+    }
+}
+
+/// Replaces all aircraft and the game variables with the ones of the snapshot. The level
+/// itself isn't reloaded. The aircraft cards are spawned like for any new aircraft. The
+/// clearance log is cleared, because its entries refer to the replaced aircraft.
+pub fn restore_game(world: &mut World, snapshot: &GameSnapshot) {
+    if world.resource::<GameVariables>().level != snapshot.variables.level {
+        warn!(
+            "Restoring a snapshot of level {}, but {} is loaded",
+            snapshot.variables.level.file,
+            world.resource::<GameVariables>().level.file
+        );
+    }
+    let existing: Vec<Entity> = world
+        .query_filtered::<Entity, With<Aircraft>>()
+        .iter(world)
+        .collect();
+    for entity in existing {
+        world.despawn(entity);
+    }
+    world.insert_resource(snapshot.variables.clone());
+    world.insert_resource(snapshot.wind);
+    let last_sequence = snapshot
+        .aircraft
+        .iter()
+        .flat_map(|snapshot| &snapshot.applied_clearances)
+        .map(|applied| applied.sequence)
+        .max();
+    world.resource_mut::<ClearanceLog>().clear(last_sequence);
+
+    let mesh_materials = world.resource::<AircraftMeshMaterials>().clone();
+    let mut commands = world.commands();
+    let spawned: Vec<Entity> = snapshot
+        .aircraft
+        .iter()
+        .map(|snapshot| {
+            spawn_aircraft_with_speed_indicator(
+                &mut commands,
+                snapshot.aircraft.clone(),
+                snapshot.position,
+                &mesh_materials,
+            )
+        })
+        .collect();
+    // Inserted after spawning, so that they replace what the observers of new aircraft added,
+    // e.g. a freshly assigned squawk
+    world.flush();
+    let snapshot_aircraft = &snapshot.aircraft;
+    for (entity, snapshot) in spawned.iter().zip(snapshot_aircraft) {
+        let mut entity_mut = world.entity_mut(*entity);
+        if let Some(landing_clearance) = &snapshot.landing_clearance {
+            entity_mut.insert(landing_clearance.clone());
+        }
+        if let Some(ils_intercept) = &snapshot.ils_intercept {
+            entity_mut.insert(ils_intercept.clone());
+        }
+        if let Some(visual_approach) = &snapshot.visual_approach {
+            entity_mut.insert(visual_approach.clone());
+        }
+        if !snapshot.queued_clearances.is_empty() {
+            entity_mut.insert(CommandQueue(snapshot.queued_clearances.clone()));
+        }
+        if let Some(handoff) = &snapshot.handoff {
+            entity_mut.insert(handoff.clone());
+        }
+        if let Some(emergency) = snapshot.emergency {
+            entity_mut.insert(emergency);
+        }
+        if let Some(code) = snapshot.squawk {
+            entity_mut.insert(AssignedSquawk(code));
+        }
+        if let Some(following) = &snapshot.following {
+            let leader = snapshot_aircraft
+                .iter()
+                .position(|leader| leader.aircraft.call_sign == following.call_sign)
+                .map(|index| spawned[index]);
+            if let Some(leader) = leader {
+                entity_mut.insert(FollowAircraft {
+                    leader,
+                    spacing_nm: following.spacing_nm,
+                });
+            }
+        }
+        if snapshot.climbing_out {
+            entity_mut.insert(ClimbingOut);
+        }
+        if snapshot.in_sector {
+            entity_mut.insert(InSector);
+        }
+        if snapshot.handoff_due {
+            entity_mut.insert(HandoffDue);
+        }
+        if !snapshot.applied_clearances.is_empty() {
+            entity_mut.insert(AppliedClearances(snapshot.applied_clearances.clone()));
+        }
+    }
+    for entity in spawned {
+        world.send_event(AircraftJustSpawned(entity));
+    }
+}
+
+fn quick_save(world: &mut World) {
+    let snapshot = snapshot_game(world);
+    let result = snapshot
+        .to_ron()
+        .map_err(|err| err.to_string())
+        .and_then(|text| std::fs::write(QUICK_SAVE_FILE, text).map_err(|err| err.to_string()));
+    match result {
+        Ok(()) => info!("Saved the game to {QUICK_SAVE_FILE}"),
+        Err(err) => error!("Failed to save the game: {err}"),
+    }
+}
+
+fn quick_load(world: &mut World) {
+    let result = std::fs::read_to_string(QUICK_SAVE_FILE)
+        .map_err(|err| err.to_string())
+        .and_then(|text| GameSnapshot::from_ron(&text).map_err(|err| err.to_string()));
+    match result {
+        Ok(snapshot) => {
+            restore_game(world, &snapshot);
+            info!("Restored the game from {QUICK_SAVE_FILE}");
+        }
+        Err(err) => error!("Failed to load the game: {err}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use aviation_helper_rs::clearance::aviation_command::AviationCommandPart;
+    use aviation_helper_rs::types::altitude::{Altitude, AltitudeConstraint};
+    use aviation_helper_rs::types::heading::Heading;
+    use bevy::prelude::*;
+
+    use super::{GameSnapshot, restore_game, snapshot_game};
+    use crate::game::GameVariables;
+    use crate::game::aircraft::{
+        Aircraft, AircraftJustSpawned, AircraftMeshMaterials, test_aircraft,
+    };
+    use crate::game::clearance::{
+        AppliedClearances, ClearanceLog, ClearedValues, CommandQueue, QueuedClearance,
+        TimestampedClearance,
+    };
+    use crate::game::emergency::Emergency;
+    use crate::game::handoff::InSector;
+    use crate::game::landing::{ClimbingOut, LandingClearance, LandingClearanceKind};
+    use crate::game::sequencing::FollowAircraft;
+    use crate::game::wind::Wind;
+    use crate::menu::LevelMeta;
+
+    fn cleared_aircraft(call_sign: &str, heading: f64) -> Aircraft {
+        Aircraft {
+            call_sign: call_sign.to_owned(),
+            cleared_altitude_feet: Some(4000.),
+            cleared_altitude_discretion: true,
+            cleared_heading: Some(Heading::from(heading + 20.)),
+            cleared_speed_knots: Some(210.),
+            altitude_change_feet_per_second: -12.5,
+            heading: Heading::from(heading),
+            heading_change_degrees_per_second: 1.5,
+            speed_knots: 240.,
+            acceleration_knots_per_second: -0.5,
+            ..test_aircraft()
+        }
+    }
+
+    fn test_world() -> World {
+        let mut world = World::new();
+        world.init_resource::<AircraftMeshMaterials>();
+        world.init_resource::<Events<AircraftJustSpawned>>();
+        world.insert_resource(GameVariables::new(LevelMeta {
+            file: "test.ron".to_owned(),
+            name: "Test".to_owned(),
+        }));
+        world.init_resource::<Wind>();
+        world.init_resource::<ClearanceLog>();
+        world
+    }
+
+    #[test]
+    fn test_snapshot_round_trip() {
+        let mut world = test_world();
+        world.resource_mut::<GameVariables>().qnh_hpa = 1002.;
+        world.insert_resource(Wind {
+            direction_degrees: 250.,
+            speed_knots: 15.,
+        });
+        world.spawn((
+            cleared_aircraft("EZY2902", 30.),
+            Transform::from_xyz(-120., 40., 8.),
+            Emergency::RadioFailure,
+            CommandQueue(vec![QueuedClearance {
+                trigger: None,
+                command: AviationCommandPart::CrossFix {
+                    fix: "BAGEL".to_owned(),
+                    constraint: AltitudeConstraint::AtOrAbove,
+                    altitude: Altitude::FlightLevel(100),
+                },
+                coalesce: false,
+            }]),
+        ));
+        let leader = world
+            .spawn((
+                cleared_aircraft("DLH123", 270.),
+                Transform::from_xyz(300., -25.5, 8.),
+                LandingClearance {
+                    runway: "27".to_owned(),
+                    kind: LandingClearanceKind::FullStop,
+                },
+                InSector,
+            ))
+            .id();
+        world.spawn((
+            cleared_aircraft("AFR1012", 270.),
+            Transform::from_xyz(380., -25.5, 8.),
+            FollowAircraft {
+                leader,
+                spacing_nm: 5.,
+            },
+            AppliedClearances(vec![TimestampedClearance {
+                sequence: 7,
+                applied_at: Duration::from_secs(42),
+                command: AviationCommandPart::ChangeSpeed { knots: 210 },
+            }]),
+        ));
+        world.spawn((
+            cleared_aircraft("RYR88", 90.),
+            Transform::from_xyz(-300., 0., 8.),
+            ClimbingOut,
+        ));
+
+        let snapshot = snapshot_game(&mut world);
+        let text = snapshot.to_ron().unwrap();
+        let restored_snapshot = GameSnapshot::from_ron(&text).unwrap();
+        assert_eq!(restored_snapshot, snapshot);
+
+        let mut restored = test_world();
+        // Aircraft, that exist before restoring, are replaced
+        let replaced = restored
+            .spawn((cleared_aircraft("BAW1", 90.), Transform::default()))
+            .id();
+        restored.resource_mut::<ClearanceLog>().push(
+            replaced,
+            &AviationCommandPart::ResumeNormalSpeed,
+            ClearedValues::of(&cleared_aircraft("BAW1", 90.)),
+            Duration::ZERO,
+        );
+        restore_game(&mut restored, &restored_snapshot);
+        assert_eq!(snapshot_game(&mut restored), snapshot);
+        assert_eq!(
+            restored
+                .resource::<Events<AircraftJustSpawned>>()
+                .iter_current_update_events()
+                .count(),
+            4
+        );
+
+        // The follower follows the restored leader
+        let mut q_follow = restored.query::<&FollowAircraft>();
+        let leader = q_follow.single(&restored).unwrap().leader;
+        let mut q_aircraft = restored.query::<&Aircraft>();
+        assert_eq!(
+            q_aircraft.get(&restored, leader).unwrap().call_sign,
+            "DLH123"
+        );
+
+        // The entries of the replaced aircraft are gone and new ones don't reuse the sequence
+        // of a restored clearance
+        let mut log = restored.resource_mut::<ClearanceLog>();
+        assert!(log.entries.is_empty());
+        log.push(
+            leader,
+            &AviationCommandPart::ResumeNormalSpeed,
+            ClearedValues::of(&cleared_aircraft("DLH123", 270.)),
+            Duration::ZERO,
+        );
+        assert_eq!(log.entries[0].sequence, 8);
+    }
+}
@@ -0,0 +1,105 @@
+use bevy::platform::collections::HashMap;
+use bevy::prelude::*;
+
+use crate::game::aircraft::Aircraft;
+use crate::util::units::nm_to_pixels;
+
+/// Edge length of a grid cell. Queries up to this radius look at no more than 9 cells.
+const SPATIAL_GRID_CELL_SIZE_NM: f64 = 8.0;
+
+/// Aircraft positions, bucketed into square cells, so that lookups around a position don't
+/// have to check every aircraft
+#[derive(Resource, Debug, Clone)]
+pub struct SpatialGrid {
+    cell_size: f32,
+    cells: HashMap<IVec2, Vec<(Entity, Vec2)>>,
+}
+
+impl Default for SpatialGrid {
+    fn default() -> Self {
+        Self::new(nm_to_pixels(SPATIAL_GRID_CELL_SIZE_NM))
+    }
+}
+
+impl SpatialGrid {
+    pub fn new(cell_size: f32) -> Self {
+        Self {
+            cell_size,
+            cells: HashMap::default(),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.cells.clear();
+    }
+
+    pub fn insert(&mut self, entity: Entity, position: Vec2) {
+        self.cells
+            .entry(self.cell(position))
+            .or_default()
+            .push((entity, position));
+    }
+
+    /// All entities with a distance to the position of at most the radius (both in pixels)
+    pub fn query_radius(&self, position: Vec2, radius: f32) -> impl Iterator<Item = Entity> + '_ {
+        let min = self.cell(position - Vec2::splat(radius));
+        let max = self.cell(position + Vec2::splat(radius));
+        (min.x..=max.x)
+            .flat_map(move |x| (min.y..=max.y).map(move |y| IVec2::new(x, y)))
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .filter(move |(_, other)| other.distance(position) <= radius)
+            .map(|(entity, _)| *entity)
+    }
+
+    fn cell(&self, position: Vec2) -> IVec2 {
+        (position / self.cell_size).floor().as_ivec2()
+    }
+}
+
+/// Refills the grid with the current aircraft positions. Runs before every system, that
+/// queries the grid.
+pub fn rebuild_spatial_grid(
+    mut grid: ResMut<SpatialGrid>,
+    q_aircraft: Query<(Entity, &Transform), With<Aircraft>>,
+) {
+    grid.clear();
+    for (entity, transform) in &q_aircraft {
+        grid.insert(entity, transform.translation.truncate());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::platform::collections::HashSet;
+    use bevy::prelude::*;
+
+    use super::SpatialGrid;
+
+    #[test]
+    fn test_query_radius() {
+        let mut world = World::new();
+        let mut grid = SpatialGrid::new(100.);
+        let mut spawn_at = |x, y| {
+            let entity = world.spawn_empty().id();
+            grid.insert(entity, Vec2::new(x, y));
+            entity
+        };
+        let center = spawn_at(10., 10.);
+        // Neighbouring cell, inside the radius
+        let near = spawn_at(-40., 10.);
+        // Same cell, but outside the radius
+        let same_cell_far = spawn_at(90., 90.);
+        let far = spawn_at(500., -300.);
+        // Exactly on the radius
+        let on_radius = spawn_at(10., 70.);
+
+        let found: HashSet<Entity> = grid.query_radius(Vec2::new(10., 10.), 60.).collect();
+        assert_eq!(found, HashSet::from_iter([center, near, on_radius]));
+        assert!(!found.contains(&same_cell_far));
+        assert!(!found.contains(&far));
+
+        grid.clear();
+        assert_eq!(grid.query_radius(Vec2::new(10., 10.), 60.).count(), 0);
+    }
+}
@@ -0,0 +1,153 @@
+use std::ops::RangeInclusive;
+
+use bevy::platform::collections::HashSet;
+use bevy::prelude::*;
+
+use crate::game::GameState;
+use crate::game::aircraft::Aircraft;
+use crate::game::console::ConsoleLogLine;
+use crate::game::emergency::Emergency;
+
+/// Codes, that the pool assigns by default. Leaves out the conspicuity and emergency codes.
+const DEFAULT_SQUAWK_RANGE: RangeInclusive<u16> = 2001..=6777;
+
+pub struct SquawkPlugin;
+
+impl Plugin for SquawkPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SquawkPool>()
+            .add_observer(assign_squawk_on_spawn)
+            .add_systems(
+                Update,
+                validate_assigned_squawks.run_if(in_state(GameState::Running)),
+            );
+    }
+}
+
+/// Transponder code, that the aircraft was told to squawk
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AssignedSquawk(pub u16);
+
+/// Hands out transponder codes, that no other aircraft squawks
+#[derive(Resource, Debug, Clone)]
+pub struct SquawkPool {
+    range: RangeInclusive<u16>,
+    in_use: HashSet<u16>,
+}
+
+impl Default for SquawkPool {
+    fn default() -> Self {
+        Self::new(DEFAULT_SQUAWK_RANGE)
+    }
+}
+
+impl SquawkPool {
+    /// Pool of the valid codes in the range. Codes are written as their four octal digits,
+    /// e.g. 2001..=2007 contains 7 codes.
+    pub fn new(range: RangeInclusive<u16>) -> Self {
+        Self {
+            range,
+            in_use: HashSet::default(),
+        }
+    }
+
+    /// Lowest free code of the range, which is marked as in use. None, if all are in use.
+    pub fn allocate(&mut self) -> Option<u16> {
+        let code = self
+            .range
+            .clone()
+            .filter(|code| is_valid_squawk(*code))
+            .find(|code| !self.in_use.contains(code))?;
+        self.in_use.insert(code);
+        Some(code)
+    }
+
+    /// Replaces the codes in use, e.g. with the ones currently assigned to aircraft
+    pub fn set_in_use(&mut self, codes: impl IntoIterator<Item = u16>) {
+        self.in_use = codes.into_iter().collect();
+    }
+}
+
+/// Whether the code consists of four octal digits
+pub fn is_valid_squawk(code: u16) -> bool {
+    code <= 7777
+        && [1, 10, 100, 1000]
+            .iter()
+            .all(|place| code / place % 10 <= 7)
+}
+
+fn assign_squawk_on_spawn(
+    trigger: Trigger<OnAdd, Aircraft>,
+    mut commands: Commands,
+    mut pool: ResMut<SquawkPool>,
+) {
+    match pool.allocate() {
+        Some(code) => {
+            commands
+                .entity(trigger.target())
+                .insert(AssignedSquawk(code));
+        }
+        None => warn!("No free squawk code left"),
+    }
+}
+
+/// Keeps the pool in sync with the assigned codes and warns about codes, that were assigned
+/// to more than one aircraft. Emergency codes are shared on purpose.
+fn validate_assigned_squawks(
+    mut pool: ResMut<SquawkPool>,
+    mut removed: RemovedComponents<AssignedSquawk>,
+    q_changed: Query<(Entity, &Aircraft, &AssignedSquawk), Changed<AssignedSquawk>>,
+    q_assigned: Query<(Entity, &AssignedSquawk)>,
+    mut log_writer: EventWriter<ConsoleLogLine>,
+) {
+    let any_removed = removed.read().count() > 0;
+    if q_changed.is_empty() && !any_removed {
+        return;
+    }
+    pool.set_in_use(q_assigned.iter().map(|(_, AssignedSquawk(code))| *code));
+    for (entity, aircraft, AssignedSquawk(code)) in &q_changed {
+        if Emergency::from_squawk(*code).is_some() {
+            continue;
+        }
+        let duplicate = q_assigned
+            .iter()
+            .any(|(other, AssignedSquawk(other_code))| other != entity && other_code == code);
+        if duplicate {
+            let message = format!(
+                "{}: squawk {code:04} is already assigned to another aircraft",
+                aircraft.call_sign
+            );
+            warn!("{message}");
+            log_writer.write(ConsoleLogLine(message));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SquawkPool, is_valid_squawk};
+
+    #[test]
+    fn test_allocate_unique_codes() {
+        let mut pool = SquawkPool::new(2001..=2012);
+        pool.set_in_use([2003, 3000]);
+
+        let mut allocated = vec![];
+        while let Some(code) = pool.allocate() {
+            assert!(is_valid_squawk(code), "Invalid code {code}");
+            assert!(!allocated.contains(&code), "Code {code} allocated twice");
+            allocated.push(code);
+        }
+        // 2001..=2007 and 2010..=2012 without 2003, that is in use
+        assert_eq!(
+            allocated,
+            vec![2001, 2002, 2004, 2005, 2006, 2007, 2010, 2011, 2012]
+        );
+        assert_eq!(pool.allocate(), None);
+
+        // The aircraft squawking 2005 left
+        pool.set_in_use(allocated.iter().copied().filter(|code| *code != 2005));
+        assert_eq!(pool.allocate(), Some(2005));
+        assert_eq!(pool.allocate(), None);
+    }
+}
@@ -0,0 +1,235 @@
+use aviation_helper_rs::types::heading::Heading;
+use bevy::prelude::*;
+use bevy_prng::WyRand;
+use bevy_rand::global::GlobalEntropy;
+use rand_core::RngCore;
+
+use crate::game::aircraft::{
+    Aircraft, AircraftJustSpawned, AircraftMeshMaterials, spawn_aircraft_with_speed_indicator,
+};
+use crate::game::level::{Runway, Waypoint};
+use crate::game::{GameState, GameVariables};
+use crate::util::units::nm_to_pixels;
+
+/// Mean time between two inbound aircraft at difficulty 1
+const BASE_SPAWN_INTERVAL_SECONDS: f64 = 120.;
+const FIRST_SPAWN_SECONDS: f64 = 10.;
+/// Chance per difficulty, that an aircraft is sent over the entry fix and at the altitude of the
+/// previous one
+const CONFLICT_CHANCE_PER_DIFFICULTY: f64 = 0.15;
+const MAX_CONFLICT_CHANCE: f64 = 0.6;
+/// Distance before the entry fix, at which inbound aircraft appear
+const SPAWN_DISTANCE_BEFORE_FIX_NM: f64 = 10.;
+const ENTRY_ALTITUDES_FEET: [f64; 5] = [8000., 10000., 12000., 14000., 16000.];
+const ENTRY_SPEED_KNOTS: f64 = 250.;
+const AIRLINES: [&str; 5] = ["DLH", "BAW", "AFR", "RYR", "EZY"];
+const AIRCRAFT_TYPES: [&str; 3] = ["a320", "b737", "b747"];
+
+pub struct TrafficPlugin;
+
+impl Plugin for TrafficPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TrafficDirector>().add_systems(
+            FixedUpdate,
+            direct_traffic.run_if(in_state(GameState::Running)),
+        );
+    }
+}
+
+/// Spawns inbound aircraft over the entry fixes. The difficulty in `GameVariables` scales how
+/// often they come and how often they conflict with the previous one.
+#[derive(Resource, Debug, Clone)]
+pub struct TrafficDirector {
+    next_spawn_in_seconds: f64,
+    /// Entry fix and altitude of the previous inbound aircraft
+    last_entry: Option<(String, f64)>,
+}
+
+impl Default for TrafficDirector {
+    fn default() -> Self {
+        Self {
+            next_spawn_in_seconds: FIRST_SPAWN_SECONDS,
+            last_entry: None,
+        }
+    }
+}
+
+impl TrafficDirector {
+    /// Advances the time. Returns true, if the next aircraft is due. No aircraft are spawned
+    /// at a difficulty of 0.
+    pub fn tick(&mut self, delta_seconds: f64, difficulty: f64, rng: &mut impl RngCore) -> bool {
+        if difficulty <= 0. {
+            return false;
+        }
+        self.next_spawn_in_seconds -= delta_seconds;
+        if self.next_spawn_in_seconds > 0. {
+            return false;
+        }
+        self.next_spawn_in_seconds += spawn_interval_seconds(difficulty, rng);
+        true
+    }
+
+    /// Entry fix and altitude of the next aircraft. At higher difficulties it more often
+    /// follows the previous one at the same altitude.
+    pub fn choose_entry(
+        &mut self,
+        entry_fixes: &[String],
+        difficulty: f64,
+        rng: &mut impl RngCore,
+    ) -> Option<(String, f64)> {
+        let conflict_chance =
+            (difficulty * CONFLICT_CHANCE_PER_DIFFICULTY).min(MAX_CONFLICT_CHANCE);
+        let entry = match &self.last_entry {
+            Some(last_entry) if random_fraction(rng) < conflict_chance => last_entry.clone(),
+            _ => {
+                let fix = entry_fixes.get(random_index(rng, entry_fixes.len())?)?;
+                let altitude_feet =
+                    ENTRY_ALTITUDES_FEET[random_index(rng, ENTRY_ALTITUDES_FEET.len())?];
+                (fix.clone(), altitude_feet)
+            }
+        };
+        self.last_entry = Some(entry.clone());
+        Some(entry)
+    }
+}
+
+/// Random time until the next aircraft, evenly spread around the mean interval of the difficulty
+pub fn spawn_interval_seconds(difficulty: f64, rng: &mut impl RngCore) -> f64 {
+    let mean = BASE_SPAWN_INTERVAL_SECONDS / difficulty;
+    mean * (0.5 + random_fraction(rng))
+}
+
+fn random_fraction(rng: &mut impl RngCore) -> f64 {
+    rng.next_u32() as f64 / u32::MAX as f64
+}
+
+fn random_index(rng: &mut impl RngCore, len: usize) -> Option<usize> {
+    (len > 0).then(|| rng.next_u32() as usize % len)
+}
+
+/// The outer half of the waypoints, seen from the airport
+fn entry_fixes(waypoints: &[(String, Vec2)], airport: Vec2) -> Vec<String> {
+    let mut by_distance: Vec<_> = waypoints.iter().collect();
+    by_distance.sort_by(|(_, a), (_, b)| a.distance(airport).total_cmp(&b.distance(airport)));
+    by_distance[by_distance.len() / 2..]
+        .iter()
+        .map(|(name, _)| name.clone())
+        .collect()
+}
+
+#[allow(clippy::too_many_arguments)]
+fn direct_traffic(
+    mut commands: Commands,
+    time: Res<Time>,
+    variables: Res<GameVariables>,
+    mut director: ResMut<TrafficDirector>,
+    mut rng: GlobalEntropy<WyRand>,
+    mesh_materials: Res<AircraftMeshMaterials>,
+    mut writer: EventWriter<AircraftJustSpawned>,
+    q_waypoints: Query<(&Waypoint, &Transform)>,
+    q_runways: Query<&Runway>,
+) {
+    if !director.tick(time.delta_secs_f64(), variables.difficulty, &mut *rng) {
+        return;
+    }
+    let waypoints: Vec<(String, Vec2)> = q_waypoints
+        .iter()
+        .map(|(waypoint, transform)| (waypoint.name.clone(), transform.translation.truncate()))
+        .collect();
+    let airport = q_runways
+        .iter()
+        .next()
+        .map_or(Vec2::ZERO, |runway| runway.threshold);
+    let fixes = entry_fixes(&waypoints, airport);
+    let Some((fix, altitude_feet)) = director.choose_entry(&fixes, variables.difficulty, &mut *rng)
+    else {
+        return;
+    };
+    let Some(fix_position) = waypoints
+        .iter()
+        .find(|(name, _)| *name == fix)
+        .map(|(_, position)| *position)
+    else {
+        return;
+    };
+
+    // Coming from outside, flying towards the fix
+    let outbound = (fix_position - airport).normalize_or(Vec2::Y);
+    let position = fix_position + outbound * nm_to_pixels(SPAWN_DISTANCE_BEFORE_FIX_NM);
+    let heading = Heading::from_bevy_rotation((-outbound).to_angle() as f64);
+    let airline = AIRLINES[rng.next_u32() as usize % AIRLINES.len()];
+    let aircraft_type = AIRCRAFT_TYPES[rng.next_u32() as usize % AIRCRAFT_TYPES.len()];
+    let aircraft = Aircraft {
+        aircraft_type_id: aircraft_type.to_owned(),
+        call_sign: format!("{airline}{}", 100 + rng.next_u32() % 900),
+        cleared_altitude_feet: None,
+        cleared_altitude_discretion: false,
+        cleared_altitude_block_feet: None,
+        wanted_altitude_feet: altitude_feet,
+        cleared_heading: Some(heading),
+        cleared_heading_change_direction: None,
+        cleared_speed_knots: None,
+        wanted_speed_knots: ENTRY_SPEED_KNOTS,
+        altitude_feet,
+        altitude_change_feet_per_second: 0.,
+        heading,
+        heading_change_degrees_per_second: 0.,
+        speed_knots: ENTRY_SPEED_KNOTS,
+        acceleration_knots_per_second: 0.,
+    };
+    info!(
+        "{} inbound over {fix} at {altitude_feet} feet",
+        aircraft.call_sign
+    );
+    let entity =
+        spawn_aircraft_with_speed_indicator(&mut commands, aircraft, position, &mesh_materials);
+    writer.write(AircraftJustSpawned(entity));
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+    use bevy_prng::WyRand;
+    use rand_core::SeedableRng;
+
+    use super::{TrafficDirector, entry_fixes};
+
+    #[test]
+    fn test_higher_difficulty_spawns_more_often() {
+        let mean_interval = |difficulty| {
+            let mut rng = WyRand::seed_from_u64(42);
+            let mut director = TrafficDirector::default();
+            let seconds = 4. * 3600.;
+            let delta_seconds = 0.5;
+            let spawns = (0..(seconds / delta_seconds) as usize)
+                .filter(|_| director.tick(delta_seconds, difficulty, &mut rng))
+                .count();
+            seconds / spawns as f64
+        };
+        let easy = mean_interval(0.5);
+        let normal = mean_interval(1.);
+        let hard = mean_interval(3.);
+        assert!(
+            hard < normal && normal < easy,
+            "Mean intervals: easy {easy}s, normal {normal}s, hard {hard}s"
+        );
+
+        let mut director = TrafficDirector::default();
+        let mut rng = WyRand::seed_from_u64(42);
+        assert!(!(0..1000).any(|_| director.tick(1., 0., &mut rng)));
+    }
+
+    #[test]
+    fn test_entry_fixes_are_the_outer_waypoints() {
+        let waypoints = [
+            ("NEAR".to_owned(), Vec2::new(10., 0.)),
+            ("FAR".to_owned(), Vec2::new(0., -900.)),
+            ("MID".to_owned(), Vec2::new(300., 300.)),
+            ("OUTER".to_owned(), Vec2::new(-500., 0.)),
+        ];
+        assert_eq!(
+            entry_fixes(&waypoints, Vec2::ZERO),
+            vec!["OUTER".to_owned(), "FAR".to_owned()]
+        );
+    }
+}
@@ -0,0 +1,73 @@
+use aviation_helper_rs::conversions::aviation_degrees_to_bevy_rotation;
+use aviation_helper_rs::metar::MetarData;
+use aviation_helper_rs::types::heading::Heading;
+use bevy::math::DVec2;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Wind of the level, the same at all altitudes. Calm by default.
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq, Reflect, Serialize, Deserialize)]
+pub struct Wind {
+    /// Direction, from which the wind blows
+    pub direction_degrees: f64,
+    pub speed_knots: f64,
+}
+
+/// Weather of the level, if it has a METAR. Decides, whether visual approaches are allowed.
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct Metar(pub MetarData);
+
+impl Wind {
+    /// Mean wind of the report. Variable wind is treated as blowing from the north.
+    pub fn from_metar(metar: &MetarData) -> Self {
+        Self {
+            direction_degrees: metar.wind_dir.unwrap_or(360) as f64,
+            speed_knots: metar.wind_speed as f64,
+        }
+    }
+
+    /// Velocity of the air mass in knots, in world coordinates
+    pub fn velocity_knots(&self) -> DVec2 {
+        // The wind blows towards the opposite of its direction
+        let rotation = aviation_degrees_to_bevy_rotation(self.direction_degrees + 180.);
+        DVec2::from_angle(rotation) * self.speed_knots
+    }
+
+    /// Velocity over ground in knots of an aircraft flying the heading at the airspeed
+    pub fn ground_velocity_knots(&self, heading: Heading, airspeed_knots: f64) -> DVec2 {
+        DVec2::from_angle(heading.to_bevy_rotation()) * airspeed_knots + self.velocity_knots()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use aviation_helper_rs::types::heading::Heading;
+
+    use super::Wind;
+
+    #[test]
+    fn test_ground_speed() {
+        let wind = Wind {
+            direction_degrees: 270.,
+            speed_knots: 30.,
+        };
+        let crosswind_ground_speed = (200f64.powi(2) + 30f64.powi(2)).sqrt();
+        for (wind, heading, expected) in [
+            // Headwind
+            (wind, 270., 170.),
+            // Tailwind
+            (wind, 90., 230.),
+            // Crosswind
+            (wind, 360., crosswind_ground_speed),
+            (Wind::default(), 45., 200.),
+        ] {
+            let actual = wind
+                .ground_velocity_knots(Heading::from(heading), 200.)
+                .length();
+            assert!(
+                (actual - expected).abs() < 1e-9,
+                "Expected {expected}, got {actual}"
+            );
+        }
+    }
+}
@@ -29,15 +29,32 @@ pub static APP_CONFIG: LazyLock<AppConfig> = LazyLock::new(AppConfig::from_env);
 pub struct AppConfig {
     dev_gui: bool,
     log_state_transitions: bool,
+    /// Name of the file in assets/card_layouts without extension, e.g. "minimal"
+    card_layout: String,
+    /// "default", "colorblind" or the name of a file in assets/color_themes without extension
+    color_theme: String,
+    /// Address, on which clients, e.g. a stream overlay, can connect to read the game events
+    event_stream_address: String,
+    /// Recognize transmissions from the microphone with Whisper
+    voice_control: bool,
 }
 
 impl AppConfig {
     fn from_env() -> Self {
         let dev_gui = env::var("DEV_GUI").as_deref() != Ok("0");
         let log_state_transitions = env::var("LOG_STATE_TRANSITIONS").as_deref() == Ok("1");
+        let card_layout = env::var("CARD_LAYOUT").unwrap_or_else(|_| "full".to_owned());
+        let color_theme = env::var("COLOR_THEME").unwrap_or_else(|_| "default".to_owned());
+        let event_stream_address =
+            env::var("EVENT_STREAM_ADDRESS").unwrap_or_else(|_| "127.0.0.1:7878".to_owned());
+        let voice_control = env::var("VOICE_CONTROL").as_deref() == Ok("1");
         Self {
             dev_gui,
             log_state_transitions,
+            card_layout,
+            color_theme,
+            event_stream_address,
+            voice_control,
         }
     }
 }
@@ -65,6 +82,11 @@ fn main() -> anyhow::Result<()> {
             })
             // Prevents blurry sprites
             .set(ImagePlugin::default_nearest())
+            // Hot reload of level and aircraft type files, while the dev gui is enabled
+            .set(AssetPlugin {
+                watch_for_changes_override: Some(APP_CONFIG.dev_gui),
+                ..default()
+            })
             .set(WindowPlugin {
                 primary_window: Some(Window {
                     // provide the ID selector string here
@@ -1,11 +1,15 @@
 // Based on:
 // https://github.com/bevyengine/bevy/blob/main/examples/ui/flex_layout.rs
 //!
+use std::path::{Path, PathBuf};
+
+#[cfg(not(target_arch = "wasm32"))]
+use bevy::asset::io::file::FileAssetReader;
 use bevy::asset::{AssetServer, Assets, Handle};
 use bevy::ecs::system::command;
 use bevy::prelude::*;
 use bevy_common_assets::ron::RonAssetPlugin;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 use crate::game::GameVariables;
 use crate::{AppState, util::entities::despawn_all};
@@ -154,6 +158,16 @@ fn level_select_menu_setup(
         ));
         return;
     };
+    let missing = levels_dir()
+        .map(|levels_dir| index.missing_levels(&levels_dir))
+        .unwrap_or_default();
+    for level in &missing {
+        bevy::log::error!(
+            "Level file levels/{} of level {} does not exist",
+            level.file,
+            level.name
+        );
+    }
     commands
         .spawn((
             OnLevelSelectScreen,
@@ -176,7 +190,7 @@ fn level_select_menu_setup(
             ),],
         ))
         .with_children(|parent| {
-            for level in &index.levels {
+            for level in index.levels.iter().filter(|level| !missing.contains(level)) {
                 parent.spawn((
                     Button,
                     BackgroundColor(NORMAL_BUTTON),
@@ -322,9 +336,56 @@ pub struct LevelIndexFile {
     pub levels: Vec<LevelMeta>,
 }
 
-#[derive(Deserialize, Clone, Debug, PartialEq, Reflect)]
+impl LevelIndexFile {
+    /// Levels, whose file doesn't exist in the levels directory
+    pub fn missing_levels(&self, levels_dir: &Path) -> Vec<&LevelMeta> {
+        self.levels
+            .iter()
+            .filter(|level| !levels_dir.join(&level.file).is_file())
+            .collect()
+    }
+}
+
+/// Directory of the level files. Not available on the web, where assets are fetched over http.
+#[cfg(not(target_arch = "wasm32"))]
+fn levels_dir() -> Option<PathBuf> {
+    Some(FileAssetReader::get_base_path().join("assets/levels"))
+}
+
+#[cfg(target_arch = "wasm32")]
+fn levels_dir() -> Option<PathBuf> {
+    None
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Reflect)]
 pub struct LevelMeta {
     pub file: String,
     pub name: String,
     // Optional: pub description: Option<String>,
 }
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::{LevelIndexFile, LevelMeta};
+
+    const LEVELS_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/assets/levels");
+
+    #[test]
+    fn test_level_index_deserializes_and_references_existing_files() {
+        let index: LevelIndexFile =
+            ron::from_str(include_str!("../assets/levels/index.ron")).unwrap();
+        assert!(!index.levels.is_empty());
+        assert!(index.missing_levels(Path::new(LEVELS_DIR)).is_empty());
+
+        let missing = LevelMeta {
+            file: "does_not_exist.ron".to_owned(),
+            name: "Missing".to_owned(),
+        };
+        let index = LevelIndexFile {
+            levels: vec![index.levels[0].clone(), missing.clone()],
+        };
+        assert_eq!(index.missing_levels(Path::new(LEVELS_DIR)), vec![&missing]);
+    }
+}
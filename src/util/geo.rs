@@ -0,0 +1,151 @@
+//! Geographic positions and their projection to world coordinates, for sectors, that are too
+//! large to be treated as flat
+
+use std::f64::consts::{FRAC_PI_2, FRAC_PI_4};
+
+use aviation_helper_rs::types::heading::Heading;
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::util::units::{nm_to_pixels, pixels_to_nm};
+
+/// Mean radius of the earth
+pub const EARTH_RADIUS_NM: f64 = 3440.065;
+
+/// Latitude and longitude in degrees
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Reflect)]
+pub struct LatLon {
+    pub lat: f64,
+    pub lon: f64,
+}
+
+/// Great-circle distance in NM
+pub fn great_circle_distance_nm(from: LatLon, to: LatLon) -> f64 {
+    let (lat_from, lat_to) = (from.lat.to_radians(), to.lat.to_radians());
+    let delta_lat = lat_to - lat_from;
+    let delta_lon = (to.lon - from.lon).to_radians();
+    let haversine = (delta_lat / 2.).sin().powi(2)
+        + lat_from.cos() * lat_to.cos() * (delta_lon / 2.).sin().powi(2);
+    2. * EARTH_RADIUS_NM * haversine.sqrt().asin()
+}
+
+/// Initial bearing of the great circle from one position to the other
+pub fn great_circle_bearing(from: LatLon, to: LatLon) -> Heading {
+    let (lat_from, lat_to) = (from.lat.to_radians(), to.lat.to_radians());
+    let delta_lon = (to.lon - from.lon).to_radians();
+    let y = delta_lon.sin() * lat_to.cos();
+    let x = lat_from.cos() * lat_to.sin() - lat_from.sin() * lat_to.cos() * delta_lon.cos();
+    Heading::from(y.atan2(x).to_degrees())
+}
+
+/// Mercator projection, that is true to scale at the origin, which is at the world origin
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Mercator {
+    pub origin: LatLon,
+}
+
+impl Mercator {
+    /// Radius of the projection, so that one NM at the origin is one NM on the map
+    fn radius_nm(&self) -> f64 {
+        EARTH_RADIUS_NM * self.origin.lat.to_radians().cos()
+    }
+
+    fn mercator_y(lat: f64) -> f64 {
+        (FRAC_PI_4 + lat.to_radians() / 2.).tan().ln()
+    }
+
+    pub fn project(&self, position: LatLon) -> Vec2 {
+        let radius = self.radius_nm();
+        let x = radius * (position.lon - self.origin.lon).to_radians();
+        let y = radius * (Self::mercator_y(position.lat) - Self::mercator_y(self.origin.lat));
+        Vec2::new(nm_to_pixels(x), nm_to_pixels(y))
+    }
+
+    pub fn unproject(&self, position: Vec2) -> LatLon {
+        let radius = self.radius_nm();
+        let lon = self.origin.lon + (pixels_to_nm(position.x) / radius).to_degrees();
+        let y = pixels_to_nm(position.y) / radius + Self::mercator_y(self.origin.lat);
+        let lat = (2. * y.exp().atan() - FRAC_PI_2).to_degrees();
+        LatLon { lat, lon }
+    }
+}
+
+/// How world positions relate to the earth
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq)]
+pub enum MapProjection {
+    /// Flat, distances and bearings are measured on the map
+    #[default]
+    Planar,
+    /// Distances and bearings are measured on the sphere
+    Mercator(Mercator),
+}
+
+impl MapProjection {
+    /// World position of something, that has a geographic position in the level file. Planar
+    /// levels use the world position of the file.
+    pub fn world_position(&self, lat_lon: Option<LatLon>, pos: Vec2) -> Vec2 {
+        match (self, lat_lon) {
+            (MapProjection::Mercator(mercator), Some(lat_lon)) => mercator.project(lat_lon),
+            _ => pos,
+        }
+    }
+
+    pub fn distance_nm(&self, from: Vec2, to: Vec2) -> f64 {
+        match self {
+            MapProjection::Planar => pixels_to_nm(from.distance(to)),
+            MapProjection::Mercator(mercator) => {
+                great_circle_distance_nm(mercator.unproject(from), mercator.unproject(to))
+            }
+        }
+    }
+
+    pub fn bearing(&self, from: Vec2, to: Vec2) -> Heading {
+        match self {
+            MapProjection::Planar => Heading::from_bevy_rotation((to - from).to_angle() as f64),
+            MapProjection::Mercator(mercator) => {
+                great_circle_bearing(mercator.unproject(from), mercator.unproject(to))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+
+    use super::{LatLon, MapProjection, Mercator, great_circle_distance_nm};
+
+    #[test]
+    fn test_planar_and_great_circle_distance() {
+        let frankfurt = LatLon {
+            lat: 50.0,
+            lon: 8.5,
+        };
+        let mercator = Mercator { origin: frankfurt };
+        assert_eq!(mercator.project(frankfurt), Vec2::ZERO);
+        // Relative difference between the distance on the map and on the sphere
+        let planar_error = |to: LatLon| {
+            let from = mercator.project(frankfurt);
+            let to_projected = mercator.project(to);
+            let great_circle = great_circle_distance_nm(frankfurt, to);
+            let spherical = MapProjection::Mercator(mercator).distance_nm(from, to_projected);
+            assert!((spherical - great_circle).abs() < 0.01);
+            (MapProjection::Planar.distance_nm(from, to_projected) - great_circle).abs()
+                / great_circle
+        };
+
+        // About 10 NM away, the map is flat enough
+        let nearby = LatLon {
+            lat: 50.1,
+            lon: 8.7,
+        };
+        assert!(planar_error(nearby) < 0.005);
+        // Reykjavik is more than 1000 NM away, the map is off by far
+        let far = LatLon {
+            lat: 64.0,
+            lon: -22.0,
+        };
+        assert!(great_circle_distance_nm(frankfurt, far) > 1000.);
+        assert!(planar_error(far) > 0.1);
+    }
+}
@@ -1,3 +1,5 @@
 pub mod consts;
 pub mod entities;
+pub mod geo;
 pub mod reflect;
+pub mod units;
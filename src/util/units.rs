@@ -0,0 +1,64 @@
+//! Conversions between world units (pixels) and aviation units
+
+/// Scale of the map, levels are laid out with 50 pixels per statute mile
+pub const PIXELS_PER_STATUTE_MILE: f64 = 50.;
+pub const STATUTE_MILES_PER_NM: f64 = 1.150779;
+/// All conversions between pixels and nautical miles derive from it
+pub const PIXELS_PER_NM: f64 = PIXELS_PER_STATUTE_MILE * STATUTE_MILES_PER_NM;
+const SECONDS_PER_HOUR: f64 = 3600.;
+
+pub fn pixels_to_nm(pixels: f32) -> f64 {
+    pixels as f64 / PIXELS_PER_NM
+}
+
+pub fn nm_to_pixels(nm: f64) -> f32 {
+    (nm * PIXELS_PER_NM) as f32
+}
+
+/// A knot is one nautical mile per hour
+pub fn knots_to_pixels_per_second(knots: f64) -> f64 {
+    knots / SECONDS_PER_HOUR * PIXELS_PER_NM
+}
+
+/// Shows feet below the transition altitude and flight levels at or above it
+pub fn format_feet(altitude_feet: f64, transition_altitude_feet: f64) -> String {
+    if altitude_feet < transition_altitude_feet {
+        (altitude_feet as i32).to_string()
+    } else {
+        format!("FL{}", altitude_feet as i32 / 100)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        PIXELS_PER_NM, format_feet, knots_to_pixels_per_second, nm_to_pixels, pixels_to_nm,
+    };
+
+    const TRANSITION_ALTITUDE_FEET: f64 = 18000.;
+
+    #[test]
+    fn test_distance_conversions() {
+        // 50 pixels are one statute mile
+        assert!((pixels_to_nm(50.) - 1. / 1.150779).abs() < 1e-9);
+        assert!((nm_to_pixels(1.) - 57.53895).abs() < 1e-3);
+        assert!((pixels_to_nm(nm_to_pixels(5.)) - 5.).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_knots_to_pixels_per_second() {
+        // 360 knots are 0.1 nautical miles per second
+        assert!((knots_to_pixels_per_second(360.) - 0.1 * PIXELS_PER_NM).abs() < 1e-9);
+        assert_eq!(knots_to_pixels_per_second(0.), 0.);
+    }
+
+    #[test]
+    fn test_format_feet_below_transition() {
+        assert_eq!(format_feet(3500., TRANSITION_ALTITUDE_FEET), "3500");
+    }
+
+    #[test]
+    fn test_format_feet_above_transition() {
+        assert_eq!(format_feet(35000., TRANSITION_ALTITUDE_FEET), "FL350");
+    }
+}